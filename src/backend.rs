@@ -0,0 +1,208 @@
+//! What differs between i3 and sway, behind a common interface.
+//!
+//! Sway speaks the i3 IPC protocol, with extensions: native Wayland windows
+//! are identified by their `app_id` rather than an X11 class, input devices
+//! can be listed with `GET_INPUTS` and configured with `input` commands,
+//! outputs can be scaled and windows can keep the screen from idling. A
+//! [`Backend`], picked at runtime by [`detect`] from the version reply, lets
+//! helpers use those capabilities on sway while staying functional on i3,
+//! where they are missing or left to X11.
+
+use crate::{
+    command::{quote, Action, CommandBuilder},
+    config::invalid_data,
+    I3Commands, WindowManager,
+};
+use i3_ipc::msg::Msg;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, io};
+
+/// The type of sway's `GET_INPUTS` message.
+const GET_INPUTS: u32 = 100;
+
+/// An input device, as listed by sway.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Input {
+    /// The identifier `input` commands refer to the device by, like
+    /// `1:1:AT_Translated_Set_2_keyboard`.
+    pub identifier: String,
+    pub name: String,
+    /// `keyboard`, `pointer`, `touchpad`, etc.
+    #[serde(rename = "type")]
+    pub input_type: String,
+    /// The keyboard layouts of keyboards.
+    #[serde(default)]
+    pub xkb_layout_names: Vec<String>,
+    pub xkb_active_layout_index: Option<usize>,
+}
+
+/// The capabilities of a window manager that go beyond the i3 protocol.
+pub trait Backend {
+    fn window_manager(&self) -> WindowManager;
+
+    /// Whether windows may be native Wayland ones, with an `app_id` and no
+    /// X11 properties.
+    fn has_app_ids(&self) -> bool;
+
+    /// The input devices, or none where the window manager doesn't handle
+    /// them.
+    fn get_inputs(&self, i3: &mut dyn I3Commands) -> io::Result<Vec<Input>>;
+
+    /// The scale factor of each active output, by name.
+    fn output_scales(&self, i3: &mut dyn I3Commands) -> io::Result<HashMap<String, f64>>;
+
+    /// The command applying `setting`, e.g. `xkb_switch_layout 1`, to the
+    /// input device `identifier`, if the window manager has one.
+    fn input_command(&self, identifier: &str, setting: &str) -> Option<String>;
+
+    /// The command keeping the screen from idling as long as the window `id`
+    /// is open, or letting it idle again, if the window manager has one.
+    fn inhibit_idle_command(&self, id: usize, inhibit: bool) -> Option<String>;
+}
+
+/// Pick the backend for the window manager on the other end of `i3`.
+pub fn detect(i3: &mut dyn I3Commands) -> io::Result<Box<dyn Backend>> {
+    Ok(match i3.window_manager()? {
+        WindowManager::I3 => Box::new(I3Backend),
+        WindowManager::Sway => Box::new(SwayBackend),
+    })
+}
+
+/// i3, which leaves input devices and scaling to X11.
+pub struct I3Backend;
+
+impl Backend for I3Backend {
+    fn window_manager(&self) -> WindowManager {
+        WindowManager::I3
+    }
+
+    fn has_app_ids(&self) -> bool {
+        false
+    }
+
+    fn get_inputs(&self, _i3: &mut dyn I3Commands) -> io::Result<Vec<Input>> {
+        Ok(Vec::new())
+    }
+
+    fn output_scales(&self, i3: &mut dyn I3Commands) -> io::Result<HashMap<String, f64>> {
+        Ok(i3
+            .get_outputs()?
+            .into_iter()
+            .filter(|output| output.active)
+            .map(|output| (output.name, 1.0))
+            .collect())
+    }
+
+    fn input_command(&self, _identifier: &str, _setting: &str) -> Option<String> {
+        None
+    }
+
+    fn inhibit_idle_command(&self, _id: usize, _inhibit: bool) -> Option<String> {
+        None
+    }
+}
+
+pub struct SwayBackend;
+
+impl Backend for SwayBackend {
+    fn window_manager(&self) -> WindowManager {
+        WindowManager::Sway
+    }
+
+    fn has_app_ids(&self) -> bool {
+        true
+    }
+
+    fn get_inputs(&self, i3: &mut dyn I3Commands) -> io::Result<Vec<Input>> {
+        parse_reply(i3.send_message(GET_INPUTS, "")?)
+    }
+
+    fn output_scales(&self, i3: &mut dyn I3Commands) -> io::Result<HashMap<String, f64>> {
+        // Sway adds the scale to the outputs reply, which `reply::Output`
+        // doesn't know about.
+        #[derive(Deserialize)]
+        struct Output {
+            name: String,
+            active: bool,
+            scale: Option<f64>,
+        }
+
+        let outputs: Vec<Output> = parse_reply(i3.send_message(Msg::Outputs.into(), "")?)?;
+        Ok(outputs
+            .into_iter()
+            .filter(|output| output.active)
+            .map(|output| (output.name, output.scale.unwrap_or(1.0)))
+            .collect())
+    }
+
+    fn input_command(&self, identifier: &str, setting: &str) -> Option<String> {
+        Some(format!("input {} {setting}", quote(identifier)))
+    }
+
+    fn inhibit_idle_command(&self, id: usize, inhibit: bool) -> Option<String> {
+        let command = CommandBuilder::con_id(id).action(Action::InhibitIdle(inhibit));
+        Some(command.build())
+    }
+}
+
+fn parse_reply<T: serde::de::DeserializeOwned>(reply: Value) -> io::Result<T> {
+    serde_json::from_value(reply).map_err(invalid_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockI3;
+    use serde_json::json;
+
+    #[test]
+    fn reads_inputs_and_scales_on_sway() {
+        let mut i3 = MockI3::default();
+        i3.window_manager = WindowManager::Sway;
+        i3.messages.insert(
+            GET_INPUTS,
+            json!([{
+                "identifier": "1:1:AT_Translated_Set_2_keyboard",
+                "name": "AT Translated Set 2 keyboard",
+                "type": "keyboard",
+                "xkb_layout_names": ["English (US)", "German"],
+                "xkb_active_layout_index": 1,
+            }]),
+        );
+        i3.messages.insert(
+            Msg::Outputs.into(),
+            json!([
+                {"name": "eDP-1", "active": true, "scale": 2.0},
+                {"name": "HDMI-A-1", "active": false, "scale": -1.0},
+            ]),
+        );
+        let backend = detect(&mut i3).unwrap();
+        let inputs = backend.get_inputs(&mut i3).unwrap();
+        assert_eq!(inputs[0].xkb_active_layout_index, Some(1));
+        assert_eq!(
+            backend.output_scales(&mut i3).unwrap(),
+            HashMap::from([("eDP-1".to_owned(), 2.0)])
+        );
+        assert_eq!(
+            backend.input_command(&inputs[0].identifier, "xkb_switch_layout 0"),
+            Some("input \"1:1:AT_Translated_Set_2_keyboard\" xkb_switch_layout 0".to_owned())
+        );
+        assert_eq!(
+            backend.inhibit_idle_command(5, false),
+            Some("[con_id=5] inhibit_idle none".to_owned())
+        );
+    }
+
+    #[test]
+    fn falls_back_to_nothing_on_i3() {
+        let mut i3 = MockI3::default();
+        let backend = detect(&mut i3).unwrap();
+        assert!(backend.get_inputs(&mut i3).unwrap().is_empty());
+        assert_eq!(
+            backend.input_command("1:1:kbd", "xkb_switch_layout 0"),
+            None
+        );
+        assert_eq!(backend.inhibit_idle_command(5, true), None);
+    }
+}
@@ -0,0 +1,202 @@
+//! Utility to send new windows to workspaces chosen by rule.
+//!
+//! i3's `assign` always sends the windows of an application to the same
+//! workspace. This program listens for events from i3 and moves new windows
+//! to a workspace picked among several, either the one with the fewest
+//! windows or each one in turn, or to the workspace of an existing window,
+//! e.g. to keep a chat application next to another.
+//!
+//! # Configuration
+//!
+//! The first rule matching a new window applies:
+//!
+//! ```toml
+//! # Spread terminals over workspaces 1 to 3, the emptiest first.
+//! [[rule]]
+//! match = { class = "Alacritty" }
+//! workspaces = ["1", "2", "3"]
+//! strategy = "least-populated"
+//!
+//! # Open Slack wherever Discord is, or on workspace 9 otherwise.
+//! [[rule]]
+//! match = { class = "Slack" }
+//! near = { class = "discord" }
+//! workspaces = ["9"]
+//! ```
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    config::{self, invalid_data},
+    criteria::{Criteria, CriteriaConfig},
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData},
+    reply::Node,
+};
+use log::info;
+use serde::Deserialize;
+use std::{io, path::PathBuf};
+
+const CONFIG_FILE_NAME: &str = "assign.toml";
+
+/// Send new windows to workspaces chosen by rule.
+#[derive(Parser)]
+struct Args {
+    /// The configuration file defining the rules. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/assign.toml`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(rename = "rule")]
+    rules: Vec<RuleConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    #[serde(rename = "match")]
+    criteria: CriteriaConfig,
+    near: Option<CriteriaConfig>,
+    #[serde(default)]
+    workspaces: Vec<String>,
+    #[serde(default)]
+    strategy: Strategy,
+}
+
+/// How to pick one of the workspaces of a rule.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Strategy {
+    /// The workspace with the fewest windows, the first one on ties.
+    #[default]
+    LeastPopulated,
+    /// Each workspace in turn.
+    RoundRobin,
+}
+
+struct Rule {
+    criteria: Criteria,
+    /// Send windows to the workspace of an existing window matching these
+    /// criteria, if any.
+    near: Option<Criteria>,
+    workspaces: Vec<String>,
+    strategy: Strategy,
+    /// Index in `workspaces` of the next one, for round-robin.
+    next: usize,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let path = args
+        .config
+        .or_else(|| config::default_path(CONFIG_FILE_NAME))
+        .ok_or_else(|| invalid_data("cannot find the configuration file, use --config"))?;
+    let config: Config = config::load(&path)?;
+    let rules = config
+        .rules
+        .into_iter()
+        .map(RuleConfig::into_rule)
+        .collect::<io::Result<_>>()?;
+    EventLoop::connect()?.run(&mut Assign { rules })
+}
+
+impl RuleConfig {
+    fn into_rule(self) -> io::Result<Rule> {
+        if self.near.is_none() && self.workspaces.is_empty() {
+            return Err(invalid_data("rules need workspaces, near, or both"));
+        }
+        Ok(Rule {
+            criteria: self.criteria.into_criteria()?,
+            near: self.near.map(CriteriaConfig::into_criteria).transpose()?,
+            workspaces: self.workspaces,
+            strategy: self.strategy,
+            next: 0,
+        })
+    }
+}
+
+pub struct Assign {
+    rules: Vec<Rule>,
+}
+
+impl Helper for Assign {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window]
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        if event.change != WindowChange::New {
+            return Ok(());
+        }
+        let window = &event.container;
+        let Some(rule) = self
+            .rules
+            .iter_mut()
+            .find(|rule| rule.criteria.matches(window))
+        else {
+            return Ok(());
+        };
+        let tree = i3.get_tree()?;
+        let Some(target) = rule.pick_workspace(&tree, window.id) else {
+            return Ok(());
+        };
+        let current = tree::workspace_of(&tree, window.id).and_then(|ws| ws.name.as_ref());
+        if current == Some(&target) {
+            return Ok(());
+        }
+        info!("sending {} to workspace {target:?}", tree::describe(window));
+        let command = CommandBuilder::con_id(window.id).action(Action::MoveToWorkspace(target));
+        i3.run_command(&command.build())
+    }
+}
+
+impl Rule {
+    /// The workspace to send the new window `id` to.
+    fn pick_workspace(&mut self, tree: &Node, id: usize) -> Option<String> {
+        if let Some(near) = &self.near {
+            let workspace = tree::windows(tree)
+                .filter(|window| window.id != id && near.matches(window))
+                .filter(|window| !tree::is_hidden_in_scratchpad(tree, window.id))
+                .find_map(|window| tree::workspace_of(tree, window.id))
+                .and_then(|workspace| workspace.name.clone());
+            if workspace.is_some() {
+                return workspace;
+            }
+        }
+        match self.strategy {
+            Strategy::LeastPopulated => {
+                let population = |name: &String| {
+                    tree::workspaces(tree)
+                        .find(|workspace| workspace.name.as_ref() == Some(name))
+                        .map_or(0, |workspace| {
+                            tree::windows(workspace)
+                                .filter(|window| window.id != id)
+                                .count()
+                        })
+                };
+                // `min_by_key` keeps the first of equal elements.
+                self.workspaces
+                    .iter()
+                    .min_by_key(|name| population(name))
+                    .cloned()
+            }
+            Strategy::RoundRobin => {
+                let workspace = self.workspaces.get(self.next).cloned();
+                self.next = (self.next + 1) % self.workspaces.len().max(1);
+                workspace
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Utility to alternate between horizontal and vertical splits automatically.
+//!
+//! This program listens for events from i3 and, whenever a window is focused,
+//! sets the split orientation of its container according to its shape: wide
+//! windows are split horizontally and tall windows vertically. New windows
+//! then open next to or below the focused one, producing a spiral-like layout
+//! without having to think about splits at all.
+//!
+//! It is a native take on the popular `autotiling` script.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    helpers::autotiling::Autotiling,
+    logging::{self, Verbosity},
+    EventLoop,
+};
+use std::io;
+
+/// Alternate between horizontal and vertical splits automatically.
+///
+/// Whenever a window is focused, its container is split horizontally if it is
+/// wider than tall, and vertically otherwise.
+#[derive(Parser)]
+struct Args {
+    /// Only act on these workspaces (by name). Can be given multiple times.
+    /// Defaults to all workspaces.
+    #[arg(short, long)]
+    workspace: Vec<String>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut autotiling = Autotiling::new(args.workspace);
+    EventLoop::connect()?.run(&mut autotiling)
+}
@@ -0,0 +1,389 @@
+//! Utility to send windows back to that workspace when they lose focus.
+//!
+//! This program listens for events from i3 and sends windows that lose focus
+//! back to the scratchpad, if their `class`, `instance`, `window_role`, `title`
+//! and/or `app_id` attributes match the ones provided as arguments, either
+//! verbatim or through regular expressions. It works with both i3 and sway.
+//!
+//! # Use case
+//!
+//! This is useful, for example, when using the scratchpad for a terminal
+//! application. We can configure i3 to open a terminal window in the scratchpad
+//! workspace and use a key binding to toggle its visibility, bringing it to the
+//! current workspace in full screen mode and then back again to the scratchpad.
+//!
+//! However, when the terminal is being displayed, any event that shifts focus
+//! to another window can occur, like when we switch to another window or when a
+//! new window happens to pop up. The terminal window will then remain visible
+//! in the current workspace while also losing its full screen status, which is
+//! not what we want. This program solves this problem by sending the terminal
+//! back to the scratchpad when it loses focus.
+
+use clap::{ArgGroup, Parser, Subcommand};
+use i3_helpers::{
+    cli,
+    config::invalid_data,
+    control,
+    criteria::{exact, glob, Criteria},
+    expression::Expression,
+    geometry::Geometry,
+    helpers::back_to_scratch::{self, FocusMonitor, Multi, Rule, WINDOWS_HIDDEN},
+    idle::IdleMonitor,
+    instance::InstanceLock,
+    logging::{self, Verbosity},
+    metrics, record,
+    status::StatusReporter,
+    EventLoop,
+};
+use log::LevelFilter;
+use regex::Regex;
+use std::{io, path::PathBuf, time::Duration};
+
+/// Send windows back to the scratchpad when they lose focus.
+///
+/// This program listens for events from i3 and sends windows that lose focus
+/// back to the scratchpad, if their `class`, `instance`, `title` and/or
+/// `app_id` attributes match the ones provided as arguments. When several
+/// criteria are given, windows must match all of them.
+///
+/// Alternatively, several rules with their own criteria and behavior can be
+/// loaded from a TOML configuration file with `--config`.
+#[derive(Parser)]
+#[command(
+    group(ArgGroup::new("criteria").required(true).multiple(true)),
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The X11 class of the windows to send back to the scratchpad. Can be
+    /// given multiple times to manage several scratchpad windows at once.
+    #[arg(short, long, group = "criteria", conflicts_with = "class_regex")]
+    class: Vec<String>,
+
+    /// A regular expression matching the X11 class of the windows to send back
+    /// to the scratchpad.
+    #[arg(long, group = "criteria", value_parser = Regex::new)]
+    class_regex: Option<Regex>,
+
+    /// A shell-style glob matching the X11 class of the windows to send back
+    /// to the scratchpad, e.g. `scratch-*`.
+    #[arg(
+        long,
+        group = "criteria",
+        conflicts_with_all = ["class", "class_regex"],
+        value_parser = |pattern: &str| glob(pattern, false)
+    )]
+    class_glob: Option<Regex>,
+
+    /// The X11 instance of the windows to send back to the scratchpad.
+    #[arg(short, long, group = "criteria")]
+    instance: Option<String>,
+
+    /// The X11 window role (`WM_WINDOW_ROLE`) of the windows to send back to
+    /// the scratchpad, e.g. to tell a browser's popups from its main windows
+    /// along with `--class`.
+    #[arg(long, group = "criteria")]
+    role: Option<String>,
+
+    /// A regular expression matching the title of the windows to send back to
+    /// the scratchpad.
+    #[arg(long, group = "criteria", value_parser = Regex::new)]
+    title_regex: Option<Regex>,
+
+    /// The Wayland app_id of the windows to send back to the scratchpad (sway
+    /// only).
+    #[arg(long, group = "criteria")]
+    app_id: Option<String>,
+
+    /// A mark set on the windows to send back to the scratchpad, e.g. by
+    /// `for_window` rules in the i3 configuration.
+    #[arg(long, group = "criteria")]
+    mark: Option<String>,
+
+    /// A match expression combining criteria with `&&`, `||`, `!` and
+    /// parentheses, comparing attributes verbatim with `=` or through regular
+    /// expressions with `~`, e.g. `class=Alacritty && title~scratch`.
+    #[arg(long = "match", value_name = "EXPRESSION", group = "criteria")]
+    expression: Option<Expression>,
+
+    /// Match the class, instance, title and other attributes regardless of
+    /// case, e.g. so that `--class firefox` also matches `Firefox`.
+    #[arg(long, conflicts_with = "config")]
+    ignore_case: bool,
+
+    /// Re-enable fullscreen mode on windows that were fullscreen when sent back
+    /// to the scratchpad, once they are shown again.
+    #[arg(long, conflicts_with = "config")]
+    restore_fullscreen: bool,
+
+    /// Send windows back to the workspace they were first seen on, rather than
+    /// to the scratchpad. Useful for windows temporarily summoned to the
+    /// current workspace, like a music player.
+    #[arg(long, conflicts_with = "config")]
+    return_to_origin: bool,
+
+    /// Center windows shown on a different output than the one they were sent
+    /// back to the scratchpad from, so they don't keep an off-screen position.
+    #[arg(long, conflicts_with = "config")]
+    recenter: bool,
+
+    /// Resize windows and center them whenever they are shown, e.g. `80%x60%`
+    /// of the output they are shown on, or `1200x800` pixels.
+    #[arg(long, conflicts_with = "config")]
+    geometry: Option<Geometry>,
+
+    /// Only send windows back to the scratchpad when focus moves to another
+    /// window on the same workspace, leaving them be when switching to a
+    /// different workspace.
+    #[arg(long, conflicts_with = "config")]
+    same_workspace_only: bool,
+
+    /// Send windows back to the scratchpad as soon as another workspace is
+    /// focused, even if no window on it takes focus, e.g. when focusing a
+    /// workspace on another output.
+    #[arg(long, conflicts_with_all = ["config", "same_workspace_only"])]
+    hide_on_workspace_switch: bool,
+
+    /// Only send windows back to the scratchpad when focus moves to a
+    /// fullscreen window, which they would otherwise float on top of.
+    #[arg(long, conflicts_with_all = ["config", "hide_on_workspace_switch"])]
+    only_on_fullscreen: bool,
+
+    /// Keep windows visible while they are urgent, e.g. after a long command
+    /// finished in a terminal, and only send them back to the scratchpad once
+    /// they no longer are.
+    #[arg(long, conflicts_with = "config")]
+    keep_while_urgent: bool,
+
+    /// Milliseconds to wait after a window loses focus before sending it back
+    /// to the scratchpad. The window stays visible if it regains focus
+    /// meanwhile, e.g. after a transient dialog is closed.
+    #[arg(long, default_value_t = 0, conflicts_with = "config")]
+    delay_ms: u64,
+
+    /// The X11 class of windows that can take focus without the scratchpad
+    /// window being sent back, e.g. file pickers spawned by it. Can be given
+    /// multiple times.
+    #[arg(long, conflicts_with = "config")]
+    except_class: Vec<String>,
+
+    /// A regular expression matching the title of windows that can take focus
+    /// without the scratchpad window being sent back. Can be given multiple
+    /// times.
+    #[arg(long, conflicts_with = "config", value_parser = Regex::new)]
+    except_title: Vec<Regex>,
+
+    /// Which windows to manage when several match the criteria: the last
+    /// focused one, the newest or oldest one only, all of them, hidden
+    /// together once focus moves to another window, or the last focused one
+    /// on each workspace.
+    #[arg(long, value_enum, default_value_t = Multi::LastFocused, conflicts_with = "config")]
+    multi: Multi,
+
+    /// Load the rules from a TOML configuration file instead of the command
+    /// line arguments. The file is reloaded when receiving SIGHUP.
+    #[arg(
+        long,
+        group = "criteria",
+        conflicts_with_all = ["class", "class_regex", "class_glob", "instance", "role", "title_regex", "app_id", "mark", "expression"]
+    )]
+    config: Option<PathBuf>,
+
+    /// Listen for commands like `status`, `pause`, `resume` or `hide-now` on a
+    /// Unix socket, `$XDG_RUNTIME_DIR/i3-back-to-scratch.sock` by default. See
+    /// `i3-helpers-ctl`.
+    #[arg(long, value_name = "PATH")]
+    control_socket: Option<Option<PathBuf>>,
+
+    /// The command of the key binding pausing or resuming hiding windows, e.g.
+    /// `bindsym $mod+F12 nop back-to-scratch-toggle`. Useful to keep a window
+    /// visible alongside others for a while.
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        default_value = "nop back-to-scratch-toggle"
+    )]
+    toggle_binding: String,
+
+    /// Pause while the session is idle, after this many seconds without input
+    /// under i3 when built with the `x11` feature, or when told by
+    /// `session:idle` and `session:active` ticks from an idle daemon.
+    #[arg(long, value_name = "SECONDS")]
+    pause_when_idle: Option<u64>,
+
+    /// When terminated by SIGTERM or SIGINT, bring the windows we sent to the
+    /// scratchpad back to the focused workspace, tiled, rather than leaving
+    /// them hidden without anything to show them again.
+    #[arg(long)]
+    unhide_on_exit: bool,
+
+    /// On startup, send the windows matching a rule on the focused workspace
+    /// back to the scratchpad right away, unless focused, instead of waiting
+    /// for focus to move.
+    #[arg(long)]
+    hide_on_start: bool,
+
+    /// Log which windows would be sent back to the scratchpad and why, without
+    /// actually doing it.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Take over from an instance already running with the same rules, which
+    /// is asked to exit, instead of refusing to start.
+    #[arg(long)]
+    replace: bool,
+
+    /// Record the events received, and the replies to our queries, to a file
+    /// that can be replayed with `--replay`, e.g. to attach to a bug report.
+    #[arg(long, value_name = "PATH")]
+    record: Option<PathBuf>,
+
+    /// Replay the events recorded with `--record`, printing the commands that
+    /// would be run, each after the line of the recording it responds to,
+    /// without connecting to i3.
+    #[arg(long, value_name = "PATH", conflicts_with = "record")]
+    replay: Option<PathBuf>,
+
+    /// Print the windows being tracked to stdout whenever they change, as JSON
+    /// for an i3blocks block or a waybar module to show.
+    #[arg(long, conflicts_with = "replay")]
+    status: bool,
+
+    /// Notify systemd when ready and ping its watchdog, when run as a
+    /// `Type=notify` service.
+    #[cfg(feature = "systemd")]
+    #[arg(long)]
+    systemd: bool,
+
+    /// Publish Prometheus metrics over HTTP on this address, e.g.
+    /// `127.0.0.1:9188`.
+    #[cfg(feature = "metrics")]
+    #[arg(long, value_name = "ADDR")]
+    metrics_addr: Option<std::net::SocketAddr>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the state of the running daemon, listening with
+    /// `--control-socket`: the windows it tracks, those it sent to the
+    /// scratchpad and how often each rule matched and hid windows.
+    Status {
+        /// The socket the daemon listens on, instead of the default one.
+        #[arg(long, value_name = "PATH")]
+        control_socket: Option<PathBuf>,
+    },
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    // Decisions are logged at the info level, which is what users running a
+    // dry run are after.
+    let mut level = args.verbosity.level();
+    if args.dry_run {
+        level = level.max(LevelFilter::Info);
+    }
+    logging::init(level);
+    if let Some(Command::Status { control_socket }) = args.command {
+        let path = control_socket
+            .or_else(|| control::default_socket_path("i3-back-to-scratch"))
+            .ok_or_else(|| invalid_data("XDG_RUNTIME_DIR is not set, use --control-socket"))?;
+        print!("{}", control::send(&path, "status")?);
+        return Ok(());
+    }
+    let rules = match &args.config {
+        Some(path) => back_to_scratch::config::load(path)?,
+        None => rules_from_args(&args),
+    };
+    let key: Vec<_> = rules.iter().map(|rule| rule.criteria.to_string()).collect();
+    let mut focus_monitor = FocusMonitor::new(rules, args.config.clone());
+    focus_monitor.set_toggle_binding(args.toggle_binding);
+    focus_monitor.set_unhide_on_exit(args.unhide_on_exit);
+    focus_monitor.set_hide_on_start(args.hide_on_start);
+    if let Some(timeout) = args.pause_when_idle {
+        focus_monitor.set_pause_when_idle(IdleMonitor::new(Duration::from_secs(timeout)));
+    }
+    if let Some(path) = &args.replay {
+        return record::replay(path, &mut focus_monitor);
+    }
+    // Dry runs send no commands, so they can watch alongside a real instance.
+    let _lock = match args.dry_run {
+        true => None,
+        false => InstanceLock::acquire("i3-back-to-scratch", &key.join("\n"), args.replace)?,
+    };
+    let mut event_loop = EventLoop::connect()?;
+    event_loop.set_dry_run(args.dry_run);
+    event_loop.set_reload_on_sighup(args.config.is_some());
+    event_loop.set_record(args.record.as_deref())?;
+    event_loop.set_control_socket(match args.control_socket {
+        Some(Some(path)) => Some(path),
+        Some(None) => Some(
+            control::default_socket_path("i3-back-to-scratch").ok_or_else(|| {
+                invalid_data("XDG_RUNTIME_DIR is not set, use --control-socket PATH")
+            })?,
+        ),
+        None => None,
+    });
+    #[cfg(feature = "systemd")]
+    if args.systemd {
+        event_loop.enable_systemd();
+    }
+    #[cfg(feature = "metrics")]
+    event_loop.set_metrics_addr(args.metrics_addr);
+    metrics::register(&WINDOWS_HIDDEN);
+    match args.status {
+        true => event_loop.run(&mut StatusReporter::new(focus_monitor)),
+        false => event_loop.run(&mut focus_monitor),
+    }
+}
+
+fn rules_from_args(args: &Args) -> Vec<Rule> {
+    let case = |criteria: Criteria| match args.ignore_case {
+        true => criteria.ignoring_case(),
+        false => criteria,
+    };
+    let except_classes = args.except_class.iter().map(|class| Criteria {
+        class: Some(exact(class)),
+        ..Criteria::default()
+    });
+    let except_titles = args.except_title.iter().map(|title| Criteria {
+        title: Some(title.clone()),
+        ..Criteria::default()
+    });
+    let except: Vec<Criteria> = except_classes.chain(except_titles).map(case).collect();
+    let classes = if args.class.is_empty() {
+        vec![args.class_regex.clone().or(args.class_glob.clone())]
+    } else {
+        args.class.iter().map(|class| Some(exact(class))).collect()
+    };
+    classes
+        .into_iter()
+        .map(|class| Rule {
+            criteria: case(Criteria {
+                class,
+                instance: args.instance.as_deref().map(exact),
+                role: args.role.as_deref().map(exact),
+                title: args.title_regex.clone(),
+                app_id: args.app_id.as_deref().map(exact),
+                mark: args.mark.as_deref().map(exact),
+                expression: args.expression.clone(),
+            }),
+            restore_fullscreen: args.restore_fullscreen,
+            return_to_origin: args.return_to_origin,
+            recenter: args.recenter,
+            geometry: args.geometry,
+            same_workspace_only: args.same_workspace_only,
+            hide_on_workspace_switch: args.hide_on_workspace_switch,
+            only_on_fullscreen: args.only_on_fullscreen,
+            keep_while_urgent: args.keep_while_urgent,
+            delay: Duration::from_millis(args.delay_ms),
+            except: except.clone(),
+            multi: args.multi,
+        })
+        .collect()
+}
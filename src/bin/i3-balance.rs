@@ -0,0 +1,80 @@
+//! Utility to give all the containers of a workspace equal sizes.
+//!
+//! Like vim's `Ctrl-W =`, this program walks the tiled containers of the
+//! focused workspace and resizes the children of every split container so that
+//! they all get the same share of it. Tabbed and stacked containers are left
+//! alone, as their children all take the whole space anyway.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder, Orientation},
+    logging::{self, Verbosity},
+    tree, Connection, I3Commands,
+};
+use i3_ipc::reply::{Node, NodeLayout, NodeType};
+use std::io;
+
+/// Give all the containers of the focused workspace equal sizes.
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+/// Differences in shares smaller than this are not worth a resize.
+const TOLERANCE: f64 = 0.01;
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let tree = i3.get_tree()?;
+    let workspace = tree::focused(&tree)
+        .and_then(|focused| match focused.node_type {
+            NodeType::Workspace => Some(focused),
+            _ => tree::workspace_of(&tree, focused.id),
+        })
+        .ok_or_else(|| io::Error::other("no focused workspace"))?;
+    let mut commands = Vec::new();
+    balance(workspace, &mut commands);
+    if commands.is_empty() {
+        return Ok(());
+    }
+    i3.run_commands(&commands)
+}
+
+/// Add the commands balancing the tiled descendants of `node` to `commands`.
+fn balance(node: &Node, commands: &mut Vec<String>) {
+    let dimension = match node.layout {
+        NodeLayout::SplitH => Some(Orientation::Horizontal),
+        NodeLayout::SplitV => Some(Orientation::Vertical),
+        _ => None,
+    };
+    let count = node.nodes.len();
+    if let (Some(dimension), true) = (dimension, count > 1) {
+        let share = 1.0 / count as f64;
+        let mut shares: Vec<f64> = node
+            .nodes
+            .iter()
+            .map(|child| child.percent.unwrap_or(share))
+            .collect();
+        // Resizing a container takes the difference from the next one, so
+        // going from first to last leaves the last with the remaining share.
+        for index in 0..count - 1 {
+            let difference = shares[index] - share;
+            if difference.abs() <= TOLERANCE {
+                continue;
+            }
+            shares[index] = share;
+            shares[index + 1] += difference;
+            let percent = (share * 100.0).round() as u32;
+            let command = CommandBuilder::con_id(node.nodes[index].id)
+                .action(Action::ResizeShare(dimension, percent));
+            commands.push(command.build());
+        }
+    }
+    for child in &node.nodes {
+        balance(child, commands);
+    }
+}
@@ -0,0 +1,127 @@
+//! Utility to bind Emacs-style key sequences, like `C-x C-f`.
+//!
+//! The chords are defined in a configuration file, each by its sequence of
+//! keys and the i3 command it runs:
+//!
+//! ```toml
+//! [chords]
+//! "C-x C-f" = "exec firefox"
+//! "C-x b" = "exec i3-window-menu"
+//! "C-x 4 t" = "exec alacritty"
+//! ```
+//!
+//! `i3-chords generate` writes the nested binding modes implementing them,
+//! for the i3 configuration to include, and `i3-chords daemon` leaves these
+//! modes when no key of the chord is pressed within `--timeout`, so that a
+//! chord left unfinished doesn't keep hijacking the keyboard.
+//!
+//! i3 reads included files before running any `exec`, so the file must be
+//! generated before i3 loads its configuration: once by hand, then whenever
+//! the chords change, right before reloading:
+//!
+//! ```text
+//! include ~/.config/i3/chords
+//! exec --no-startup-id i3-chords daemon --timeout 2s
+//! bindsym $mod+Shift+c exec i3-chords generate --output ~/.config/i3/chords && i3-msg reload
+//! ```
+
+use clap::{Parser, Subcommand};
+use i3_helpers::{
+    chords::Chords,
+    cli,
+    config::{self, invalid_data},
+    helpers::mode_timeout::ModeTimeout,
+    i3_config,
+    logging::{self, Verbosity},
+    Connection, EventLoop, I3Commands,
+};
+use log::warn;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, io, path::PathBuf, time::Duration};
+
+const CONFIG_FILE_NAME: &str = "chords.toml";
+
+/// Bind Emacs-style key sequences, like `C-x C-f`.
+#[derive(Parser)]
+struct Args {
+    /// The configuration file defining the chords. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/chords.toml`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    action: Action,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Subcommand)]
+enum Action {
+    /// Write the i3 configuration binding the chords.
+    Generate {
+        /// The file to write, instead of the standard output.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Leave the modes of unfinished chords once no key is pressed for a
+    /// while.
+    Daemon {
+        /// How long to wait for the next key of a chord, e.g. `2s`.
+        #[arg(long, default_value = "2s", value_parser = cli::parse_duration)]
+        timeout: Duration,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    chords: BTreeMap<String, String>,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let path = args
+        .config
+        .or_else(|| config::default_path(CONFIG_FILE_NAME))
+        .ok_or_else(|| invalid_data("cannot find the configuration file, use --config"))?;
+    let config: Config = config::load(&path)?;
+    let chords = Chords::new(config.chords).map_err(invalid_data)?;
+    match args.action {
+        Action::Generate { output: None } => {
+            print!("{}", chords.to_config());
+            Ok(())
+        }
+        Action::Generate {
+            output: Some(output),
+        } => fs::write(output, chords.to_config()),
+        Action::Daemon { timeout } => {
+            let modes: Vec<String> = chords.modes().into_iter().collect();
+            // No modes would mean leaving all of them, not only ours.
+            if modes.is_empty() {
+                warn!("no chord has more than one key, there are no modes to leave");
+                return Ok(());
+            }
+            warn_about_missing_modes(&mut Connection::connect()?, &modes)?;
+            // Each key of a chord enters a new mode, restarting the timer.
+            let mut mode_timeout = ModeTimeout::new(timeout, modes);
+            EventLoop::connect()?.run(&mut mode_timeout)
+        }
+    }
+}
+
+fn warn_about_missing_modes(i3: &mut dyn I3Commands, modes: &[String]) -> io::Result<()> {
+    let config = i3_config::load(i3)?;
+    let missing: Vec<&String> = modes
+        .iter()
+        .filter(|mode| config.mode(mode).is_none())
+        .collect();
+    if !missing.is_empty() {
+        warn!(
+            "modes {missing:?} are missing from the i3 configuration, \
+             include the output of `i3-chords generate` and reload"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Utility to cycle focus through outputs in their geometric order.
+//!
+//! i3 orders outputs as they were reported by RandR, which rarely matches how
+//! monitors sit on the desk. This program sorts the active outputs by position,
+//! left to right or top to bottom, and focuses the one following the focused
+//! output, wrapping around at the end. With `--move`, the focused container is
+//! moved there instead.
+
+use clap::{Parser, ValueEnum};
+use i3_helpers::{
+    cli,
+    command::quote,
+    logging::{self, Verbosity},
+    Connection, I3Commands,
+};
+use i3_ipc::reply::Output;
+use std::io;
+
+/// Cycle focus through outputs in their geometric order.
+#[derive(Parser)]
+struct Args {
+    /// How to sort the outputs.
+    #[arg(long, value_enum, default_value_t = Order::LeftToRight)]
+    order: Order,
+
+    /// Go to the previous output instead of the next one.
+    #[arg(long)]
+    reverse: bool,
+
+    /// Move the focused container to the output instead of focusing it.
+    #[arg(long)]
+    r#move: bool,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Order {
+    /// By horizontal position, then vertical.
+    LeftToRight,
+    /// By vertical position, then horizontal.
+    TopToBottom,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let focused = i3
+        .get_workspaces()?
+        .into_iter()
+        .find(|workspace| workspace.focused)
+        .ok_or_else(|| io::Error::other("no focused workspace"))?;
+    let mut outputs: Vec<Output> = i3
+        .get_outputs()?
+        .into_iter()
+        .filter(|output| output.active)
+        .collect();
+    match args.order {
+        Order::LeftToRight => outputs.sort_by_key(|output| (output.rect.x, output.rect.y)),
+        Order::TopToBottom => outputs.sort_by_key(|output| (output.rect.y, output.rect.x)),
+    }
+    let Some(current) = outputs
+        .iter()
+        .position(|output| output.name == focused.output)
+    else {
+        return Err(io::Error::other(format!(
+            "the focused output {:?} is not active",
+            focused.output
+        )));
+    };
+    let target = match args.reverse {
+        true => (current + outputs.len() - 1) % outputs.len(),
+        false => (current + 1) % outputs.len(),
+    };
+    if target == current {
+        return Ok(());
+    }
+    let output = quote(&outputs[target].name);
+    if args.r#move {
+        i3.run_command(&format!("move container to output {output}"))
+    } else {
+        i3.run_command(&format!("focus output {output}"))
+    }
+}
@@ -0,0 +1,127 @@
+//! Utility to go through the scratchpad windows in a predictable order.
+//!
+//! With several windows in the scratchpad, i3's `scratchpad show` picks the
+//! one to show in an order that is hard to follow. This program lists the
+//! scratchpad windows from the layout tree, in the order they were created,
+//! and shows a specific one, by class, mark or position in that list, or the
+//! one after or before the scratchpad window currently shown, hiding it.
+
+use clap::{ArgGroup, Args as ClapArgs, Parser, Subcommand};
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder, CommandQueue},
+    logging::{self, Verbosity},
+    tree::{self, WindowEntry},
+    Connection, I3Commands,
+};
+use i3_ipc::reply::Node;
+use std::io;
+
+/// Go through the scratchpad windows in a predictable order.
+#[derive(Parser)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the scratchpad windows, flagging the ones shown with `*`.
+    List,
+    /// Show a specific scratchpad window.
+    Show(Selection),
+    /// Hide the scratchpad window shown, and show the next one.
+    Next,
+    /// Hide the scratchpad window shown, and show the previous one.
+    Prev,
+}
+
+#[derive(ClapArgs)]
+#[command(group(ArgGroup::new("selection").required(true)))]
+struct Selection {
+    /// The X11 class, or Wayland app_id, of the window.
+    #[arg(long, group = "selection")]
+    class: Option<String>,
+
+    /// A mark of the window.
+    #[arg(long, group = "selection")]
+    mark: Option<String>,
+
+    /// The position of the window in the list, from 0.
+    #[arg(long, group = "selection")]
+    index: Option<usize>,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let tree = i3.get_tree()?;
+    let windows = scratchpad_windows(&tree);
+    let shown = windows
+        .iter()
+        .position(|entry| entry.window.focused)
+        .or_else(|| windows.iter().position(|entry| is_shown(entry)));
+    let target = match &args.command {
+        Command::List => {
+            for (index, entry) in windows.iter().enumerate() {
+                let flag = if is_shown(entry) { '*' } else { ' ' };
+                println!("{index} {flag} {} {:?}", entry.class(), entry.title());
+            }
+            return Ok(());
+        }
+        Command::Show(selection) => windows
+            .iter()
+            .enumerate()
+            .position(|(index, entry)| selection.matches(index, entry))
+            .ok_or_else(|| io::Error::other("no such scratchpad window"))?,
+        Command::Next | Command::Prev if windows.is_empty() => return Ok(()),
+        Command::Next => shown.map_or(0, |index| (index + 1) % windows.len()),
+        Command::Prev => shown.map_or(windows.len() - 1, |index| {
+            (index + windows.len() - 1) % windows.len()
+        }),
+    };
+    let mut commands = CommandQueue::new();
+    if let Some(shown) = shown.filter(|&index| index != target) {
+        let window = windows[shown].window;
+        commands.push(CommandBuilder::con_id(window.id).action(Action::MoveScratchpad));
+    }
+    let window = windows[target].window;
+    let action = match is_shown(&windows[target]) {
+        true => Action::Focus,
+        false => Action::ScratchpadShow,
+    };
+    commands.push(CommandBuilder::con_id(window.id).action(action));
+    commands.flush(&mut i3)
+}
+
+impl Selection {
+    fn matches(&self, index: usize, entry: &WindowEntry) -> bool {
+        if let Some(class) = &self.class {
+            return entry.class() == class;
+        }
+        if let Some(mark) = &self.mark {
+            return entry.marks().any(|m| m == mark);
+        }
+        self.index == Some(index)
+    }
+}
+
+/// The windows moved to the scratchpad, hidden or not, in the order they were
+/// created.
+fn scratchpad_windows(tree: &Node) -> Vec<WindowEntry<'_>> {
+    let mut windows: Vec<WindowEntry> = tree::list_windows(tree)
+        .into_iter()
+        .filter(|entry| tree::is_scratchpad_window(tree, entry.window))
+        .collect();
+    // Container ids are allocated in increasing order.
+    windows.sort_by_key(|entry| entry.window.id);
+    windows
+}
+
+fn is_shown(entry: &WindowEntry) -> bool {
+    entry.workspace.name.as_deref() != Some(tree::SCRATCHPAD_WORKSPACE)
+}
@@ -0,0 +1,247 @@
+//! Utility to dim the outputs that don't have the focus.
+//!
+//! This program listens for events from i3 and, once focus moved to another
+//! output and stayed there for `--delay-ms`, dims the other outputs to
+//! `--level`, so that the focused one stands out on multi-monitor setups. The
+//! focused output is brought back to full brightness right away:
+//!
+//! ```text
+//! exec --no-startup-id i3-dim-inactive-outputs --level 0.6 --delay-ms 500
+//! ```
+//!
+//! Under i3, outputs are dimmed through their gamma with `xrandr
+//! --brightness`. Sway has no such command, so outputs can only be blanked,
+//! with `--level 0` turning them off, unless `--command` is given to dim them
+//! by other means, e.g. through DDC/CI. All outputs are brought back to full
+//! brightness on exit.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::quote,
+    logging::{self, Verbosity},
+    EventLoop, Helper, I3Commands, WindowManager,
+};
+use i3_ipc::event::{Event, Subscribe, WorkspaceChange, WorkspaceData};
+use log::{debug, info, warn};
+use std::{
+    collections::BTreeSet,
+    io,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+/// Dim the outputs that don't have the focus.
+#[derive(Parser)]
+struct Args {
+    /// The brightness of unfocused outputs, from 0, blanked, to 1.
+    #[arg(long, default_value_t = 0.5, value_parser = parse_level)]
+    level: f64,
+
+    /// Milliseconds focus must stay on an output before the others are
+    /// dimmed, so that crossing outputs briefly doesn't flicker.
+    #[arg(long, default_value_t = 300)]
+    delay_ms: u64,
+
+    /// The command setting the brightness of an output, run through `sh -c`
+    /// after replacing `{output}` with its name and `{level}` with the
+    /// brightness, from 0 to 1, e.g. `my-ddc-dim {output} {level}`. Defaults to
+    /// `xrandr` under i3.
+    #[arg(long)]
+    command: Option<String>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn parse_level(arg: &str) -> Result<f64, String> {
+    match arg.parse() {
+        Ok(level) if (0.0..=1.0).contains(&level) => Ok(level),
+        _ => Err("expected a number between 0 and 1".to_owned()),
+    }
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut dim = DimInactiveOutputs {
+        level: args.level,
+        delay: Duration::from_millis(args.delay_ms),
+        command: args.command,
+        dimmer: None,
+        focused: None,
+        dimmed: BTreeSet::new(),
+        pending: None,
+    };
+    EventLoop::connect()?.run(&mut dim)
+}
+
+/// A way of setting the brightness of an output.
+trait Dimmer {
+    fn set_level(&mut self, i3: &mut dyn I3Commands, output: &str, level: f64) -> io::Result<()>;
+}
+
+pub struct DimInactiveOutputs {
+    level: f64,
+    delay: Duration,
+    command: Option<String>,
+    /// Picked once connected, as it depends on the window manager.
+    dimmer: Option<Box<dyn Dimmer>>,
+    /// The output holding the focused workspace.
+    focused: Option<String>,
+    dimmed: BTreeSet<String>,
+    /// When to dim the outputs other than the focused one.
+    pending: Option<Instant>,
+}
+
+impl Helper for DimInactiveOutputs {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Workspace, Subscribe::Output]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let dimmer: Box<dyn Dimmer> = match (&self.command, i3.window_manager()?) {
+            (Some(command), _) => Box::new(Shell {
+                command: command.clone(),
+            }),
+            (None, WindowManager::I3) => Box::new(Xrandr),
+            (None, WindowManager::Sway) if self.level == 0.0 => Box::new(SwayPower),
+            (None, WindowManager::Sway) => {
+                return Err(io::Error::other(
+                    "sway can only blank outputs, use --level 0 or --command",
+                ))
+            }
+        };
+        self.dimmer = Some(dimmer);
+        self.update(i3)
+    }
+
+    fn on_workspace(&mut self, i3: &mut dyn I3Commands, event: &WorkspaceData) -> io::Result<()> {
+        match event.change {
+            WorkspaceChange::Focus => self.update(i3),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_event(&mut self, i3: &mut dyn I3Commands, event: &Event) -> io::Result<()> {
+        match event {
+            Event::Output(_) => {
+                // Outputs that went away can't be brightened anymore, and new
+                // ones are yet to be dimmed.
+                let outputs = active_outputs(i3)?;
+                self.dimmed.retain(|output| outputs.contains(output));
+                self.update(i3)?;
+                self.pending.get_or_insert(Instant::now() + self.delay);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.pending
+    }
+
+    fn on_deadline(&mut self, i3: &mut dyn I3Commands, _now: Instant) -> io::Result<()> {
+        self.pending = None;
+        for output in active_outputs(i3)? {
+            if Some(&output) != self.focused.as_ref() && !self.dimmed.contains(&output) {
+                debug!("dimming {output}");
+                self.set_level(i3, &output, self.level)?;
+                self.dimmed.insert(output);
+            }
+        }
+        Ok(())
+    }
+
+    fn on_exit(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        for output in std::mem::take(&mut self.dimmed) {
+            self.set_level(i3, &output, 1.0)?;
+        }
+        Ok(())
+    }
+}
+
+impl DimInactiveOutputs {
+    /// Brighten the output holding the focused workspace if it moved to
+    /// another one, and dim the others once the delay expires.
+    fn update(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let focused = i3
+            .get_workspaces()?
+            .into_iter()
+            .find(|workspace| workspace.focused)
+            .map(|workspace| workspace.output);
+        if focused == self.focused {
+            return Ok(());
+        }
+        if let Some(output) = &focused {
+            if self.dimmed.remove(output) {
+                info!("focus moved to {output}");
+                self.set_level(i3, output, 1.0)?;
+            }
+        }
+        self.focused = focused;
+        self.pending = Some(Instant::now() + self.delay);
+        Ok(())
+    }
+
+    fn set_level(&mut self, i3: &mut dyn I3Commands, output: &str, level: f64) -> io::Result<()> {
+        match &mut self.dimmer {
+            Some(dimmer) => dimmer.set_level(i3, output, level),
+            None => Ok(()),
+        }
+    }
+}
+
+fn active_outputs(i3: &mut dyn I3Commands) -> io::Result<Vec<String>> {
+    Ok(i3
+        .get_outputs()?
+        .into_iter()
+        .filter(|output| output.active)
+        .map(|output| output.name)
+        .collect())
+}
+
+/// Sets the gamma of outputs with `xrandr --brightness`.
+struct Xrandr;
+
+impl Dimmer for Xrandr {
+    fn set_level(&mut self, _i3: &mut dyn I3Commands, output: &str, level: f64) -> io::Result<()> {
+        let status = Command::new("xrandr")
+            .args(["--output", output, "--brightness", &level.to_string()])
+            .status()?;
+        if !status.success() {
+            warn!("xrandr failed to set the brightness of {output}");
+        }
+        Ok(())
+    }
+}
+
+/// Turns outputs off and back on, through sway's `output power` command.
+struct SwayPower;
+
+impl Dimmer for SwayPower {
+    fn set_level(&mut self, i3: &mut dyn I3Commands, output: &str, level: f64) -> io::Result<()> {
+        let power = if level > 0.0 { "on" } else { "off" };
+        i3.run_command(&format!("output {} power {power}", quote(output)))
+    }
+}
+
+/// Runs the command given with `--command`.
+struct Shell {
+    command: String,
+}
+
+impl Dimmer for Shell {
+    fn set_level(&mut self, _i3: &mut dyn I3Commands, output: &str, level: f64) -> io::Result<()> {
+        let command = self
+            .command
+            .replace("{output}", output)
+            .replace("{level}", &level.to_string());
+        let status = Command::new("sh").args(["-c", &command]).status()?;
+        if !status.success() {
+            warn!("{command:?} failed");
+        }
+        Ok(())
+    }
+}
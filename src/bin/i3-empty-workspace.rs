@@ -0,0 +1,41 @@
+//! Utility to go to the first unused numbered workspace.
+//!
+//! This program finds the lowest workspace number not used by any existing
+//! workspace and either switches to it or, with `--move`, moves the focused
+//! container there.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    logging::{self, Verbosity},
+    Connection, I3Commands,
+};
+use std::{collections::HashSet, io};
+
+/// Go to the first unused numbered workspace.
+#[derive(Parser)]
+struct Args {
+    /// Move the focused container to the workspace instead of switching to it.
+    #[arg(long)]
+    r#move: bool,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let used: HashSet<i32> = i3
+        .get_workspaces()?
+        .iter()
+        .map(|workspace| workspace.num)
+        .collect();
+    let number = (1..).find(|number| !used.contains(number)).unwrap_or(1);
+    if args.r#move {
+        i3.run_command(&format!("move container to workspace number {number}"))
+    } else {
+        i3.run_command(&format!("workspace number {number}"))
+    }
+}
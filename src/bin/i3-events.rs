@@ -0,0 +1,194 @@
+//! Utility to print i3 events as newline-delimited JSON, for scripts.
+//!
+//! Each event is printed on its own line as the JSON object i3 sent, with an
+//! added `type` field naming the kind of event, e.g.
+//!
+//! ```text
+//! {"type":"window","change":"focus","container":{...}}
+//! ```
+//!
+//! so that shell scripts can react to events with tools like `jq`, without
+//! writing their own IPC client.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    criteria::exact,
+    expression::Expression,
+    logging::{self, Verbosity},
+    tree, Criteria, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::event::{Event, Subscribe, WindowChange};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+/// Print i3 events as newline-delimited JSON.
+#[derive(Parser)]
+struct Args {
+    /// The types of events to print, separated by commas: window, workspace,
+    /// output, mode, barconfig_update, binding or tick.
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        default_value = "window,workspace",
+        value_parser = parse_subscription
+    )]
+    events: Vec<Subscribe>,
+
+    /// Only print window events about windows of this X11 class (or app_id
+    /// under sway).
+    #[arg(long)]
+    class: Option<String>,
+
+    /// Only print window events about windows matching this expression, e.g.
+    /// `class=firefox && title~YouTube`.
+    #[arg(long = "match", value_name = "EXPRESSION")]
+    expression: Option<Expression>,
+
+    /// Only print workspace events about this workspace, and window events
+    /// about windows on it.
+    #[arg(long)]
+    workspace: Option<String>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn parse_subscription(name: &str) -> Result<Subscribe, String> {
+    serde_json::from_value(Value::from(name)).map_err(|_| format!("unknown event type {name:?}"))
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut printer = EventPrinter {
+        subscriptions: args.events,
+        classes: args.class.map(|class| {
+            [
+                Criteria {
+                    class: Some(exact(&class)),
+                    ..Criteria::default()
+                },
+                Criteria {
+                    app_id: Some(exact(&class)),
+                    ..Criteria::default()
+                },
+            ]
+        }),
+        expression: args.expression,
+        workspace: args.workspace,
+        window_workspaces: HashMap::new(),
+    };
+    match EventLoop::connect()?.run(&mut printer) {
+        // The reader went away, e.g. `i3-events | head`.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+pub struct EventPrinter {
+    subscriptions: Vec<Subscribe>,
+    classes: Option<[Criteria; 2]>,
+    expression: Option<Expression>,
+    workspace: Option<String>,
+    /// The workspace each window was last seen on, for `--workspace` to still
+    /// match windows once closed and gone from the tree.
+    window_workspaces: HashMap<NodeId, String>,
+}
+
+type NodeId = usize;
+
+impl Helper for EventPrinter {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        self.subscriptions.clone()
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        if self.workspace.is_some() {
+            let tree = i3.get_tree()?;
+            for workspace in tree::workspaces(&tree) {
+                let Some(name) = &workspace.name else {
+                    continue;
+                };
+                for window in tree::windows(workspace) {
+                    self.window_workspaces.insert(window.id, name.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, i3: &mut dyn I3Commands, event: &Event) -> io::Result<()> {
+        if !self.wanted(i3, event)? {
+            return Ok(());
+        }
+        let (kind, data) = match event {
+            Event::Window(data) => ("window", serde_json::to_value(data)),
+            Event::Workspace(data) => ("workspace", serde_json::to_value(data)),
+            Event::Output(data) => ("output", serde_json::to_value(data)),
+            Event::Mode(data) => ("mode", serde_json::to_value(data)),
+            Event::BarConfig(data) => ("barconfig_update", serde_json::to_value(data)),
+            Event::Binding(data) => ("binding", serde_json::to_value(data)),
+            Event::Shutdown(data) => ("shutdown", serde_json::to_value(data)),
+            Event::Tick(data) => ("tick", serde_json::to_value(data)),
+        };
+        let mut data = data?;
+        if let Value::Object(object) = &mut data {
+            object.insert("type".to_owned(), Value::from(kind));
+        }
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout, "{data}")?;
+        stdout.flush()
+    }
+}
+
+impl EventPrinter {
+    /// Whether `event` passes the `--class`, `--match` and `--workspace`
+    /// filters.
+    fn wanted(&mut self, i3: &mut dyn I3Commands, event: &Event) -> io::Result<bool> {
+        match event {
+            Event::Window(data) => {
+                let container = &data.container;
+                let closed_on = match data.change {
+                    WindowChange::Close => self.window_workspaces.remove(&container.id),
+                    _ => None,
+                };
+                if let Some(classes) = &self.classes {
+                    if !classes.iter().any(|class| class.matches(container)) {
+                        return Ok(false);
+                    }
+                }
+                if let Some(expression) = &self.expression {
+                    if !expression.matches(container) {
+                        return Ok(false);
+                    }
+                }
+                let Some(workspace) = &self.workspace else {
+                    return Ok(true);
+                };
+                let name = match data.change {
+                    WindowChange::Close => closed_on,
+                    _ => {
+                        let tree = i3.get_tree()?;
+                        let name =
+                            tree::workspace_of(&tree, container.id).and_then(|ws| ws.name.clone());
+                        if let Some(name) = &name {
+                            self.window_workspaces.insert(container.id, name.clone());
+                        }
+                        name
+                    }
+                };
+                Ok(name.as_ref() == Some(workspace))
+            }
+            Event::Workspace(data) => Ok(self.workspace.as_ref().is_none_or(|workspace| {
+                let current = data.current.as_ref().and_then(|ws| ws.name.as_ref());
+                current == Some(workspace)
+            })),
+            _ => Ok(true),
+        }
+    }
+}
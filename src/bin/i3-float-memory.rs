@@ -0,0 +1,196 @@
+//! Utility to remember where floating windows were, and put them back there.
+//!
+//! This program listens for events from i3 and records the size and position
+//! of floating windows when they lose focus or are closed, keyed by their
+//! class and instance, and optionally part of their title. When a window with
+//! the same key appears again, or is made floating, it gets the same size and
+//! position back.
+//!
+//! i3 sends no event when a floating window is moved or resized with the
+//! mouse, hence the geometry being captured when focus moves away instead.
+//!
+//! The geometries are kept across restarts in
+//! `$XDG_DATA_HOME/i3-helpers/float-memory.json`.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    config::{self, invalid_data},
+    focus::FocusTracker,
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData},
+    reply::{Node, Rect},
+};
+use log::{debug, info};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::PathBuf,
+};
+
+/// Remember where floating windows were, and put them back there.
+#[derive(Parser)]
+struct Args {
+    /// Tell windows apart by the part of their title matching this regular
+    /// expression, on top of their class and instance, e.g. `^[^-]+` to keep
+    /// what comes before the first dash.
+    #[arg(long, value_parser = Regex::new)]
+    title_regex: Option<Regex>,
+
+    /// The file holding the geometries, instead of
+    /// `$XDG_DATA_HOME/i3-helpers/float-memory.json`.
+    #[arg(long)]
+    database: Option<PathBuf>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let database = args
+        .database
+        .or_else(|| config::data_path("float-memory.json"))
+        .ok_or_else(|| invalid_data("cannot find where to store geometries, use --database"))?;
+    let geometries = match fs::read_to_string(&database) {
+        Ok(contents) => serde_json::from_str(&contents).map_err(invalid_data)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+        Err(err) => return Err(err),
+    };
+    let mut memory = FloatMemory {
+        geometries,
+        database,
+        title_regex: args.title_regex,
+        focus: FocusTracker::new(),
+        restored: HashSet::new(),
+    };
+    EventLoop::connect()?.run(&mut memory)
+}
+
+type NodeId = usize;
+
+/// The size and position of a floating window, in pixels.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Geometry {
+    x: isize,
+    y: isize,
+    width: isize,
+    height: isize,
+}
+
+impl From<&Rect> for Geometry {
+    fn from(rect: &Rect) -> Self {
+        Self {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+        }
+    }
+}
+
+pub struct FloatMemory {
+    /// Geometries by window key.
+    geometries: HashMap<String, Geometry>,
+    database: PathBuf,
+    title_regex: Option<Regex>,
+    focus: FocusTracker,
+    /// Windows already put back where they were, so that they are left alone
+    /// when they are made floating again.
+    restored: HashSet<NodeId>,
+}
+
+impl Helper for FloatMemory {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window]
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        let window = &event.container;
+        if let Some(change) = self.focus.update(event) {
+            if let Some(previous) = change.previous {
+                let tree = i3.get_tree()?;
+                if let Some(previous) = tree::find(&tree, previous) {
+                    self.remember(previous)?;
+                }
+            }
+        }
+        match event.change {
+            WindowChange::New | WindowChange::Floating if tree::is_floating(window) => {
+                self.restore(i3, window)
+            }
+            WindowChange::Close => {
+                self.restored.remove(&window.id);
+                self.remember(window)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FloatMemory {
+    /// The key telling `window` apart from others, if it has X11 properties.
+    fn key(&self, window: &Node) -> Option<String> {
+        let props = window.window_properties.as_ref()?;
+        let mut key = format!(
+            "{}:{}",
+            props.class.as_deref().unwrap_or_default(),
+            props.instance.as_deref().unwrap_or_default()
+        );
+        if let Some(regex) = &self.title_regex {
+            let title = window.name.as_deref().unwrap_or_default();
+            if let Some(found) = regex.find(title) {
+                key += ":";
+                key += found.as_str();
+            }
+        }
+        Some(key)
+    }
+
+    /// Record the geometry of `window`, if floating, saving it to disk if it
+    /// changed.
+    fn remember(&mut self, window: &Node) -> io::Result<()> {
+        if !tree::is_floating(window) {
+            return Ok(());
+        }
+        let Some(key) = self.key(window) else {
+            return Ok(());
+        };
+        let geometry = Geometry::from(&window.rect);
+        if self.geometries.get(&key) == Some(&geometry) {
+            return Ok(());
+        }
+        debug!("remembering the geometry of {key:?}");
+        self.geometries.insert(key, geometry);
+        self.save()
+    }
+
+    fn restore(&mut self, i3: &mut dyn I3Commands, window: &Node) -> io::Result<()> {
+        if !self.restored.insert(window.id) {
+            return Ok(());
+        }
+        let Some(geometry) = self.key(window).and_then(|key| self.geometries.get(&key)) else {
+            return Ok(());
+        };
+        info!("putting {} back where it was", tree::describe(window));
+        let command = CommandBuilder::con_id(window.id)
+            .action(Action::Resize(geometry.width, geometry.height))
+            .action(Action::MovePosition(geometry.x, geometry.y));
+        i3.run_command(&command.build())
+    }
+
+    fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.database.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = serde_json::to_string_pretty(&self.geometries).map_err(invalid_data)?;
+        fs::write(&self.database, contents)
+    }
+}
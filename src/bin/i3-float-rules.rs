@@ -0,0 +1,210 @@
+//! Utility to set up new windows by rule: floating, sticky, border, size and
+//! position.
+//!
+//! This program listens for events from i3 and applies the settings of every
+//! rule matching a new window, in order, as a single batch of commands. Unlike
+//! i3's `for_window`, sizes and positions can be given in percentages of the
+//! output the window appears on, and rules can be limited to some outputs,
+//! e.g. to size a window differently on a laptop screen and on a monitor.
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [[rule]]
+//! match = { class = "Pavucontrol" }
+//! floating = true
+//! sticky = true
+//! border = "pixel 2"
+//! size = "40%x50%"
+//! position = "center"
+//!
+//! # Bigger on the laptop screen, in its top right corner.
+//! [[rule]]
+//! match = { class = "Pavucontrol" }
+//! output = "eDP-1"
+//! size = "60%x70%"
+//! position = "40%,0"
+//! ```
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    config::{self, invalid_data},
+    criteria::{Criteria, CriteriaConfig},
+    geometry::{Geometry, Position},
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData},
+    reply::Node,
+};
+use log::info;
+use serde::Deserialize;
+use std::{io, path::PathBuf};
+
+const CONFIG_FILE_NAME: &str = "float-rules.toml";
+
+/// Set up new windows by rule: floating, sticky, border, size and position.
+#[derive(Parser)]
+struct Args {
+    /// The configuration file defining the rules. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/float-rules.toml`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(rename = "rule")]
+    rules: Vec<RuleConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    #[serde(rename = "match")]
+    criteria: CriteriaConfig,
+    output: Option<String>,
+    floating: Option<bool>,
+    sticky: Option<bool>,
+    border: Option<String>,
+    size: Option<String>,
+    position: Option<String>,
+}
+
+/// Settings to apply to the new windows matching `criteria`.
+struct Rule {
+    criteria: Criteria,
+    /// Only apply the rule to windows appearing on this output.
+    output: Option<String>,
+    floating: Option<bool>,
+    sticky: Option<bool>,
+    /// Border style, e.g. `none` or `pixel 2`.
+    border: Option<String>,
+    size: Option<Geometry>,
+    position: Option<Position>,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let path = args
+        .config
+        .or_else(|| config::default_path(CONFIG_FILE_NAME))
+        .ok_or_else(|| invalid_data("cannot find the configuration file, use --config"))?;
+    let config: Config = config::load(&path)?;
+    let rules = config
+        .rules
+        .into_iter()
+        .map(RuleConfig::into_rule)
+        .collect::<io::Result<_>>()?;
+    EventLoop::connect()?.run(&mut FloatRules { rules })
+}
+
+impl RuleConfig {
+    fn into_rule(self) -> io::Result<Rule> {
+        Ok(Rule {
+            criteria: self.criteria.into_criteria()?,
+            output: self.output,
+            floating: self.floating,
+            sticky: self.sticky,
+            border: self.border,
+            size: self
+                .size
+                .map(|size| size.parse())
+                .transpose()
+                .map_err(invalid_data)?,
+            position: self
+                .position
+                .map(|position| position.parse())
+                .transpose()
+                .map_err(invalid_data)?,
+        })
+    }
+}
+
+pub struct FloatRules {
+    rules: Vec<Rule>,
+}
+
+impl Helper for FloatRules {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window]
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        if event.change != WindowChange::New {
+            return Ok(());
+        }
+        let window = &event.container;
+        if !self.rules.iter().any(|rule| rule.criteria.matches(window)) {
+            return Ok(());
+        }
+        let tree = i3.get_tree()?;
+        let Some(output) = tree::output_of(&tree, window.id) else {
+            return Ok(());
+        };
+        let commands = self.commands(window, output);
+        if commands.is_empty() {
+            return Ok(());
+        }
+        info!("setting up {}", tree::describe(window));
+        i3.run_commands(&commands)
+    }
+}
+
+impl FloatRules {
+    /// The commands applying the rules matching `window`, which appeared on
+    /// `output`.
+    fn commands(&self, window: &Node, output: &Node) -> Vec<String> {
+        let id = window.id;
+        let rules = self.rules.iter().filter(|rule| {
+            rule.criteria.matches(window)
+                && rule
+                    .output
+                    .as_ref()
+                    .is_none_or(|name| output.name.as_ref() == Some(name))
+        });
+        // Later rules override the settings of earlier ones.
+        let (mut floating, mut sticky, mut border, mut size, mut position) =
+            (None, None, None, None, None);
+        for rule in rules {
+            floating = rule.floating.or(floating);
+            sticky = rule.sticky.or(sticky);
+            border = rule.border.as_ref().or(border);
+            size = rule.size.or(size);
+            position = rule.position.or(position);
+        }
+        let mut commands = Vec::new();
+        let command = |action| CommandBuilder::con_id(id).action(action).build();
+        if let Some(floating) = floating {
+            commands.push(command(match floating {
+                true => Action::FloatingEnable,
+                false => Action::FloatingDisable,
+            }));
+        }
+        if let Some(sticky) = sticky {
+            commands.push(command(match sticky {
+                true => Action::StickyEnable,
+                false => Action::StickyDisable,
+            }));
+        }
+        if let Some(border) = border {
+            commands.push(command(Action::Border(border.clone())));
+        }
+        if let Some(size) = size {
+            // Resizing also centers the window, before it is moved elsewhere.
+            commands.push(size.command(id, &output.rect));
+        }
+        if let Some(position) = position {
+            commands.push(position.command(id, &output.rect));
+        }
+        commands
+    }
+}
@@ -0,0 +1,195 @@
+//! Utility to focus windows as the mouse hovers them, with a delay and
+//! exceptions.
+//!
+//! i3's `focus_follows_mouse` can only be on or off: windows are focused as
+//! soon as the pointer crosses them, e.g. on its way to another window, and
+//! popups like menus or tooltips grab focus as well. This program follows the
+//! pointer through X11 instead, and focuses the window under it once it
+//! stayed there for `--delay-ms`, unless its class is excluded.
+//!
+//! `focus_follows_mouse` is a configuration setting that can't be changed at
+//! runtime, so i3's own must be turned off in the configuration, which the
+//! program refuses to start without:
+//!
+//! ```text
+//! focus_follows_mouse no
+//! exec --no-startup-id i3-focus-follows-mouse --delay-ms 300 --exclude-class Rofi
+//! ```
+//!
+//! Like i3's own, it only acts when the pointer moves, so that windows
+//! appearing under a still pointer don't get focus. Requires building with
+//! the `x11` feature.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    config::invalid_data,
+    i3_config,
+    logging::{self, Verbosity},
+    tree,
+    x11::X11,
+    EventLoop, Helper, I3Commands, WindowManager,
+};
+use i3_ipc::{
+    event::Subscribe,
+    reply::{Node, NodeLayout, Rect},
+};
+use log::debug;
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+/// Focus windows as the mouse hovers them, with a delay and exceptions.
+#[derive(Parser)]
+struct Args {
+    /// Milliseconds the pointer must stay over a window before it is focused.
+    #[arg(long, default_value_t = 200)]
+    delay_ms: u64,
+
+    /// The X11 class of windows never focused by hovering them, e.g. popups
+    /// or docks. Can be given multiple times.
+    #[arg(long)]
+    exclude_class: Vec<String>,
+
+    /// Milliseconds between checks of the pointer position.
+    #[arg(long, default_value_t = 50)]
+    poll_ms: u64,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+type NodeId = usize;
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut focus_follows_mouse = FocusFollowsMouse {
+        x11: X11::connect()?,
+        delay: Duration::from_millis(args.delay_ms),
+        exclude_classes: args.exclude_class,
+        poll: Duration::from_millis(args.poll_ms),
+        next_poll: Instant::now(),
+        position: None,
+        hovered: None,
+    };
+    EventLoop::connect()?.run(&mut focus_follows_mouse)
+}
+
+pub struct FocusFollowsMouse {
+    x11: X11,
+    delay: Duration,
+    exclude_classes: Vec<String>,
+    poll: Duration,
+    next_poll: Instant,
+    /// Where the pointer was at the last check.
+    position: Option<(i32, i32)>,
+    /// The window under the pointer, and when to focus it.
+    hovered: Option<(NodeId, Instant)>,
+}
+
+impl Helper for FocusFollowsMouse {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        Vec::new()
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        if i3.window_manager()? == WindowManager::Sway {
+            return Err(io::Error::other(
+                "only i3 is supported, sway windows cannot be followed through X11",
+            ));
+        }
+        if i3_config::load(i3)?.focus_follows_mouse != Some(false) {
+            return Err(invalid_data(
+                "i3's focus_follows_mouse is on, set `focus_follows_mouse no` in its configuration",
+            ));
+        }
+        Ok(())
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        Some(self.next_poll)
+    }
+
+    fn on_deadline(&mut self, i3: &mut dyn I3Commands, now: Instant) -> io::Result<()> {
+        self.next_poll = now + self.poll;
+        let position = self.x11.pointer_position()?;
+        if self.position != Some(position) {
+            self.position = Some(position);
+            let tree = i3.get_tree()?;
+            let visible: Vec<String> = i3
+                .get_workspaces()?
+                .into_iter()
+                .filter(|workspace| workspace.visible)
+                .map(|workspace| workspace.name)
+                .collect();
+            let window = tree::workspaces(&tree)
+                .filter(|workspace| {
+                    workspace
+                        .name
+                        .as_ref()
+                        .is_some_and(|name| visible.contains(name))
+                })
+                .find_map(|workspace| window_at(workspace, position));
+            self.hovered = window
+                .filter(|window| !window.focused && !self.is_excluded(window))
+                .map(|window| (window.id, now + self.delay));
+        }
+        match self.hovered {
+            Some((id, at)) if at <= now => {
+                debug!("focusing window {id} under the pointer");
+                self.hovered = None;
+                i3.run_command(&CommandBuilder::con_id(id).action(Action::Focus).build())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FocusFollowsMouse {
+    fn is_excluded(&self, window: &Node) -> bool {
+        let class = window
+            .window_properties
+            .as_ref()
+            .and_then(|props| props.class.as_ref());
+        class.is_some_and(|class| self.exclude_classes.contains(class))
+    }
+}
+
+/// The window shown at `position` within `node`, floating windows first.
+fn window_at(node: &Node, position: (i32, i32)) -> Option<&Node> {
+    if !contains(&node.rect, position) {
+        return None;
+    }
+    // Floating windows are drawn on top, the last one topmost.
+    let floating = node
+        .floating_nodes
+        .iter()
+        .rev()
+        .find_map(|child| window_at(child, position));
+    if floating.is_some() {
+        return floating;
+    }
+    if tree::is_window(node) {
+        return Some(node);
+    }
+    match node.layout {
+        // Only the focused tab is shown, all of them sharing the same area.
+        NodeLayout::Tabbed | NodeLayout::Stacked => {
+            let focused = node.focus.first()?;
+            let child = node.nodes.iter().find(|child| child.id == *focused)?;
+            window_at(child, position)
+        }
+        _ => node
+            .nodes
+            .iter()
+            .find_map(|child| window_at(child, position)),
+    }
+}
+
+fn contains(rect: &Rect, (x, y): (i32, i32)) -> bool {
+    let (x, y) = (x as isize, y as isize);
+    (rect.x..rect.x + rect.width).contains(&x) && (rect.y..rect.y + rect.height).contains(&y)
+}
@@ -0,0 +1,279 @@
+//! Utility to move through the history of focused windows.
+//!
+//! This program listens for events from i3 and records the order in which
+//! windows are focused, across all workspaces. Key bindings can then ask it,
+//! through i3 tick events, to focus the previously focused window or to cycle
+//! through the most recently focused ones, alt-tab style. This is something i3
+//! cannot do natively, as its `focus` command only moves around spatially.
+//!
+//! # Commands
+//!
+//! Commands are sent as the payload of a tick event:
+//!
+//! ```text
+//! # Jump back and forth between the two most recently focused windows.
+//! bindsym $mod+grave exec --no-startup-id i3-msg -t send_tick focus-history:previous
+//! # Cycle through the most recently focused windows.
+//! bindsym $mod+Tab exec --no-startup-id i3-msg -t send_tick focus-history:next
+//! bindsym $mod+Shift+Tab exec --no-startup-id i3-msg -t send_tick focus-history:prev
+//! ```
+//!
+//! While cycling, the history is not reordered, so that repeated `next`
+//! commands go further back in time. The window we land on is moved to the
+//! front of the history once no command has been received for a while.
+//!
+//! `stack-next` and `stack-prev` cycle the same way through the windows of the
+//! focused workspace only, optionally skipping floating windows and those of
+//! another class than the focused one, as sent by `i3-window-stack`:
+//!
+//! ```text
+//! bindsym Mod1+grave exec --no-startup-id i3-msg -t send_tick 'focus-history:stack-next same-class'
+//! ```
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Subscribe, TickData, WindowChange, WindowData},
+    reply::Node,
+};
+use std::{
+    collections::VecDeque,
+    io,
+    time::{Duration, Instant},
+};
+
+/// Move through the history of focused windows.
+///
+/// Commands are sent through i3 tick events, e.g.
+/// `i3-msg -t send_tick focus-history:previous`. Supported commands are
+/// `previous`, to focus the previously focused window, `next`/`prev`, to
+/// cycle through the most recently focused windows, and `stack-next` and
+/// `stack-prev`, to only cycle through those of the focused workspace.
+#[derive(Parser)]
+struct Args {
+    /// Number of windows to remember.
+    #[arg(long, default_value_t = 20)]
+    size: usize,
+
+    /// Milliseconds without cycling commands after which a cycle is considered
+    /// over, and the window it landed on becomes the most recent one.
+    #[arg(long, default_value_t = 1000)]
+    cycle_timeout_ms: u64,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut focus_history = FocusHistory {
+        history: VecDeque::new(),
+        size: args.size,
+        cycle_timeout: Duration::from_millis(args.cycle_timeout_ms),
+        cycle: None,
+    };
+    EventLoop::connect()?.run(&mut focus_history)
+}
+
+const TICK_PREFIX: &str = "focus-history:";
+
+type NodeId = usize;
+
+/// Restricts the windows cycled through by `stack-next` and `stack-prev` to
+/// the ones on the workspace of the most recently focused window.
+#[derive(Default)]
+struct StackFilter {
+    skip_floating: bool,
+    /// Only keep the windows of the same class as the most recent one.
+    same_class: bool,
+}
+
+impl StackFilter {
+    /// Parse the options following the command, separated by spaces.
+    fn parse<'a>(options: impl Iterator<Item = &'a str>) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for option in options {
+            match option {
+                "skip-floating" => filter.skip_floating = true,
+                "same-class" => filter.same_class = true,
+                _ => return Err(format!("unknown option {option:?}")),
+            }
+        }
+        Ok(filter)
+    }
+
+    /// The positions in `history` of the windows to cycle through, found in
+    /// `tree`. The most recent window is always part of them.
+    fn positions(&self, tree: &Node, history: &VecDeque<NodeId>) -> Vec<usize> {
+        let Some(&recent) = history.front() else {
+            return Vec::new();
+        };
+        let workspace = tree::workspace_of(tree, recent).map(|ws| ws.id);
+        let recent_class = tree::find(tree, recent).and_then(class);
+        let keep = |id: NodeId| {
+            let Some(window) = tree::find(tree, id) else {
+                return false;
+            };
+            tree::workspace_of(tree, id).map(|ws| ws.id) == workspace
+                && !(self.skip_floating && tree::is_floating(window))
+                && !(self.same_class && class(window) != recent_class)
+        };
+        let others = (1..history.len()).filter(|&position| keep(history[position]));
+        std::iter::once(0).chain(others).collect()
+    }
+}
+
+/// The X11 class of the window, or its app_id for native Wayland windows.
+fn class(window: &Node) -> Option<&str> {
+    let props = window.window_properties.as_ref();
+    props
+        .and_then(|props| props.class.as_deref())
+        .or(window.app_id.as_deref())
+}
+
+/// An ongoing cycle through the history.
+struct Cycle {
+    /// Position in the history of the window we last focused.
+    position: usize,
+    ends_at: Instant,
+}
+
+pub struct FocusHistory {
+    /// Focused windows, most recent first.
+    history: VecDeque<NodeId>,
+    size: usize,
+    cycle_timeout: Duration,
+    cycle: Option<Cycle>,
+}
+
+impl Helper for FocusHistory {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window, Subscribe::Tick]
+    }
+
+    fn on_window(&mut self, _i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        let id = event.container.id;
+        match event.change {
+            WindowChange::Focus => {
+                let cycled_to = self
+                    .cycle
+                    .as_ref()
+                    .map(|cycle| self.history[cycle.position]);
+                // Focus changes we caused while cycling must not reorder the
+                // history, anything else ends the cycle.
+                if cycled_to != Some(id) {
+                    self.end_cycle();
+                    self.push(id);
+                }
+            }
+            WindowChange::Close => {
+                self.end_cycle();
+                self.history.retain(|&other| other != id);
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn on_tick(&mut self, i3: &mut dyn I3Commands, event: &TickData) -> io::Result<()> {
+        let Some(payload) = event.payload.strip_prefix(TICK_PREFIX) else {
+            return Ok(());
+        };
+        let mut words = payload.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        match command {
+            "previous" => {
+                self.end_cycle();
+                self.focus(i3, 1)
+            }
+            "next" => self.cycle(i3, true, None),
+            "prev" => self.cycle(i3, false, None),
+            "stack-next" | "stack-prev" => match StackFilter::parse(words) {
+                Ok(filter) => self.cycle(i3, command == "stack-next", Some(filter)),
+                Err(err) => {
+                    log::warn!("invalid command {payload:?}: {err}");
+                    Ok(())
+                }
+            },
+            _ => {
+                log::warn!("unknown command {command:?}");
+                Ok(())
+            }
+        }
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.cycle.as_ref().map(|cycle| cycle.ends_at)
+    }
+
+    fn on_deadline(&mut self, _i3: &mut dyn I3Commands, _now: Instant) -> io::Result<()> {
+        self.end_cycle();
+        Ok(())
+    }
+}
+
+impl FocusHistory {
+    /// Move `id` to the front of the history.
+    fn push(&mut self, id: NodeId) {
+        self.history.retain(|&other| other != id);
+        self.history.push_front(id);
+        self.history.truncate(self.size);
+    }
+
+    /// Move one position through the history, back in time if `forward`,
+    /// wrapping around, relative to the current position of the cycle, if
+    /// any. With a `filter`, windows it leaves out are skipped.
+    fn cycle(
+        &mut self,
+        i3: &mut dyn I3Commands,
+        forward: bool,
+        filter: Option<StackFilter>,
+    ) -> io::Result<()> {
+        let positions: Vec<usize> = match filter {
+            Some(filter) => filter.positions(&i3.get_tree()?, &self.history),
+            None => (0..self.history.len()).collect(),
+        };
+        if positions.len() < 2 {
+            return Ok(());
+        }
+        let current = self.cycle.as_ref().map_or(0, |cycle| cycle.position);
+        // The window we cycled to may have been left out since, e.g. after
+        // moving to another workspace, in which case we start over.
+        let index = positions
+            .iter()
+            .position(|&position| position == current)
+            .unwrap_or(0);
+        let index = match forward {
+            true => (index + 1) % positions.len(),
+            false => (index + positions.len() - 1) % positions.len(),
+        };
+        let position = positions[index];
+        self.cycle = Some(Cycle {
+            position,
+            ends_at: Instant::now() + self.cycle_timeout,
+        });
+        self.focus(i3, position)
+    }
+
+    /// Make the window the cycle landed on the most recent one.
+    fn end_cycle(&mut self) {
+        if let Some(cycle) = self.cycle.take() {
+            if let Some(&id) = self.history.get(cycle.position) {
+                self.push(id);
+            }
+        }
+    }
+
+    fn focus(&mut self, i3: &mut dyn I3Commands, position: usize) -> io::Result<()> {
+        match self.history.get(position) {
+            Some(&id) => i3.run_command(&CommandBuilder::con_id(id).action(Action::Focus).build()),
+            None => Ok(()),
+        }
+    }
+}
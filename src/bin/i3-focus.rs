@@ -0,0 +1,124 @@
+//! Utility to focus the visually nearest window in a direction.
+//!
+//! i3's `focus right` follows the layout tree, which often lands on a window
+//! that isn't the one next to the focused one on screen, and handles floating
+//! windows and outputs separately. This program instead compares the positions
+//! of all the visible windows, tiling or floating and on any output, and
+//! focuses the nearest one in the given direction. When there is none, it
+//! wraps around to the window furthest in the opposite direction, as if the
+//! outputs were side by side on a loop.
+
+use clap::{Parser, ValueEnum};
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    logging::{self, Verbosity},
+    tree, Connection, I3Commands,
+};
+use i3_ipc::reply::{Node, NodeLayout, Rect};
+use std::io;
+
+/// Focus the visually nearest window in a direction.
+#[derive(Parser)]
+struct Args {
+    #[arg(value_enum)]
+    direction: Direction,
+
+    /// Do nothing when there is no window in that direction, instead of
+    /// wrapping around.
+    #[arg(long)]
+    no_wrap: bool,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// How much more an offset across the direction weighs than one along it, so
+/// that windows straight ahead are preferred over closer ones off to a side.
+const ACROSS_WEIGHT: isize = 2;
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let tree = i3.get_tree()?;
+    let visible: Vec<String> = i3
+        .get_workspaces()?
+        .into_iter()
+        .filter(|workspace| workspace.visible)
+        .map(|workspace| workspace.name)
+        .collect();
+    let windows: Vec<&Node> = tree::workspaces(&tree)
+        .filter(|workspace| workspace.name.as_ref().is_some_and(|n| visible.contains(n)))
+        .flat_map(tree::windows)
+        .filter(|window| is_visible(&tree, window))
+        .collect();
+    let Some(focused) = windows.iter().find(|window| window.focused) else {
+        return Ok(());
+    };
+    let (x, y) = center(&focused.rect);
+    let others = windows.iter().filter(|window| window.id != focused.id);
+    // Offsets along and across the direction from the focused window.
+    let offsets = |window: &&&Node| {
+        let (cx, cy) = center(&window.rect);
+        match args.direction {
+            Direction::Left => (x - cx, (cy - y).abs()),
+            Direction::Right => (cx - x, (cy - y).abs()),
+            Direction::Up => (y - cy, (cx - x).abs()),
+            Direction::Down => (cy - y, (cx - x).abs()),
+        }
+    };
+    let score = |window: &&&Node| {
+        let (along, across) = offsets(window);
+        along + ACROSS_WEIGHT * across
+    };
+    let ahead = others
+        .clone()
+        .filter(|window| offsets(window).0 > 0)
+        .min_by_key(score);
+    let target = match ahead {
+        Some(window) => window,
+        None if args.no_wrap => return Ok(()),
+        // The furthest window behind is the first one after wrapping around.
+        None => match others.min_by_key(score) {
+            Some(window) => window,
+            None => return Ok(()),
+        },
+    };
+    i3.run_command(
+        &CommandBuilder::con_id(target.id)
+            .action(Action::Focus)
+            .build(),
+    )
+}
+
+fn center(rect: &Rect) -> (isize, isize) {
+    (rect.x + rect.width / 2, rect.y + rect.height / 2)
+}
+
+/// Whether `window` is shown, rather than hidden behind another tab of a
+/// tabbed or stacked container.
+fn is_visible(tree: &Node, window: &Node) -> bool {
+    let mut child = window;
+    while let Some(parent) = tree::parent_of(tree, child.id) {
+        if matches!(parent.layout, NodeLayout::Tabbed | NodeLayout::Stacked) {
+            let shown = parent
+                .focus
+                .iter()
+                .find(|id| parent.nodes.iter().any(|node| node.id == **id));
+            if shown.is_some_and(|&id| id != child.id) {
+                return false;
+            }
+        }
+        child = parent;
+    }
+    true
+}
@@ -0,0 +1,47 @@
+//! Utility to send commands to running helpers through their control socket.
+//!
+//! It prints the reply of the helper and exits with an error if the command
+//! failed, so it can be used from key bindings and scripts alike.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    config::invalid_data,
+    control,
+    logging::{self, Verbosity},
+};
+use std::{io, path::PathBuf, process::ExitCode};
+
+/// Send a command to a running helper.
+#[derive(Parser)]
+struct Args {
+    /// The helper to talk to, listening on its default socket.
+    #[arg(long, default_value = "i3-back-to-scratch")]
+    helper: String,
+
+    /// The socket to talk to, instead of the default one of `--helper`.
+    #[arg(long)]
+    socket: Option<PathBuf>,
+
+    /// The command to send, e.g. `status`, `pause` or `resume`.
+    #[arg(required = true)]
+    command: Vec<String>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<ExitCode> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let path = args
+        .socket
+        .or_else(|| control::default_socket_path(&args.helper))
+        .ok_or_else(|| invalid_data("XDG_RUNTIME_DIR is not set, use --socket"))?;
+    let reply = control::send(&path, &args.command.join(" "))?;
+    print!("{reply}");
+    Ok(match reply.starts_with("error:") {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    })
+}
@@ -0,0 +1,668 @@
+//! Utility to run several helpers in a single process.
+//!
+//! Each daemon of this crate keeps two connections to i3, one to receive
+//! events and one to send commands. Running all of them adds up, so the
+//! `daemon` subcommand runs the enabled helpers together over a single pair of
+//! connections instead, passing each event on to the helpers interested in it:
+//!
+//! ```text
+//! exec --no-startup-id i3-helpers daemon --enable back-to-scratch,autotiling,workspace-names
+//! ```
+//!
+//! The options of the daemon can also be set in a configuration file, found
+//! through [`config::discover`], in which case command line flags take
+//! precedence over the values from the file:
+//!
+//! ```toml
+//! enable = ["back-to-scratch", "autotiling"]
+//! autotiling_workspaces = ["1", "2"]
+//! ```
+//!
+//! The `doctor` subcommand checks that the helpers can work in the current
+//! environment, and suggests how to fix what would prevent them from doing so.
+//!
+//! The `check-bindings` subcommand looks for mistakes in the key bindings of
+//! the i3 configuration, like keys bound twice.
+//!
+//! The `completions` and `man` subcommands generate the shell completions and
+//! man pages of all the binaries of the crate, for packagers and users alike.
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_complete::{Generator, Shell};
+use i3_helpers::{
+    binding_check::{self, Severity},
+    cli,
+    config::{self, invalid_data},
+    control,
+    helpers::{
+        autotiling::Autotiling,
+        back_to_scratch::{self, FocusMonitor, WINDOWS_HIDDEN},
+        workspace_names::{self, WorkspaceNames},
+    },
+    i3_config,
+    instance::InstanceLock,
+    logging::{self, Verbosity},
+    metrics,
+    multiplexer::Multiplexer,
+    record, tree, Connection, EventLoop, I3Commands, WindowManager,
+};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// The other binaries of the crate, which `completions` and `man` run to
+/// generate their files. Keep in sync with `src/bin`.
+const BINARIES: &[&str] = &[
+    "i3-assign",
+    "i3-autotiling",
+    "i3-back-to-scratch",
+    "i3-balance",
+    "i3-chords",
+    "i3-cycle-outputs",
+    "i3-cycle-scratchpads",
+    "i3-dim-inactive-outputs",
+    "i3-empty-workspace",
+    "i3-events",
+    "i3-float-memory",
+    "i3-float-rules",
+    "i3-focus",
+    "i3-focus-follows-mouse",
+    "i3-focus-history",
+    "i3-helpers-ctl",
+    "i3-inhibit-idle",
+    "i3-input-rules",
+    "i3-keybind-overlay",
+    "i3-kill",
+    "i3-layout",
+    "i3-master-stack",
+    "i3-maximize",
+    "i3-mode-timeout",
+    "i3-notify-events",
+    "i3-on-next-window",
+    "i3-opacity",
+    "i3-output-profiles",
+    "i3-per-window-layout",
+    "i3-pin-workspaces",
+    "i3-project",
+    "i3-rename-workspace",
+    "i3-resize-mode",
+    "i3-scratchpad-manager",
+    "i3-smart-borders",
+    "i3-swallow",
+    "i3-swap-workspaces",
+    "i3-tab-overview",
+    "i3-time-tracker",
+    "i3-title-format",
+    "i3-urgent-jump",
+    "i3-wait-for",
+    "i3-window-marks",
+    "i3-window-menu",
+    "i3-window-placement",
+    "i3-window-stack",
+    "i3-workspace-groups",
+    "i3-workspace-history",
+    "i3-workspace-layout",
+    "i3-workspace-names",
+    "i3-ws-next",
+    "i3-ws-prev",
+];
+
+/// The oldest i3 version supported: tick events, which several helpers are
+/// driven by, appeared in 4.15.
+const MIN_I3_VERSION: (usize, usize) = (4, 15);
+
+/// The oldest sway version supported.
+const MIN_SWAY_VERSION: (usize, usize) = (1, 0);
+
+/// Run several i3 helpers in a single process.
+#[derive(Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the enabled helpers over a single connection to i3.
+    Daemon(DaemonArgs),
+    /// Check that the helpers can work in the current environment.
+    Doctor,
+    /// Look for mistakes in the key bindings of the i3 configuration.
+    CheckBindings {
+        /// Check this configuration file instead of the one i3 was loaded
+        /// with, without checking the outputs it refers to.
+        #[arg(long, value_name = "PATH")]
+        file: Option<PathBuf>,
+    },
+    /// Generate the shell completions of all the binaries.
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+
+        /// Write the completions of each binary to a file of this directory,
+        /// named as the shell expects, instead of printing them all.
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Generate the man pages of all the binaries, as `NAME.1` files.
+    Man {
+        /// The directory to write the man pages to.
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+    },
+}
+
+#[derive(Args)]
+struct DaemonArgs {
+    /// The helpers to run, separated by commas. Required, unless given in the
+    /// configuration file.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    enable: Vec<HelperName>,
+
+    /// The rules of back-to-scratch, in the format of the `--config` file of
+    /// `i3-back-to-scratch`. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/back-to-scratch.toml`. The file is
+    /// reloaded when receiving SIGHUP.
+    #[arg(long, value_name = "PATH")]
+    back_to_scratch_config: Option<PathBuf>,
+
+    /// Only autotile these workspaces (by name). Can be given multiple times.
+    /// Defaults to all workspaces.
+    #[arg(long, value_name = "WORKSPACE")]
+    autotiling_workspace: Vec<String>,
+
+    /// The class to icon map of workspace-names, in the format of the
+    /// `--config` file of `i3-workspace-names`.
+    #[arg(long, value_name = "PATH")]
+    workspace_names_config: Option<PathBuf>,
+
+    /// Listen for commands on a Unix socket, `$XDG_RUNTIME_DIR/i3-helpers.sock`
+    /// by default. Commands start with the name of the helper they are meant
+    /// for, e.g. `i3-helpers-ctl --helper i3-helpers back-to-scratch pause`.
+    #[arg(long, value_name = "PATH")]
+    control_socket: Option<Option<PathBuf>>,
+
+    /// Log the commands the helpers would run, without actually running them.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Print the effective configuration, merging the command line over the
+    /// configuration file, and exit.
+    #[arg(long)]
+    print_config: bool,
+
+    /// Take over from a daemon already running with the same configuration,
+    /// which is asked to exit, instead of refusing to start.
+    #[arg(long)]
+    replace: bool,
+
+    /// Record the events received, and the replies to the queries of the
+    /// helpers, to a file that can be replayed with `--replay`, e.g. to attach
+    /// to a bug report.
+    #[arg(long, value_name = "PATH")]
+    record: Option<PathBuf>,
+
+    /// Replay the events recorded with `--record`, printing the commands that
+    /// would be run, each after the line of the recording it responds to,
+    /// without connecting to i3.
+    #[arg(long, value_name = "PATH", conflicts_with = "record")]
+    replay: Option<PathBuf>,
+
+    /// Notify systemd when ready and ping its watchdog, when run as a
+    /// `Type=notify` service.
+    #[cfg(feature = "systemd")]
+    #[arg(long)]
+    systemd: bool,
+
+    /// Publish Prometheus metrics over HTTP on this address, e.g.
+    /// `127.0.0.1:9188`.
+    #[cfg(feature = "metrics")]
+    #[arg(long, value_name = "ADDR")]
+    metrics_addr: Option<std::net::SocketAddr>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum HelperName {
+    /// Send windows back to the scratchpad when they lose focus.
+    BackToScratch,
+    /// Alternate between horizontal and vertical splits.
+    Autotiling,
+    /// Name workspaces after the windows they contain.
+    WorkspaceNames,
+}
+
+/// The options of the daemon that can be set in the configuration file.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct DaemonConfig {
+    #[serde(default)]
+    enable: Vec<HelperName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    back_to_scratch_config: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    autotiling_workspaces: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workspace_names_config: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    control_socket: Option<PathBuf>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+impl DaemonArgs {
+    /// Override the values of `config` with the ones given on the command
+    /// line. Flags can only be turned on.
+    fn merge_over(&self, mut config: DaemonConfig) -> io::Result<DaemonConfig> {
+        if !self.enable.is_empty() {
+            config.enable = self.enable.clone();
+        }
+        if let Some(path) = &self.back_to_scratch_config {
+            config.back_to_scratch_config = Some(path.clone());
+        }
+        if !self.autotiling_workspace.is_empty() {
+            config.autotiling_workspaces = self.autotiling_workspace.clone();
+        }
+        if let Some(path) = &self.workspace_names_config {
+            config.workspace_names_config = Some(path.clone());
+        }
+        match &self.control_socket {
+            Some(Some(path)) => config.control_socket = Some(path.clone()),
+            Some(None) => {
+                config.control_socket =
+                    Some(control::default_socket_path("i3-helpers").ok_or_else(|| {
+                        invalid_data("XDG_RUNTIME_DIR is not set, use --control-socket PATH")
+                    })?)
+            }
+            None => (),
+        }
+        config.dry_run |= self.dry_run;
+        Ok(config)
+    }
+}
+
+fn main() -> io::Result<()> {
+    let cli = cli::parse::<Cli>();
+    logging::init(cli.verbosity.level());
+    match cli.command {
+        Command::Daemon(args) => daemon(args),
+        Command::Doctor => doctor(),
+        Command::CheckBindings { file } => check_bindings(file.as_deref()),
+        Command::Completions { shell, out_dir } => generate_all(
+            &format!("completions:{shell}"),
+            |name| shell.file_name(name),
+            out_dir.as_deref(),
+        ),
+        Command::Man { out_dir } => generate_all("man", |name| format!("{name}.1"), Some(&out_dir)),
+    }
+}
+
+/// Generate the files of this binary and of the others, next to it, as asked
+/// by `request` in the format of `$I3_HELPERS_GENERATE`. They are written to
+/// `out_dir`, named by `file_name`, if given, and printed otherwise.
+fn generate_all(
+    request: &str,
+    file_name: impl Fn(&str) -> String,
+    out_dir: Option<&Path>,
+) -> io::Result<()> {
+    let exe = env::current_exe()?;
+    let bin_dir = exe.parent().unwrap_or(Path::new("."));
+    if let Some(out_dir) = out_dir {
+        fs::create_dir_all(out_dir)?;
+    }
+    let mut stdout = io::stdout().lock();
+    for &name in std::iter::once(&"i3-helpers").chain(BINARIES) {
+        let contents = if name == "i3-helpers" {
+            let mut contents = Vec::new();
+            cli::generate::<Cli>(request, &mut contents)?;
+            contents
+        } else {
+            let output = process::Command::new(bin_dir.join(name))
+                .env(cli::GENERATE_VAR, request)
+                .output();
+            match output {
+                Ok(output) if output.status.success() => output.stdout,
+                Ok(output) => {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    warn!("skipping {name}: {}", stderr.trim());
+                    continue;
+                }
+                Err(err) => {
+                    warn!("skipping {name}, which cannot be run: {err}");
+                    continue;
+                }
+            }
+        };
+        match out_dir {
+            Some(out_dir) => fs::write(out_dir.join(file_name(name)), contents)?,
+            None => stdout.write_all(&contents)?,
+        }
+    }
+    Ok(())
+}
+
+/// The outcome of the checks of `doctor`, printed as they are made.
+#[derive(Default)]
+struct Report {
+    problems: usize,
+}
+
+impl Report {
+    fn ok(&mut self, message: impl std::fmt::Display) {
+        println!("ok       {message}");
+    }
+
+    fn warn(&mut self, message: impl std::fmt::Display, hint: &str) {
+        println!("warning  {message}");
+        println!("         {hint}");
+    }
+
+    fn error(&mut self, message: impl std::fmt::Display, hint: &str) {
+        self.problems += 1;
+        println!("error    {message}");
+        println!("         {hint}");
+    }
+}
+
+fn doctor() -> io::Result<()> {
+    let mut report = Report::default();
+    check_instances(&mut report);
+    match Connection::connect() {
+        Ok(mut i3) => {
+            report.ok("connected to the IPC socket");
+            check_window_manager(&mut report, &mut i3)?;
+            check_criteria(&mut report, &mut i3)?;
+        }
+        Err(err) => report.error(
+            format!("cannot connect to the IPC socket: {err}"),
+            "check that i3 or sway is running, and that $I3SOCK or $SWAYSOCK, if \
+             set, point to its socket, e.g. when running over SSH",
+        ),
+    }
+    match report.problems {
+        0 => Ok(()),
+        1 => Err(io::Error::other("found 1 problem")),
+        n => Err(io::Error::other(format!("found {n} problems"))),
+    }
+}
+
+fn check_window_manager(report: &mut Report, i3: &mut Connection) -> io::Result<()> {
+    let window_manager = i3.window_manager()?;
+    let version = i3.stream().get_version()?;
+    let (name, min_version) = match window_manager {
+        WindowManager::I3 => ("i3", MIN_I3_VERSION),
+        WindowManager::Sway => ("sway", MIN_SWAY_VERSION),
+    };
+    if (version.major, version.minor) < min_version {
+        report.error(
+            format!("running {name} {}", version.human_readable),
+            &format!(
+                "the helpers need {name} {}.{} or later, upgrade it",
+                min_version.0, min_version.1
+            ),
+        );
+    } else {
+        report.ok(format!("running {name} {}", version.human_readable));
+    }
+    Ok(())
+}
+
+/// Check that the rules of back-to-scratch, if any, match some window.
+fn check_criteria(report: &mut Report, i3: &mut Connection) -> io::Result<()> {
+    let path = match config::discover() {
+        Some(path) => match config::load::<DaemonConfig>(&path) {
+            Ok(config) => config.back_to_scratch_config,
+            Err(err) => {
+                report.error(
+                    format!("cannot load {}: {err}", path.display()),
+                    "fix the daemon configuration, see `i3-helpers daemon --help`",
+                );
+                None
+            }
+        },
+        None => None,
+    };
+    let Some(path) = path
+        .or_else(|| config::default_path("back-to-scratch.toml"))
+        .filter(|path| path.exists())
+    else {
+        return Ok(());
+    };
+    let rules = match back_to_scratch::config::load(&path) {
+        Ok(rules) => rules,
+        Err(err) => {
+            report.error(
+                format!("cannot load {}: {err}", path.display()),
+                "fix the back-to-scratch rules, see `i3-back-to-scratch --help`",
+            );
+            return Ok(());
+        }
+    };
+    let window_manager = i3.window_manager()?;
+    let tree = i3.get_tree()?;
+    for rule in &rules {
+        let criteria = &rule.criteria;
+        let count = tree::windows(&tree)
+            .filter(|window| criteria.matches(window))
+            .count();
+        if count > 0 {
+            report.ok(format!("{count} window(s) match {criteria}"));
+        } else if window_manager == WindowManager::I3 && criteria.app_id.is_some() {
+            report.error(
+                format!("no window matches {criteria}"),
+                "i3 windows have no app_id, match them by class instead",
+            );
+        } else if window_manager == WindowManager::Sway && criteria.uses_x11_properties() {
+            report.warn(
+                format!("no window matches {criteria}"),
+                "under sway, class and instance only match XWayland windows, \
+                 match native ones by app_id instead",
+            );
+        } else {
+            report.warn(
+                format!("no window matches {criteria}"),
+                "open one of the windows, or check the criteria against `i3-events`",
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The daemons that must not run more than once, with the subcommand running
+/// them as such for binaries that have subcommands.
+///
+/// One-shot tools, daemons that only print, like `i3-events`, and
+/// i3-back-to-scratch, which may run once per set of rules and locks each
+/// through [`InstanceLock`](i3_helpers::instance::InstanceLock), are left out.
+const DAEMONS: &[(&str, Option<&str>)] = &[
+    ("i3-assign", None),
+    ("i3-autotiling", None),
+    ("i3-chords", Some("daemon")),
+    ("i3-dim-inactive-outputs", None),
+    ("i3-float-memory", None),
+    ("i3-float-rules", None),
+    ("i3-focus-follows-mouse", None),
+    ("i3-focus-history", None),
+    ("i3-helpers", Some("daemon")),
+    ("i3-inhibit-idle", None),
+    ("i3-input-rules", None),
+    ("i3-keybind-overlay", None),
+    ("i3-master-stack", None),
+    ("i3-mode-timeout", None),
+    ("i3-notify-events", None),
+    ("i3-opacity", None),
+    ("i3-output-profiles", None),
+    ("i3-per-window-layout", None),
+    ("i3-pin-workspaces", None),
+    ("i3-scratchpad-manager", Some("daemon")),
+    ("i3-smart-borders", None),
+    ("i3-swallow", None),
+    ("i3-time-tracker", Some("record")),
+    ("i3-title-format", None),
+    ("i3-urgent-jump", Some("daemon")),
+    ("i3-window-placement", None),
+    ("i3-workspace-groups", None),
+    ("i3-workspace-history", None),
+    ("i3-workspace-layout", None),
+    ("i3-workspace-names", None),
+];
+
+/// Check that no daemon runs more than once, as the instances would fight over
+/// the same windows.
+fn check_instances(report: &mut Report) {
+    let mut instances = BTreeMap::<String, Vec<u32>>::new();
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|pid| pid.parse().ok()) else {
+            continue;
+        };
+        let Ok(cmdline) = fs::read(entry.path().join("cmdline")) else {
+            continue;
+        };
+        let mut args = cmdline
+            .split(|&byte| byte == 0)
+            .map(String::from_utf8_lossy);
+        let Some(name) = args.next().and_then(|arg0| {
+            let name = Path::new(arg0.as_ref()).file_name()?.to_str()?.to_owned();
+            Some(name)
+        }) else {
+            continue;
+        };
+        let is_daemon = match DAEMONS.iter().find(|(daemon, _)| *daemon == name) {
+            Some((_, Some(subcommand))) => args.any(|arg| arg == *subcommand),
+            Some((_, None)) => true,
+            None => false,
+        };
+        if is_daemon {
+            instances.entry(name).or_default().push(pid);
+        }
+    }
+    let mut duplicated = false;
+    for (name, pids) in instances.iter().filter(|(_, pids)| pids.len() > 1) {
+        duplicated = true;
+        let pids: Vec<_> = pids.iter().map(u32::to_string).collect();
+        report.error(
+            format!(
+                "{name} is running {} times, as {}",
+                pids.len(),
+                pids.join(", ")
+            ),
+            "the instances would fight over the same windows, check for duplicate \
+             `exec` lines in the i3 config and kill all but one",
+        );
+    }
+    if !duplicated {
+        report.ok(format!("{} daemon(s) running, once each", instances.len()));
+    }
+}
+
+fn check_bindings(file: Option<&Path>) -> io::Result<()> {
+    let (config, outputs) = match file {
+        Some(path) => (i3_config::parse(&fs::read_to_string(path)?), None),
+        None => {
+            let mut i3 = Connection::connect()?;
+            let outputs: Vec<String> = i3
+                .get_outputs()?
+                .into_iter()
+                .filter(|output| output.active)
+                .map(|output| output.name)
+                .collect();
+            (i3_config::load(&mut i3)?, Some(outputs))
+        }
+    };
+    let findings = binding_check::check(&config, outputs.as_deref());
+    for finding in &findings {
+        println!(
+            "{:<8} line {}: {}",
+            finding.severity, finding.line, finding.message
+        );
+    }
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    match errors {
+        0 => Ok(()),
+        1 => Err(io::Error::other("found 1 error")),
+        n => Err(io::Error::other(format!("found {n} errors"))),
+    }
+}
+
+fn daemon(args: DaemonArgs) -> io::Result<()> {
+    let path = config::discover();
+    let file_config = match &path {
+        Some(path) => {
+            log::info!("loading the configuration from {}", path.display());
+            config::load(path)?
+        }
+        None => DaemonConfig::default(),
+    };
+    let config = args.merge_over(file_config)?;
+    if args.print_config {
+        if let Some(path) = &path {
+            println!("# merged over {}", path.display());
+        }
+        print!("{}", toml::to_string(&config).map_err(invalid_data)?);
+        return Ok(());
+    }
+    if config.enable.is_empty() {
+        return Err(invalid_data(
+            "no helper enabled, use --enable or set `enable` in the configuration file",
+        ));
+    }
+    let key = toml::to_string(&config).map_err(invalid_data)?;
+    let mut multiplexer = Multiplexer::new();
+    let mut reload_on_sighup = false;
+    if config.enable.contains(&HelperName::BackToScratch) {
+        let path = config
+            .back_to_scratch_config
+            .or_else(|| config::default_path("back-to-scratch.toml"))
+            .ok_or_else(|| {
+                invalid_data("cannot find the back-to-scratch rules, use --back-to-scratch-config")
+            })?;
+        let rules = back_to_scratch::config::load(&path)?;
+        multiplexer.add("back-to-scratch", FocusMonitor::new(rules, Some(path)));
+        metrics::register(&WINDOWS_HIDDEN);
+        reload_on_sighup = true;
+    }
+    if config.enable.contains(&HelperName::Autotiling) {
+        multiplexer.add("autotiling", Autotiling::new(config.autotiling_workspaces));
+    }
+    if config.enable.contains(&HelperName::WorkspaceNames) {
+        let names = match &config.workspace_names_config {
+            Some(path) => config::load(path)?,
+            None => workspace_names::Config::default(),
+        };
+        multiplexer.add("workspace-names", WorkspaceNames::new(names));
+    }
+    if let Some(path) = &args.replay {
+        return record::replay(path, &mut multiplexer);
+    }
+    let _lock = match config.dry_run {
+        true => None,
+        false => InstanceLock::acquire("i3-helpers", &key, args.replace)?,
+    };
+    let mut event_loop = EventLoop::connect()?;
+    event_loop.set_dry_run(config.dry_run);
+    event_loop.set_record(args.record.as_deref())?;
+    event_loop.set_reload_on_sighup(reload_on_sighup);
+    event_loop.set_control_socket(config.control_socket);
+    #[cfg(feature = "systemd")]
+    if args.systemd {
+        event_loop.enable_systemd();
+    }
+    #[cfg(feature = "metrics")]
+    event_loop.set_metrics_addr(args.metrics_addr);
+    event_loop.run(&mut multiplexer)
+}
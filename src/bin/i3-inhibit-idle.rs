@@ -0,0 +1,313 @@
+//! Utility to keep the screen from blanking while some windows are in use.
+//!
+//! This program listens for events from i3 and keeps the screen from idling
+//! while a window matching one of the given expressions is fullscreen on a
+//! visible workspace, or focused with `--when focused`, e.g. a video player
+//! or a presentation, and lets it idle again once the window closes, leaves
+//! fullscreen or loses focus:
+//!
+//! ```text
+//! exec --no-startup-id i3-inhibit-idle --match 'class=mpv' --match 'class~^libreoffice'
+//! ```
+//!
+//! Idling is inhibited through logind, by running `systemd-inhibit`, which
+//! swayidle and most screen lockers honor, through `xset`, turning the X11
+//! screen saver and DPMS off and back on, or on sway through its own
+//! `inhibit_idle` command.
+
+use clap::{Parser, ValueEnum};
+use i3_helpers::{
+    backend::{self, Backend},
+    cli,
+    criteria::Criteria,
+    expression::Expression,
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands, WindowManager,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData, WorkspaceData},
+    reply::{FullscreenMode, Node},
+};
+use log::{info, warn};
+use std::{
+    io,
+    process::{Child, Command, Stdio},
+};
+
+/// Keep the screen from blanking while some windows are in use.
+#[derive(Parser)]
+struct Args {
+    /// A match expression for the windows keeping the screen awake, e.g.
+    /// `class=mpv` or `app_id~^firefox && title~YouTube`. Can be given
+    /// multiple times.
+    #[arg(long = "match", value_name = "EXPRESSION", required = true)]
+    expressions: Vec<Expression>,
+
+    /// When the windows keep the screen awake.
+    #[arg(long, value_enum, default_value_t = When::Fullscreen)]
+    when: When,
+
+    /// How to keep the screen awake. `auto` uses `sway` under sway and
+    /// `systemd` otherwise.
+    #[arg(long, value_enum, default_value_t = Method::Auto)]
+    method: Method,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum When {
+    /// While fullscreen on a visible workspace.
+    Fullscreen,
+    /// While focused.
+    Focused,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Method {
+    Auto,
+    /// Run `systemd-inhibit` for as long as needed.
+    Systemd,
+    /// Turn the X11 screen saver and DPMS off with `xset`, and back on.
+    Xset,
+    /// Set sway's `inhibit_idle` on the window.
+    Sway,
+}
+
+type NodeId = usize;
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let rules = args
+        .expressions
+        .into_iter()
+        .map(|expression| Criteria {
+            expression: Some(expression),
+            ..Criteria::default()
+        })
+        .collect();
+    let mut inhibit_idle = InhibitIdle {
+        rules,
+        when: args.when,
+        method: args.method,
+        inhibitor: None,
+        inhibiting: None,
+    };
+    EventLoop::connect()?.run(&mut inhibit_idle)
+}
+
+/// A way of keeping the screen awake.
+trait Inhibitor {
+    /// Keep the screen awake on behalf of `window`, replacing the previous
+    /// window if still inhibiting.
+    fn inhibit(&mut self, i3: &mut dyn I3Commands, window: NodeId) -> io::Result<()>;
+
+    /// Let the screen idle again.
+    fn release(&mut self, i3: &mut dyn I3Commands) -> io::Result<()>;
+}
+
+pub struct InhibitIdle {
+    rules: Vec<Criteria>,
+    when: When,
+    method: Method,
+    /// Picked once connected, as it may depend on the window manager.
+    inhibitor: Option<Box<dyn Inhibitor>>,
+    /// The window keeping the screen awake, if any.
+    inhibiting: Option<NodeId>,
+}
+
+impl Helper for InhibitIdle {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window, Subscribe::Workspace]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let backend = backend::detect(i3)?;
+        self.inhibitor = Some(match self.method {
+            Method::Systemd => Box::new(Systemd { child: None }),
+            Method::Xset => Box::new(Xset { off: false }),
+            Method::Sway | Method::Auto if backend.window_manager() == WindowManager::Sway => {
+                Box::new(Sway {
+                    backend,
+                    window: None,
+                })
+            }
+            Method::Sway => {
+                warn!("not running under sway, using systemd-inhibit instead");
+                Box::new(Systemd { child: None })
+            }
+            Method::Auto => Box::new(Systemd { child: None }),
+        });
+        self.update(i3)
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        match event.change {
+            WindowChange::Focus
+            | WindowChange::FullscreenMode
+            | WindowChange::Close
+            | WindowChange::Move
+            | WindowChange::Title => self.update(i3),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_workspace(&mut self, i3: &mut dyn I3Commands, _event: &WorkspaceData) -> io::Result<()> {
+        if self.when == When::Fullscreen {
+            self.update(i3)?;
+        }
+        Ok(())
+    }
+
+    fn on_exit(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        match &mut self.inhibitor {
+            Some(inhibitor) if self.inhibiting.is_some() => inhibitor.release(i3),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl InhibitIdle {
+    /// Inhibit idling if a window calls for it, or release the inhibitor.
+    fn update(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let tree = i3.get_tree()?;
+        let visible: Vec<String> = match self.when {
+            When::Fullscreen => i3
+                .get_workspaces()?
+                .into_iter()
+                .filter(|workspace| workspace.visible)
+                .map(|workspace| workspace.name)
+                .collect(),
+            When::Focused => Vec::new(),
+        };
+        let window = tree::windows(&tree).find(|window| {
+            self.rules.iter().any(|rule| rule.matches(window))
+                && match self.when {
+                    When::Focused => window.focused,
+                    When::Fullscreen => {
+                        window.fullscreen_mode != FullscreenMode::None
+                            && is_on(&tree, window, &visible)
+                    }
+                }
+        });
+        let window = window.map(|window| (window.id, tree::describe(window)));
+        if window.as_ref().map(|(id, _)| *id) == self.inhibiting {
+            return Ok(());
+        }
+        let Some(inhibitor) = &mut self.inhibitor else {
+            return Ok(());
+        };
+        match window {
+            Some((id, description)) => {
+                info!("keeping the screen awake for {description}");
+                inhibitor.inhibit(i3, id)?;
+                self.inhibiting = Some(id);
+            }
+            None => {
+                info!("letting the screen idle");
+                inhibitor.release(i3)?;
+                self.inhibiting = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `window` is on one of the `workspaces`.
+fn is_on(tree: &Node, window: &Node, workspaces: &[String]) -> bool {
+    tree::workspace_of(tree, window.id)
+        .and_then(|workspace| workspace.name.as_ref())
+        .is_some_and(|name| workspaces.contains(name))
+}
+
+/// Holds a logind idle inhibitor lock through `systemd-inhibit`.
+struct Systemd {
+    child: Option<Child>,
+}
+
+impl Inhibitor for Systemd {
+    fn inhibit(&mut self, _i3: &mut dyn I3Commands, _window: NodeId) -> io::Result<()> {
+        if self.child.is_none() {
+            let child = Command::new("systemd-inhibit")
+                .args(["--what=idle", "--who=i3-inhibit-idle"])
+                .args(["--why=A window is in use", "--mode=block"])
+                .args(["sleep", "infinity"])
+                .stdin(Stdio::null())
+                .spawn()?;
+            self.child = Some(child);
+        }
+        Ok(())
+    }
+
+    fn release(&mut self, _i3: &mut dyn I3Commands) -> io::Result<()> {
+        if let Some(mut child) = self.child.take() {
+            // systemd-inhibit may have failed already, which is fine.
+            let _ = child.kill();
+            child.wait()?;
+        }
+        Ok(())
+    }
+}
+
+/// Turns the X11 screen saver and DPMS off, and back on.
+struct Xset {
+    off: bool,
+}
+
+impl Xset {
+    fn run(args: &[&str]) -> io::Result<()> {
+        let status = Command::new("xset").args(args).status()?;
+        if !status.success() {
+            warn!("xset {} failed", args.join(" "));
+        }
+        Ok(())
+    }
+}
+
+impl Inhibitor for Xset {
+    fn inhibit(&mut self, _i3: &mut dyn I3Commands, _window: NodeId) -> io::Result<()> {
+        if !self.off {
+            Xset::run(&["s", "off", "-dpms"])?;
+            self.off = true;
+        }
+        Ok(())
+    }
+
+    fn release(&mut self, _i3: &mut dyn I3Commands) -> io::Result<()> {
+        if self.off {
+            Xset::run(&["s", "on", "+dpms"])?;
+            self.off = false;
+        }
+        Ok(())
+    }
+}
+
+/// Sets sway's `inhibit_idle` on the window keeping the screen awake.
+struct Sway {
+    backend: Box<dyn Backend>,
+    window: Option<NodeId>,
+}
+
+impl Inhibitor for Sway {
+    fn inhibit(&mut self, i3: &mut dyn I3Commands, window: NodeId) -> io::Result<()> {
+        self.release(i3)?;
+        if let Some(command) = self.backend.inhibit_idle_command(window, true) {
+            i3.run_command(&command)?;
+        }
+        self.window = Some(window);
+        Ok(())
+    }
+
+    fn release(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let Some(window) = self.window.take() else {
+            return Ok(());
+        };
+        // The window may be gone, in which case sway already let go.
+        if let Some(command) = self.backend.inhibit_idle_command(window, false) {
+            i3.run_command(&command)?;
+        }
+        Ok(())
+    }
+}
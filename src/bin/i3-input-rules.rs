@@ -0,0 +1,215 @@
+//! Utility to configure input devices by focused window, on sway.
+//!
+//! This program listens for focus events and applies the input settings of
+//! the first rule matching the focused window, e.g. natural scrolling in a
+//! document viewer or another keyboard variant in a game, through sway's
+//! `input` commands. Once focus moves to a window no rule matches, the default
+//! settings are applied again. Under i3, which leaves input devices to X11,
+//! it does nothing.
+//!
+//! # Configuration
+//!
+//! Inputs are named as in sway's `input` command: by identifier, as listed by
+//! `swaymsg -t get_inputs`, by type, like `type:touchpad`, or `*` for all of
+//! them. Every input a rule configures needs defaults to restore:
+//!
+//! ```toml
+//! [[default]]
+//! input = "type:touchpad"
+//! settings = ["natural_scroll disabled"]
+//!
+//! [[rule]]
+//! match = { app_id = "org.gnome.Evince" }
+//! input = "type:touchpad"
+//! settings = ["natural_scroll enabled"]
+//! ```
+
+use clap::Parser;
+use i3_helpers::{
+    backend::{self, Backend},
+    cli,
+    config::{self, invalid_data},
+    criteria::{Criteria, CriteriaConfig},
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands, WindowManager,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData},
+    reply::Node,
+};
+use log::{info, warn};
+use serde::Deserialize;
+use std::{collections::BTreeMap, io, path::PathBuf};
+
+const CONFIG_FILE_NAME: &str = "input-rules.toml";
+
+/// Configure input devices by focused window, on sway.
+#[derive(Parser)]
+struct Args {
+    /// The configuration file defining the rules. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/input-rules.toml`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(rename = "default", default)]
+    defaults: Vec<InputSettings>,
+    #[serde(rename = "rule")]
+    rules: Vec<RuleConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    #[serde(rename = "match")]
+    criteria: CriteriaConfig,
+    input: String,
+    settings: Vec<String>,
+}
+
+/// Settings of an input, as given to sway's `input` command.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputSettings {
+    input: String,
+    settings: Vec<String>,
+}
+
+struct Rule {
+    criteria: Criteria,
+    settings: InputSettings,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let path = args
+        .config
+        .or_else(|| config::default_path(CONFIG_FILE_NAME))
+        .ok_or_else(|| invalid_data("cannot find the configuration file, use --config"))?;
+    let config: Config = config::load(&path)?;
+    let defaults: BTreeMap<String, Vec<String>> = config
+        .defaults
+        .into_iter()
+        .map(|default| (default.input, default.settings))
+        .collect();
+    let rules: Vec<Rule> = config
+        .rules
+        .into_iter()
+        .map(|rule| {
+            Ok(Rule {
+                criteria: rule.criteria.into_criteria()?,
+                settings: InputSettings {
+                    input: rule.input,
+                    settings: rule.settings,
+                },
+            })
+        })
+        .collect::<io::Result<_>>()?;
+    if let Some(rule) = rules
+        .iter()
+        .find(|rule| !defaults.contains_key(&rule.settings.input))
+    {
+        return Err(invalid_data(format!(
+            "input {:?} has no defaults to restore",
+            rule.settings.input
+        )));
+    }
+    let mut input_rules = InputRules {
+        rules,
+        defaults,
+        backend: None,
+        applied: BTreeMap::new(),
+    };
+    EventLoop::connect()?.run(&mut input_rules)
+}
+
+pub struct InputRules {
+    rules: Vec<Rule>,
+    /// The settings of each input while no rule applies to it.
+    defaults: BTreeMap<String, Vec<String>>,
+    /// Known once connected.
+    backend: Option<Box<dyn Backend>>,
+    /// The settings last applied to each input.
+    applied: BTreeMap<String, Vec<String>>,
+}
+
+impl Helper for InputRules {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let backend = backend::detect(i3)?;
+        let inputs = backend.get_inputs(i3)?;
+        if backend.window_manager() == WindowManager::I3 {
+            warn!("input devices can only be configured under sway, doing nothing");
+        }
+        for input in self.defaults.keys() {
+            let known = input == "*"
+                || input.starts_with("type:")
+                || inputs.iter().any(|known| &known.identifier == input);
+            if !known && !inputs.is_empty() {
+                warn!("no input device is identified by {input:?}");
+            }
+        }
+        self.backend = Some(backend);
+        let tree = i3.get_tree()?;
+        match tree::focused(&tree).filter(|node| tree::is_window(node)) {
+            Some(window) => self.apply(i3, window),
+            None => Ok(()),
+        }
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        match event.change {
+            WindowChange::Focus => self.apply(i3, &event.container),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl InputRules {
+    /// Apply the settings of the rules matching `window`, and the defaults of
+    /// the inputs they don't configure, unless already in effect.
+    fn apply(&mut self, i3: &mut dyn I3Commands, window: &Node) -> io::Result<()> {
+        let Some(backend) = &self.backend else {
+            return Ok(());
+        };
+        let mut commands = Vec::new();
+        for (input, defaults) in &self.defaults {
+            let rule = self
+                .rules
+                .iter()
+                .find(|rule| &rule.settings.input == input && rule.criteria.matches(window));
+            let settings = rule.map_or(defaults, |rule| &rule.settings.settings);
+            if self.applied.get(input) == Some(settings) {
+                continue;
+            }
+            let mut input_commands = Vec::new();
+            for setting in settings {
+                match backend.input_command(input, setting) {
+                    Some(command) => input_commands.push(command),
+                    None => return Ok(()),
+                }
+            }
+            info!(
+                "applying {} to {input} for {}",
+                match rule {
+                    Some(_) => "the settings of a rule",
+                    None => "the default settings",
+                },
+                tree::describe(window)
+            );
+            commands.extend(input_commands);
+            self.applied.insert(input.clone(), settings.clone());
+        }
+        i3.run_commands(&commands)
+    }
+}
@@ -0,0 +1,196 @@
+//! Utility to show the key bindings of the current i3 binding mode.
+//!
+//! Modes like the usual `resize` one are easy to enter and forget the keys of.
+//! This program listens for mode events from i3 and, whenever a mode other
+//! than the default one is entered, shows the bindings available in it, as
+//! found in the configuration i3 was loaded with, awesome-style. They are
+//! shown in a desktop notification, or fed to a program of choice, one per
+//! line, e.g. rofi or yad, which is killed once the mode is left:
+//!
+//! ```text
+//! exec --no-startup-id i3-keybind-overlay --command "yad --text-info --no-buttons --title bindings"
+//! ```
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    i3_config::{self, Binding, Mode},
+    logging::{self, Verbosity},
+    notification::{self, escape_markup, Notifier},
+    EventLoop, Helper, I3Commands,
+};
+use i3_ipc::event::{Event, Subscribe};
+use log::{debug, info, warn};
+use std::{
+    io::{self, Write},
+    process::{Child, Command, Stdio},
+};
+
+/// Show the key bindings of the current i3 binding mode.
+#[derive(Parser)]
+struct Args {
+    /// Show the bindings by running this command through `sh -c`, fed one
+    /// binding per line, instead of in a notification. It is killed once the
+    /// mode is left.
+    #[arg(long)]
+    command: Option<String>,
+
+    /// How long notifications stay up, in milliseconds, if the mode isn't
+    /// left before.
+    #[arg(long, default_value_t = 10_000)]
+    timeout_ms: u32,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let display: Box<dyn Display> = match args.command {
+        Some(command) => Box::new(Program {
+            command,
+            child: None,
+        }),
+        None => Box::new(Notification {
+            notifier: Notifier::new("i3-keybind-overlay"),
+            timeout_ms: args.timeout_ms,
+            id: None,
+        }),
+    };
+    EventLoop::connect()?.run(&mut KeybindOverlay { display })
+}
+
+/// Where to show the bindings of a mode.
+trait Display {
+    fn show(&mut self, mode: &Mode) -> io::Result<()>;
+
+    /// Take down what [`Display::show`] showed, if still up.
+    fn hide(&mut self) -> io::Result<()>;
+}
+
+pub struct KeybindOverlay {
+    display: Box<dyn Display>,
+}
+
+impl Helper for KeybindOverlay {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Mode]
+    }
+
+    fn on_event(&mut self, i3: &mut dyn I3Commands, event: &Event) -> io::Result<()> {
+        let Event::Mode(event) = event else {
+            return Ok(());
+        };
+        self.display.hide()?;
+        if event.change == "default" {
+            return Ok(());
+        }
+        // The configuration is fetched every time, so that it is up to date
+        // after i3 reloads it.
+        let config = i3_config::load(i3)?;
+        match config.mode(&event.change) {
+            Some(mode) => {
+                info!("showing the bindings of mode {:?}", mode.name);
+                self.display.show(mode)
+            }
+            None => {
+                warn!("mode {:?} is not in the configuration", event.change);
+                Ok(())
+            }
+        }
+    }
+
+    fn on_exit(&mut self, _i3: &mut dyn I3Commands) -> io::Result<()> {
+        self.display.hide()
+    }
+}
+
+/// The bindings of `mode`, one per line, with their commands aligned.
+fn lines(mode: &Mode) -> Vec<String> {
+    let width = mode
+        .bindings
+        .iter()
+        .map(|binding| binding.key.chars().count())
+        .max()
+        .unwrap_or_default();
+    mode.bindings
+        .iter()
+        .map(|Binding { key, command, .. }| format!("{key:width$}  {command}"))
+        .collect()
+}
+
+/// Shows bindings in a desktop notification.
+struct Notification {
+    notifier: Notifier,
+    timeout_ms: u32,
+    /// The notification currently up, if any.
+    id: Option<u32>,
+}
+
+impl Display for Notification {
+    fn show(&mut self, mode: &Mode) -> io::Result<()> {
+        let body = lines(mode)
+            .iter()
+            .map(|line| escape_markup(line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let notification = notification::Notification {
+            summary: format!("Mode {}", mode.name),
+            body,
+            timeout_ms: Some(self.timeout_ms),
+            ..Default::default()
+        };
+        match self.notifier.notify(&notification, None) {
+            Ok(id) => self.id = Some(id),
+            Err(err) => warn!("failed to show the bindings: {err}"),
+        }
+        Ok(())
+    }
+
+    fn hide(&mut self) -> io::Result<()> {
+        let Some(id) = self.id.take() else {
+            return Ok(());
+        };
+        if let Err(err) = self.notifier.close(id) {
+            debug!("failed to close notification {id}: {err}");
+        }
+        Ok(())
+    }
+}
+
+/// Shows bindings by feeding them to a program.
+struct Program {
+    command: String,
+    child: Option<Child>,
+}
+
+impl Display for Program {
+    fn show(&mut self, mode: &Mode) -> io::Result<()> {
+        let mut child = Command::new("sh")
+            .args(["-c", &self.command])
+            .env("I3_MODE", &mode.name)
+            .stdin(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            for line in lines(mode) {
+                // The program may not read all of its input.
+                if writeln!(stdin, "{line}").is_err() {
+                    break;
+                }
+            }
+        }
+        self.child = Some(child);
+        Ok(())
+    }
+
+    fn hide(&mut self) -> io::Result<()> {
+        let Some(mut child) = self.child.take() else {
+            return Ok(());
+        };
+        // The program may have exited already, which is fine.
+        let _ = child.kill();
+        child.wait()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+//! Utility to close all the windows matching some criteria.
+//!
+//! This program walks the layout tree and closes every window matching the
+//! given criteria, optionally only on one workspace and sparing the focused
+//! window. Useful to clean up a workspace full of popups or to close all the
+//! windows of an application at once.
+
+use clap::{ArgGroup, Parser};
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    criteria::CriteriaArgs,
+    logging::{self, Verbosity},
+    tree, Connection, I3Commands,
+};
+use log::{info, LevelFilter};
+use std::io;
+
+/// Close all the windows matching some criteria.
+///
+/// When several criteria are given, windows must match all of them.
+#[derive(Parser)]
+#[command(group(ArgGroup::new("criteria").required(true).multiple(true)))]
+struct Args {
+    #[command(flatten)]
+    criteria: CriteriaArgs,
+
+    /// Only close windows on this workspace (by name), including floating
+    /// ones.
+    #[arg(short, long, group = "criteria")]
+    workspace: Option<String>,
+
+    /// Leave the focused window open, even if it matches.
+    #[arg(long)]
+    except_focused: bool,
+
+    /// Log which windows would be closed, without actually closing them.
+    #[arg(long)]
+    dry_run: bool,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    let mut level = args.verbosity.level();
+    if args.dry_run {
+        level = level.max(LevelFilter::Info);
+    }
+    logging::init(level);
+    let criteria = args.criteria.into_criteria();
+    let mut i3 = Connection::connect()?;
+    i3.set_dry_run(args.dry_run);
+    let tree = i3.get_tree()?;
+    let root = match &args.workspace {
+        Some(name) => tree::workspaces(&tree)
+            .find(|workspace| workspace.name.as_ref() == Some(name))
+            .ok_or_else(|| io::Error::other(format!("no workspace named {name:?}")))?,
+        None => &tree,
+    };
+    let commands: Vec<String> = tree::windows(root)
+        .filter(|window| criteria.matches(window))
+        .filter(|window| !(args.except_focused && window.focused))
+        .map(|window| {
+            info!("closing {}", tree::describe(window));
+            CommandBuilder::con_id(window.id)
+                .action(Action::Kill)
+                .build()
+        })
+        .collect();
+    if commands.is_empty() {
+        info!("no window to close");
+        return Ok(());
+    }
+    i3.run_commands(&commands)
+}
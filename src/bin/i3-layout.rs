@@ -0,0 +1,226 @@
+//! Utility to save the layout of a workspace and restore it later.
+//!
+//! `save NAME` dumps the focused workspace to `NAME.json` in the layouts
+//! directory, in the format expected by i3's `append_layout` command: volatile
+//! fields are stripped and each window is replaced by swallow criteria built
+//! from its class and instance, and optionally its title.
+//!
+//! Alongside, `NAME.toml` lists the commands launching each window of the
+//! layout. It is generated on the first save, guessing the commands from the
+//! window classes, and is left alone afterwards so that it can be edited:
+//!
+//! ```toml
+//! [[window]]
+//! class = "firefox"
+//! exec = "firefox"
+//! ```
+//!
+//! `restore NAME` appends the layout to the focused workspace, where its
+//! placeholders swallow matching windows as they appear, and launches the
+//! windows of each class that are missing from that workspace. Placeholders
+//! only swallow new windows, so windows running on other workspaces are
+//! launched again rather than moved in.
+
+use clap::{Parser, Subcommand};
+use i3_helpers::{
+    cli,
+    command::quote,
+    config::{self, invalid_data},
+    logging::{self, Verbosity},
+    tree, Connection, I3Commands,
+};
+use i3_ipc::reply::{Node, NodeType};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Save the layout of a workspace and restore it later.
+#[derive(Parser)]
+struct Args {
+    /// The directory holding the layouts. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/layouts`.
+    #[arg(long)]
+    dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    action: Action,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Subcommand)]
+enum Action {
+    /// Save the layout of the focused workspace.
+    Save {
+        name: String,
+        /// Also match windows by their current title when restoring.
+        #[arg(long)]
+        match_title: bool,
+    },
+    /// Restore a layout on the focused workspace, launching missing windows.
+    Restore { name: String },
+}
+
+/// The commands launching the windows of a layout.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct ExecList {
+    #[serde(rename = "window", default)]
+    windows: Vec<WindowExec>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct WindowExec {
+    class: String,
+    exec: String,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let dir = args
+        .dir
+        .or_else(|| config::default_path("layouts"))
+        .ok_or_else(|| invalid_data("cannot find the layouts directory, use --dir"))?;
+    let mut i3 = Connection::connect()?;
+    match args.action {
+        Action::Save { name, match_title } => save(&mut i3, &dir, &name, match_title),
+        Action::Restore { name } => restore(&mut i3, &dir, &name),
+    }
+}
+
+fn save(i3: &mut dyn I3Commands, dir: &Path, name: &str, match_title: bool) -> io::Result<()> {
+    let tree = i3.get_tree()?;
+    let workspace = tree::focused(&tree)
+        .and_then(|focused| match focused.node_type {
+            NodeType::Workspace => Some(focused),
+            _ => tree::workspace_of(&tree, focused.id),
+        })
+        .ok_or_else(|| io::Error::other("no focused workspace"))?;
+    // i3 expects the top-level containers one after the other, not in an array.
+    let mut layout = String::new();
+    for node in tree::children(workspace) {
+        layout += &serde_json::to_string_pretty(&clean(node, match_title))?;
+        layout += "\n";
+    }
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(format!("{name}.json")), layout)?;
+
+    let exec_path = dir.join(format!("{name}.toml"));
+    if !exec_path.exists() {
+        let windows = tree::windows(workspace)
+            .filter_map(class)
+            .map(|class| WindowExec {
+                class: class.to_owned(),
+                exec: class.to_lowercase(),
+            })
+            .collect();
+        let exec_list = toml::to_string(&ExecList { windows }).map_err(invalid_data)?;
+        fs::write(&exec_path, exec_list)?;
+        log::info!(
+            "review the commands launching each window in {}",
+            exec_path.display()
+        );
+    }
+    Ok(())
+}
+
+fn restore(i3: &mut dyn I3Commands, dir: &Path, name: &str) -> io::Result<()> {
+    let layout_path = dir.join(format!("{name}.json"));
+    if !layout_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no layout named {name:?} in {}", dir.display()),
+        ));
+    }
+    let exec_path = dir.join(format!("{name}.toml"));
+    let exec_list = match exec_path.exists() {
+        true => config::load(&exec_path)?,
+        false => ExecList {
+            windows: Vec::new(),
+        },
+    };
+    // Count the windows of each class already on the workspace, e.g. from a
+    // previous restore, which the layout doesn't need launched again. Windows
+    // elsewhere wouldn't end up in the placeholders.
+    let tree = i3.get_tree()?;
+    let workspace =
+        tree::focused_workspace(&tree).ok_or_else(|| io::Error::other("no focused workspace"))?;
+    let mut running: HashMap<&str, usize> = HashMap::new();
+    for class in tree::windows(workspace).filter_map(class) {
+        *running.entry(class).or_default() += 1;
+    }
+    let path = layout_path
+        .to_str()
+        .ok_or_else(|| invalid_data("the layout path is not valid UTF-8"))?;
+    i3.run_command(&format!("append_layout {}", quote(path)))?;
+    for window in &exec_list.windows {
+        match running.get_mut(window.class.as_str()) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => i3.run_command(&format!("exec --no-startup-id {}", window.exec))?,
+        }
+    }
+    Ok(())
+}
+
+/// The layout of `node` without the volatile fields, as `append_layout`
+/// expects it.
+fn clean(node: &Node, match_title: bool) -> Value {
+    let mut layout = json!({
+        "type": node.node_type,
+        "border": node.border,
+        "current_border_width": node.current_border_width,
+        "layout": node.layout,
+        "percent": node.percent,
+    });
+    if let Some(marks) = &node.marks {
+        layout["marks"] = json!(marks.0);
+    }
+    if let Some(floating) = node.floating {
+        layout["floating"] = json!(floating);
+    }
+    if node.node_type == NodeType::FloatingCon {
+        layout["rect"] = json!(node.rect);
+    }
+    if tree::is_window(node) {
+        layout["name"] = json!(node.name);
+        layout["geometry"] = json!(node.geometry);
+        layout["swallows"] = json!([swallows(node, match_title)]);
+    } else {
+        let nodes: Vec<Value> = tree::children(node)
+            .map(|child| clean(child, match_title))
+            .collect();
+        layout["nodes"] = json!(nodes);
+    }
+    layout
+}
+
+/// The criteria matching `window`, as exact regular expressions.
+fn swallows(window: &Node, match_title: bool) -> Value {
+    let exact = |value: &str| format!("^{}$", regex::escape(value));
+    let mut swallows = json!({});
+    if let Some(props) = &window.window_properties {
+        if let Some(class) = &props.class {
+            swallows["class"] = json!(exact(class));
+        }
+        if let Some(instance) = &props.instance {
+            swallows["instance"] = json!(exact(instance));
+        }
+        if match_title {
+            if let Some(title) = &props.title {
+                swallows["title"] = json!(exact(title));
+            }
+        }
+    }
+    swallows
+}
+
+fn class(window: &Node) -> Option<&str> {
+    window.window_properties.as_ref()?.class.as_deref()
+}
@@ -0,0 +1,306 @@
+//! Utility to keep a master and stack layout on some workspaces, dwm-style.
+//!
+//! This program listens for events from i3 and, whenever windows are opened,
+//! closed or moved, rearranges the tiled windows of the managed workspaces so
+//! that the first one, the master, takes a fixed share of the workspace and
+//! the others are stacked next to it in a single container.
+//!
+//! Windows are only moved around through `split`, `move container to mark`,
+//! `layout` and `resize` commands, so the layout stays a regular i3 layout
+//! that can still be changed by hand in between.
+//!
+//! # Configuration
+//!
+//! Each workspace can have its own settings when loaded from a TOML file:
+//!
+//! ```toml
+//! [[workspace]]
+//! name = "1"
+//! master_size = 60
+//! stack = "right"
+//!
+//! [[workspace]]
+//! name = "2: web"
+//! stack = "tabbed"
+//! ```
+
+use clap::{Parser, ValueEnum};
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder, Layout, Orientation},
+    config,
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData},
+    reply::{Node, NodeLayout},
+};
+use log::debug;
+use serde::Deserialize;
+use std::{io, path::PathBuf};
+
+/// Keep a master and stack layout on some workspaces.
+///
+/// The first tiled window of each managed workspace is the master, and the
+/// others are stacked next to it.
+#[derive(Parser)]
+#[command(group(clap::ArgGroup::new("workspaces").required(true)))]
+struct Args {
+    /// A workspace (by name) to manage. Can be given multiple times.
+    #[arg(short, long, group = "workspaces")]
+    workspace: Vec<String>,
+
+    /// The share of the workspace taken by the master window, in percent.
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u32).range(10..=90))]
+    master_size: u32,
+
+    /// Where and how to lay out the other windows.
+    #[arg(long, value_enum, default_value_t = Stack::Right)]
+    stack: Stack,
+
+    /// Load the managed workspaces and their settings from a TOML file instead
+    /// of the command line arguments.
+    #[arg(long, group = "workspaces", conflicts_with = "workspace")]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Clone, Copy, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+enum Stack {
+    /// Master on the left, the others split vertically on the right.
+    Right,
+    /// Master on top, the others split horizontally at the bottom.
+    Bottom,
+    /// Master on the left, the others tabbed on the right.
+    Tabbed,
+    /// Master on the left, the others stacked on the right.
+    Stacked,
+}
+
+impl Stack {
+    /// The layout of the workspace holding the master and the stack.
+    fn workspace_layout(self) -> NodeLayout {
+        match self {
+            Stack::Bottom => NodeLayout::SplitV,
+            _ => NodeLayout::SplitH,
+        }
+    }
+
+    /// The layout of the container holding the stacked windows.
+    fn stack_layout(self) -> NodeLayout {
+        match self {
+            Stack::Right => NodeLayout::SplitV,
+            Stack::Bottom => NodeLayout::SplitH,
+            Stack::Tabbed => NodeLayout::Tabbed,
+            Stack::Stacked => NodeLayout::Stacked,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(rename = "workspace")]
+    workspaces: Vec<WorkspaceConfig>,
+}
+
+/// How to lay out a managed workspace.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkspaceConfig {
+    name: String,
+    #[serde(default = "default_master_size")]
+    master_size: u32,
+    #[serde(default = "default_stack")]
+    stack: Stack,
+}
+
+fn default_master_size() -> u32 {
+    60
+}
+
+fn default_stack() -> Stack {
+    Stack::Right
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let workspaces = match &args.config {
+        Some(path) => config::load::<Config>(path)?.workspaces,
+        None => args
+            .workspace
+            .iter()
+            .map(|name| WorkspaceConfig {
+                name: name.clone(),
+                master_size: args.master_size,
+                stack: args.stack,
+            })
+            .collect(),
+    };
+    if let Some(workspace) = workspaces
+        .iter()
+        .find(|workspace| !(10..=90).contains(&workspace.master_size))
+    {
+        return Err(config::invalid_data(format!(
+            "the master size of workspace {:?} must be between 10 and 90",
+            workspace.name
+        )));
+    }
+    EventLoop::connect()?.run(&mut MasterStack { workspaces })
+}
+
+/// Temporary mark used as the destination of stacked windows.
+const STACK_MARK: &str = "_master_stack";
+
+pub struct MasterStack {
+    workspaces: Vec<WorkspaceConfig>,
+}
+
+impl Helper for MasterStack {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        self.arrange_all(i3, true)
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        match event.change {
+            // Windows coming and going change the share of the master, which
+            // we restore. Other changes may be manual resizes we leave alone.
+            WindowChange::New | WindowChange::Close => self.arrange_all(i3, true),
+            WindowChange::Move | WindowChange::Floating => self.arrange_all(i3, false),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl MasterStack {
+    fn arrange_all(&self, i3: &mut dyn I3Commands, resize: bool) -> io::Result<()> {
+        let tree = i3.get_tree()?;
+        for workspace in tree::workspaces(&tree) {
+            let config = self
+                .workspaces
+                .iter()
+                .find(|config| workspace.name.as_ref() == Some(&config.name));
+            if let Some(config) = config {
+                for command in arrange(workspace, config, resize) {
+                    i3.run_command(&command)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The commands turning the tiled windows of `workspace` into a master and
+/// stack layout. Empty if the layout is already right.
+fn arrange(workspace: &Node, config: &WorkspaceConfig, resize: bool) -> Vec<String> {
+    let windows: Vec<&Node> = workspace.nodes.iter().flat_map(tree::windows).collect();
+    if windows.len() < 2 {
+        return Vec::new();
+    }
+    // The master is the first window sitting right on the workspace. Anything
+    // else was rearranged by hand in ways we don't try to undo.
+    let Some(master) = workspace.nodes.iter().find(|node| tree::is_window(node)) else {
+        debug!(
+            "no window directly on workspace {:?}, leaving it",
+            workspace.name.as_deref().unwrap_or_default()
+        );
+        return Vec::new();
+    };
+    let stacked: Vec<&Node> = windows
+        .into_iter()
+        .filter(|window| window.id != master.id)
+        .collect();
+    let first = stacked[0];
+    let mut commands = Vec::new();
+    if workspace.nodes[0].id != master.id {
+        commands.push(
+            CommandBuilder::con_id(master.id)
+                .action(Action::Swap(workspace.nodes[0].id))
+                .build(),
+        );
+    }
+    if workspace.layout != config.stack.workspace_layout() {
+        let layout = Action::Layout(layout_of(config.stack.workspace_layout()));
+        commands.push(CommandBuilder::con_id(master.id).action(layout).build());
+    }
+    if stacked.len() > 1 {
+        // The stack is the container of the first stacked window, created if
+        // that window sits right on the workspace.
+        let parent = tree::parent_of(workspace, first.id);
+        let stack = parent.filter(|parent| parent.id != workspace.id);
+        if stack.is_none() {
+            let split = Action::Split(Orientation::Vertical);
+            commands.push(CommandBuilder::con_id(first.id).action(split).build());
+        }
+        let misplaced: Vec<&Node> = match stack {
+            Some(stack) => stacked[1..]
+                .iter()
+                .copied()
+                .filter(|window| !stack.nodes.iter().any(|node| node.id == window.id))
+                .collect(),
+            None => stacked[1..].to_vec(),
+        };
+        if !misplaced.is_empty() {
+            commands.push(mark_stack(first.id));
+            // Each window lands right after the mark, so going backwards keeps
+            // them in order.
+            for window in misplaced.iter().rev() {
+                commands.push(move_to_stack(window.id));
+            }
+            commands.push(unmark_stack());
+        }
+        if stack.is_none_or(|stack| stack.layout != config.stack.stack_layout()) {
+            let layout = Action::Layout(layout_of(config.stack.stack_layout()));
+            commands.push(CommandBuilder::con_id(first.id).action(layout).build());
+        }
+    } else if tree::parent_of(workspace, first.id).is_some_and(|parent| parent.id != workspace.id) {
+        // A single stacked window sits right next to the master.
+        commands.push(mark_stack(master.id));
+        commands.push(move_to_stack(first.id));
+        commands.push(unmark_stack());
+    }
+    let share = f64::from(config.master_size) / 100.0;
+    let master_share = master.percent.unwrap_or_default();
+    if (resize || !commands.is_empty()) && (master_share - share).abs() > 0.01 {
+        let orientation = match config.stack.workspace_layout() {
+            NodeLayout::SplitV => Orientation::Vertical,
+            _ => Orientation::Horizontal,
+        };
+        let resize = Action::ResizeShare(orientation, config.master_size);
+        commands.push(CommandBuilder::con_id(master.id).action(resize).build());
+    }
+    commands
+}
+
+fn layout_of(layout: NodeLayout) -> Layout {
+    match layout {
+        NodeLayout::SplitV => Layout::SplitV,
+        NodeLayout::Tabbed => Layout::Tabbed,
+        NodeLayout::Stacked => Layout::Stacking,
+        _ => Layout::SplitH,
+    }
+}
+
+fn mark_stack(id: usize) -> String {
+    let mark = Action::Mark(STACK_MARK.to_owned());
+    CommandBuilder::con_id(id).action(mark).build()
+}
+
+fn move_to_stack(id: usize) -> String {
+    let r#move = Action::MoveToMark(STACK_MARK.to_owned());
+    CommandBuilder::con_id(id).action(r#move).build()
+}
+
+fn unmark_stack() -> String {
+    let unmark = Action::Unmark(STACK_MARK.to_owned());
+    CommandBuilder::new().action(unmark).build()
+}
@@ -0,0 +1,197 @@
+//! Utility to maximize the focused window and put it back where it was.
+//!
+//! i3's fullscreen mode hides the bars, and making a window floating to resize
+//! it loses its place in the layout. This program instead moves the focused
+//! tiling window alone to a temporary workspace, where it takes all the space
+//! but the bars, and moves it back to its exact place in the layout when run
+//! again.
+//!
+//! The window is put back next to the sibling it had, window or split
+//! container, which is marked for the purpose. Where to find it is recorded in
+//! a mark on the window itself, so that no state needs to be kept between
+//! runs: marks starting with an underscore aren't shown by i3.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{quote, Action, CommandBuilder, CommandQueue},
+    logging::{self, Verbosity},
+    tree, Connection, I3Commands,
+};
+use i3_ipc::reply::Node;
+use log::{info, warn};
+use std::io;
+
+/// Prefix of the mark set on maximized windows, followed by their id, where to
+/// put them back and the workspace they come from, separated by colons.
+const MARK_PREFIX: &str = "_maximized";
+
+/// Prefix of the mark set on the sibling of a maximized window, followed by
+/// the id of the window.
+const ANCHOR_PREFIX: &str = "_maximized-anchor";
+
+/// Prefix of the mark set on the parent of the sibling while putting a window
+/// back, followed by the id of the window.
+const PARENT_PREFIX: &str = "_maximized-parent";
+
+/// Maximize the focused window, or put it back where it was.
+#[derive(Parser)]
+struct Args {
+    /// Prefix of the name of the temporary workspace, followed by the name of
+    /// the workspace the window comes from.
+    #[arg(long, default_value = "max:")]
+    workspace_prefix: String,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+/// Where to put a maximized window back, relative to its anchor.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Placement {
+    /// After the anchor, which is where i3 moves containers to a mark on
+    /// their parent once the anchor is focused.
+    After,
+    /// Before the anchor, by swapping the window with it once after it.
+    Before,
+    /// Alone on its workspace, without any anchor.
+    Alone,
+}
+
+impl Placement {
+    fn name(self) -> &'static str {
+        match self {
+            Placement::After => "after",
+            Placement::Before => "before",
+            Placement::Alone => "alone",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "after" => Some(Placement::After),
+            // Marks set by earlier versions, which moved the window instead.
+            "before" | "before-left" | "before-up" => Some(Placement::Before),
+            "alone" => Some(Placement::Alone),
+            _ => None,
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let tree = i3.get_tree()?;
+    let focused = tree::focused(&tree)
+        .filter(|node| tree::is_window(node))
+        .ok_or_else(|| io::Error::other("no focused window"))?;
+    let mark = focused
+        .marks
+        .iter()
+        .flat_map(|marks| &marks.0)
+        .find(|mark| {
+            mark.strip_prefix(MARK_PREFIX)
+                .is_some_and(|rest| rest.starts_with(':'))
+        });
+    match mark {
+        Some(mark) => restore(&mut i3, &tree, focused, mark),
+        None => maximize(&mut i3, &tree, focused, &args.workspace_prefix),
+    }
+}
+
+fn maximize(
+    i3: &mut dyn I3Commands,
+    tree: &Node,
+    window: &Node,
+    workspace_prefix: &str,
+) -> io::Result<()> {
+    if tree::is_floating(window) {
+        return Err(io::Error::other("floating windows cannot be maximized"));
+    }
+    let workspace = tree::workspace_of(tree, window.id)
+        .and_then(|ws| ws.name.as_deref())
+        .ok_or_else(|| io::Error::other("the window is on no workspace"))?;
+    let parent = tree::parent_of(tree, window.id)
+        .ok_or_else(|| io::Error::other("the window has no parent"))?;
+    let index = parent
+        .nodes
+        .iter()
+        .position(|node| node.id == window.id)
+        .unwrap_or_default();
+    // The window is put back after its anchor, so we would rather anchor it
+    // to its previous sibling.
+    let previous = index.checked_sub(1).map(|index| &parent.nodes[index]);
+    let next = parent.nodes.get(index + 1);
+    let (anchor, placement) = match (previous, next) {
+        (Some(previous), _) => (Some(previous), Placement::After),
+        (None, Some(next)) => (Some(next), Placement::Before),
+        (None, None) => (None, Placement::Alone),
+    };
+    let mut commands = CommandQueue::new();
+    if let Some(anchor) = anchor {
+        let anchor_mark = format!("{ANCHOR_PREFIX}:{}", window.id);
+        commands.push(CommandBuilder::con_id(anchor.id).action(Action::Mark(anchor_mark)));
+    }
+    let target = format!("{workspace_prefix}{workspace}");
+    let mark = format!(
+        "{MARK_PREFIX}:{}:{}:{workspace}",
+        window.id,
+        placement.name()
+    );
+    info!("maximizing {} on {target:?}", tree::describe(window));
+    commands.push(
+        CommandBuilder::con_id(window.id)
+            .action(Action::Mark(mark))
+            .action(Action::MoveToWorkspace(target.clone())),
+    );
+    commands.push(format!(
+        "workspace --no-auto-back-and-forth {}",
+        quote(&target)
+    ));
+    commands.flush(i3)
+}
+
+fn restore(i3: &mut dyn I3Commands, tree: &Node, window: &Node, mark: &str) -> io::Result<()> {
+    let mut fields = mark.splitn(4, ':').skip(2);
+    let placement = fields.next().and_then(Placement::from_name);
+    let (Some(placement), Some(workspace)) = (placement, fields.next()) else {
+        return Err(io::Error::other(format!("invalid mark {mark:?}")));
+    };
+    let anchor_mark = format!("{ANCHOR_PREFIX}:{}", window.id);
+    let anchor = tree::descendants(tree).find(|node| {
+        node.marks
+            .as_ref()
+            .is_some_and(|marks| marks.0.contains(&anchor_mark))
+    });
+    let parent = anchor.and_then(|anchor| tree::parent_of(tree, anchor.id));
+    let mut commands = CommandQueue::new();
+    let command = CommandBuilder::con_id(window.id).action(Action::Unmark(mark.to_owned()));
+    match (anchor.zip(parent), placement) {
+        (Some((anchor, parent)), Placement::After | Placement::Before) => {
+            info!("putting {} back in place", tree::describe(window));
+            // Moving to a mark on a window would do, but on a split container
+            // i3 would move the window inside it. Moving to a mark on the
+            // parent instead puts the window right after its focused child.
+            let parent_mark = format!("{PARENT_PREFIX}:{}", window.id);
+            commands
+                .push(CommandBuilder::con_id(parent.id).action(Action::Mark(parent_mark.clone())));
+            commands.push(CommandBuilder::con_id(anchor.id).action(Action::Focus));
+            commands.push(command.action(Action::MoveToMark(parent_mark.clone())));
+            if placement == Placement::Before {
+                commands.push(CommandBuilder::con_id(window.id).action(Action::Swap(anchor.id)));
+            }
+            commands.push(CommandBuilder::new().action(Action::Unmark(anchor_mark)));
+            commands.push(CommandBuilder::new().action(Action::Unmark(parent_mark)));
+        }
+        _ => {
+            if placement != Placement::Alone {
+                warn!("the window next to {} is gone", tree::describe(window));
+            }
+            info!("putting {} back on {workspace:?}", tree::describe(window));
+            commands.push(command.action(Action::MoveToWorkspace(workspace.to_owned())));
+        }
+    }
+    commands.push(CommandBuilder::con_id(window.id).action(Action::Focus));
+    commands.flush(i3)
+}
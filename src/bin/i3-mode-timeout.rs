@@ -0,0 +1,43 @@
+//! Utility to leave binding modes that are left unused.
+//!
+//! This program listens for events from i3 and switches back to the default
+//! binding mode once a mode, like a resize mode, has been active for
+//! `--timeout` without any of its bindings being pressed, so that a forgotten
+//! mode doesn't keep hijacking the keyboard. Each binding pressed in the mode
+//! restarts the timer:
+//!
+//! ```text
+//! exec --no-startup-id i3-mode-timeout --timeout 3s --mode resize
+//! ```
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    helpers::mode_timeout::ModeTimeout,
+    logging::{self, Verbosity},
+    EventLoop,
+};
+use std::{io, time::Duration};
+
+/// Leave binding modes that are left unused.
+#[derive(Parser)]
+struct Args {
+    /// How long a mode may stay unused, e.g. `500ms` or `5s`.
+    #[arg(long, default_value = "5s", value_parser = cli::parse_duration)]
+    timeout: Duration,
+
+    /// A mode to leave when unused, instead of all of them. Can be given
+    /// multiple times.
+    #[arg(long)]
+    mode: Vec<String>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut mode_timeout = ModeTimeout::new(args.timeout, args.mode);
+    EventLoop::connect()?.run(&mut mode_timeout)
+}
@@ -0,0 +1,395 @@
+//! Utility to send desktop notifications on i3 events.
+//!
+//! This program listens for events from i3 and shows a notification for each
+//! rule matching one, e.g. when a window becomes urgent on another workspace,
+//! a window is sent back to the scratchpad by i3-back-to-scratch, or a monitor
+//! is unplugged.
+//!
+//! # Configuration
+//!
+//! Each rule handles one `event`: `urgent`, when a window becomes urgent,
+//! `scratchpad`, when a window is moved to the scratchpad, `workspace_empty`,
+//! when the last window of a workspace is closed or moved away, and
+//! `output_connected` and `output_disconnected`. Rules for window events can
+//! be limited to some windows with `match`.
+//!
+//! The summary and body are templates that can refer to `{title}`, `{class}`,
+//! `{instance}`, `{app_id}` and `{workspace}` for window events, `{workspace}`
+//! for `workspace_empty` and `{output}` for output events:
+//!
+//! ```toml
+//! [[rule]]
+//! event = "urgent"
+//! match = { class = "Slack" }
+//! summary = "{class}"
+//! body = "{title}, on workspace {workspace}"
+//! urgency = "critical"
+//!
+//! [[rule]]
+//! event = "output_disconnected"
+//! summary = "{output} disconnected"
+//! icon = "video-display"
+//! timeout_ms = 3000
+//! ```
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    config::{self, invalid_data},
+    criteria::{Criteria, CriteriaConfig},
+    logging::{self, Verbosity},
+    notification::{escape_markup, Notification, Notifier, Urgency},
+    tree::{self, SCRATCHPAD_WORKSPACE},
+    EventLoop, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Event, Subscribe, WindowChange, WindowData},
+    reply::Node,
+};
+use log::{info, warn};
+use serde::Deserialize;
+use std::{collections::HashSet, io, path::PathBuf, str::FromStr};
+
+const CONFIG_FILE_NAME: &str = "notify-events.toml";
+
+/// Send desktop notifications on i3 events.
+#[derive(Parser)]
+struct Args {
+    /// The configuration file defining the rules. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/notify-events.toml`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(rename = "rule")]
+    rules: Vec<RuleConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    event: EventKind,
+    #[serde(rename = "match")]
+    criteria: Option<CriteriaConfig>,
+    summary: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    icon: String,
+    #[serde(default)]
+    urgency: Urgency,
+    timeout_ms: Option<u32>,
+}
+
+/// The events rules can notify of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EventKind {
+    Urgent,
+    Scratchpad,
+    WorkspaceEmpty,
+    OutputConnected,
+    OutputDisconnected,
+}
+
+impl EventKind {
+    /// The placeholders the templates of the rules for this event can use.
+    fn placeholders(self) -> &'static [&'static str] {
+        match self {
+            EventKind::Urgent | EventKind::Scratchpad => {
+                &["title", "class", "instance", "app_id", "workspace"]
+            }
+            EventKind::WorkspaceEmpty => &["workspace"],
+            EventKind::OutputConnected | EventKind::OutputDisconnected => &["output"],
+        }
+    }
+
+    fn is_about_windows(self) -> bool {
+        matches!(self, EventKind::Urgent | EventKind::Scratchpad)
+    }
+}
+
+struct Rule {
+    event: EventKind,
+    /// The windows the rule is limited to, for window events.
+    criteria: Option<Criteria>,
+    summary: Template,
+    body: Template,
+    icon: String,
+    urgency: Urgency,
+    timeout_ms: Option<u32>,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let path = args
+        .config
+        .or_else(|| config::default_path(CONFIG_FILE_NAME))
+        .ok_or_else(|| invalid_data("cannot find the configuration file, use --config"))?;
+    let config: Config = config::load(&path)?;
+    let rules = config
+        .rules
+        .into_iter()
+        .map(RuleConfig::into_rule)
+        .collect::<io::Result<_>>()?;
+    let mut notify_events = NotifyEvents {
+        rules,
+        notifier: Notifier::new("i3-notify-events"),
+        non_empty_workspaces: HashSet::new(),
+        active_outputs: HashSet::new(),
+    };
+    EventLoop::connect()?.run(&mut notify_events)
+}
+
+impl RuleConfig {
+    fn into_rule(self) -> io::Result<Rule> {
+        let criteria = match self.criteria {
+            Some(_) if !self.event.is_about_windows() => {
+                return Err(invalid_data("`match` can only be used with window events"));
+            }
+            Some(criteria) => Some(criteria.into_criteria()?),
+            None => None,
+        };
+        let template = |text: &str| {
+            let template: Template = text.parse().map_err(invalid_data)?;
+            template.check(self.event.placeholders())?;
+            Ok::<_, io::Error>(template)
+        };
+        Ok(Rule {
+            event: self.event,
+            criteria,
+            summary: template(&self.summary)?,
+            body: template(&self.body)?,
+            icon: self.icon,
+            urgency: self.urgency,
+            timeout_ms: self.timeout_ms,
+        })
+    }
+}
+
+/// A notification template, made of literal text and placeholders.
+struct Template(Vec<Segment>);
+
+enum Segment {
+    Literal(String),
+    Placeholder(String),
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("unclosed placeholder in {s:?}"))?;
+            segments.push(Segment::Literal(rest[..start].to_owned()));
+            segments.push(Segment::Placeholder(
+                rest[start + 1..start + end].to_owned(),
+            ));
+            rest = &rest[start + end + 1..];
+        }
+        segments.push(Segment::Literal(rest.to_owned()));
+        Ok(Template(segments))
+    }
+}
+
+impl Template {
+    /// Fail if the template uses placeholders other than `placeholders`.
+    fn check(&self, placeholders: &[&str]) -> io::Result<()> {
+        for segment in &self.0 {
+            if let Segment::Placeholder(name) = segment {
+                if !placeholders.contains(&name.as_str()) {
+                    return Err(invalid_data(format!(
+                        "unknown placeholder {{{name}}}, expected one of {}",
+                        placeholders.join(", ")
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill in the template with the values of `vars`, passed through
+    /// `escape`.
+    fn render(&self, vars: &[(&str, &str)], escape: impl Fn(&str) -> String) -> String {
+        let mut text = String::new();
+        for segment in &self.0 {
+            match segment {
+                Segment::Literal(literal) => text += literal,
+                Segment::Placeholder(name) => {
+                    let value = vars.iter().find(|(var, _)| var == name);
+                    text += &escape(value.map_or("", |(_, value)| value));
+                }
+            }
+        }
+        text
+    }
+}
+
+pub struct NotifyEvents {
+    rules: Vec<Rule>,
+    notifier: Notifier,
+    /// The workspaces holding windows, if there are `workspace_empty` rules.
+    non_empty_workspaces: HashSet<String>,
+    /// The active outputs, if there are output rules.
+    active_outputs: HashSet<String>,
+}
+
+impl Helper for NotifyEvents {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        let mut subscriptions = vec![Subscribe::Window];
+        if self.has_rules(&[EventKind::OutputConnected, EventKind::OutputDisconnected]) {
+            subscriptions.push(Subscribe::Output);
+        }
+        subscriptions
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        if self.has_rules(&[EventKind::WorkspaceEmpty]) {
+            self.non_empty_workspaces = non_empty_workspaces(&i3.get_tree()?);
+        }
+        if self.has_rules(&[EventKind::OutputConnected, EventKind::OutputDisconnected]) {
+            self.active_outputs = active_outputs(i3)?;
+        }
+        Ok(())
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        let window = &event.container;
+        let kind = match event.change {
+            WindowChange::Urgent if window.urgent => Some(EventKind::Urgent),
+            WindowChange::Move => Some(EventKind::Scratchpad),
+            _ => None,
+        };
+        let kind = kind.filter(|&kind| self.matching_rules(kind, Some(window)).next().is_some());
+        let tracks_workspaces = self.has_rules(&[EventKind::WorkspaceEmpty])
+            && matches!(
+                event.change,
+                WindowChange::New | WindowChange::Close | WindowChange::Move
+            );
+        if kind.is_none() && !tracks_workspaces {
+            return Ok(());
+        }
+        let tree = i3.get_tree()?;
+        let workspace = tree::workspace_of(&tree, window.id)
+            .and_then(|workspace| workspace.name.as_deref())
+            .unwrap_or_default();
+        match kind {
+            Some(EventKind::Scratchpad) if workspace != SCRATCHPAD_WORKSPACE => (),
+            Some(kind) => {
+                let props = window.window_properties.as_ref();
+                let vars = [
+                    ("title", window.name.as_deref().unwrap_or_default()),
+                    (
+                        "class",
+                        props
+                            .and_then(|props| props.class.as_deref())
+                            .unwrap_or_default(),
+                    ),
+                    (
+                        "instance",
+                        props
+                            .and_then(|props| props.instance.as_deref())
+                            .unwrap_or_default(),
+                    ),
+                    ("app_id", window.app_id.as_deref().unwrap_or_default()),
+                    ("workspace", workspace),
+                ];
+                self.notify(kind, Some(window), &vars);
+            }
+            None => (),
+        }
+        if tracks_workspaces {
+            let non_empty = non_empty_workspaces(&tree);
+            for emptied in self.non_empty_workspaces.difference(&non_empty) {
+                self.notify(EventKind::WorkspaceEmpty, None, &[("workspace", emptied)]);
+            }
+            self.non_empty_workspaces = non_empty;
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, i3: &mut dyn I3Commands, event: &Event) -> io::Result<()> {
+        if !matches!(event, Event::Output(_)) {
+            return Ok(());
+        }
+        let active = active_outputs(i3)?;
+        for output in active.difference(&self.active_outputs) {
+            self.notify(EventKind::OutputConnected, None, &[("output", output)]);
+        }
+        for output in self.active_outputs.difference(&active) {
+            self.notify(EventKind::OutputDisconnected, None, &[("output", output)]);
+        }
+        self.active_outputs = active;
+        Ok(())
+    }
+}
+
+impl NotifyEvents {
+    fn has_rules(&self, events: &[EventKind]) -> bool {
+        self.rules.iter().any(|rule| events.contains(&rule.event))
+    }
+
+    /// The rules for `event`, about `window` for window events.
+    fn matching_rules<'a>(
+        &'a self,
+        event: EventKind,
+        window: Option<&'a Node>,
+    ) -> impl Iterator<Item = &'a Rule> {
+        self.rules.iter().filter(move |rule| {
+            rule.event == event
+                && match (&rule.criteria, window) {
+                    (Some(criteria), Some(window)) => criteria.matches(window),
+                    _ => true,
+                }
+        })
+    }
+
+    /// Show the notifications of the rules for `event`, filled in with `vars`.
+    ///
+    /// Failures are only logged, as the notification daemon may come and go.
+    fn notify(&self, event: EventKind, window: Option<&Node>, vars: &[(&str, &str)]) {
+        for rule in self.matching_rules(event, window) {
+            let notification = Notification {
+                // Summaries are plain text, unlike bodies.
+                summary: rule.summary.render(vars, str::to_owned),
+                body: rule.body.render(vars, escape_markup),
+                icon: rule.icon.clone(),
+                urgency: rule.urgency,
+                timeout_ms: rule.timeout_ms,
+            };
+            info!("notifying: {}", notification.summary);
+            if let Err(err) = self.notifier.notify(&notification, None) {
+                warn!("failed to send a notification: {err}");
+            }
+        }
+    }
+}
+
+/// The names of the workspaces holding windows, the scratchpad aside.
+fn non_empty_workspaces(tree: &Node) -> HashSet<String> {
+    tree::workspaces(tree)
+        .filter(|workspace| tree::windows(workspace).next().is_some())
+        .filter_map(|workspace| workspace.name.clone())
+        .filter(|name| name != SCRATCHPAD_WORKSPACE)
+        .collect()
+}
+
+fn active_outputs(i3: &mut dyn I3Commands) -> io::Result<HashSet<String>> {
+    Ok(i3
+        .get_outputs()?
+        .into_iter()
+        .filter(|output| output.active)
+        .map(|output| output.name)
+        .collect())
+}
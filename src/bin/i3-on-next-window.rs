@@ -0,0 +1,80 @@
+//! Utility to run i3 commands on the next window matching some criteria.
+//!
+//! This program waits for the next new window matching the given criteria and
+//! runs the commands on that window only, by prefixing them with its
+//! `con_id`, then exits. Unlike a `for_window` rule, it applies once, to a
+//! window that didn't exist yet, so scripts can place the windows they launch:
+//!
+//! ```text
+//! i3-on-next-window --class mpv --exec 'mpv talk.mkv' -- 'move to workspace video, fullscreen enable'
+//! ```
+//!
+//! Commands are chained with commas, all applying to the window, and commands
+//! separated by `;` are chained with commas too, so that they also apply to
+//! the window only. With
+//! `--exec`, the program is launched once listening for new windows, so that
+//! its window can't be missed.
+
+use clap::{ArgGroup, Parser};
+use i3_helpers::{
+    cli,
+    command::{self, Action, CommandBuilder},
+    criteria::CriteriaArgs,
+    logging::{self, Verbosity},
+    tree,
+    wait::EventWaiter,
+    Connection, I3Commands,
+};
+use i3_ipc::event::Subscribe;
+use log::info;
+use std::{io, time::Duration};
+
+/// Run i3 commands on the next window matching some criteria.
+#[derive(Parser)]
+#[command(group(ArgGroup::new("criteria").required(true).multiple(true)))]
+struct Args {
+    #[command(flatten)]
+    criteria: CriteriaArgs,
+
+    /// A command launching the window, run by i3 once listening for it.
+    #[arg(long)]
+    exec: Option<String>,
+
+    /// How long to wait, e.g. `500ms` or `5s`. Waits forever if not given.
+    #[arg(long, value_parser = cli::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// The i3 commands to run on the window, e.g. `move to workspace 2,
+    /// floating enable`.
+    #[arg(last = true, required = true)]
+    commands: Vec<String>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let criteria = args.criteria.into_criteria();
+    let mut waiter = EventWaiter::subscribe(&[Subscribe::Window])?;
+    let mut i3 = Connection::connect()?;
+    if let Some(exec) = &args.exec {
+        // Let i3 spawn the program, so that it isn't tied to our process.
+        i3.run_command(&format!("exec --no-startup-id {exec}"))?;
+    }
+    let window = waiter
+        .wait_for_new_window(&criteria, args.timeout)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "no matching window appeared"))?;
+    info!("running the commands on {}", tree::describe(&window));
+    // Criteria only apply up to the next `;`, so chain all the commands
+    // with commas instead.
+    let commands = args.commands.join(" ");
+    let command = command::split_actions(&commands)
+        .into_iter()
+        .filter(|action| !action.is_empty())
+        .fold(CommandBuilder::con_id(window.id), |command, action| {
+            command.action(Action::Raw(action.to_owned()))
+        });
+    i3.run_command(&command.build())
+}
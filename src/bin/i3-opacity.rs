@@ -0,0 +1,189 @@
+//! Utility to dim unfocused windows and keep scratchpad windows translucent.
+//!
+//! This program listens for events from i3 and sets the opacity of windows as
+//! focus moves: the focused window is opaque, the others get the inactive
+//! opacity, and scratchpad windows always get their own, if given.
+//!
+//! Under sway, opacity is set with its `opacity` command. Under i3, it is set
+//! through the `_NET_WM_WINDOW_OPACITY` X11 property, which a compositor such
+//! as picom must be running to honor, and requires building with the `x11`
+//! feature. Windows get back to opaque when the program exits.
+
+use clap::{ArgGroup, Parser};
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    focus::FocusTracker,
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands, WindowManager,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData},
+    reply::Node,
+};
+use log::debug;
+use std::{collections::HashMap, io};
+
+/// Dim unfocused windows and keep scratchpad windows translucent.
+#[derive(Parser)]
+#[command(group(ArgGroup::new("opacity").required(true).multiple(true)))]
+struct Args {
+    /// The opacity of unfocused windows, from 0 to 1.
+    #[arg(long, group = "opacity", value_parser = parse_opacity)]
+    inactive: Option<f64>,
+
+    /// The opacity of scratchpad windows, focused or not, from 0 to 1.
+    #[arg(long, group = "opacity", value_parser = parse_opacity)]
+    scratchpad: Option<f64>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn parse_opacity(s: &str) -> Result<f64, String> {
+    match s.parse() {
+        Ok(opacity) if (0.0..=1.0).contains(&opacity) => Ok(opacity),
+        _ => Err(format!(
+            "invalid opacity {s:?}, expected a number from 0 to 1"
+        )),
+    }
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut opacity = Opacity {
+        backend: None,
+        inactive: args.inactive,
+        scratchpad: args.scratchpad,
+        focus: FocusTracker::new(),
+        opacities: HashMap::new(),
+    };
+    EventLoop::connect()?.run(&mut opacity)
+}
+
+type NodeId = usize;
+
+/// How opacities are applied to windows.
+enum Backend {
+    Sway,
+    #[cfg(feature = "x11")]
+    X11(Box<i3_helpers::x11::X11>),
+}
+
+pub struct Opacity {
+    /// Set up on start, once we know the window manager.
+    backend: Option<Backend>,
+    inactive: Option<f64>,
+    scratchpad: Option<f64>,
+    focus: FocusTracker,
+    /// The opacity we last set on each window.
+    opacities: HashMap<NodeId, f64>,
+}
+
+impl Helper for Opacity {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        self.backend = Some(match i3.window_manager()? {
+            WindowManager::Sway => Backend::Sway,
+            #[cfg(feature = "x11")]
+            WindowManager::I3 => Backend::X11(Box::new(i3_helpers::x11::X11::connect()?)),
+            #[cfg(not(feature = "x11"))]
+            WindowManager::I3 => {
+                return Err(io::Error::other(
+                    "setting opacity under i3 requires building with the x11 feature",
+                ))
+            }
+        });
+        let tree = i3.get_tree()?;
+        for window in tree::windows(&tree) {
+            if window.focused {
+                self.focus.update(&WindowData {
+                    change: WindowChange::Focus,
+                    container: window.clone(),
+                });
+            }
+            self.update(i3, &tree, window)?;
+        }
+        Ok(())
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        let change = self.focus.update(event);
+        match event.change {
+            WindowChange::Close => {
+                self.opacities.remove(&event.container.id);
+                return Ok(());
+            }
+            WindowChange::Focus | WindowChange::New | WindowChange::Move => {}
+            _ => return Ok(()),
+        }
+        let tree = i3.get_tree()?;
+        let previous = change.and_then(|change| change.previous);
+        let ids = previous.into_iter().chain([event.container.id]);
+        for id in ids {
+            if let Some(window) = tree::find(&tree, id) {
+                self.update(i3, &tree, window)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn on_exit(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let tree = i3.get_tree()?;
+        for (id, _) in std::mem::take(&mut self.opacities) {
+            if let Some(window) = tree::find(&tree, id) {
+                self.apply(i3, window, 1.0)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Opacity {
+    /// The opacity `window` should have in `tree`.
+    fn opacity_of(&self, tree: &Node, window: &Node) -> f64 {
+        if let Some(opacity) = self.scratchpad {
+            if tree::is_scratchpad_window(tree, window) {
+                return opacity;
+            }
+        }
+        if self.focus.focused() == Some(window.id) {
+            return 1.0;
+        }
+        self.inactive.unwrap_or(1.0)
+    }
+
+    fn update(&mut self, i3: &mut dyn I3Commands, tree: &Node, window: &Node) -> io::Result<()> {
+        let opacity = self.opacity_of(tree, window);
+        let current = self.opacities.get(&window.id).copied().unwrap_or(1.0);
+        if opacity == current {
+            return Ok(());
+        }
+        debug!(
+            "setting the opacity of {} to {opacity}",
+            tree::describe(window)
+        );
+        self.apply(i3, window, opacity)?;
+        self.opacities.insert(window.id, opacity);
+        Ok(())
+    }
+
+    fn apply(&self, i3: &mut dyn I3Commands, window: &Node, opacity: f64) -> io::Result<()> {
+        match &self.backend {
+            Some(Backend::Sway) => {
+                let command = CommandBuilder::con_id(window.id).action(Action::Opacity(opacity));
+                i3.run_command(&command.build())
+            }
+            #[cfg(feature = "x11")]
+            Some(Backend::X11(x11)) => match window.window {
+                Some(xid) => x11.set_opacity(xid as u32, opacity),
+                None => Ok(()),
+            },
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Utility to apply monitor layout profiles when outputs come and go.
+//!
+//! This program listens for output events from i3, e.g. when docking or
+//! undocking a laptop, picks the first profile whose outputs are all active,
+//! runs its commands and moves the workspaces it assigns to the outputs they
+//! belong on. Workspaces assigned to an inactive output are left alone.
+//!
+//! Output events come in bursts while monitors are being set up, so profiles
+//! are only applied once outputs have settled for a short while.
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [[profile]]
+//! name = "docked"
+//! outputs = ["eDP-1", "DP-1"]
+//! commands = ["exec --no-startup-id autorandr --load docked"]
+//! workspaces = { "1" = "DP-1", "2" = "DP-1", "10" = "eDP-1" }
+//!
+//! [[profile]]
+//! name = "laptop"
+//! outputs = ["eDP-1"]
+//! ```
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{move_workspace, quote, CommandQueue},
+    config::{self, invalid_data},
+    logging::{self, Verbosity},
+    EventLoop, Helper, I3Commands,
+};
+use i3_ipc::event::{Event, Subscribe};
+use log::{debug, info};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+const CONFIG_FILE_NAME: &str = "output-profiles.toml";
+
+/// Apply monitor layout profiles when outputs come and go.
+#[derive(Parser)]
+struct Args {
+    /// The configuration file defining the profiles. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/output-profiles.toml`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// How long outputs must stay unchanged before applying a profile, in
+    /// milliseconds.
+    #[arg(long, default_value_t = 500)]
+    settle_delay: u64,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(rename = "profile")]
+    profiles: Vec<Profile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Profile {
+    name: String,
+    /// The outputs that must all be active for the profile to apply.
+    outputs: Vec<String>,
+    /// i3 commands to run when switching to the profile.
+    #[serde(default)]
+    commands: Vec<String>,
+    /// The output each workspace belongs on, by workspace name.
+    #[serde(default)]
+    workspaces: HashMap<String, String>,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let path = args
+        .config
+        .or_else(|| config::default_path(CONFIG_FILE_NAME))
+        .ok_or_else(|| invalid_data("cannot find the configuration file, use --config"))?;
+    let config: Config = config::load(&path)?;
+    let mut profiles = OutputProfiles {
+        profiles: config.profiles,
+        settle_delay: Duration::from_millis(args.settle_delay),
+        current: None,
+        pending: None,
+    };
+    EventLoop::connect()?.run(&mut profiles)
+}
+
+pub struct OutputProfiles {
+    profiles: Vec<Profile>,
+    settle_delay: Duration,
+    /// Name of the profile applied last.
+    current: Option<String>,
+    /// When to apply the profile matching the outputs, once they settled.
+    pending: Option<Instant>,
+}
+
+impl Helper for OutputProfiles {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Output]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        self.apply(i3)
+    }
+
+    fn on_event(&mut self, _i3: &mut dyn I3Commands, event: &Event) -> io::Result<()> {
+        if let Event::Output(_) = event {
+            debug!("outputs changed, waiting for them to settle");
+            self.pending = Some(Instant::now() + self.settle_delay);
+        }
+        Ok(())
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.pending
+    }
+
+    fn on_deadline(&mut self, i3: &mut dyn I3Commands, _now: Instant) -> io::Result<()> {
+        self.pending = None;
+        self.apply(i3)
+    }
+}
+
+impl OutputProfiles {
+    /// Apply the first profile whose outputs are all active, if any.
+    fn apply(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let active: Vec<String> = i3
+            .get_outputs()?
+            .into_iter()
+            .filter(|output| output.active)
+            .map(|output| output.name)
+            .collect();
+        let Some(profile) = self
+            .profiles
+            .iter()
+            .find(|profile| profile.outputs.iter().all(|name| active.contains(name)))
+        else {
+            debug!("no profile for outputs {active:?}");
+            return Ok(());
+        };
+        let mut commands = CommandQueue::new();
+        if self.current.as_ref() != Some(&profile.name) {
+            info!("switching to profile {:?}", profile.name);
+            self.current = Some(profile.name.clone());
+            for command in &profile.commands {
+                commands.push(command.as_str());
+            }
+        }
+        move_workspaces(i3, &mut commands, &profile.workspaces, &active)?;
+        commands.flush(i3)
+    }
+}
+
+/// Queue the commands moving the workspaces in `assignments` that are on the
+/// wrong output to the one they belong on, if active, and focusing the same
+/// workspace again.
+fn move_workspaces(
+    i3: &mut dyn I3Commands,
+    commands: &mut CommandQueue,
+    assignments: &HashMap<String, String>,
+    active: &[String],
+) -> io::Result<()> {
+    let workspaces = i3.get_workspaces()?;
+    let mut moved = false;
+    for workspace in &workspaces {
+        let Some(output) = assignments.get(&workspace.name) else {
+            continue;
+        };
+        if *output == workspace.output || !active.contains(output) {
+            continue;
+        }
+        info!("moving workspace {:?} to output {output:?}", workspace.name);
+        commands.push(move_workspace(&workspace.name, output));
+        moved = true;
+    }
+    if !moved {
+        return Ok(());
+    }
+    if let Some(focused) = workspaces.iter().find(|workspace| workspace.focused) {
+        commands.push(format!(
+            "workspace --no-auto-back-and-forth {}",
+            quote(&focused.name)
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,113 @@
+//! Utility to remember the keyboard layout of each window.
+//!
+//! This program listens for events from i3 and, whenever focus moves to
+//! another window, records the keyboard layout that was active in the window
+//! losing focus and switches to the one last used in the window gaining it.
+//! Windows that never had focus get the default layout, if one is given, or
+//! keep the current one.
+//!
+//! Layouts are read and switched with `xkb-switch`, so this works with X11
+//! keyboard layouts, i.e. under i3.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    focus::{FocusChange, FocusTracker},
+    logging::{self, Verbosity},
+    EventLoop, Helper, I3Commands,
+};
+use i3_ipc::event::{Subscribe, WindowChange, WindowData};
+use log::{debug, warn};
+use std::{collections::HashMap, io, process::Command};
+
+/// Remember the keyboard layout of each window.
+///
+/// When focus moves to another window, the layout active in the previous one
+/// is recorded and the one last used in the new one is restored.
+#[derive(Parser)]
+struct Args {
+    /// The layout of windows that never had focus, e.g. `us`. By default, they
+    /// keep the current layout.
+    #[arg(long)]
+    default_layout: Option<String>,
+
+    /// The `xkb-switch` program used to read and switch layouts.
+    #[arg(long, default_value = "xkb-switch")]
+    xkb_switch: String,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut per_window_layout = PerWindowLayout {
+        focus: FocusTracker::new(),
+        layouts: HashMap::new(),
+        default_layout: args.default_layout,
+        xkb_switch: args.xkb_switch,
+    };
+    EventLoop::connect()?.run(&mut per_window_layout)
+}
+
+type NodeId = usize;
+
+pub struct PerWindowLayout {
+    focus: FocusTracker,
+    /// The layout last active in each window.
+    layouts: HashMap<NodeId, String>,
+    default_layout: Option<String>,
+    xkb_switch: String,
+}
+
+impl Helper for PerWindowLayout {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window]
+    }
+
+    fn on_window(&mut self, _i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        if let WindowChange::Close = event.change {
+            self.layouts.remove(&event.container.id);
+        }
+        let Some(FocusChange { previous, current }) = self.focus.update(event) else {
+            return Ok(());
+        };
+        let active = self.current_layout()?;
+        if let Some(previous) = previous {
+            self.layouts.insert(previous, active.clone());
+        }
+        let wanted = self.layouts.get(&current).or(self.default_layout.as_ref());
+        match wanted {
+            Some(wanted) if *wanted != active => {
+                debug!("switching to layout {wanted:?} for window {current}");
+                self.switch_layout(wanted)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl PerWindowLayout {
+    fn current_layout(&self) -> io::Result<String> {
+        let output = Command::new(&self.xkb_switch).arg("-p").output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "{} -p failed: {}",
+                self.xkb_switch,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    }
+
+    fn switch_layout(&self, layout: &str) -> io::Result<()> {
+        let status = Command::new(&self.xkb_switch)
+            .args(["-s", layout])
+            .status()?;
+        if !status.success() {
+            warn!("failed to switch to layout {layout:?}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+//! Utility to keep workspaces on the outputs they are pinned to.
+//!
+//! i3's `workspace N output NAME` only applies when a workspace is created.
+//! This program listens for events from i3 and, whenever a workspace is
+//! created or moved, or outputs change, moves the workspaces that ended up on
+//! the wrong output back to the one they are pinned to, if active. It thus
+//! also heals the arrangement after a monitor is disconnected and reconnected.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{move_workspace, quote, CommandQueue},
+    logging::{self, Verbosity},
+    EventLoop, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Event, Subscribe, WorkspaceChange, WorkspaceData},
+    reply::Workspace,
+};
+use log::info;
+use std::{io, str::FromStr};
+
+/// Keep workspaces on the outputs they are pinned to.
+#[derive(Parser)]
+struct Args {
+    /// Pin workspaces to an output, as `WORKSPACES=OUTPUT` where WORKSPACES is
+    /// either a workspace name or a range of workspace numbers, e.g.
+    /// `1-5=DP-1`. Can be repeated, the first matching pin applies.
+    #[arg(long = "pin", value_name = "WORKSPACES=OUTPUT", required = true)]
+    pins: Vec<Pin>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    EventLoop::connect()?.run(&mut PinWorkspaces { pins: args.pins })
+}
+
+#[derive(Clone)]
+struct Pin {
+    workspaces: Workspaces,
+    output: String,
+}
+
+#[derive(Clone)]
+enum Workspaces {
+    Name(String),
+    /// Workspaces numbered from the first to the second, inclusive.
+    Range(i32, i32),
+}
+
+impl Pin {
+    fn matches(&self, workspace: &Workspace) -> bool {
+        match &self.workspaces {
+            Workspaces::Name(name) => workspace.name == *name,
+            Workspaces::Range(first, last) => (*first..=*last).contains(&workspace.num),
+        }
+    }
+}
+
+impl FromStr for Pin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (workspaces, output) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("invalid pin {s:?}, expected WORKSPACES=OUTPUT"))?;
+        let range = workspaces
+            .split_once('-')
+            .and_then(|(first, last)| Some((first.parse().ok()?, last.parse().ok()?)));
+        let workspaces = match range {
+            Some((first, last)) => Workspaces::Range(first, last),
+            None => Workspaces::Name(workspaces.to_owned()),
+        };
+        Ok(Pin {
+            workspaces,
+            output: output.to_owned(),
+        })
+    }
+}
+
+pub struct PinWorkspaces {
+    pins: Vec<Pin>,
+}
+
+impl Helper for PinWorkspaces {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Workspace, Subscribe::Output]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        self.enforce(i3)
+    }
+
+    fn on_workspace(&mut self, i3: &mut dyn I3Commands, event: &WorkspaceData) -> io::Result<()> {
+        match event.change {
+            WorkspaceChange::Init | WorkspaceChange::Move => self.enforce(i3),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_event(&mut self, i3: &mut dyn I3Commands, event: &Event) -> io::Result<()> {
+        match event {
+            Event::Output(_) => self.enforce(i3),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl PinWorkspaces {
+    /// Move the workspaces on the wrong output back where they are pinned,
+    /// and focus the same workspace again.
+    fn enforce(&self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let active: Vec<String> = i3
+            .get_outputs()?
+            .into_iter()
+            .filter(|output| output.active)
+            .map(|output| output.name)
+            .collect();
+        let workspaces = i3.get_workspaces()?;
+        let mut commands = CommandQueue::new();
+        for workspace in &workspaces {
+            let Some(pin) = self.pins.iter().find(|pin| pin.matches(workspace)) else {
+                continue;
+            };
+            if pin.output == workspace.output || !active.contains(&pin.output) {
+                continue;
+            }
+            info!(
+                "moving workspace {:?} back to output {:?}",
+                workspace.name, pin.output
+            );
+            commands.push(move_workspace(&workspace.name, &pin.output));
+        }
+        if commands.is_empty() {
+            return Ok(());
+        }
+        if let Some(focused) = workspaces.iter().find(|workspace| workspace.focused) {
+            commands.push(format!(
+                "workspace --no-auto-back-and-forth {}",
+                quote(&focused.name)
+            ));
+        }
+        commands.flush(i3)
+    }
+}
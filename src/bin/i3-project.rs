@@ -0,0 +1,401 @@
+//! Utility to switch between projects, each a set of named workspaces.
+//!
+//! Each project is defined in a configuration file by its workspaces, the
+//! layout to restore on each of them and the windows to launch there:
+//!
+//! ```toml
+//! [[project.web.workspace]]
+//! name = "web:code"
+//! # A layout saved with `i3-layout save web-code`.
+//! layout = "web-code"
+//!
+//! [[project.web.workspace.window]]
+//! match = { class = "Alacritty", title_regex = "^web$" }
+//! exec = "alacritty --title web --working-directory ~/src/web"
+//!
+//! [[project.web.workspace]]
+//! name = "web:browser"
+//!
+//! [[project.web.workspace.window]]
+//! match = { class = "firefox" }
+//! exec = "firefox --new-window localhost:8080"
+//! ```
+//!
+//! `open web` brings back the windows hidden by a previous `close`, switches
+//! to each workspace of the project, restoring its layout if the workspace
+//! didn't exist yet, moves there the windows of the project open on other
+//! workspaces and launches the ones that are missing, before focusing the
+//! first workspace. Windows are launched with startup
+//! notifications, so that i3 places them on the workspace they were launched
+//! from even if they take a while to appear.
+//!
+//! `close web` hides the windows on the workspaces of the project in the
+//! scratchpad, marking them so that `open` can put them back where they were,
+//! or kills them with `--kill`.
+//!
+//! `status` keeps running, printing the project of the focused workspace for
+//! status bars, see [`i3_helpers::status`].
+
+use clap::{Parser, Subcommand};
+use i3_helpers::{
+    cli,
+    command::{quote, Action, CommandBuilder, CommandQueue},
+    config::{self, invalid_data},
+    criteria::{Criteria, CriteriaConfig},
+    logging::{self, Verbosity},
+    status::{ReportStatus, Status, StatusReporter},
+    tree, Connection, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Subscribe, WorkspaceData},
+    reply::Node,
+};
+use log::{info, warn};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_FILE_NAME: &str = "projects.toml";
+
+/// Prefix of the marks set on windows hidden by `close`, followed by the
+/// project, the workspace and the window id, separated by colons. i3 doesn't
+/// show marks starting with an underscore.
+const MARK_PREFIX: &str = "_project";
+
+/// Switch between projects, each a set of named workspaces.
+#[derive(Parser)]
+struct Args {
+    /// The configuration file defining the projects. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/projects.toml`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// The directory holding the layouts saved with `i3-layout`. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/layouts`.
+    #[arg(long)]
+    layouts_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    action: ProjectAction,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Subcommand)]
+enum ProjectAction {
+    /// List the projects, flagging the open ones with `*`.
+    List,
+    /// Switch to the workspaces of a project, launching its missing windows.
+    Open { name: String },
+    /// Hide the windows of a project in the scratchpad.
+    Close {
+        name: String,
+        /// Kill the windows instead of hiding them.
+        #[arg(long)]
+        kill: bool,
+    },
+    /// Keep running and print the project of the focused workspace whenever it
+    /// changes, as JSON for an i3blocks block or a waybar module to show.
+    Status,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(rename = "project")]
+    projects: BTreeMap<String, ProjectConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProjectConfig {
+    #[serde(rename = "workspace")]
+    workspaces: Vec<WorkspaceConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkspaceConfig {
+    name: String,
+    layout: Option<String>,
+    #[serde(rename = "window", default)]
+    windows: Vec<WindowConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WindowConfig {
+    #[serde(rename = "match")]
+    criteria: CriteriaConfig,
+    exec: String,
+}
+
+struct Project {
+    name: String,
+    workspaces: Vec<ProjectWorkspace>,
+}
+
+struct ProjectWorkspace {
+    name: String,
+    /// The layout to restore when the workspace is created.
+    layout: Option<String>,
+    /// The windows to launch on the workspace, unless they already exist.
+    windows: Vec<(Criteria, String)>,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let path = args
+        .config
+        .or_else(|| config::default_path(CONFIG_FILE_NAME))
+        .ok_or_else(|| invalid_data("cannot find the configuration file, use --config"))?;
+    let layouts_dir = args
+        .layouts_dir
+        .or_else(|| config::default_path("layouts"))
+        .ok_or_else(|| invalid_data("cannot find the layouts directory, use --layouts-dir"))?;
+    let mut projects = load_projects(&path)?;
+    let mut i3 = Connection::connect()?;
+    let tree = i3.get_tree()?;
+    match args.action {
+        ProjectAction::List => {
+            for project in &projects {
+                let flag = if project.is_open(&tree) { '*' } else { ' ' };
+                println!("{flag} {}", project.name);
+            }
+            Ok(())
+        }
+        ProjectAction::Open { name } => {
+            take(&mut projects, &name)?.open(&mut i3, &tree, &layouts_dir)
+        }
+        ProjectAction::Close { name, kill } => {
+            take(&mut projects, &name)?.close(&mut i3, &tree, kill)
+        }
+        ProjectAction::Status => {
+            let status = ProjectStatus {
+                projects,
+                open: Vec::new(),
+                active: None,
+            };
+            EventLoop::connect()?.run(&mut StatusReporter::new(status))
+        }
+    }
+}
+
+fn load_projects(path: &Path) -> io::Result<Vec<Project>> {
+    let config: Config = config::load(path)?;
+    config
+        .projects
+        .into_iter()
+        .map(|(name, project)| {
+            let workspaces = project
+                .workspaces
+                .into_iter()
+                .map(|workspace| {
+                    let windows = workspace
+                        .windows
+                        .into_iter()
+                        .map(|window| Ok((window.criteria.into_criteria()?, window.exec)))
+                        .collect::<io::Result<_>>()?;
+                    Ok(ProjectWorkspace {
+                        name: workspace.name,
+                        layout: workspace.layout,
+                        windows,
+                    })
+                })
+                .collect::<io::Result<_>>()?;
+            Ok(Project { name, workspaces })
+        })
+        .collect()
+}
+
+fn take(projects: &mut Vec<Project>, name: &str) -> io::Result<Project> {
+    let index = projects
+        .iter()
+        .position(|project| project.name == name)
+        .ok_or_else(|| invalid_data(format!("unknown project {name:?}")))?;
+    Ok(projects.remove(index))
+}
+
+impl Project {
+    /// Whether any workspace of the project exists.
+    fn is_open(&self, tree: &Node) -> bool {
+        self.workspaces
+            .iter()
+            .any(|workspace| find_workspace(tree, &workspace.name).is_some())
+    }
+
+    fn open(&self, i3: &mut dyn I3Commands, tree: &Node, layouts_dir: &Path) -> io::Result<()> {
+        let mut commands = CommandQueue::new();
+        let hidden = self.hidden_windows(tree);
+        // Windows already taken care of, not to be moved again.
+        let mut placed: Vec<usize> = hidden.iter().map(|(window, _, _)| window.id).collect();
+        for (window, mark, workspace) in hidden {
+            info!("bringing back {} to {workspace:?}", tree::describe(window));
+            let command = CommandBuilder::con_id(window.id)
+                .action(Action::ScratchpadShow)
+                .action(Action::FloatingDisable)
+                .action(Action::MoveToWorkspace(workspace.to_owned()))
+                .action(Action::Unmark(mark.to_owned()));
+            commands.push(command);
+        }
+        for workspace in &self.workspaces {
+            commands.push(focus_workspace(&workspace.name));
+            if find_workspace(tree, &workspace.name).is_none() {
+                if let Some(layout) = &workspace.layout {
+                    let path = layouts_dir.join(format!("{layout}.json"));
+                    match path.to_str().filter(|_| path.exists()) {
+                        Some(path) => {
+                            commands.push(format!("append_layout {}", quote(path)));
+                        }
+                        _ => warn!("cannot restore layout {}", path.display()),
+                    }
+                }
+            }
+            for (criteria, exec) in &workspace.windows {
+                let window = tree::windows(tree)
+                    .find(|window| criteria.matches(window) && !placed.contains(&window.id));
+                match window {
+                    Some(window) => {
+                        placed.push(window.id);
+                        let current = tree::workspace_of(tree, window.id)
+                            .and_then(|current| current.name.as_deref());
+                        if current != Some(workspace.name.as_str()) {
+                            info!("moving {} to {:?}", tree::describe(window), workspace.name);
+                            let command = CommandBuilder::con_id(window.id)
+                                .action(Action::MoveToWorkspace(workspace.name.clone()));
+                            commands.push(command);
+                        }
+                    }
+                    None if tree::windows(tree).any(|window| criteria.matches(window)) => (),
+                    None => {
+                        info!("launching {exec:?} on {:?}", workspace.name);
+                        // With a startup notification, the window lands on
+                        // this workspace even if another one is focused by
+                        // then.
+                        commands.push(format!("exec {exec}"));
+                    }
+                }
+            }
+        }
+        if let Some(first) = self.workspaces.first() {
+            commands.push(focus_workspace(&first.name));
+        }
+        commands.flush(i3)
+    }
+
+    fn close(&self, i3: &mut dyn I3Commands, tree: &Node, kill: bool) -> io::Result<()> {
+        let mut commands = CommandQueue::new();
+        for workspace in &self.workspaces {
+            let Some(node) = find_workspace(tree, &workspace.name) else {
+                continue;
+            };
+            for window in tree::windows(node) {
+                let command = match kill {
+                    true => CommandBuilder::con_id(window.id).action(Action::Kill),
+                    false => {
+                        let mark = self.mark(&workspace.name, window.id);
+                        CommandBuilder::con_id(window.id)
+                            .action(Action::Mark(mark))
+                            .action(Action::MoveScratchpad)
+                    }
+                };
+                commands.push(command);
+            }
+        }
+        commands.flush(i3)
+    }
+
+    /// The mark set on `window` when hidden from `workspace` by `close`.
+    fn mark(&self, workspace: &str, window: usize) -> String {
+        format!("{MARK_PREFIX}:{}:{workspace}:{window}", self.name)
+    }
+
+    /// The windows of the project hidden by `close`, along with their mark
+    /// and the workspace they were hidden from.
+    fn hidden_windows<'a>(&self, tree: &'a Node) -> Vec<(&'a Node, &'a str, &'a str)> {
+        let prefix = format!("{MARK_PREFIX}:{}:", self.name);
+        tree::windows(tree)
+            .filter_map(|window| {
+                let marks = window.marks.as_ref()?;
+                let mark = marks.0.iter().find(|mark| mark.starts_with(&prefix))?;
+                let (workspace, _) = mark[prefix.len()..].rsplit_once(':')?;
+                Some((window, mark.as_str(), workspace))
+            })
+            .collect()
+    }
+}
+
+/// Follows which project the focused workspace belongs to, for status bars.
+pub struct ProjectStatus {
+    projects: Vec<Project>,
+    /// The open projects.
+    open: Vec<String>,
+    /// The project of the focused workspace, if any.
+    active: Option<String>,
+}
+
+impl Helper for ProjectStatus {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Workspace]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        self.update(&i3.get_tree()?);
+        Ok(())
+    }
+
+    fn on_workspace(&mut self, i3: &mut dyn I3Commands, _event: &WorkspaceData) -> io::Result<()> {
+        self.update(&i3.get_tree()?);
+        Ok(())
+    }
+}
+
+impl ProjectStatus {
+    fn update(&mut self, tree: &Node) {
+        let focused = tree::focused(tree)
+            .and_then(|focused| tree::workspace_of(tree, focused.id))
+            .and_then(|workspace| workspace.name.as_deref());
+        self.active = self
+            .projects
+            .iter()
+            .find(|project| {
+                project
+                    .workspaces
+                    .iter()
+                    .any(|workspace| Some(workspace.name.as_str()) == focused)
+            })
+            .map(|project| project.name.clone());
+        self.open = self
+            .projects
+            .iter()
+            .filter(|project| project.is_open(tree))
+            .map(|project| project.name.clone())
+            .collect();
+    }
+}
+
+impl ReportStatus for ProjectStatus {
+    fn status(&self) -> Status {
+        Status {
+            text: self.active.clone().unwrap_or_default(),
+            tooltip: format!("open projects: {}", self.open.join(", ")),
+            class: match self.active {
+                Some(_) => "active".to_owned(),
+                None => "none".to_owned(),
+            },
+        }
+    }
+}
+
+fn find_workspace<'a>(tree: &'a Node, name: &str) -> Option<&'a Node> {
+    tree::workspaces(tree).find(|workspace| workspace.name.as_deref() == Some(name))
+}
+
+fn focus_workspace(name: &str) -> String {
+    format!("workspace --no-auto-back-and-forth {}", quote(name))
+}
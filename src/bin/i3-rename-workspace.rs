@@ -0,0 +1,105 @@
+//! Utility to rename the focused workspace, keeping its number.
+//!
+//! The new name is taken from the command line, from a menu program like rofi
+//! or dmenu, or from the standard input. Numbered workspaces keep their number
+//! as a prefix so that `workspace number` bindings still work: renaming
+//! `3: web` to `mail` results in `3: mail`. If another workspace already has
+//! the resulting name, a counter is appended to make it unique.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::quote,
+    logging::{self, Verbosity},
+    picker, Connection, I3Commands,
+};
+use i3_ipc::reply::Workspace;
+use std::io::{self, BufRead};
+
+/// Rename the focused workspace, keeping its number.
+#[derive(Parser)]
+struct Args {
+    /// The new name, without the number. Prompted for when missing.
+    name: Option<String>,
+
+    /// A menu command to prompt for the name with, run through `sh -c`. It
+    /// receives the current name on its standard input and must print the new
+    /// one, e.g. `rofi -dmenu -p rename` or `dmenu -p rename`. Without it, the
+    /// name is read from the standard input.
+    #[arg(long, conflicts_with = "name")]
+    menu: Option<String>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let workspaces = i3.get_workspaces()?;
+    let focused = workspaces
+        .iter()
+        .find(|workspace| workspace.focused)
+        .ok_or_else(|| io::Error::other("no focused workspace"))?;
+    let current = label(focused);
+    let input = match (args.name, args.menu) {
+        (Some(name), _) => name,
+        (None, Some(menu)) => picker::pick(&menu, &[current])?.unwrap_or_default(),
+        (None, None) => prompt_stdin(current)?,
+    };
+    let input = input.trim();
+    // An empty name (e.g. the menu was dismissed) leaves the workspace as is.
+    if input.is_empty() {
+        return Ok(());
+    }
+    let name = unique_name(&workspaces, focused, input);
+    if name != focused.name {
+        i3.run_command(&format!(
+            "rename workspace {} to {}",
+            quote(&focused.name),
+            quote(&name)
+        ))?;
+    }
+    Ok(())
+}
+
+/// The name of the workspace without its number prefix.
+fn label(workspace: &Workspace) -> &str {
+    match workspace.num {
+        num if num >= 0 => {
+            let name = workspace.name.strip_prefix(&num.to_string());
+            name.map(|name| name.trim_start_matches(':').trim_start())
+                .unwrap_or(&workspace.name)
+        }
+        _ => &workspace.name,
+    }
+}
+
+/// The full name for `workspace` given its new `label`, made unique among the
+/// other `workspaces`.
+fn unique_name(workspaces: &[Workspace], workspace: &Workspace, label: &str) -> String {
+    let with_number = |label: &str| match workspace.num {
+        num if num >= 0 => format!("{num}: {label}"),
+        _ => label.to_owned(),
+    };
+    let taken = |name: &str| {
+        workspaces
+            .iter()
+            .any(|other| other.id != workspace.id && other.name == name)
+    };
+    let mut name = with_number(label);
+    let mut counter = 2;
+    while taken(&name) {
+        name = with_number(&format!("{label} ({counter})"));
+        counter += 1;
+    }
+    name
+}
+
+fn prompt_stdin(current: &str) -> io::Result<String> {
+    eprint!("Rename workspace {current:?} to: ");
+    let mut name = String::new();
+    io::stdin().lock().read_line(&mut name)?;
+    Ok(name)
+}
@@ -0,0 +1,167 @@
+//! Utility to move the edge of the focused container in a direction.
+//!
+//! i3's `resize grow right` does nothing for the rightmost container, so the
+//! usual resize bindings behave differently depending on where the container
+//! sits. This program instead moves a divider of the focused container towards
+//! the given direction: the right one if there is a container to its right,
+//! which grows, or the left one otherwise, which shrinks it. Floating windows
+//! grow to the right and bottom, and shrink to the left and top.
+//!
+//! ```text
+//! mode "resize" {
+//!     bindsym Left exec --no-startup-id i3-resize-mode left
+//!     bindsym Right exec --no-startup-id i3-resize-mode right
+//!     bindsym Up exec --no-startup-id i3-resize-mode up
+//!     bindsym Down exec --no-startup-id i3-resize-mode down
+//!     bindsym Escape mode "default"
+//! }
+//! ```
+
+use clap::{Parser, ValueEnum};
+use i3_helpers::{
+    cli,
+    logging::{self, Verbosity},
+    tree, Connection, I3Commands,
+};
+use i3_ipc::reply::{Node, NodeLayout, NodeType};
+use std::io;
+
+/// Move the edge of the focused container in a direction.
+#[derive(Parser)]
+struct Args {
+    /// Where to move the edge to.
+    #[arg(value_enum)]
+    direction: Direction,
+
+    /// How far to move the edge of tiling containers, in percentage points of
+    /// their parent.
+    #[arg(long, default_value_t = 5)]
+    ppt: u32,
+
+    /// How far to move the edge of floating windows, in pixels.
+    #[arg(long, default_value_t = 20)]
+    px: u32,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    fn name(self) -> &'static str {
+        match self {
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::Up => "up",
+            Direction::Down => "down",
+        }
+    }
+
+    fn opposite(self) -> Self {
+        match self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+        }
+    }
+
+    /// The layout of the splits whose children are side by side in this
+    /// direction.
+    fn layout(self) -> NodeLayout {
+        match self {
+            Direction::Left | Direction::Right => NodeLayout::SplitH,
+            Direction::Up | Direction::Down => NodeLayout::SplitV,
+        }
+    }
+
+    /// Whether going this way goes towards the end of the children of a split.
+    fn is_forward(self) -> bool {
+        matches!(self, Direction::Right | Direction::Down)
+    }
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let tree = i3.get_tree()?;
+    let focused = tree::focused(&tree)
+        .filter(|node| node.node_type == NodeType::Con)
+        .ok_or_else(|| io::Error::other("no focused container"))?;
+    let direction = args.direction;
+    let command = if tree::is_floating(focused) || is_floating_container(&tree, focused) {
+        let (action, dimension) = match direction {
+            Direction::Right => ("grow", "width"),
+            Direction::Left => ("shrink", "width"),
+            Direction::Down => ("grow", "height"),
+            Direction::Up => ("shrink", "height"),
+        };
+        format!("resize {action} {dimension} {} px", args.px)
+    } else if has_neighbor(&tree, focused, direction) {
+        format!(
+            "resize grow {} {} px or {} ppt",
+            direction.name(),
+            args.px,
+            args.ppt
+        )
+    } else {
+        format!(
+            "resize shrink {} {} px or {} ppt",
+            direction.opposite().name(),
+            args.px,
+            args.ppt
+        )
+    };
+    i3.run_command(&command)
+}
+
+/// Whether `node` has a tiling container next to it in `direction`, as a
+/// sibling of one of its ancestors within the workspace.
+fn has_neighbor(tree: &Node, node: &Node, direction: Direction) -> bool {
+    let mut child = node;
+    while let Some(parent) = tree::parent_of(tree, child.id) {
+        if parent.node_type != NodeType::Con && parent.node_type != NodeType::Workspace {
+            return false;
+        }
+        if parent.layout == direction.layout() {
+            if let Some(index) = parent.nodes.iter().position(|node| node.id == child.id) {
+                let neighbor = match direction.is_forward() {
+                    true => index + 1 < parent.nodes.len(),
+                    false => index > 0,
+                };
+                if neighbor {
+                    return true;
+                }
+            }
+        }
+        if parent.node_type == NodeType::Workspace {
+            return false;
+        }
+        child = parent;
+    }
+    false
+}
+
+/// Whether `node` is inside a floating container, e.g. a split holding several
+/// floating windows.
+fn is_floating_container(tree: &Node, node: &Node) -> bool {
+    let mut child = node;
+    while let Some(parent) = tree::parent_of(tree, child.id) {
+        if parent.floating_nodes.iter().any(|node| node.id == child.id) {
+            return true;
+        }
+        if parent.node_type != NodeType::Con {
+            return false;
+        }
+        child = parent;
+    }
+    false
+}
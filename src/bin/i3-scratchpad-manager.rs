@@ -0,0 +1,284 @@
+//! Utility to manage several named scratchpad windows.
+//!
+//! Each scratchpad is defined in a configuration file by a name, the criteria
+//! identifying its window and the command launching it:
+//!
+//! ```toml
+//! [scratchpad.terminal]
+//! match = { class = "dropdown" }
+//! exec = "alacritty --class dropdown"
+//!
+//! [scratchpad.notes]
+//! match = { class = "notes" }
+//! exec = "alacritty --class notes -e nvim ~/notes.md"
+//! ```
+//!
+//! `i3-scratchpad-manager toggle notes` then launches the notes window if it
+//! doesn't exist, shows it if it is hidden in the scratchpad or visible on
+//! another workspace, and hides it if it is visible on the focused one. A
+//! launched window is waited for, up to `--timeout-ms`, then moved to the
+//! scratchpad and shown, resized to the optional `geometry` of the
+//! scratchpad, e.g. `geometry = "80%x60%"`. Running
+//! `i3-scratchpad-manager daemon` alongside makes sure windows of every
+//! scratchpad end up in the scratchpad as soon as they are created, however
+//! they were launched, leaving the ones launched by `toggle` and `show` to
+//! them, see [`i3_helpers::helpers::scratchpad_manager`].
+
+use clap::{Parser, Subcommand};
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    config::{self, invalid_data},
+    criteria::{Criteria, CriteriaConfig},
+    geometry::Geometry,
+    helpers::scratchpad_manager::{launch_tick, ScratchpadDaemon},
+    logging::{self, Verbosity},
+    tree,
+    wait::EventWaiter,
+    Connection, EventLoop, I3Commands,
+};
+use i3_ipc::{event::Subscribe, msg::Msg};
+use log::{info, warn};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const CONFIG_FILE_NAME: &str = "scratchpad-manager.toml";
+
+/// Manage several named scratchpad windows.
+#[derive(Parser)]
+struct Args {
+    /// The configuration file defining the scratchpads. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/scratchpad-manager.toml`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Milliseconds to wait for a launched window to appear before giving up
+    /// on showing it.
+    #[arg(long, default_value_t = 10_000)]
+    timeout_ms: u64,
+
+    #[command(subcommand)]
+    action: ScratchpadAction,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Subcommand)]
+enum ScratchpadAction {
+    /// Show the scratchpad window if hidden or on another workspace, hide it
+    /// if visible on the focused one, and launch it if it doesn't exist.
+    Toggle { name: String },
+    /// Show the scratchpad window on the focused workspace, launching it if it
+    /// doesn't exist.
+    Show { name: String },
+    /// Hide the scratchpad window.
+    Hide { name: String },
+    /// Move new windows of every scratchpad to the scratchpad as soon as they
+    /// are created.
+    Daemon,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(rename = "scratchpad")]
+    scratchpads: HashMap<String, ScratchpadConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScratchpadConfig {
+    #[serde(rename = "match")]
+    criteria: CriteriaConfig,
+    #[serde(alias = "launch")]
+    exec: String,
+    geometry: Option<String>,
+}
+
+/// A named scratchpad window.
+pub struct Scratchpad {
+    name: String,
+    criteria: Criteria,
+    /// Command launching the window.
+    exec: String,
+    /// The size to give the window once launched.
+    geometry: Option<Geometry>,
+    /// How long to wait for the window once launched.
+    timeout: Duration,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let path = args
+        .config
+        .or_else(|| config::default_path(CONFIG_FILE_NAME))
+        .ok_or_else(|| invalid_data("cannot find the configuration file, use --config"))?;
+    let timeout = Duration::from_millis(args.timeout_ms);
+    let mut scratchpads = load_scratchpads(&path, timeout)?;
+    match args.action {
+        ScratchpadAction::Toggle { name } => {
+            take(&mut scratchpads, &name)?.toggle(&mut Connection::connect()?)
+        }
+        ScratchpadAction::Show { name } => {
+            take(&mut scratchpads, &name)?.show(&mut Connection::connect()?)
+        }
+        ScratchpadAction::Hide { name } => {
+            take(&mut scratchpads, &name)?.hide(&mut Connection::connect()?)
+        }
+        ScratchpadAction::Daemon => {
+            let scratchpads = scratchpads
+                .into_iter()
+                .map(|(name, scratchpad)| (name, scratchpad.criteria))
+                .collect();
+            EventLoop::connect()?.run(&mut ScratchpadDaemon::new(scratchpads, timeout))
+        }
+    }
+}
+
+fn load_scratchpads(path: &Path, timeout: Duration) -> io::Result<HashMap<String, Scratchpad>> {
+    let config: Config = config::load(path)?;
+    config
+        .scratchpads
+        .into_iter()
+        .map(|(name, scratchpad)| {
+            let scratchpad = Scratchpad {
+                name: name.clone(),
+                criteria: scratchpad.criteria.into_criteria()?,
+                exec: scratchpad.exec,
+                geometry: scratchpad
+                    .geometry
+                    .map(|geometry| geometry.parse())
+                    .transpose()
+                    .map_err(invalid_data)?,
+                timeout,
+            };
+            Ok((name, scratchpad))
+        })
+        .collect()
+}
+
+fn take(scratchpads: &mut HashMap<String, Scratchpad>, name: &str) -> io::Result<Scratchpad> {
+    scratchpads
+        .remove(name)
+        .ok_or_else(|| invalid_data(format!("unknown scratchpad {name:?}")))
+}
+
+/// Where a scratchpad window currently is.
+enum State {
+    Missing,
+    Hidden(usize),
+    /// Visible on the focused workspace.
+    Visible(usize),
+    /// Visible on another workspace.
+    Elsewhere(usize),
+}
+
+impl Scratchpad {
+    fn state(&self, i3: &mut dyn I3Commands) -> io::Result<State> {
+        let tree = i3.get_tree()?;
+        let window = tree::windows(&tree).find(|window| self.criteria.matches(window));
+        Ok(match window {
+            None => State::Missing,
+            Some(window) if tree::is_hidden_in_scratchpad(&tree, window.id) => {
+                State::Hidden(window.id)
+            }
+            Some(window) => {
+                let workspace = tree::workspace_of(&tree, window.id).map(|ws| ws.id);
+                let focused = tree::focused_workspace(&tree).map(|ws| ws.id);
+                match workspace == focused {
+                    true => State::Visible(window.id),
+                    false => State::Elsewhere(window.id),
+                }
+            }
+        })
+    }
+
+    fn toggle(&self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        match self.state(i3)? {
+            State::Missing => self.launch(i3),
+            State::Hidden(id) => show(i3, id),
+            State::Visible(id) => hide(i3, id),
+            State::Elsewhere(id) => bring(i3, id),
+        }
+    }
+
+    fn show(&self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        match self.state(i3)? {
+            State::Missing => self.launch(i3),
+            State::Hidden(id) => show(i3, id),
+            State::Elsewhere(id) => bring(i3, id),
+            State::Visible(_) => Ok(()),
+        }
+    }
+
+    fn hide(&self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        match self.state(i3)? {
+            State::Visible(id) | State::Elsewhere(id) => hide(i3, id),
+            State::Missing | State::Hidden(_) => Ok(()),
+        }
+    }
+
+    /// Launch the window, wait for it to appear and show it as a scratchpad
+    /// window.
+    fn launch(&self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        // Subscribe before launching, so as not to miss the window.
+        let mut waiter = EventWaiter::subscribe(&[Subscribe::Window])?;
+        // Tell the daemon, if running, to leave the window to us.
+        i3.send_message(Msg::Tick.into(), &launch_tick(&self.name))?;
+        // Let i3 spawn the program, so that it isn't tied to our process.
+        i3.run_command(&format!("exec --no-startup-id {}", self.exec))?;
+        let window = waiter.wait_for_new_window(&self.criteria, Some(self.timeout))?;
+        let Some(id) = window.map(|window| window.id) else {
+            warn!(
+                "no window matching {} appeared after launching it",
+                self.criteria
+            );
+            return Ok(());
+        };
+        info!("launched window {id}");
+        hide(i3, id)?;
+        show(i3, id)?;
+        match self.geometry {
+            Some(geometry) => resize(i3, id, geometry),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Resize window `id` to `geometry` of the output it is shown on.
+fn resize(i3: &mut dyn I3Commands, id: usize, geometry: Geometry) -> io::Result<()> {
+    let tree = i3.get_tree()?;
+    match tree::output_of(&tree, id).map(|output| output.rect.clone()) {
+        Some(rect) => i3.run_command(&geometry.command(id, &rect)),
+        None => Ok(()),
+    }
+}
+
+fn show(i3: &mut dyn I3Commands, id: usize) -> io::Result<()> {
+    i3.run_command(
+        &CommandBuilder::con_id(id)
+            .action(Action::ScratchpadShow)
+            .build(),
+    )
+}
+
+fn hide(i3: &mut dyn I3Commands, id: usize) -> io::Result<()> {
+    i3.run_command(
+        &CommandBuilder::con_id(id)
+            .action(Action::MoveScratchpad)
+            .build(),
+    )
+}
+
+/// Bring window `id`, visible on another workspace, to the focused one.
+fn bring(i3: &mut dyn I3Commands, id: usize) -> io::Result<()> {
+    hide(i3, id)?;
+    show(i3, id)
+}
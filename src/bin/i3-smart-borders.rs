@@ -0,0 +1,173 @@
+//! Utility to drop borders and gaps around lone windows.
+//!
+//! This program listens for events from i3 and removes the border of the only
+//! tiling window of a workspace, and optionally the gaps around it with
+//! i3-gaps or sway, putting them back once more windows share the workspace.
+//! Floating windows are left alone and don't count.
+//!
+//! Gaps can only be changed on the focused workspace, so workspaces that
+//! changed in the background are updated once they get focus.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder, CommandQueue},
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::event::{Subscribe, WindowChange, WindowData, WorkspaceChange, WorkspaceData};
+use log::debug;
+use std::{collections::HashSet, io};
+
+/// Drop borders and gaps around lone windows.
+#[derive(Parser)]
+struct Args {
+    /// The border style to give windows back once they are not alone
+    /// anymore, e.g. `normal 2` or `pixel 3`.
+    #[arg(long, default_value = "normal")]
+    border: String,
+
+    /// The inner gaps to set back once a window is not alone anymore. Gaps are
+    /// left alone unless given.
+    #[arg(long, value_name = "PIXELS")]
+    inner_gaps: Option<u32>,
+
+    /// The outer gaps to set back once a window is not alone anymore. Gaps are
+    /// left alone unless given.
+    #[arg(long, value_name = "PIXELS")]
+    outer_gaps: Option<u32>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut smart_borders = SmartBorders {
+        border: args.border,
+        inner_gaps: args.inner_gaps,
+        outer_gaps: args.outer_gaps,
+        borderless: HashSet::new(),
+        gapless: HashSet::new(),
+    };
+    EventLoop::connect()?.run(&mut smart_borders)
+}
+
+type NodeId = usize;
+
+pub struct SmartBorders {
+    border: String,
+    inner_gaps: Option<u32>,
+    outer_gaps: Option<u32>,
+    /// Windows whose border we removed.
+    borderless: HashSet<NodeId>,
+    /// Names of the workspaces whose gaps we removed.
+    gapless: HashSet<String>,
+}
+
+impl Helper for SmartBorders {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window, Subscribe::Workspace]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        self.update(i3)
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        match event.change {
+            WindowChange::Close => {
+                self.borderless.remove(&event.container.id);
+                self.update(i3)
+            }
+            WindowChange::New | WindowChange::Move | WindowChange::Floating => self.update(i3),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_workspace(&mut self, i3: &mut dyn I3Commands, event: &WorkspaceData) -> io::Result<()> {
+        match event.change {
+            WorkspaceChange::Focus => self.update(i3),
+            WorkspaceChange::Empty => {
+                let name = event.current.as_ref().and_then(|ws| ws.name.as_ref());
+                if let Some(name) = name {
+                    self.gapless.remove(name);
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl SmartBorders {
+    fn manages_gaps(&self) -> bool {
+        self.inner_gaps.is_some() || self.outer_gaps.is_some()
+    }
+
+    /// Bring the borders of all windows, and the gaps of the focused
+    /// workspace, in line with the number of windows on their workspace.
+    fn update(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let tree = i3.get_tree()?;
+        let focused = tree::focused(&tree).map(|node| node.id);
+        let mut commands = CommandQueue::new();
+        let mut all_tiling: HashSet<NodeId> = HashSet::new();
+        for workspace in tree::workspaces(&tree) {
+            let Some(name) = workspace.name.as_ref() else {
+                continue;
+            };
+            if name == tree::SCRATCHPAD_WORKSPACE {
+                continue;
+            }
+            let tiling: Vec<NodeId> = tree::windows(workspace)
+                .filter(|window| !tree::is_floating(window))
+                .map(|window| window.id)
+                .collect();
+            all_tiling.extend(&tiling);
+            let alone = tiling.len() == 1;
+            for &id in &tiling {
+                if alone && self.borderless.insert(id) {
+                    commands.push(border(id, "none"));
+                } else if !alone && self.borderless.remove(&id) {
+                    commands.push(border(id, &self.border));
+                }
+            }
+            let is_focused = focused.is_some_and(|id| tree::find(workspace, id).is_some());
+            if !self.manages_gaps() || !is_focused {
+                continue;
+            }
+            if alone && self.gapless.insert(name.clone()) {
+                debug!("removing the gaps of workspace {name:?}");
+                if self.inner_gaps.is_some() {
+                    commands.push("gaps inner current set 0".to_owned());
+                }
+                if self.outer_gaps.is_some() {
+                    commands.push("gaps outer current set 0".to_owned());
+                }
+            } else if !alone && self.gapless.remove(name) {
+                debug!("restoring the gaps of workspace {name:?}");
+                if let Some(inner) = self.inner_gaps {
+                    commands.push(format!("gaps inner current set {inner}"));
+                }
+                if let Some(outer) = self.outer_gaps {
+                    commands.push(format!("gaps outer current set {outer}"));
+                }
+            }
+        }
+        // Windows made floating or sent to the scratchpad get their border back.
+        self.borderless.retain(|&id| {
+            if all_tiling.contains(&id) {
+                return true;
+            }
+            commands.push(border(id, &self.border));
+            false
+        });
+        commands.flush(i3)
+    }
+}
+
+/// The command giving window `id` the border `style`.
+fn border(id: NodeId, style: &str) -> CommandBuilder {
+    CommandBuilder::con_id(id).action(Action::Border(style.to_owned()))
+}
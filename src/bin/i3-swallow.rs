@@ -0,0 +1,65 @@
+//! Utility to make terminals swallow the graphical programs they launch.
+//!
+//! This program listens for events from i3 and, when a new window belongs to
+//! a process launched from a terminal window, takes the place of the terminal,
+//! which is hidden in the scratchpad. Once the new window is closed, the
+//! terminal comes back. This mimics the swallow patch of dwm, and prevents
+//! terminals that are blocked waiting for a graphical program from wasting
+//! screen space.
+//!
+//! Windows are related to processes through their `_NET_WM_PID` property, so
+//! this only works with X11 windows, and requires the `x11` feature. See
+//! [`i3_helpers::helpers::swallow`].
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    criteria::{exact, Criteria, CriteriaArgs},
+    helpers::swallow::Swallow,
+    logging::{self, Verbosity},
+    x11::X11,
+    EventLoop,
+};
+use std::io;
+
+/// Make terminals swallow the graphical programs they launch.
+///
+/// When a new window belongs to a process launched from a terminal window, it
+/// takes the place of the terminal, which is hidden in the scratchpad until
+/// the new window is closed. Criteria like `--class` or `--match` limit which
+/// windows swallow terminals.
+#[derive(Parser)]
+struct Args {
+    /// The X11 class of terminal windows. Can be given multiple times.
+    #[arg(short, long, required = true)]
+    terminal: Vec<String>,
+
+    /// The X11 class of windows that should never swallow a terminal. Can be
+    /// given multiple times.
+    #[arg(long)]
+    except_class: Vec<String>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+
+    // Only windows matching these criteria swallow terminals, any window if
+    // none is given.
+    #[command(flatten, next_help_heading = "Swallowing windows")]
+    criteria: CriteriaArgs,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let by_class = |class: &String| Criteria {
+        class: Some(exact(class)),
+        ..Criteria::default()
+    };
+    let mut swallow = Swallow::new(
+        X11::connect()?,
+        args.terminal.iter().map(by_class).collect(),
+        args.except_class.iter().map(by_class).collect(),
+        args.criteria.into_criteria(),
+    );
+    EventLoop::connect()?.run(&mut swallow)
+}
@@ -0,0 +1,92 @@
+//! Utility to exchange two workspaces between outputs.
+//!
+//! By default, the workspaces visible on two outputs swap places. With
+//! `--with`, a given workspace swaps places with the focused one instead. In
+//! both cases, focus stays on the output that was focused before.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::quote,
+    logging::{self, Verbosity},
+    Connection, I3Commands,
+};
+use i3_ipc::reply::Workspace;
+use std::io;
+
+/// Exchange two workspaces between outputs.
+///
+/// Without options, swaps the workspaces visible on the only two active
+/// outputs.
+#[derive(Parser)]
+struct Args {
+    /// Swap the workspaces visible on these two outputs.
+    #[arg(long, num_args = 2, value_names = ["OUTPUT", "OUTPUT"])]
+    outputs: Option<Vec<String>>,
+
+    /// Swap this workspace (by name) with the focused one.
+    #[arg(long, value_name = "WORKSPACE", conflicts_with = "outputs")]
+    with: Option<String>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let workspaces = i3.get_workspaces()?;
+    let focused = workspaces
+        .iter()
+        .find(|workspace| workspace.focused)
+        .ok_or_else(|| io::Error::other("no focused workspace"))?;
+    let (a, b) = match (&args.outputs, &args.with) {
+        (Some(outputs), _) => (
+            visible_on(&workspaces, &outputs[0])?,
+            visible_on(&workspaces, &outputs[1])?,
+        ),
+        (None, Some(name)) => (focused, named(&workspaces, name)?),
+        (None, None) => {
+            let visible: Vec<&Workspace> = workspaces.iter().filter(|ws| ws.visible).collect();
+            match visible[..] {
+                [a, b] => (a, b),
+                _ => {
+                    return Err(io::Error::other(
+                        "there aren't exactly two active outputs, use --outputs",
+                    ))
+                }
+            }
+        }
+    };
+    if a.output == b.output {
+        return Ok(());
+    }
+    // After swapping, focus whichever workspace ended up on the output that
+    // was focused before.
+    let refocus = if a.output == focused.output { b } else { a };
+    i3.run_command(&format!(
+        "workspace --no-auto-back-and-forth {a}; move workspace to output {b_output}; \
+         workspace --no-auto-back-and-forth {b}; move workspace to output {a_output}; \
+         workspace --no-auto-back-and-forth {refocus}",
+        a = quote(&a.name),
+        b = quote(&b.name),
+        a_output = quote(&a.output),
+        b_output = quote(&b.output),
+        refocus = quote(&refocus.name),
+    ))
+}
+
+fn visible_on<'a>(workspaces: &'a [Workspace], output: &str) -> io::Result<&'a Workspace> {
+    workspaces
+        .iter()
+        .find(|workspace| workspace.visible && workspace.output == output)
+        .ok_or_else(|| io::Error::other(format!("no active output named {output:?}")))
+}
+
+fn named<'a>(workspaces: &'a [Workspace], name: &str) -> io::Result<&'a Workspace> {
+    workspaces
+        .iter()
+        .find(|workspace| workspace.name == name)
+        .ok_or_else(|| io::Error::other(format!("no workspace named {name:?}")))
+}
@@ -0,0 +1,94 @@
+//! Utility to pick a window from a grid of the windows on the workspace.
+//!
+//! Like macOS' exposé, the windows of the focused workspace, or of all of them
+//! with `--all`, are shown side by side with their icon and title, and the
+//! chosen one is focused. This is handy for windows hidden in tabbed or
+//! stacked containers. The grid is drawn by rofi, with icons from the icon
+//! theme named after the class of the windows:
+//!
+//! ```text
+//! bindsym $mod+o exec --no-startup-id i3-tab-overview
+//! ```
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    logging::{self, Verbosity},
+    picker,
+    tree::{self, WindowEntry},
+    Connection, I3Commands,
+};
+use std::io;
+
+/// Rofi showing the windows in a grid of icons with the title underneath.
+const GRID_MENU: &str = "rofi -dmenu -i -show-icons -p window -theme-str \
+    'listview { columns: 4; lines: 3; } element { orientation: vertical; } \
+    element-icon { size: 64px; } element-text { horizontal-align: 0.5; }'";
+
+/// Pick a window from a grid of the windows on the workspace.
+#[derive(Parser)]
+struct Args {
+    /// Show the windows of all workspaces, rather than the focused one.
+    #[arg(long)]
+    all: bool,
+
+    /// The menu command to pick the window with, run through `sh -c`. It
+    /// receives the numbered windows on its standard input, one per line,
+    /// and must print the chosen line, number included.
+    #[arg(long, default_value = GRID_MENU)]
+    menu: String,
+
+    /// Leave out the icons, for menus other than rofi.
+    #[arg(long)]
+    no_icons: bool,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let tree = i3.get_tree()?;
+    let mut windows = tree::list_windows(&tree);
+    if !args.all {
+        let focused = tree::focused_workspace(&tree).map(|workspace| workspace.id);
+        windows.retain(|window| Some(window.workspace.id) == focused);
+    }
+    if windows.is_empty() {
+        return Ok(());
+    }
+    let items: Vec<String> = windows
+        .iter()
+        .map(|window| {
+            let item = format_item(window, args.all);
+            if args.no_icons {
+                item
+            } else {
+                picker::with_icon(&item, &window.icon_name())
+            }
+        })
+        .collect();
+    match picker::pick_index(&args.menu, &items)? {
+        Some(index) => {
+            let command = CommandBuilder::con_id(windows[index].window.id).action(Action::Focus);
+            i3.run_command(&command.build())
+        }
+        None => Ok(()),
+    }
+}
+
+/// The title of the window, after its workspace if listing all of them.
+fn format_item(window: &WindowEntry, all: bool) -> String {
+    let title = match window.title() {
+        "" => window.class(),
+        title => title,
+    };
+    if all {
+        format!("{}: {title}", window.workspace_name())
+    } else {
+        title.to_owned()
+    }
+}
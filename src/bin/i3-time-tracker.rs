@@ -0,0 +1,377 @@
+//! Utility to track where the time goes, from the focused window.
+//!
+//! `record` listens for events from i3 and logs how long each window stayed
+//! focused, along with its workspace, class and title, one line of CSV per
+//! stretch of focus:
+//!
+//! ```text
+//! start,seconds,workspace,class,title
+//! 1760512345,125,2,firefox,"Rust, the book"
+//! ```
+//!
+//! Time spent away from the keyboard isn't counted: the current stretch ends
+//! once the session is idle, for `--idle-timeout` seconds without input under
+//! i3 when built with the `x11` feature, or when told so by ticks from an idle
+//! daemon, see [`i3_helpers::idle`].
+//!
+//! `report` adds the time up by class, workspace or title, for today only
+//! with `--today`. A stretch is only logged once focus moves on, so the
+//! current one is left out of the report until then.
+//!
+//! The log is kept in `$XDG_DATA_HOME/i3-helpers/time-tracker.csv`.
+
+use clap::{Parser, Subcommand};
+use i3_helpers::{
+    cli,
+    config::{self, invalid_data},
+    idle::{IdleChange, IdleMonitor},
+    logging::{self, Verbosity},
+    tree::{self, WindowEntry},
+    EventLoop, Helper, I3Commands,
+};
+use i3_ipc::event::{
+    Subscribe, TickData, WindowChange, WindowData, WorkspaceChange, WorkspaceData,
+};
+use log::debug;
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const HEADER: &str = "start,seconds,workspace,class,title";
+
+/// Track where the time goes, from the focused window.
+#[derive(Parser)]
+struct Args {
+    /// The file holding the log, instead of
+    /// `$XDG_DATA_HOME/i3-helpers/time-tracker.csv`.
+    #[arg(long)]
+    database: Option<PathBuf>,
+
+    #[command(subcommand)]
+    action: TrackerAction,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Subcommand)]
+enum TrackerAction {
+    /// Keep running and log how long each window stays focused.
+    Record {
+        /// Seconds without input after which the session is idle.
+        #[arg(long, default_value_t = 300)]
+        idle_timeout: u64,
+    },
+    /// Print the time spent, by class unless told otherwise.
+    Report {
+        /// Only count the time spent since midnight.
+        #[arg(long)]
+        today: bool,
+        /// Add the time up by window class.
+        #[arg(long, group = "by")]
+        by_class: bool,
+        /// Add the time up by workspace.
+        #[arg(long, group = "by")]
+        by_workspace: bool,
+        /// Add the time up by window title.
+        #[arg(long, group = "by")]
+        by_title: bool,
+    },
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let database = args
+        .database
+        .or_else(|| config::data_path("time-tracker.csv"))
+        .ok_or_else(|| invalid_data("cannot find where to store the log, use --database"))?;
+    match args.action {
+        TrackerAction::Record { idle_timeout } => {
+            let mut tracker = TimeTracker {
+                database,
+                current: None,
+                idle: IdleMonitor::new(Duration::from_secs(idle_timeout)),
+            };
+            EventLoop::connect()?.run(&mut tracker)
+        }
+        TrackerAction::Report {
+            today,
+            by_workspace,
+            by_title,
+            ..
+        } => {
+            let key = if by_workspace {
+                Field::Workspace
+            } else if by_title {
+                Field::Title
+            } else {
+                Field::Class
+            };
+            let since = if today { local_midnight()? } else { 0 };
+            report(&database, key, since)
+        }
+    }
+}
+
+/// What has the focus.
+#[derive(Clone, PartialEq, Eq)]
+struct Activity {
+    workspace: String,
+    /// Empty for empty workspaces, along with the title.
+    class: String,
+    title: String,
+}
+
+pub struct TimeTracker {
+    database: PathBuf,
+    /// What has the focus, and since when, in seconds since the epoch.
+    current: Option<(Activity, u64)>,
+    idle: IdleMonitor,
+}
+
+impl Helper for TimeTracker {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window, Subscribe::Workspace, Subscribe::Tick]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        self.idle.start(i3)?;
+        self.update(i3)
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        match event.change {
+            WindowChange::Focus
+            | WindowChange::Title
+            | WindowChange::Close
+            | WindowChange::Move => self.update(i3),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_workspace(&mut self, i3: &mut dyn I3Commands, event: &WorkspaceData) -> io::Result<()> {
+        match event.change {
+            WorkspaceChange::Focus | WorkspaceChange::Rename => self.update(i3),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_tick(&mut self, i3: &mut dyn I3Commands, event: &TickData) -> io::Result<()> {
+        match self.idle.on_tick(event) {
+            Some(change) => self.on_idle_change(i3, change),
+            None => Ok(()),
+        }
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.idle.next_deadline()
+    }
+
+    fn on_deadline(&mut self, i3: &mut dyn I3Commands, now: Instant) -> io::Result<()> {
+        match self.idle.on_deadline(now)? {
+            Some(change) => self.on_idle_change(i3, change),
+            None => Ok(()),
+        }
+    }
+
+    fn on_exit(&mut self, _i3: &mut dyn I3Commands) -> io::Result<()> {
+        match self.current.take() {
+            Some((activity, start)) => self.log(&activity, start, now()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl TimeTracker {
+    /// End the current stretch at the last input when the session goes idle,
+    /// and start a new one when it is active again.
+    fn on_idle_change(&mut self, i3: &mut dyn I3Commands, change: IdleChange) -> io::Result<()> {
+        match change {
+            IdleChange::Idle(since) => match self.current.take() {
+                Some((activity, start)) => {
+                    let since = since.duration_since(UNIX_EPOCH).unwrap_or_default();
+                    self.log(&activity, start, since.as_secs())
+                }
+                None => Ok(()),
+            },
+            IdleChange::Active => self.update(i3),
+        }
+    }
+
+    /// Log the previous activity if the focus moved on to another one.
+    fn update(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        if self.idle.is_idle() {
+            return Ok(());
+        }
+        let tree = i3.get_tree()?;
+        let Some(workspace) = tree::focused_workspace(&tree) else {
+            return Ok(());
+        };
+        let activity = match tree::focused(&tree).filter(|node| tree::is_window(node)) {
+            Some(window) => {
+                let entry = WindowEntry { window, workspace };
+                Activity {
+                    workspace: entry.workspace_name().to_owned(),
+                    class: entry.class().to_owned(),
+                    title: entry.title().to_owned(),
+                }
+            }
+            None => Activity {
+                workspace: workspace.name.clone().unwrap_or_default(),
+                class: String::new(),
+                title: String::new(),
+            },
+        };
+        if self.current.as_ref().map(|(current, _)| current) == Some(&activity) {
+            return Ok(());
+        }
+        let now = now();
+        if let Some((previous, start)) = self.current.replace((activity, now)) {
+            self.log(&previous, start, now)?;
+        }
+        Ok(())
+    }
+
+    /// Append a line for `activity`, unless it lasted less than a second.
+    fn log(&self, activity: &Activity, start: u64, end: u64) -> io::Result<()> {
+        if end <= start {
+            return Ok(());
+        }
+        debug!("{}s on {:?}", end - start, activity.title);
+        if let Some(dir) = self.database.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.database)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{HEADER}")?;
+        }
+        let fields = [&activity.workspace, &activity.class, &activity.title];
+        let fields: Vec<String> = fields.iter().map(|field| escape(field)).collect();
+        writeln!(file, "{start},{},{}", end - start, fields.join(","))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Workspace,
+    Class,
+    Title,
+}
+
+/// Print the time logged in `database` since `since`, in seconds since the
+/// epoch, added up by `key`, longest first.
+fn report(database: &Path, key: Field, since: u64) -> io::Result<()> {
+    let contents = match fs::read_to_string(database) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    let mut totals: HashMap<String, u64> = HashMap::new();
+    for (number, line) in contents.lines().enumerate().skip(1) {
+        let invalid = || invalid_data(format!("invalid line {} in the log", number + 1));
+        let fields = parse_line(line).ok_or_else(invalid)?;
+        let [start, seconds, workspace, class, title] = &fields[..] else {
+            return Err(invalid());
+        };
+        let start: u64 = start.parse().map_err(|_| invalid())?;
+        let seconds: u64 = seconds.parse().map_err(|_| invalid())?;
+        // Only count the part of a stretch after `since`.
+        let seconds = (start + seconds).saturating_sub(since.max(start));
+        if seconds == 0 {
+            continue;
+        }
+        let key = match key {
+            Field::Workspace => workspace,
+            Field::Class => class,
+            Field::Title => title,
+        };
+        *totals.entry(key.clone()).or_default() += seconds;
+    }
+    let mut totals: Vec<(String, u64)> = totals.into_iter().collect();
+    totals.sort_by(|(a_key, a), (b_key, b)| b.cmp(a).then_with(|| a_key.cmp(b_key)));
+    let mut stdout = io::stdout().lock();
+    for (key, seconds) in totals {
+        let key = if key.is_empty() { "(none)" } else { &key };
+        writeln!(stdout, "{:>8}  {key}", format_duration(seconds))?;
+    }
+    Ok(())
+}
+
+/// `seconds` as hours and minutes, or minutes and seconds below an hour.
+fn format_duration(seconds: u64) -> String {
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, minutes, seconds) => format!("{minutes}m {seconds:02}s"),
+        (hours, minutes, _) => format!("{hours}h {minutes:02}m"),
+    }
+}
+
+/// `field` as a CSV field, quoted if needed. Line breaks, which only ever
+/// show up in titles, are replaced with spaces to keep one line per record.
+fn escape(field: &str) -> String {
+    let field = field.replace(['\n', '\r'], " ");
+    if field.contains([',', '"']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// The fields of a line written by [`TimeTracker::log`].
+fn parse_line(line: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    '"' => break,
+                    c => field.push(c),
+                }
+            }
+        }
+        let mut more = false;
+        for c in chars.by_ref() {
+            if c == ',' {
+                more = true;
+                break;
+            }
+            field.push(c);
+        }
+        fields.push(field);
+        if !more {
+            return Some(fields);
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// The last local midnight, in seconds since the epoch, as told by `date`.
+fn local_midnight() -> io::Result<u64> {
+    let output = Command::new("date").args(["-d", "00:00", "+%s"]).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("cannot tell when midnight was with date"));
+    }
+    let output = String::from_utf8_lossy(&output.stdout);
+    output.trim().parse().map_err(invalid_data)
+}
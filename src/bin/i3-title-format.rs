@@ -0,0 +1,283 @@
+//! Utility to rewrite the titles of windows by rule.
+//!
+//! i3's `title_format` can only rearrange a few window properties. This
+//! program listens for events from i3 and sets the `title_format` of the
+//! windows matching a rule to a template filled in by us, so that titles can
+//! show the workspace of the window, be stripped of noisy suffixes, or get an
+//! icon by class. Titles are rewritten whenever they change or their window is
+//! moved, and restored once no rule matches anymore.
+//!
+//! # Configuration
+//!
+//! The first rule matching a window applies. Templates can refer to `{title}`,
+//! `{class}`, `{instance}`, `{app_id}` and `{workspace}`:
+//!
+//! ```toml
+//! # Set to false when i3 is not using a pango font.
+//! markup = true
+//!
+//! [[rule]]
+//! match = { class = "firefox" }
+//! format = "<b>[{workspace}]</b> {title}"
+//! strip = " — Mozilla Firefox$"
+//!
+//! # Prefix terminals with an icon from Font Awesome.
+//! [[rule]]
+//! match = { class = "Alacritty" }
+//! format = "\uf120 {title}"
+//! ```
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    config::{self, invalid_data},
+    criteria::{Criteria, CriteriaConfig},
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData},
+    reply::Node,
+};
+use log::debug;
+use regex::Regex;
+use serde::Deserialize;
+use std::{collections::HashMap, io, path::PathBuf, str::FromStr};
+
+const CONFIG_FILE_NAME: &str = "title-format.toml";
+
+/// Rewrite the titles of windows by rule.
+#[derive(Parser)]
+struct Args {
+    /// The configuration file defining the rules. Defaults to
+    /// `$XDG_CONFIG_HOME/i3-helpers/title-format.toml`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    /// Whether i3 renders titles as pango markup, which window properties
+    /// must then be escaped for.
+    #[serde(default = "default_markup")]
+    markup: bool,
+    #[serde(rename = "rule")]
+    rules: Vec<RuleConfig>,
+}
+
+fn default_markup() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    #[serde(rename = "match")]
+    criteria: CriteriaConfig,
+    format: String,
+    strip: Option<String>,
+}
+
+struct Rule {
+    criteria: Criteria,
+    format: Template,
+    /// Removed from titles before filling in the template.
+    strip: Option<Regex>,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let path = args
+        .config
+        .or_else(|| config::default_path(CONFIG_FILE_NAME))
+        .ok_or_else(|| invalid_data("cannot find the configuration file, use --config"))?;
+    let config: Config = config::load(&path)?;
+    let rules = config
+        .rules
+        .into_iter()
+        .map(RuleConfig::into_rule)
+        .collect::<io::Result<_>>()?;
+    let mut title_format = TitleFormat {
+        rules,
+        markup: config.markup,
+        formats: HashMap::new(),
+    };
+    EventLoop::connect()?.run(&mut title_format)
+}
+
+impl RuleConfig {
+    fn into_rule(self) -> io::Result<Rule> {
+        Ok(Rule {
+            criteria: self.criteria.into_criteria()?,
+            format: self.format.parse().map_err(invalid_data)?,
+            strip: self
+                .strip
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(invalid_data)?,
+        })
+    }
+}
+
+/// A title template, made of literal text and window properties.
+struct Template(Vec<Segment>);
+
+enum Segment {
+    Literal(String),
+    Title,
+    Class,
+    Instance,
+    AppId,
+    Workspace,
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("unclosed placeholder in {s:?}"))?;
+            segments.push(Segment::Literal(rest[..start].to_owned()));
+            segments.push(match &rest[start + 1..start + end] {
+                "title" => Segment::Title,
+                "class" => Segment::Class,
+                "instance" => Segment::Instance,
+                "app_id" => Segment::AppId,
+                "workspace" => Segment::Workspace,
+                name => return Err(format!("unknown placeholder {{{name}}} in {s:?}")),
+            });
+            rest = &rest[start + end + 1..];
+        }
+        segments.push(Segment::Literal(rest.to_owned()));
+        Ok(Template(segments))
+    }
+}
+
+pub struct TitleFormat {
+    rules: Vec<Rule>,
+    markup: bool,
+    /// The title format we last set on each window.
+    formats: HashMap<usize, String>,
+}
+
+impl Helper for TitleFormat {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let tree = i3.get_tree()?;
+        for window in tree::windows(&tree) {
+            self.update(i3, window)?;
+        }
+        Ok(())
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        match event.change {
+            WindowChange::New | WindowChange::Title | WindowChange::Move => {
+                self.update(i3, &event.container)
+            }
+            WindowChange::Close => {
+                self.formats.remove(&event.container.id);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl TitleFormat {
+    /// Set the title format of `window` from the rule matching it, or restore
+    /// the default one if we changed it before.
+    fn update(&mut self, i3: &mut dyn I3Commands, window: &Node) -> io::Result<()> {
+        let format = match self.rules.iter().find(|rule| rule.criteria.matches(window)) {
+            Some(rule) => {
+                let tree = i3.get_tree()?;
+                let workspace = tree::workspace_of(&tree, window.id)
+                    .and_then(|workspace| workspace.name.as_deref())
+                    .unwrap_or_default();
+                Some(self.render(rule, window, workspace))
+            }
+            None => None,
+        };
+        let format = match format {
+            Some(format) if self.formats.get(&window.id) == Some(&format) => return Ok(()),
+            Some(format) => {
+                self.formats.insert(window.id, format.clone());
+                format
+            }
+            None if self.formats.remove(&window.id).is_some() => "%title".to_owned(),
+            None => return Ok(()),
+        };
+        debug!("setting the title format of {}", tree::describe(window));
+        let command = CommandBuilder::con_id(window.id).action(Action::TitleFormat(format));
+        i3.run_command(&command.build())
+    }
+
+    /// The title format for `window`, on `workspace`, following `rule`.
+    fn render(&self, rule: &Rule, window: &Node, workspace: &str) -> String {
+        let props = window.window_properties.as_ref();
+        let title = window.name.as_deref().unwrap_or_default();
+        let title = match &rule.strip {
+            Some(strip) => strip.replace_all(title, ""),
+            None => title.into(),
+        };
+        let mut format = String::new();
+        for segment in &rule.format.0 {
+            let value = match segment {
+                // Literal text is markup written by the user, kept as is.
+                Segment::Literal(text) => {
+                    format += text;
+                    continue;
+                }
+                Segment::Title => &title,
+                Segment::Class => props
+                    .and_then(|props| props.class.as_deref())
+                    .unwrap_or_default(),
+                Segment::Instance => props
+                    .and_then(|props| props.instance.as_deref())
+                    .unwrap_or_default(),
+                Segment::AppId => window.app_id.as_deref().unwrap_or_default(),
+                Segment::Workspace => workspace,
+            };
+            format += &self.escape(value);
+        }
+        format
+    }
+
+    /// Escape `value` so that i3 shows it verbatim: pango markup if enabled,
+    /// and percent signs, which would otherwise start placeholders like
+    /// `%class`.
+    fn escape(&self, value: &str) -> String {
+        if !self.markup {
+            // Without markup, there is no way to keep i3 from expanding
+            // placeholders, so make them harmless with a lookalike sign.
+            return value.replace('%', "\u{FF05}");
+        }
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            match c {
+                '&' => escaped += "&amp;",
+                '<' => escaped += "&lt;",
+                '>' => escaped += "&gt;",
+                '\'' => escaped += "&apos;",
+                '"' => escaped += "&quot;",
+                '%' => escaped += "&#37;",
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+}
@@ -0,0 +1,150 @@
+//! Utility to jump to urgent windows, and back.
+//!
+//! Run on its own, this program focuses an urgent window, e.g. a chat window
+//! that just received a message, wherever it is. Before jumping, the focused
+//! window is marked so that `--return` can bring focus back to it afterwards.
+//!
+//! i3 doesn't tell when windows became urgent, so when several are, the one
+//! picked is the first in the layout tree. The `daemon` subcommand instead
+//! keeps the order in which windows became urgent, and reacts to commands sent
+//! through i3 tick events by jumping to the most recently urgent one:
+//!
+//! ```text
+//! exec --no-startup-id i3-urgent-jump daemon
+//! bindsym $mod+u exec --no-startup-id i3-msg -t send_tick urgent-jump:jump
+//! bindsym $mod+Shift+u exec --no-startup-id i3-msg -t send_tick urgent-jump:return
+//! ```
+
+use clap::{Parser, Subcommand};
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder, CommandQueue, Criterion},
+    logging::{self, Verbosity},
+    tree, Connection, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::event::{Subscribe, TickData, WindowChange, WindowData};
+use log::info;
+use std::io;
+
+/// Jump to urgent windows, and back.
+#[derive(Parser)]
+struct Args {
+    /// Focus the window that was focused before the last jump instead.
+    #[arg(long = "return")]
+    r#return: bool,
+
+    #[command(subcommand)]
+    action: Option<JumpAction>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Subcommand)]
+enum JumpAction {
+    /// Keep track of the order in which windows become urgent, and jump on
+    /// `urgent-jump:jump` and `urgent-jump:return` tick events.
+    Daemon,
+}
+
+/// Mark of the window focused before the last jump.
+const RETURN_MARK: &str = "_urgent_jump_return";
+
+const TICK_PREFIX: &str = "urgent-jump:";
+
+type NodeId = usize;
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    match args.action {
+        Some(JumpAction::Daemon) => {
+            EventLoop::connect()?.run(&mut UrgencyQueue { urgent: Vec::new() })
+        }
+        None => {
+            let mut i3 = Connection::connect()?;
+            match args.r#return {
+                true => jump_back(&mut i3),
+                false => jump(&mut i3, None),
+            }
+        }
+    }
+}
+
+pub struct UrgencyQueue {
+    /// Urgent windows, most recently urgent last.
+    urgent: Vec<NodeId>,
+}
+
+impl Helper for UrgencyQueue {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window, Subscribe::Tick]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let tree = i3.get_tree()?;
+        self.urgent = tree::windows(&tree)
+            .filter(|window| window.urgent)
+            .map(|window| window.id)
+            .collect();
+        Ok(())
+    }
+
+    fn on_window(&mut self, _i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        let id = event.container.id;
+        match event.change {
+            WindowChange::Urgent => {
+                self.urgent.retain(|&other| other != id);
+                if event.container.urgent {
+                    self.urgent.push(id);
+                }
+            }
+            WindowChange::Close => self.urgent.retain(|&other| other != id),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn on_tick(&mut self, i3: &mut dyn I3Commands, event: &TickData) -> io::Result<()> {
+        match event.payload.strip_prefix(TICK_PREFIX) {
+            Some("jump") => jump(i3, self.urgent.last().copied()),
+            Some("return") => jump_back(i3),
+            Some(command) => {
+                log::warn!("unknown command {command:?}");
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Focus `target`, or the first urgent window if not given, marking the
+/// focused window to come back to it.
+fn jump(i3: &mut dyn I3Commands, target: Option<NodeId>) -> io::Result<()> {
+    let tree = i3.get_tree()?;
+    let target = target.or_else(|| {
+        tree::windows(&tree)
+            .find(|window| window.urgent)
+            .map(|window| window.id)
+    });
+    let Some(target) = target else {
+        info!("no urgent window");
+        return Ok(());
+    };
+    let mut commands = CommandQueue::new();
+    if let Some(focused) = tree::focused(&tree).filter(|focused| focused.id != target) {
+        commands
+            .push(CommandBuilder::con_id(focused.id).action(Action::Mark(RETURN_MARK.to_owned())));
+    }
+    commands.push(CommandBuilder::con_id(target).action(Action::Focus));
+    commands.flush(i3)
+}
+
+/// Focus the window focused before the last jump, if it still exists.
+fn jump_back(i3: &mut dyn I3Commands) -> io::Result<()> {
+    let command = CommandBuilder::new()
+        .criterion(Criterion::Mark(RETURN_MARK.to_owned()))
+        .action(Action::Focus)
+        .action(Action::Unmark(RETURN_MARK.to_owned()));
+    i3.run_command(&command.build())
+}
@@ -0,0 +1,49 @@
+//! Utility to wait for a window to appear, for startup scripts.
+//!
+//! This program returns as soon as a window matching the given criteria
+//! exists, right away if there is one already, printing its container id and
+//! its X11 window id, if any. It fails if none appears within `--timeout`, so
+//! that scripts can act on windows they launched without racing them:
+//!
+//! ```text
+//! alacritty --class editor &
+//! i3-wait-for --class editor --timeout 5s && i3-msg '[class="editor"] move to workspace 2'
+//! ```
+
+use clap::{ArgGroup, Parser};
+use i3_helpers::{
+    cli,
+    criteria::CriteriaArgs,
+    logging::{self, Verbosity},
+    wait, Connection,
+};
+use std::{io, time::Duration};
+
+/// Wait for a window to appear, for startup scripts.
+#[derive(Parser)]
+#[command(group(ArgGroup::new("criteria").required(true).multiple(true)))]
+struct Args {
+    #[command(flatten)]
+    criteria: CriteriaArgs,
+
+    /// How long to wait, e.g. `500ms` or `5s`. Waits forever if not given.
+    #[arg(long, value_parser = cli::parse_duration)]
+    timeout: Option<Duration>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let criteria = args.criteria.into_criteria();
+    let mut i3 = Connection::connect()?;
+    let window = wait::wait_for_window(&mut i3, &criteria, args.timeout)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "no matching window appeared"))?;
+    match window.window {
+        Some(x11_id) => println!("{} {x11_id}", window.id),
+        None => println!("{}", window.id),
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+//! Utility to mark windows and jump to or swap with them later.
+//!
+//! `mark` adds a mark to the focused window, `goto` focuses a marked window and
+//! `swap` exchanges the focused container with a marked one. When no mark is
+//! given, it is picked through a menu program like rofi or dmenu, listing the
+//! marked windows.
+
+use clap::{Parser, Subcommand};
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder, Criterion},
+    logging::{self, Verbosity},
+    picker, tree, Connection, I3Commands,
+};
+use std::io;
+
+/// Mark windows and jump to or swap with them later.
+#[derive(Parser)]
+struct Args {
+    /// The menu command to pick marks with, run through `sh -c`. It receives
+    /// the marked windows on its standard input, one per line, and must print
+    /// the chosen one.
+    #[arg(long, default_value = "rofi -dmenu -i -p mark")]
+    menu: String,
+
+    #[command(subcommand)]
+    action: MarkAction,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Subcommand)]
+enum MarkAction {
+    /// Add a mark to the focused window. A mark already on another window is
+    /// moved to the focused one.
+    Mark { name: Option<String> },
+    /// Focus the window with the given mark.
+    Goto { name: Option<String> },
+    /// Swap the focused container with the one with the given mark.
+    Swap { name: Option<String> },
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let (name, command): (_, fn(&str) -> CommandBuilder) = match args.action {
+        MarkAction::Mark { name } => (name, |mark| {
+            CommandBuilder::new().action(Action::Mark(mark.to_owned()))
+        }),
+        MarkAction::Goto { name } => (name, |mark| {
+            CommandBuilder::new()
+                .criterion(Criterion::Mark(mark.to_owned()))
+                .action(Action::Focus)
+        }),
+        MarkAction::Swap { name } => (name, |mark| {
+            CommandBuilder::new().action(Action::SwapWithMark(mark.to_owned()))
+        }),
+    };
+    let name = match name {
+        Some(name) => Some(name),
+        None => pick_mark(&mut i3, &args.menu)?,
+    };
+    match name {
+        Some(name) => i3.run_command(&command(&name).build()),
+        None => Ok(()),
+    }
+}
+
+/// Let the user pick one of the marked windows, or type a new mark.
+fn pick_mark(i3: &mut dyn I3Commands, menu: &str) -> io::Result<Option<String>> {
+    let tree = i3.get_tree()?;
+    let marked: Vec<(&str, String)> = tree::windows(&tree)
+        .flat_map(|window| {
+            let title = window.name.as_deref().unwrap_or_default();
+            let marks = window.marks.iter().flat_map(|marks| &marks.0);
+            marks.map(move |mark| (mark.as_str(), format!("{mark}: {title}")))
+        })
+        .collect();
+    let items: Vec<&str> = marked.iter().map(|(_, item)| item.as_str()).collect();
+    let Some(choice) = picker::pick(menu, &items)? else {
+        return Ok(None);
+    };
+    // Anything other than one of the listed windows is taken as a mark name.
+    let mark = marked
+        .iter()
+        .find(|(_, item)| *item == choice)
+        .map_or(choice.as_str(), |(mark, _)| mark);
+    Ok(Some(mark.to_owned()))
+}
@@ -0,0 +1,97 @@
+//! Utility to pick a window from a menu listing all of them.
+//!
+//! Every window is listed with its workspace, class, title and marks, urgent
+//! ones being flagged with a `!`, in columns so that the menu program can
+//! search through all of them at once. The chosen window is focused, or moved
+//! to the focused workspace or killed with `--move` and `--kill`.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder},
+    logging::{self, Verbosity},
+    picker,
+    tree::{self, WindowEntry},
+    Connection, I3Commands,
+};
+use std::io;
+
+/// Pick a window from a menu listing all of them.
+#[derive(Parser)]
+struct Args {
+    /// The menu command to pick the window with, run through `sh -c`. It
+    /// receives the numbered windows on its standard input, one per line,
+    /// and must print the chosen line, number included, e.g. `dmenu -i -l 20` or `fzf`.
+    #[arg(long, default_value = "rofi -dmenu -i -matching fuzzy -p window")]
+    menu: String,
+
+    /// Move the window to the focused workspace instead of focusing it.
+    #[arg(long, conflicts_with = "kill")]
+    r#move: bool,
+
+    /// Close the window instead of focusing it.
+    #[arg(long)]
+    kill: bool,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut i3 = Connection::connect()?;
+    let tree = i3.get_tree()?;
+    let windows = tree::list_windows(&tree);
+    let items = format_items(&windows);
+    let Some(index) = picker::pick_index(&args.menu, &items)? else {
+        return Ok(());
+    };
+    let command = CommandBuilder::con_id(windows[index].window.id);
+    if args.kill {
+        i3.run_command(&command.action(Action::Kill).build())
+    } else if args.r#move {
+        let workspace = i3
+            .get_workspaces()?
+            .into_iter()
+            .find(|workspace| workspace.focused)
+            .ok_or_else(|| io::Error::other("no focused workspace"))?;
+        let command = command
+            .action(Action::MoveToWorkspace(workspace.name))
+            .action(Action::Focus);
+        i3.run_command(&command.build())
+    } else {
+        i3.run_command(&command.action(Action::Focus).build())
+    }
+}
+
+/// One line per window, with the workspace and class in aligned columns.
+fn format_items(windows: &[WindowEntry]) -> Vec<String> {
+    let workspace_width = windows
+        .iter()
+        .map(|window| window.workspace_name().chars().count())
+        .max()
+        .unwrap_or_default();
+    let class_width = windows
+        .iter()
+        .map(|window| window.class().chars().count())
+        .max()
+        .unwrap_or_default();
+    windows
+        .iter()
+        .map(|window| {
+            let mut item = format!(
+                "{} {:workspace_width$}  {:class_width$}  {}",
+                if window.window.urgent { '!' } else { ' ' },
+                window.workspace_name(),
+                window.class(),
+                window.title(),
+            );
+            let marks: Vec<&str> = window.marks().collect();
+            if !marks.is_empty() {
+                item += &format!("  [{}]", marks.join(", "));
+            }
+            item
+        })
+        .collect()
+}
@@ -0,0 +1,205 @@
+//! Utility to open new windows in a chosen direction from the focused one.
+//!
+//! i3 opens new windows after the focused one in its container, in whatever
+//! direction that container happens to be split. This program listens for
+//! events from i3 and moves each new tiling window next to the window that
+//! had focus, to its right, left, above or below, splitting it if needed. It
+//! can also put new windows next to the largest window of the workspace
+//! instead, along its longest side, to fill the space evenly.
+//!
+//! ```text
+//! exec --no-startup-id i3-window-placement --direction below --class Alacritty=right
+//! ```
+
+use clap::{Parser, ValueEnum};
+use i3_helpers::{
+    cli,
+    command::{Action, CommandBuilder, CommandQueue, Direction, Orientation},
+    focus::FocusTracker,
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData},
+    reply::{Node, NodeLayout},
+};
+use log::{debug, info};
+use std::{io, str::FromStr};
+
+/// Mark set on the window new windows are moved next to, for the time of the
+/// move.
+const ANCHOR_MARK: &str = "_placement-anchor";
+
+/// Open new windows in a chosen direction from the focused one.
+#[derive(Parser)]
+struct Args {
+    /// Where to open new windows, relative to the focused one, or `largest` to
+    /// open them next to the largest window of the workspace.
+    #[arg(long, value_enum, default_value_t = Placement::Right)]
+    direction: Placement,
+
+    /// Where to open new windows of a given X11 class, or app_id, as
+    /// `CLASS=DIRECTION`, overriding `--direction`. Can be repeated.
+    #[arg(long = "class", value_name = "CLASS=DIRECTION")]
+    overrides: Vec<ClassOverride>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    EventLoop::connect()?.run(&mut WindowPlacement {
+        focus: FocusTracker::new(),
+        placement: args.direction,
+        overrides: args.overrides,
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Placement {
+    Right,
+    Left,
+    Above,
+    Below,
+    /// Next to the largest window, to its right if it is wider than tall and
+    /// below it otherwise.
+    Largest,
+}
+
+#[derive(Clone)]
+struct ClassOverride {
+    class: String,
+    placement: Placement,
+}
+
+impl FromStr for ClassOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (class, placement) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("invalid override {s:?}, expected CLASS=DIRECTION"))?;
+        Ok(ClassOverride {
+            class: class.to_owned(),
+            placement: Placement::from_str(placement, true)?,
+        })
+    }
+}
+
+pub struct WindowPlacement {
+    /// Keeps track of the window that had focus before the new one.
+    focus: FocusTracker,
+    placement: Placement,
+    overrides: Vec<ClassOverride>,
+}
+
+impl Helper for WindowPlacement {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window]
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        // i3 announces new windows before focusing them.
+        if event.change == WindowChange::New {
+            self.place(i3, &event.container)?;
+        }
+        self.focus.update(event);
+        Ok(())
+    }
+}
+
+impl WindowPlacement {
+    fn placement_of(&self, window: &Node) -> Placement {
+        let class = window
+            .window_properties
+            .as_ref()
+            .and_then(|props| props.class.as_deref())
+            .or(window.app_id.as_deref());
+        self.overrides
+            .iter()
+            .find(|entry| class == Some(entry.class.as_str()))
+            .map_or(self.placement, |entry| entry.placement)
+    }
+
+    /// Move the new `window` to where it should have opened.
+    fn place(&self, i3: &mut dyn I3Commands, window: &Node) -> io::Result<()> {
+        let tree = i3.get_tree()?;
+        let Some(window) = tree::find(&tree, window.id) else {
+            return Ok(());
+        };
+        if tree::is_floating(window) {
+            return Ok(());
+        }
+        let workspace = tree::workspace_of(&tree, window.id).map(|ws| ws.id);
+        let tiling = |node: &&Node| {
+            node.id != window.id
+                && !tree::is_floating(node)
+                && tree::workspace_of(&tree, node.id).map(|ws| ws.id) == workspace
+        };
+        let (anchor, direction) = match self.placement_of(window) {
+            Placement::Largest => {
+                let largest = tree::windows(&tree)
+                    .filter(tiling)
+                    .max_by_key(|node| node.rect.width * node.rect.height);
+                let Some(largest) = largest else {
+                    return Ok(());
+                };
+                let direction = match largest.rect.width >= largest.rect.height {
+                    true => Placement::Right,
+                    false => Placement::Below,
+                };
+                (largest, direction)
+            }
+            direction => {
+                let previous = self.focus.focused().and_then(|id| tree::find(&tree, id));
+                match previous.filter(tiling) {
+                    Some(previous) => (previous, direction),
+                    None => {
+                        debug!("no focused window next to {}", tree::describe(window));
+                        return Ok(());
+                    }
+                }
+            }
+        };
+        info!(
+            "moving {} next to {}",
+            tree::describe(window),
+            tree::describe(anchor)
+        );
+        let (split, layout, backwards) = match direction {
+            Placement::Left => (
+                Orientation::Horizontal,
+                NodeLayout::SplitH,
+                Some(Direction::Left),
+            ),
+            Placement::Above => (
+                Orientation::Vertical,
+                NodeLayout::SplitV,
+                Some(Direction::Up),
+            ),
+            Placement::Below => (Orientation::Vertical, NodeLayout::SplitV, None),
+            Placement::Right | Placement::Largest => {
+                (Orientation::Horizontal, NodeLayout::SplitH, None)
+            }
+        };
+        let mut commands = CommandQueue::new();
+        commands
+            .push(CommandBuilder::con_id(anchor.id).action(Action::Mark(ANCHOR_MARK.to_owned())));
+        // i3 moves containers to a mark right after it, within the same
+        // parent, which needs to be split the right way.
+        if tree::parent_of(&tree, anchor.id).is_none_or(|parent| parent.layout != layout) {
+            commands.push(CommandBuilder::con_id(anchor.id).action(Action::Split(split)));
+        }
+        commands.push(
+            CommandBuilder::con_id(window.id).action(Action::MoveToMark(ANCHOR_MARK.to_owned())),
+        );
+        if let Some(backwards) = backwards {
+            commands.push(CommandBuilder::con_id(window.id).action(Action::Move(backwards)));
+        }
+        commands.push(CommandBuilder::new().action(Action::Unmark(ANCHOR_MARK.to_owned())));
+        commands.push(CommandBuilder::con_id(window.id).action(Action::Focus));
+        commands.flush(i3)
+    }
+}
@@ -0,0 +1,72 @@
+//! Utility to cycle through the windows of the focused workspace in the order
+//! they were last focused.
+//!
+//! i3's `focus next` goes through windows in the order they appear on screen.
+//! This program instead asks the `i3-focus-history` daemon, which must be
+//! running, to go back in time through the windows of the focused workspace,
+//! alt-tab style, through an i3 tick event. With `--same-class`, it only goes
+//! through the windows of the same application as the focused one, like
+//! Alt+backtick on other desktops:
+//!
+//! ```text
+//! bindsym Mod1+grave exec --no-startup-id i3-window-stack next --same-class
+//! bindsym Mod1+Shift+grave exec --no-startup-id i3-window-stack prev --same-class
+//! ```
+
+use clap::{Parser, ValueEnum};
+use i3_helpers::{
+    cli,
+    logging::{self, Verbosity},
+    Connection,
+};
+use i3_ipc::{msg::Msg, reply::Success};
+use std::io;
+
+/// Cycle through the windows of the focused workspace, most recently focused
+/// first.
+#[derive(Parser)]
+struct Args {
+    #[arg(value_enum)]
+    direction: Direction,
+
+    /// Skip floating windows.
+    #[arg(long)]
+    skip_floating: bool,
+
+    /// Only cycle through the windows of the same X11 class, or app_id, as the
+    /// focused one.
+    #[arg(long)]
+    same_class: bool,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Direction {
+    /// Go to the window focused before the current one.
+    Next,
+    /// Go back to the window focused after the current one.
+    Prev,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let mut payload = match args.direction {
+        Direction::Next => "focus-history:stack-next".to_owned(),
+        Direction::Prev => "focus-history:stack-prev".to_owned(),
+    };
+    if args.skip_floating {
+        payload += " skip-floating";
+    }
+    if args.same_class {
+        payload += " same-class";
+    }
+    let mut i3 = Connection::connect()?;
+    let reply = i3.stream().send_receive::<_, Success>(Msg::Tick, payload)?;
+    match reply.body.success {
+        true => Ok(()),
+        false => Err(io::Error::other("i3 failed to send the tick event")),
+    }
+}
@@ -0,0 +1,204 @@
+//! Utility to organize workspaces in named groups, like `work` and `personal`.
+//!
+//! This program listens for key bindings running `nop workspace-groups ...`
+//! commands, and maps the usual workspace numbers to the workspaces of the
+//! active group: with the `work` group active, `workspace 3` goes to the
+//! workspace named `work:3`. Workspaces of the default group keep their plain
+//! numbers, so that nothing changes until another group is activated.
+//!
+//! # Commands
+//!
+//! ```text
+//! # Go to, or move the focused container to, workspace 3 of the active group.
+//! bindsym $mod+3 nop workspace-groups workspace 3
+//! bindsym $mod+Shift+3 nop workspace-groups move 3
+//! # Activate a group, going to its first workspace.
+//! bindsym $mod+F1 nop workspace-groups switch default
+//! bindsym $mod+F2 nop workspace-groups switch work
+//! # Move the focused workspace to a group, keeping its number.
+//! bindsym $mod+Shift+F2 nop workspace-groups assign work
+//! ```
+//!
+//! The active group is saved, so that it survives restarts of the daemon.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::{quote, Action, CommandBuilder},
+    config,
+    logging::{self, Verbosity},
+    status::{ReportStatus, Status, StatusReporter},
+    EventLoop, Helper, I3Commands,
+};
+use i3_ipc::event::{BindingData, Subscribe};
+use log::{info, warn};
+use std::{fs, io, path::PathBuf};
+
+/// Organize workspaces in named groups.
+///
+/// Key bindings send commands through `nop` bindings, e.g.
+/// `bindsym $mod+3 nop workspace-groups workspace 3`. Supported commands are
+/// `workspace N`, `move N`, `switch GROUP` and `assign GROUP`.
+#[derive(Parser)]
+struct Args {
+    /// The group whose workspaces are named by their plain number.
+    #[arg(long, default_value = "default")]
+    default_group: String,
+
+    /// The file the active group is saved to. Defaults to
+    /// `$XDG_STATE_HOME/i3-helpers/workspace-group`.
+    #[arg(long)]
+    state: Option<PathBuf>,
+
+    /// Print the active group to stdout whenever it changes, as JSON for an
+    /// i3blocks block or a waybar module to show.
+    #[arg(long)]
+    status: bool,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let state = args.state.or_else(|| config::state_path("workspace-group"));
+    let group = match &state {
+        Some(path) if path.exists() => fs::read_to_string(path)?.trim().to_owned(),
+        _ => args.default_group.clone(),
+    };
+    info!("active group is {group:?}");
+    let mut groups = WorkspaceGroups {
+        group,
+        default_group: args.default_group,
+        state,
+    };
+    let mut event_loop = EventLoop::connect()?;
+    match args.status {
+        true => event_loop.run(&mut StatusReporter::new(groups)),
+        false => event_loop.run(&mut groups),
+    }
+}
+
+const BINDING_PREFIX: &str = "nop workspace-groups ";
+
+pub struct WorkspaceGroups {
+    /// The active group.
+    group: String,
+    default_group: String,
+    /// Where to save the active group.
+    state: Option<PathBuf>,
+}
+
+impl Helper for WorkspaceGroups {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Binding]
+    }
+
+    fn on_binding(&mut self, i3: &mut dyn I3Commands, event: &BindingData) -> io::Result<()> {
+        let Some(command) = event.binding.command.trim().strip_prefix(BINDING_PREFIX) else {
+            return Ok(());
+        };
+        match command.split_whitespace().collect::<Vec<_>>()[..] {
+            ["workspace", number] => {
+                let name = self.workspace_name(&self.group, number);
+                i3.run_command(&format!("workspace {}", quote(&name)))
+            }
+            ["move", number] => {
+                let name = self.workspace_name(&self.group, number);
+                let command = CommandBuilder::new().action(Action::MoveToWorkspace(name));
+                i3.run_command(&command.build())
+            }
+            ["switch", group] => self.switch(i3, group),
+            ["assign", group] => self.assign(i3, group),
+            _ => {
+                warn!("unknown command {command:?}");
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ReportStatus for WorkspaceGroups {
+    fn status(&self) -> Status {
+        let class = match self.group == self.default_group {
+            true => "default",
+            false => "group",
+        };
+        Status {
+            text: self.group.clone(),
+            tooltip: format!("workspace group {:?}", self.group),
+            class: class.to_owned(),
+        }
+    }
+}
+
+impl WorkspaceGroups {
+    /// The name of workspace `number` of `group`.
+    fn workspace_name(&self, group: &str, number: &str) -> String {
+        match group == self.default_group {
+            true => number.to_owned(),
+            false => format!("{group}:{number}"),
+        }
+    }
+
+    /// The group and number of the workspace named `name`, if it is numbered.
+    fn parse_name<'a>(&'a self, name: &'a str) -> Option<(&'a str, u32)> {
+        match name.split_once(':') {
+            Some((group, number)) => Some((group, number.parse().ok()?)),
+            None => Some((&self.default_group, name.parse().ok()?)),
+        }
+    }
+
+    /// Activate `group` and go to its lowest numbered workspace.
+    fn switch(&mut self, i3: &mut dyn I3Commands, group: &str) -> io::Result<()> {
+        info!("switching to group {group:?}");
+        self.group = group.to_owned();
+        self.save()?;
+        let workspaces = i3.get_workspaces()?;
+        let first = workspaces
+            .iter()
+            .filter_map(|workspace| self.parse_name(&workspace.name))
+            .filter(|&(other, _)| other == group)
+            .map(|(_, number)| number)
+            .min()
+            .unwrap_or(1);
+        let name = self.workspace_name(group, &first.to_string());
+        i3.run_command(&format!("workspace {}", quote(&name)))
+    }
+
+    /// Move the focused workspace to `group`, keeping its number.
+    fn assign(&mut self, i3: &mut dyn I3Commands, group: &str) -> io::Result<()> {
+        let workspaces = i3.get_workspaces()?;
+        let focused = workspaces
+            .iter()
+            .find(|workspace| workspace.focused)
+            .ok_or_else(|| io::Error::other("no focused workspace"))?;
+        let Some((_, number)) = self.parse_name(&focused.name) else {
+            warn!(
+                "workspace {:?} has no number, not assigning it",
+                focused.name
+            );
+            return Ok(());
+        };
+        let name = self.workspace_name(group, &number.to_string());
+        if workspaces.iter().any(|workspace| workspace.name == name) {
+            warn!(
+                "workspace {name:?} already exists, not assigning {:?}",
+                focused.name
+            );
+            return Ok(());
+        }
+        i3.run_command(&format!("rename workspace to {}", quote(&name)))
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.state else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, format!("{}\n", self.group))
+    }
+}
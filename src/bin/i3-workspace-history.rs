@@ -0,0 +1,168 @@
+//! Utility to move back and forth through the history of focused workspaces.
+//!
+//! i3's `workspace back_and_forth` only remembers the previous workspace. This
+//! program listens for events from i3 and records the workspaces focused over
+//! time, like the history of a web browser: going back several steps and then
+//! forward again retraces the same workspaces, until another one is focused.
+//!
+//! # Commands
+//!
+//! Commands are received on the control socket, e.g. through the `i3-ws-prev`
+//! and `i3-ws-next` companion programs:
+//!
+//! ```text
+//! exec --no-startup-id i3-workspace-history
+//! bindsym $mod+bracketleft exec --no-startup-id i3-ws-prev
+//! bindsym $mod+bracketright exec --no-startup-id i3-ws-next
+//! ```
+//!
+//! - `prev [N]` focuses the workspace focused N steps back, 1 by default.
+//! - `next [N]` goes N steps forward again.
+//! - `list` prints the history, oldest first, flagging the current position.
+
+use clap::Parser;
+use i3_helpers::{
+    cli,
+    command::quote,
+    config::invalid_data,
+    control,
+    logging::{self, Verbosity},
+    EventLoop, Helper, I3Commands,
+};
+use i3_ipc::event::{Subscribe, WorkspaceChange, WorkspaceData};
+use log::debug;
+use std::{io, path::PathBuf};
+
+/// Move back and forth through the history of focused workspaces.
+#[derive(Parser)]
+struct Args {
+    /// Number of workspaces to remember.
+    #[arg(long, default_value_t = 20)]
+    size: usize,
+
+    /// The socket to listen for commands on, instead of
+    /// `$XDG_RUNTIME_DIR/i3-workspace-history.sock`.
+    #[arg(long, value_name = "PATH")]
+    control_socket: Option<PathBuf>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let socket = args
+        .control_socket
+        .or_else(|| control::default_socket_path("i3-workspace-history"))
+        .ok_or_else(|| invalid_data("XDG_RUNTIME_DIR is not set, use --control-socket PATH"))?;
+    let mut history = WorkspaceHistory {
+        history: Vec::new(),
+        position: 0,
+        size: args.size.max(2),
+        expected: None,
+    };
+    let mut event_loop = EventLoop::connect()?;
+    event_loop.set_control_socket(Some(socket));
+    event_loop.run(&mut history)
+}
+
+pub struct WorkspaceHistory {
+    /// Names of the focused workspaces, oldest first.
+    history: Vec<String>,
+    /// Position in `history` of the focused workspace.
+    position: usize,
+    size: usize,
+    /// The workspace we just asked i3 to focus, whose focus event must not
+    /// alter the history.
+    expected: Option<String>,
+}
+
+impl Helper for WorkspaceHistory {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Workspace]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let focused = i3
+            .get_workspaces()?
+            .into_iter()
+            .find(|workspace| workspace.focused);
+        if let Some(workspace) = focused {
+            self.push(workspace.name);
+        }
+        Ok(())
+    }
+
+    fn on_workspace(&mut self, _i3: &mut dyn I3Commands, event: &WorkspaceData) -> io::Result<()> {
+        if event.change != WorkspaceChange::Focus {
+            return Ok(());
+        }
+        let Some(name) = event.current.as_ref().and_then(|ws| ws.name.clone()) else {
+            return Ok(());
+        };
+        if self.expected.take().as_ref() == Some(&name) {
+            return Ok(());
+        }
+        self.push(name);
+        Ok(())
+    }
+
+    fn on_control(&mut self, i3: &mut dyn I3Commands, command: &str) -> io::Result<String> {
+        let mut words = command.split_whitespace();
+        let (action, steps) = (words.next(), words.next());
+        let steps = match steps.map(str::parse::<usize>).transpose() {
+            Ok(steps) => steps.unwrap_or(1),
+            Err(err) => return Ok(format!("error: invalid number of steps: {err}\n")),
+        };
+        match action {
+            Some("prev") if steps > self.position => {
+                Ok("error: not that many workspaces back\n".to_owned())
+            }
+            Some("prev") => self.go_to(i3, self.position - steps),
+            Some("next") if self.position + steps >= self.history.len() => {
+                Ok("error: not that many workspaces forward\n".to_owned())
+            }
+            Some("next") => self.go_to(i3, self.position + steps),
+            Some("list") => Ok(self
+                .history
+                .iter()
+                .enumerate()
+                .map(|(index, name)| {
+                    let current = if index == self.position { '*' } else { ' ' };
+                    format!("{current} {name}\n")
+                })
+                .collect()),
+            _ => Ok(format!("error: unknown command {command:?}\n")),
+        }
+    }
+}
+
+impl WorkspaceHistory {
+    /// Record `name` as the focused workspace, dropping the workspaces we went
+    /// back from.
+    fn push(&mut self, name: String) {
+        if self.history.get(self.position) == Some(&name) {
+            return;
+        }
+        self.history.truncate(self.position + 1);
+        self.history.push(name);
+        if self.history.len() > self.size {
+            self.history.remove(0);
+        }
+        self.position = self.history.len() - 1;
+        debug!("workspace history: {:?}", self.history);
+    }
+
+    fn go_to(&mut self, i3: &mut dyn I3Commands, position: usize) -> io::Result<String> {
+        let name = self.history[position].clone();
+        // The same workspace can be in the history twice, in which case i3
+        // sends no focus event when going from one to the other.
+        if name != self.history[self.position] {
+            i3.run_command(&format!("workspace {}", quote(&name)))?;
+            self.expected = Some(name.clone());
+        }
+        self.position = position;
+        Ok(format!("{name}\n"))
+    }
+}
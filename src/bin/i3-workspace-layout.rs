@@ -0,0 +1,160 @@
+//! Utility to give each workspace its own default layout.
+//!
+//! i3's `workspace_layout` setting applies to all workspaces alike. This
+//! program listens for events from i3 and sets the layout configured for a
+//! workspace, by name or number, when it is created and again when its first
+//! window appears, so that e.g. a workspace for browsers is always tabbed:
+//!
+//! ```text
+//! exec --no-startup-id i3-workspace-layout --layout web=tabbed --layout 8-10=stacking
+//! ```
+
+use clap::{Parser, ValueEnum};
+use i3_helpers::{
+    cli,
+    command::{self, Action, CommandBuilder},
+    logging::{self, Verbosity},
+    tree, EventLoop, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData, WorkspaceChange, WorkspaceData},
+    reply::Node,
+};
+use log::info;
+use std::{io, str::FromStr};
+
+/// Give each workspace its own default layout.
+#[derive(Parser)]
+struct Args {
+    /// The layout of some workspaces, as `WORKSPACES=LAYOUT` where WORKSPACES
+    /// is either a workspace name or a range of workspace numbers, e.g.
+    /// `1-3=tabbed`, and LAYOUT one of `tabbed`, `stacking`, `splith` or
+    /// `splitv`. Can be repeated, the first matching one applies.
+    #[arg(long = "layout", value_name = "WORKSPACES=LAYOUT", required = true)]
+    layouts: Vec<WorkspaceLayout>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    EventLoop::connect()?.run(&mut DefaultLayouts {
+        layouts: args.layouts,
+    })
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Layout {
+    Tabbed,
+    Stacking,
+    Splith,
+    Splitv,
+}
+
+impl From<Layout> for command::Layout {
+    fn from(layout: Layout) -> Self {
+        match layout {
+            Layout::Tabbed => command::Layout::Tabbed,
+            Layout::Stacking => command::Layout::Stacking,
+            Layout::Splith => command::Layout::SplitH,
+            Layout::Splitv => command::Layout::SplitV,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct WorkspaceLayout {
+    workspaces: Workspaces,
+    layout: Layout,
+}
+
+#[derive(Clone)]
+enum Workspaces {
+    Name(String),
+    /// Workspaces numbered from the first to the second, inclusive.
+    Range(i32, i32),
+}
+
+impl WorkspaceLayout {
+    fn matches(&self, workspace: &Node) -> bool {
+        match &self.workspaces {
+            Workspaces::Name(name) => workspace.name.as_ref() == Some(name),
+            Workspaces::Range(first, last) => workspace
+                .num
+                .is_some_and(|num| (*first..=*last).contains(&num)),
+        }
+    }
+}
+
+impl FromStr for WorkspaceLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (workspaces, layout) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("invalid layout {s:?}, expected WORKSPACES=LAYOUT"))?;
+        let range = workspaces
+            .split_once('-')
+            .and_then(|(first, last)| Some((first.parse().ok()?, last.parse().ok()?)));
+        let workspaces = match range {
+            Some((first, last)) => Workspaces::Range(first, last),
+            None => Workspaces::Name(workspaces.to_owned()),
+        };
+        Ok(WorkspaceLayout {
+            workspaces,
+            layout: Layout::from_str(layout, true)?,
+        })
+    }
+}
+
+pub struct DefaultLayouts {
+    layouts: Vec<WorkspaceLayout>,
+}
+
+impl Helper for DefaultLayouts {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Workspace, Subscribe::Window]
+    }
+
+    fn on_workspace(&mut self, i3: &mut dyn I3Commands, event: &WorkspaceData) -> io::Result<()> {
+        match (&event.change, &event.current) {
+            (WorkspaceChange::Init, Some(workspace)) => self.apply(i3, workspace, workspace.id),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        if event.change != WindowChange::New {
+            return Ok(());
+        }
+        let tree = i3.get_tree()?;
+        let window = &event.container;
+        let Some(workspace) = tree::workspace_of(&tree, window.id) else {
+            return Ok(());
+        };
+        // Only the first tiling window, which sits right under the workspace.
+        match workspace.nodes.as_slice() {
+            [only] if only.id == window.id => self.apply(i3, workspace, window.id),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl DefaultLayouts {
+    /// Set the layout configured for `workspace`, if any, on the container
+    /// `target`.
+    fn apply(&self, i3: &mut dyn I3Commands, workspace: &Node, target: usize) -> io::Result<()> {
+        let Some(layout) = self.layouts.iter().find(|layout| layout.matches(workspace)) else {
+            return Ok(());
+        };
+        let action = Action::Layout(layout.layout.into());
+        info!(
+            "setting {:?} on workspace {:?}",
+            action.to_string(),
+            workspace.name.as_deref().unwrap_or_default()
+        );
+        i3.run_command(&CommandBuilder::con_id(target).action(action).build())
+    }
+}
@@ -0,0 +1,42 @@
+//! Utility to name workspaces after the windows they contain.
+//!
+//! This program listens for events from i3 and renames numbered workspaces to
+//! `N: icon icon ...`, with one icon per distinct window class found in the
+//! workspace. Icons are taken from a configurable class to icon map, so that
+//! the bar shows at a glance what lives in each workspace.
+
+use clap::Parser;
+use i3_helpers::{
+    cli, config,
+    helpers::workspace_names::{Config, WorkspaceNames},
+    logging::{self, Verbosity},
+    EventLoop,
+};
+use std::{io, path::PathBuf};
+
+/// Name workspaces after the windows they contain.
+///
+/// Numbered workspaces are renamed to `N: icon icon ...`, with one icon per
+/// distinct window class. Workspaces without a number are left alone.
+#[derive(Parser)]
+struct Args {
+    /// TOML file with the class to icon map, e.g.
+    /// `icons = { firefox = "", alacritty = "" }`. Class names are matched
+    /// case-insensitively. Windows without an icon are shown by their class.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let config = match &args.config {
+        Some(path) => config::load(path)?,
+        None => Config::default(),
+    };
+    let mut workspace_names = WorkspaceNames::new(config);
+    EventLoop::connect()?.run(&mut workspace_names)
+}
@@ -0,0 +1,32 @@
+//! Utility to go forward through the history of focused workspaces.
+//!
+//! This is a client of `i3-workspace-history`, which must be running.
+
+use clap::Parser;
+use i3_helpers::{
+    cli, control,
+    logging::{self, Verbosity},
+};
+use std::{io, path::PathBuf, process::ExitCode};
+
+/// Go forward again through the workspace history, as recorded by `i3-workspace-history`.
+#[derive(Parser)]
+struct Args {
+    /// Number of steps to go forward.
+    #[arg(default_value_t = 1)]
+    steps: usize,
+
+    /// The socket of `i3-workspace-history`, if not the default one.
+    #[arg(long)]
+    socket: Option<PathBuf>,
+
+    #[command(flatten)]
+    verbosity: Verbosity,
+}
+
+fn main() -> io::Result<ExitCode> {
+    let args = cli::parse::<Args>();
+    logging::init(args.verbosity.level());
+    let command = format!("next {}", args.steps);
+    control::request("i3-workspace-history", args.socket, &command)
+}
@@ -0,0 +1,384 @@
+//! Static analysis of the bindings of an i3 configuration, parsed by
+//! [`i3_config`](crate::i3_config), for `i3-helpers check-bindings`.
+
+use crate::{
+    command::split_actions,
+    i3_config::{parse_criteria, tokenize, AssignTarget, Binding, BindingKind, I3Config},
+};
+use std::{collections::HashMap, fmt};
+
+/// Something wrong, or worth knowing, about a binding.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    /// The line of the configuration it is about.
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Bindings that don't work as written.
+    Error,
+    /// Bindings that likely don't do what was meant.
+    Warning,
+    /// Bindings that are fine, but may be surprising.
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        })
+    }
+}
+
+/// Words following `output` that aren't output names.
+const OUTPUT_KEYWORDS: &[&str] = &[
+    "left",
+    "right",
+    "up",
+    "down",
+    "primary",
+    "current",
+    "next",
+    "prev",
+    "nonprimary",
+];
+
+/// Words following `workspace` that aren't workspace names.
+const WORKSPACE_KEYWORDS: &[&str] = &[
+    "next",
+    "prev",
+    "next_on_output",
+    "prev_on_output",
+    "back_and_forth",
+    "current",
+];
+
+/// Check the bindings of `config`, and the outputs they refer to against
+/// `outputs`, the names of the connected ones, if known.
+pub fn check(config: &I3Config, outputs: Option<&[String]>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    check_duplicates(config, &mut findings);
+    check_modes(config, &mut findings);
+    check_workspaces(config, &mut findings);
+    if let Some(outputs) = outputs {
+        check_outputs(config, outputs, &mut findings);
+    }
+    findings.sort_by_key(|finding| (finding.line, finding.severity));
+    findings
+}
+
+/// What makes two bindings of a mode trigger on the same input: the
+/// modifiers, in any order and case, and the key.
+fn trigger(binding: &Binding) -> (BindingKind, bool, Vec<String>, String) {
+    let mut parts: Vec<&str> = binding.key.split('+').collect();
+    let key = parts.pop().unwrap_or_default().to_owned();
+    let mut modifiers: Vec<String> = parts
+        .into_iter()
+        .map(|modifier| match modifier.to_lowercase().as_str() {
+            "ctrl" => "control".to_owned(),
+            modifier => modifier.to_owned(),
+        })
+        .collect();
+    modifiers.sort();
+    let release = binding.options.iter().any(|option| option == "--release");
+    (binding.kind, release, modifiers, key)
+}
+
+fn check_duplicates(config: &I3Config, findings: &mut Vec<Finding>) {
+    let default: HashMap<_, &Binding> = config.modes[0]
+        .bindings
+        .iter()
+        .map(|binding| (trigger(binding), binding))
+        .collect();
+    for mode in &config.modes {
+        let mut seen = HashMap::new();
+        for binding in &mode.bindings {
+            let trigger = trigger(binding);
+            if let Some(first) = seen.get(&trigger) {
+                findings.push(Finding {
+                    severity: Severity::Error,
+                    line: binding.line,
+                    message: format!(
+                        "{} is bound again in mode {:?}, already on line {first}",
+                        binding.key, mode.name
+                    ),
+                });
+                continue;
+            }
+            seen.insert(trigger.clone(), binding.line);
+            let shadowed = default.get(&trigger).filter(|_| mode.name != "default");
+            if let Some(shadowed) = shadowed {
+                findings.push(Finding {
+                    severity: Severity::Note,
+                    line: binding.line,
+                    message: format!(
+                        "{} in mode {:?} shadows its default binding on line {}",
+                        binding.key, mode.name, shadowed.line
+                    ),
+                });
+            }
+        }
+    }
+}
+
+/// Every action of every binding, with the binding.
+fn actions(config: &I3Config) -> impl Iterator<Item = (&Binding, Vec<String>)> {
+    config
+        .modes
+        .iter()
+        .flat_map(|mode| &mode.bindings)
+        .flat_map(|binding| {
+            split_actions(&binding.command)
+                .into_iter()
+                .map(move |action| (binding, action_words(action)))
+        })
+}
+
+/// The words of `action`, without the criteria it may start with.
+fn action_words(action: &str) -> Vec<String> {
+    let action = match parse_criteria(action) {
+        Some((_, rest)) => rest,
+        None => action,
+    };
+    tokenize(action).into_iter().map(|(word, _)| word).collect()
+}
+
+fn check_modes(config: &I3Config, findings: &mut Vec<Finding>) {
+    let mut entered = Vec::new();
+    for (_, words) in actions(config) {
+        if words.first().is_some_and(|word| word == "mode") {
+            let name = words[1..].iter().find(|word| !word.starts_with("--"));
+            entered.extend(name.cloned());
+        }
+    }
+    for mode in &config.modes[1..] {
+        if !entered.contains(&mode.name) {
+            let line = mode.bindings.first().map_or(0, |binding| binding.line);
+            findings.push(Finding {
+                severity: Severity::Warning,
+                line,
+                message: format!("no binding enters mode {:?}", mode.name),
+            });
+        }
+    }
+}
+
+/// A workspace referred to by a command or `assign`.
+#[derive(Debug, Clone, PartialEq)]
+struct WorkspaceRef {
+    name: String,
+    /// Whether it is referred to by number, e.g. `workspace number 1`, which
+    /// also stands for `1: web`.
+    by_number: bool,
+}
+
+impl WorkspaceRef {
+    /// Parse the words following `workspace`, if they refer to a workspace.
+    fn parse(words: &[String]) -> Option<Self> {
+        let words: Vec<&String> = words.iter().filter(|w| !w.starts_with("--")).collect();
+        let (by_number, words) = match words.split_first() {
+            Some((first, rest)) if *first == "number" => (true, rest),
+            _ => (false, words.as_slice()),
+        };
+        let name = words.iter().map(|word| word.as_str()).collect::<Vec<_>>();
+        let name = name.join(" ");
+        if name.is_empty() || (!by_number && WORKSPACE_KEYWORDS.contains(&name.as_str())) {
+            return None;
+        }
+        Some(Self { name, by_number })
+    }
+
+    fn number(&self) -> Option<u32> {
+        let digits: String = self.name.chars().take_while(char::is_ascii_digit).collect();
+        digits.parse().ok()
+    }
+
+    /// Whether switching to `self` gets to `target`.
+    fn reaches(&self, target: &WorkspaceRef) -> bool {
+        match self.by_number || target.by_number {
+            true => self.number().is_some() && self.number() == target.number(),
+            false => self.name == target.name,
+        }
+    }
+}
+
+fn check_workspaces(config: &I3Config, findings: &mut Vec<Finding>) {
+    let mut switched_to = Vec::new();
+    let mut sent_to = Vec::new();
+    for (binding, words) in actions(config) {
+        let Some(index) = words.iter().position(|word| word == "workspace") else {
+            continue;
+        };
+        // `move workspace to output ...` moves the focused workspace.
+        if words.get(index + 1).is_some_and(|word| word == "to") {
+            continue;
+        }
+        let Some(workspace) = WorkspaceRef::parse(&words[index + 1..]) else {
+            continue;
+        };
+        match words[0].as_str() {
+            "workspace" => switched_to.push(workspace),
+            "move" => sent_to.push((binding.line, workspace)),
+            _ => (),
+        }
+    }
+    for assign in &config.assigns {
+        let workspace = match &assign.target {
+            AssignTarget::Workspace(name) => WorkspaceRef {
+                name: name.clone(),
+                by_number: false,
+            },
+            AssignTarget::WorkspaceNumber(name) => WorkspaceRef {
+                name: name.clone(),
+                by_number: true,
+            },
+            AssignTarget::Output(_) => continue,
+        };
+        sent_to.push((assign.line, workspace));
+    }
+    for (line, workspace) in sent_to {
+        if !switched_to.iter().any(|switch| switch.reaches(&workspace)) {
+            findings.push(Finding {
+                severity: Severity::Warning,
+                line,
+                message: format!(
+                    "windows are sent to workspace {:?}, but no binding switches to it",
+                    workspace.name
+                ),
+            });
+        }
+    }
+}
+
+fn check_outputs(config: &I3Config, outputs: &[String], findings: &mut Vec<Finding>) {
+    let mut referenced = Vec::new();
+    for (binding, words) in actions(config) {
+        if !words
+            .first()
+            .is_some_and(|word| word == "focus" || word == "move")
+        {
+            continue;
+        }
+        let Some(index) = words.iter().position(|word| word == "output") else {
+            continue;
+        };
+        // `move workspace to output` takes several outputs to cycle through.
+        for name in &words[index + 1..] {
+            referenced.push((binding.line, name.clone()));
+        }
+    }
+    for assign in &config.assigns {
+        if let AssignTarget::Output(name) = &assign.target {
+            referenced.push((assign.line, name.clone()));
+        }
+    }
+    for (line, name) in referenced {
+        if !OUTPUT_KEYWORDS.contains(&name.as_str()) && !outputs.contains(&name) {
+            findings.push(Finding {
+                severity: Severity::Warning,
+                line,
+                message: format!("output {name:?} is not connected"),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i3_config::parse;
+
+    fn messages(config: &str, outputs: Option<&[String]>) -> Vec<(usize, String)> {
+        check(&parse(config), outputs)
+            .into_iter()
+            .map(|finding| (finding.line, finding.message))
+            .collect()
+    }
+
+    #[test]
+    fn reports_duplicate_bindings_regardless_of_modifier_order() {
+        let config = "bindsym Mod4+Shift+q kill\nbindsym shift+Mod4+q exit\n";
+        assert_eq!(
+            messages(config, None),
+            [(
+                2,
+                "shift+Mod4+q is bound again in mode \"default\", already on line 1".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn tells_release_bindings_apart() {
+        let config = "bindsym Mod4+x kill\nbindsym --release Mod4+x exec shot\n";
+        assert!(messages(config, None).is_empty());
+    }
+
+    #[test]
+    fn reports_modes_shadowing_default_bindings_and_never_entered() {
+        let config = "\
+bindsym Mod4+r mode resize
+bindsym Escape exec foo
+mode resize {
+    bindsym Escape mode default
+}
+mode unused {
+    bindsym q mode default
+}
+";
+        assert_eq!(
+            messages(config, None),
+            [
+                (
+                    4,
+                    "Escape in mode \"resize\" shadows its default binding on line 2".to_owned()
+                ),
+                (7, "no binding enters mode \"unused\"".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_workspaces_windows_are_sent_to_but_never_shown() {
+        let config = "\
+bindsym Mod4+1 workspace number 1
+bindsym Mod4+Shift+1 move container to workspace number \"1: web\"
+bindsym Mod4+Shift+9 move container to workspace music
+assign [class=\"Slack\"] chat
+";
+        assert_eq!(
+            messages(config, None),
+            [
+                (
+                    3,
+                    "windows are sent to workspace \"music\", but no binding switches to it"
+                        .to_owned()
+                ),
+                (
+                    4,
+                    "windows are sent to workspace \"chat\", but no binding switches to it"
+                        .to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_disconnected_outputs() {
+        let config = "\
+bindsym Mod4+o move workspace to output right
+bindsym Mod4+p focus output HDMI-1
+bindsym Mod4+Shift+p [class=\"x\"] move container to output eDP-1
+";
+        let outputs = ["eDP-1".to_owned()];
+        assert_eq!(
+            messages(config, Some(&outputs)),
+            [(2, "output \"HDMI-1\" is not connected".to_owned())]
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Emacs-style key sequences, emulated with nested binding modes.
+//!
+//! A chord like `C-x C-f` is a sequence of keys, each written as a keysym
+//! after any of the modifiers `C-` for Control, `M-` for Mod1, usually Alt,
+//! `s-` for Mod4, usually Super, and `S-` for Shift. Keys already in i3's
+//! syntax, like `$mod+x`, are taken as is. Keys are compared regardless of
+//! how they are written, so that `C-x` and `Control+x` are the same key.
+//!
+//! i3 can only wait for the next key of a sequence from a binding mode, so
+//! every prefix of a chord gets a mode named after it: the first key enters
+//! the mode of the first prefix, where the next key either enters a deeper
+//! mode or returns to the default mode and runs the command of the chord.
+//! [`Chords::to_config`] writes these modes for i3 to include.
+
+use crate::command::quote;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    str::FromStr,
+};
+
+/// The i3 modifier of each Emacs-style prefix.
+const MODIFIERS: &[(&str, &str)] = &[
+    ("C-", "Control"),
+    ("M-", "Mod1"),
+    ("s-", "Mod4"),
+    ("S-", "Shift"),
+];
+
+const DEFAULT_MODE: &str = "default";
+
+/// A key of a chord.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Key {
+    /// The i3 modifiers, like `Control`, in the order of [`MODIFIERS`] then
+    /// by name, so that keys compare the same however they are written.
+    modifiers: Vec<String>,
+    keysym: String,
+}
+
+impl Key {
+    /// The key in i3's syntax, like `Control+x`.
+    pub fn to_i3(&self) -> String {
+        let mut parts = self.modifiers.clone();
+        parts.push(self.keysym.clone());
+        parts.join("+")
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers: Vec<String>;
+        let keysym;
+        if let Some((i3_modifiers, i3_keysym)) = s.rsplit_once('+') {
+            modifiers = i3_modifiers.split('+').map(str::to_owned).collect();
+            keysym = i3_keysym;
+        } else {
+            let mut rest = s;
+            modifiers = Vec::new();
+            while let Some((prefix, modifier)) = MODIFIERS
+                .iter()
+                .find(|(prefix, _)| rest.starts_with(prefix) && rest.len() > prefix.len())
+            {
+                modifiers.push((*modifier).to_owned());
+                rest = &rest[prefix.len()..];
+            }
+            keysym = rest;
+        }
+        // A trailing modifier, as in `C-`, lacks its keysym.
+        let is_modifier = |text: &str| MODIFIERS.iter().any(|(prefix, _)| text == *prefix);
+        if keysym.is_empty() || is_modifier(keysym) || modifiers.iter().any(|m| m.is_empty()) {
+            return Err(format!("invalid key {s:?}"));
+        }
+        modifiers.sort_by_key(|modifier| {
+            let known = MODIFIERS.iter().position(|(_, name)| name == modifier);
+            (known.unwrap_or(MODIFIERS.len()), modifier.clone())
+        });
+        modifiers.dedup();
+        Ok(Key {
+            modifiers,
+            keysym: keysym.to_owned(),
+        })
+    }
+}
+
+/// The key in Emacs' syntax, like `C-x`, unless it has modifiers without an
+/// Emacs prefix, like `$mod`, in which case it is in i3's syntax.
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let prefixes: Option<String> = self
+            .modifiers
+            .iter()
+            .map(|modifier| {
+                MODIFIERS
+                    .iter()
+                    .find(|(_, name)| name == modifier)
+                    .map(|(prefix, _)| *prefix)
+            })
+            .collect();
+        match prefixes {
+            Some(prefixes) => write!(f, "{prefixes}{}", self.keysym),
+            None => f.write_str(&self.to_i3()),
+        }
+    }
+}
+
+/// A set of chords and the commands they run.
+#[derive(Debug)]
+pub struct Chords {
+    /// The command of each chord, by sequence of keys.
+    chords: BTreeMap<Vec<Key>, String>,
+}
+
+impl Chords {
+    /// Parse the chords of `definitions`, pairs of a sequence of keys
+    /// separated by spaces and the i3 command it runs, failing if a chord is
+    /// invalid or the prefix of another, which could never be completed.
+    pub fn new<I>(definitions: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut chords = BTreeMap::new();
+        for (keys, command) in definitions {
+            let keys: Vec<Key> = keys
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()?;
+            if keys.is_empty() {
+                return Err("empty chord".to_owned());
+            }
+            if chords.contains_key(&keys) {
+                return Err(format!("chord {:?} is defined twice", join(&keys)));
+            }
+            chords.insert(keys, command);
+        }
+        for (a, b) in chords.keys().zip(chords.keys().skip(1)) {
+            // Sorted sequences put a prefix right before what it prefixes.
+            if b.starts_with(a) {
+                return Err(format!("chord {:?} is a prefix of {:?}", join(a), join(b)));
+            }
+        }
+        Ok(Self { chords })
+    }
+
+    /// The names of the modes the chords go through.
+    pub fn modes(&self) -> BTreeSet<String> {
+        self.chords
+            .keys()
+            .flat_map(|keys| (1..keys.len()).map(|len| join(&keys[..len])))
+            .collect()
+    }
+
+    /// The i3 configuration binding the chords, to be included in the main
+    /// configuration file.
+    pub fn to_config(&self) -> String {
+        // The bindings of each mode, from the key pressed to what it does.
+        let mut modes: BTreeMap<String, BTreeMap<&Key, String>> = BTreeMap::new();
+        for (keys, command) in &self.chords {
+            for (len, key) in keys.iter().enumerate() {
+                let mode = match len {
+                    0 => DEFAULT_MODE.to_owned(),
+                    _ => join(&keys[..len]),
+                };
+                let action = if len + 1 == keys.len() {
+                    format!("mode {}; {command}", quote(DEFAULT_MODE))
+                } else {
+                    format!("mode {}", quote(&join(&keys[..=len])))
+                };
+                modes.entry(mode).or_default().insert(key, action);
+            }
+        }
+        let mut config = "# Generated by i3-chords, do not edit.\n".to_owned();
+        let bindings = |bindings: &BTreeMap<&Key, String>, indent: &str| {
+            bindings
+                .iter()
+                .map(|(key, action)| format!("{indent}bindsym {} {action}\n", key.to_i3()))
+                .collect::<String>()
+        };
+        if let Some(default) = modes.remove(DEFAULT_MODE) {
+            config += &bindings(&default, "");
+        }
+        for (name, mode) in &modes {
+            config += &format!("\nmode {} {{\n", quote(name));
+            config += &bindings(mode, "    ");
+            if !mode.keys().any(|key| key.to_i3() == "Escape") {
+                config += &format!("    bindsym Escape mode {}\n", quote(DEFAULT_MODE));
+            }
+            config += "}\n";
+        }
+        config
+    }
+}
+
+fn join(keys: &[Key]) -> String {
+    let keys: Vec<String> = keys.iter().map(Key::to_string).collect();
+    keys.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chords(definitions: &[(&str, &str)]) -> Result<Chords, String> {
+        Chords::new(
+            definitions
+                .iter()
+                .map(|(keys, command)| (keys.to_string(), command.to_string())),
+        )
+    }
+
+    #[test]
+    fn generates_nested_modes() {
+        let chords = chords(&[
+            ("C-x C-f", "exec firefox"),
+            ("C-x C-c", "exit"),
+            ("C-x 4 b", "exec i3-window-menu"),
+            ("$mod+o", "exec i3-tab-overview"),
+        ])
+        .unwrap();
+        assert_eq!(
+            chords.modes(),
+            BTreeSet::from(["C-x".to_owned(), "C-x 4".to_owned()])
+        );
+        assert_eq!(
+            chords.to_config(),
+            "# Generated by i3-chords, do not edit.\n\
+             bindsym $mod+o mode \"default\"; exec i3-tab-overview\n\
+             bindsym Control+x mode \"C-x\"\n\
+             \n\
+             mode \"C-x\" {\n    \
+                 bindsym 4 mode \"C-x 4\"\n    \
+                 bindsym Control+c mode \"default\"; exit\n    \
+                 bindsym Control+f mode \"default\"; exec firefox\n    \
+                 bindsym Escape mode \"default\"\n\
+             }\n\
+             \n\
+             mode \"C-x 4\" {\n    \
+                 bindsym b mode \"default\"; exec i3-window-menu\n    \
+                 bindsym Escape mode \"default\"\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn parses_modifiers() {
+        let key: Key = "C-M-S-Return".parse().unwrap();
+        assert_eq!(key.to_i3(), "Control+Mod1+Shift+Return");
+        let key: Key = "s--".parse().unwrap();
+        assert_eq!(key.to_i3(), "Mod4+-");
+        assert!("C-".parse::<Key>().is_err());
+        assert!("".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn compares_keys_however_written() {
+        let emacs: Key = "M-C-x".parse().unwrap();
+        let i3: Key = "Control+Mod1+x".parse().unwrap();
+        assert_eq!(emacs, i3);
+        assert_eq!(i3.to_string(), "C-M-x");
+        let err = chords(&[("C-x b", "exec a"), ("Control+x b", "exec b")]).unwrap_err();
+        assert_eq!(err, "chord \"C-x b\" is defined twice");
+    }
+
+    #[test]
+    fn rejects_prefixes_of_other_chords() {
+        let err = chords(&[("C-x", "exec a"), ("C-x C-f", "exec b")]).unwrap_err();
+        assert_eq!(err, "chord \"C-x\" is a prefix of \"C-x C-f\"");
+    }
+}
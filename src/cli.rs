@@ -0,0 +1,94 @@
+//! Command line parsing shared by the binaries, which can also print their
+//! shell completions and man page, generated from their clap definitions.
+//!
+//! Each binary defines its own arguments, so `i3-helpers completions` and
+//! `i3-helpers man` run each of them with `$I3_HELPERS_GENERATE` set to ask for
+//! one or the other instead of running.
+
+use clap::{CommandFactory, Parser, ValueEnum};
+use clap_complete::Shell;
+use std::{
+    env,
+    io::{self, Write},
+    path::Path,
+    process,
+    time::Duration,
+};
+
+/// The environment variable asking a binary to print its shell completions,
+/// as `completions:SHELL`, or its man page, as `man`, and exit.
+pub const GENERATE_VAR: &str = "I3_HELPERS_GENERATE";
+
+/// Parse the command line arguments, unless `$I3_HELPERS_GENERATE` is set, in
+/// which case print what it asks for and exit.
+pub fn parse<T: Parser>() -> T {
+    if let Some(request) = env::var_os(GENERATE_VAR) {
+        let mut stdout = io::stdout().lock();
+        match generate::<T>(&request.to_string_lossy(), &mut stdout) {
+            Ok(()) => process::exit(0),
+            Err(err) => {
+                eprintln!("error: {err}");
+                process::exit(1);
+            }
+        }
+    }
+    T::parse()
+}
+
+/// Write the shell completions or the man page of the binary defining `T`,
+/// as requested in the format of `$I3_HELPERS_GENERATE`.
+pub fn generate<T: CommandFactory>(request: &str, out: &mut dyn Write) -> io::Result<()> {
+    // clap names all the binaries after the package, unless told otherwise.
+    let name = env::args_os()
+        .next()
+        .as_deref()
+        .and_then(|arg0| Path::new(arg0).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| env!("CARGO_PKG_NAME").to_owned());
+    let mut command = T::command().name(name.clone()).bin_name(name.clone());
+    if request == "man" {
+        return clap_mangen::Man::new(command).render(out);
+    }
+    let shell = request
+        .strip_prefix("completions:")
+        .and_then(|shell| Shell::from_str(shell, true).ok())
+        .ok_or_else(|| io::Error::other(format!("invalid {GENERATE_VAR} {request:?}")))?;
+    clap_complete::generate(shell, &mut command, name, out);
+    Ok(())
+}
+
+/// Parse a duration given as a number followed by `ms`, `s`, `m` or `h`, e.g.
+/// `500ms` or `1.5s`, or as a plain number of seconds, for use as a clap
+/// `value_parser`.
+pub fn parse_duration(arg: &str) -> Result<Duration, String> {
+    let split = arg
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(arg.len());
+    let (number, unit) = arg.split_at(split);
+    let seconds_per_unit = match unit {
+        "ms" => 0.001,
+        "" | "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        _ => return Err(format!("invalid unit {unit:?}, expected ms, s, m or h")),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration {arg:?}"))?;
+    Duration::try_from_secs_f64(number * seconds_per_unit).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("5s"), Ok(Duration::from_secs(5)));
+        assert_eq!(parse_duration("5"), Ok(Duration::from_secs(5)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1.5m"), Ok(Duration::from_secs(90)));
+        assert!(parse_duration("5d").is_err());
+        assert!(parse_duration("s").is_err());
+    }
+}
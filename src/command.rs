@@ -0,0 +1,387 @@
+//! Helpers for building i3 commands.
+//!
+//! Commands acting on specific windows are best built with a
+//! [`CommandBuilder`], which quotes and escapes the values it is given, so
+//! that window titles or workspace names containing `"` or `]` can't break
+//! the command, or make it act on other windows.
+
+use crate::I3Commands;
+use std::{fmt, io};
+
+/// Quote `value` as a string argument of an i3 command, escaping any double
+/// quotes and backslashes it contains.
+pub fn quote(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{escaped}\"")
+}
+
+/// The command moving the workspace `name` to `output`. Workspaces can only be
+/// moved once focused, so this focuses it first.
+pub fn move_workspace(name: &str, output: &str) -> String {
+    format!(
+        "workspace --no-auto-back-and-forth {}; move workspace to output {}",
+        quote(name),
+        quote(output)
+    )
+}
+
+/// The number of actions in `command`, which i3 replies to separately: one
+/// more than the number of `,` and `;` separators outside of quoted strings.
+pub(crate) fn count_actions(command: &str) -> usize {
+    split_actions(command).len()
+}
+
+/// The actions of `command`, split on the `,` and `;` separators outside of
+/// quoted strings, and trimmed.
+pub fn split_actions(command: &str) -> Vec<&str> {
+    let (mut actions, mut start, mut quoted, mut escaped) = (Vec::new(), 0, false, false);
+    for (index, c) in command.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' | ';' if !quoted => {
+                actions.push(command[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    actions.push(command[start..].trim());
+    actions
+}
+
+/// Commands collected while handling an event, to be sent to i3 as a single
+/// message.
+///
+/// Besides saving round trips, the commands are then run without other
+/// commands, e.g. from key bindings, sneaking in between them. Failures are
+/// logged and counted by the [`Connection`](crate::Connection).
+#[derive(Debug, Default)]
+pub struct CommandQueue {
+    commands: Vec<String>,
+}
+
+impl CommandQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, command: impl Into<String>) {
+        self.commands.push(command.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Run the queued commands, leaving the queue empty.
+    pub fn flush(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let commands = std::mem::take(&mut self.commands);
+        i3.run_commands(&commands)
+    }
+}
+
+/// Selects the windows a command applies to, with values matched exactly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Criterion {
+    ConId(usize),
+    Class(String),
+    Mark(String),
+    Title(String),
+}
+
+/// The orientation of a split, or the dimension of a container along it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+}
+
+/// The layout of a container's children.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    SplitH,
+    SplitV,
+    Tabbed,
+    Stacking,
+}
+
+/// A direction to move a container in, within its parent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// What a command does to the windows it applies to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    MoveScratchpad,
+    ScratchpadShow,
+    MoveToWorkspace(String),
+    /// Move the container next to the one with the given mark.
+    MoveToMark(String),
+    Move(Direction),
+    /// Swap the container with the one with the given id.
+    Swap(usize),
+    /// Swap the container with the one with the given mark.
+    SwapWithMark(String),
+    FloatingEnable,
+    FloatingDisable,
+    StickyEnable,
+    StickyDisable,
+    FullscreenEnable,
+    /// Resize a floating window to a width and height, in pixels.
+    Resize(isize, isize),
+    /// Resize a tiled container to a share of its parent along the given
+    /// orientation, in percentage points.
+    ResizeShare(Orientation, u32),
+    /// Move a floating window to a position, in pixels.
+    MovePosition(isize, isize),
+    MovePositionCenter,
+    Split(Orientation),
+    Layout(Layout),
+    /// Set the border style, as written in i3's configuration, e.g. `pixel 2`.
+    Border(String),
+    /// Set the opacity of the window, from 0 to 1. Sway only.
+    Opacity(f64),
+    TitleFormat(String),
+    /// Keep the session from going idle while the window is visible, or stop
+    /// doing so. Sway only.
+    InhibitIdle(bool),
+    Focus,
+    Kill,
+    /// Add a mark to the window.
+    Mark(String),
+    Unmark(String),
+    /// An action given by the user, written as is.
+    Raw(String),
+}
+
+/// Builds an i3 command running some actions on the windows matching some
+/// criteria.
+///
+/// ```
+/// use i3_helpers::command::{Action, CommandBuilder, Criterion};
+///
+/// let command = CommandBuilder::new()
+///     .criterion(Criterion::Title("[draft] \"notes\"".to_owned()))
+///     .action(Action::MoveToWorkspace("2: mail".to_owned()))
+///     .action(Action::Focus)
+///     .build();
+/// assert_eq!(
+///     command,
+///     r#"[title="^\\[draft\\] \"notes\"$"] move container to workspace "2: mail", focus"#
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CommandBuilder {
+    criteria: Vec<Criterion>,
+    actions: Vec<Action>,
+}
+
+impl CommandBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A command acting on the container with the given id, the most common
+    /// case.
+    pub fn con_id(id: usize) -> Self {
+        Self::new().criterion(Criterion::ConId(id))
+    }
+
+    /// Only act on the windows matching `criterion`, on top of the other
+    /// criteria.
+    pub fn criterion(mut self, criterion: Criterion) -> Self {
+        self.criteria.push(criterion);
+        self
+    }
+
+    /// Run `action`, after the previous ones.
+    pub fn action(mut self, action: Action) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    pub fn build(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl From<CommandBuilder> for String {
+    fn from(command: CommandBuilder) -> Self {
+        command.build()
+    }
+}
+
+impl fmt::Display for CommandBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.criteria.is_empty() {
+            f.write_str("[")?;
+            for (index, criterion) in self.criteria.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(" ")?;
+                }
+                write!(f, "{criterion}")?;
+            }
+            f.write_str("] ")?;
+        }
+        for (index, action) in self.actions.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{action}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Criterion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Besides ids, criteria values are regular expressions to i3.
+        let exact = |value: &str| quote(&format!("^{}$", regex::escape(value)));
+        match self {
+            Criterion::ConId(id) => write!(f, "con_id={id}"),
+            Criterion::Class(class) => write!(f, "class={}", exact(class)),
+            Criterion::Mark(mark) => write!(f, "con_mark={}", exact(mark)),
+            Criterion::Title(title) => write!(f, "title={}", exact(title)),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::MoveScratchpad => f.write_str("move scratchpad"),
+            Action::ScratchpadShow => f.write_str("scratchpad show"),
+            Action::MoveToWorkspace(name) => {
+                write!(f, "move container to workspace {}", quote(name))
+            }
+            Action::MoveToMark(mark) => write!(f, "move container to mark {}", quote(mark)),
+            Action::Move(direction) => write!(f, "move {direction}"),
+            Action::Swap(id) => write!(f, "swap container with con_id {id}"),
+            Action::SwapWithMark(mark) => write!(f, "swap container with mark {}", quote(mark)),
+            Action::FloatingEnable => f.write_str("floating enable"),
+            Action::FloatingDisable => f.write_str("floating disable"),
+            Action::StickyEnable => f.write_str("sticky enable"),
+            Action::StickyDisable => f.write_str("sticky disable"),
+            Action::FullscreenEnable => f.write_str("fullscreen enable"),
+            Action::Resize(width, height) => write!(f, "resize set {width} px {height} px"),
+            Action::ResizeShare(orientation, share) => {
+                let dimension = match orientation {
+                    Orientation::Horizontal => "width",
+                    Orientation::Vertical => "height",
+                };
+                write!(f, "resize set {dimension} {share} ppt")
+            }
+            Action::MovePosition(x, y) => write!(f, "move position {x} px {y} px"),
+            Action::MovePositionCenter => f.write_str("move position center"),
+            Action::Split(orientation) => write!(f, "split {orientation}"),
+            Action::Layout(layout) => write!(f, "layout {layout}"),
+            Action::Border(style) => write!(f, "border {style}"),
+            Action::Opacity(opacity) => write!(f, "opacity {opacity}"),
+            Action::TitleFormat(format) => write!(f, "title_format {}", quote(format)),
+            Action::InhibitIdle(true) => f.write_str("inhibit_idle open"),
+            Action::InhibitIdle(false) => f.write_str("inhibit_idle none"),
+            Action::Focus => f.write_str("focus"),
+            Action::Kill => f.write_str("kill"),
+            Action::Mark(mark) => write!(f, "mark --add {}", quote(mark)),
+            Action::Unmark(mark) => write!(f, "unmark {}", quote(mark)),
+            Action::Raw(action) => f.write_str(action),
+        }
+    }
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Orientation::Horizontal => "horizontal",
+            Orientation::Vertical => "vertical",
+        })
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Layout::SplitH => "splith",
+            Layout::SplitV => "splitv",
+            Layout::Tabbed => "tabbed",
+            Layout::Stacking => "stacking",
+        })
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::Up => "up",
+            Direction::Down => "down",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_values() {
+        assert_eq!(quote("2: mail"), r#""2: mail""#);
+        assert_eq!(quote(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(quote(r"C:\temp"), r#""C:\\temp""#);
+        // Separators and brackets are harmless once quoted.
+        assert_eq!(quote("a]; kill, [b"), r#""a]; kill, [b""#);
+    }
+
+    #[test]
+    fn escapes_criteria_values() {
+        let title = r#"[draft] "a", b; c\d"#;
+        let command = CommandBuilder::new()
+            .criterion(Criterion::Title(title.to_owned()))
+            .action(Action::Kill)
+            .build();
+        assert_eq!(command, r#"[title="^\\[draft\\] \"a\", b; c\\\\d$"] kill"#);
+        assert_eq!(split_actions(&command), [command.as_str()]);
+    }
+
+    #[test]
+    fn splits_actions_outside_of_quotes() {
+        let command = r#"[title="a, b; c"] focus, mark --add "x;y"; kill"#;
+        assert_eq!(
+            split_actions(command),
+            [r#"[title="a, b; c"] focus"#, r#"mark --add "x;y""#, "kill"]
+        );
+        assert_eq!(count_actions(command), 3);
+        // An escaped quote doesn't end the quoted string.
+        let command = r#"mark --add "a\", b", focus"#;
+        assert_eq!(split_actions(command), [r#"mark --add "a\", b""#, "focus"]);
+        assert_eq!(count_actions(command), 2);
+        assert_eq!(count_actions("focus"), 1);
+    }
+
+    #[test]
+    fn builds_actions() {
+        let command = CommandBuilder::con_id(4)
+            .action(Action::Swap(7))
+            .action(Action::Split(Orientation::Vertical))
+            .action(Action::Layout(Layout::Tabbed))
+            .action(Action::ResizeShare(Orientation::Horizontal, 60))
+            .action(Action::Border("pixel 2".to_owned()))
+            .action(Action::TitleFormat("<b>%title</b>".to_owned()))
+            .action(Action::Move(Direction::Left))
+            .build();
+        assert_eq!(
+            command,
+            "[con_id=4] swap container with con_id 7, split vertical, layout tabbed, \
+             resize set width 60 ppt, border pixel 2, title_format \"<b>%title</b>\", \
+             move left"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Loading of TOML configuration files, and where to find them.
+
+use serde::de::DeserializeOwned;
+use std::{env, fs, io, path::Path, path::PathBuf};
+
+/// Load and parse the TOML configuration file at `path`.
+pub fn load<T: DeserializeOwned>(path: &Path) -> io::Result<T> {
+    let contents = fs::read_to_string(path)?;
+    toml::from_str(&contents).map_err(invalid_data)
+}
+
+/// The default location of a configuration file, under
+/// `$XDG_CONFIG_HOME/i3-helpers/`, or `~/.config/i3-helpers/` if unset.
+pub fn default_path(file_name: &str) -> Option<PathBuf> {
+    xdg_path("XDG_CONFIG_HOME", ".config", file_name)
+}
+
+/// The default location of a file holding state that persists across restarts,
+/// under `$XDG_STATE_HOME/i3-helpers/`, or `~/.local/state/i3-helpers/` if
+/// unset.
+pub fn state_path(file_name: &str) -> Option<PathBuf> {
+    xdg_path("XDG_STATE_HOME", ".local/state", file_name)
+}
+
+/// The default location of a file holding data accumulated over time, under
+/// `$XDG_DATA_HOME/i3-helpers/`, or `~/.local/share/i3-helpers/` if unset.
+pub fn data_path(file_name: &str) -> Option<PathBuf> {
+    xdg_path("XDG_DATA_HOME", ".local/share", file_name)
+}
+
+/// The environment variable naming the main configuration file, which takes
+/// precedence over the default locations.
+pub const CONFIG_VAR: &str = "I3_HELPERS_CONFIG";
+
+/// The name of the main configuration file, shared by the helpers run together.
+pub const MAIN_FILE_NAME: &str = "config.toml";
+
+/// Where system-wide configuration files live, looked at after the user's.
+pub const SYSTEM_DIR: &str = "/etc/xdg/i3-helpers";
+
+/// Find the main configuration file: the one named by `$I3_HELPERS_CONFIG` if
+/// set, whether it exists or not, else the first existing one of
+/// `$XDG_CONFIG_HOME/i3-helpers/config.toml` and
+/// `/etc/xdg/i3-helpers/config.toml`.
+pub fn discover() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(CONFIG_VAR).filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    default_path(MAIN_FILE_NAME)
+        .into_iter()
+        .chain([Path::new(SYSTEM_DIR).join(MAIN_FILE_NAME)])
+        .find(|path| path.exists())
+}
+
+fn xdg_path(variable: &str, fallback: &str, file_name: &str) -> Option<PathBuf> {
+    let base = env::var_os(variable)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(env::var_os("HOME")?).join(fallback)))?;
+    Some(base.join("i3-helpers").join(file_name))
+}
+
+/// Build an error for invalid configuration values.
+pub fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
@@ -0,0 +1,200 @@
+//! Connection used by helpers to send commands and queries to i3.
+
+use crate::{command, metrics};
+use i3_ipc::{
+    msg::Msg,
+    reply::{Node, Outputs, Workspaces},
+    Connect, I3Stream, MsgResponse, I3, I3IPC,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, Write};
+
+/// A connection to i3 for sending commands and queries.
+///
+/// It must not be used to subscribe to events, as the i3_ipc library panics
+/// when it receives event messages while waiting for a reply.
+pub struct Connection {
+    stream: I3Stream,
+    dry_run: bool,
+}
+
+impl Connection {
+    pub fn connect() -> io::Result<Self> {
+        Ok(Self {
+            stream: I3::connect()?,
+            dry_run: false,
+        })
+    }
+
+    /// In dry-run mode, commands are logged instead of being run. Queries are
+    /// still sent to i3.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// The underlying stream, for messages not covered by [`I3Commands`].
+    pub fn stream(&mut self) -> &mut I3Stream {
+        &mut self.stream
+    }
+
+    /// Run `commands` in a single message, returning whether all their
+    /// actions succeeded.
+    fn run(&mut self, commands: &[String]) -> io::Result<bool> {
+        if commands.is_empty() {
+            return Ok(true);
+        }
+        let message = commands.join("; ");
+        if self.dry_run {
+            log::info!("dry run, not running command: {message}");
+            return Ok(true);
+        }
+        log::debug!("running command: {message}");
+        let replies = self
+            .stream
+            .run_command(&message)
+            .inspect_err(|_| metrics::COMMAND_ERRORS.inc())?;
+        // i3 replies once per action, and commands can hold several of them.
+        let mut replies = replies.into_iter();
+        let mut succeeded = true;
+        for command in commands {
+            for reply in replies.by_ref().take(command::count_actions(command)) {
+                if !reply.success {
+                    succeeded = false;
+                    metrics::COMMAND_ERRORS.inc();
+                    let error = reply.error.as_deref().unwrap_or("unknown error");
+                    log::warn!("command {command:?} failed: {error}");
+                }
+            }
+        }
+        Ok(succeeded)
+    }
+}
+
+/// The commands and queries helpers can send to i3.
+///
+/// Helpers are handed this trait rather than a [`Connection`], so that their
+/// logic can be tested against a [`MockI3`](crate::mock::MockI3).
+pub trait I3Commands {
+    /// Run an i3 command.
+    fn run_command(&mut self, command: &str) -> io::Result<()>;
+
+    /// Run several i3 commands at once, in a single message. See
+    /// [`CommandQueue`](crate::command::CommandQueue).
+    fn run_commands(&mut self, commands: &[String]) -> io::Result<()> {
+        if commands.is_empty() {
+            return Ok(());
+        }
+        self.run_command(&commands.join("; "))
+    }
+
+    /// Like [`run_command`](Self::run_command), but tell whether all the
+    /// actions of `command` succeeded, e.g. to notice windows that went away.
+    /// Failures are logged either way.
+    fn run_command_checked(&mut self, command: &str) -> io::Result<bool> {
+        self.run_command(command).map(|()| true)
+    }
+
+    fn get_tree(&mut self) -> io::Result<Node>;
+
+    fn get_workspaces(&mut self) -> io::Result<Workspaces>;
+
+    fn get_outputs(&mut self) -> io::Result<Outputs>;
+
+    /// The text of the configuration i3 was last loaded with, including the
+    /// files it includes, to be parsed by [`i3_config`](crate::i3_config).
+    fn get_config(&mut self) -> io::Result<String>;
+
+    /// Find out which window manager is on the other end of the connection.
+    fn window_manager(&mut self) -> io::Result<WindowManager>;
+
+    /// Send a message of any type and return the JSON reply, for the
+    /// messages of sway that i3 doesn't know about, like `GET_INPUTS`. See
+    /// [`backend`](crate::backend).
+    fn send_message(&mut self, message_type: u32, payload: &str) -> io::Result<Value>;
+}
+
+impl I3Commands for Connection {
+    fn run_command(&mut self, command: &str) -> io::Result<()> {
+        self.run_commands(&[command.to_owned()])
+    }
+
+    fn run_commands(&mut self, commands: &[String]) -> io::Result<()> {
+        self.run(commands).map(|_| ())
+    }
+
+    fn run_command_checked(&mut self, command: &str) -> io::Result<bool> {
+        self.run(&[command.to_owned()])
+    }
+
+    fn get_tree(&mut self) -> io::Result<Node> {
+        self.stream.get_tree()
+    }
+
+    fn get_workspaces(&mut self) -> io::Result<Workspaces> {
+        self.stream.get_workspaces()
+    }
+
+    fn get_outputs(&mut self) -> io::Result<Outputs> {
+        self.stream.get_outputs()
+    }
+
+    fn get_config(&mut self) -> io::Result<String> {
+        let config = self.stream.get_config()?;
+        // i3 lists the main file among the included ones, when it supports
+        // includes at all.
+        match config
+            .included_configs
+            .filter(|included| !included.is_empty())
+        {
+            Some(included) => Ok(included
+                .into_iter()
+                .map(|file| file.variable_replaced_contents)
+                .collect::<Vec<_>>()
+                .join("\n")),
+            None => Ok(config.config),
+        }
+    }
+
+    fn window_manager(&mut self) -> io::Result<WindowManager> {
+        // Sway adds a `variant` field to the version reply, which is not part
+        // of the i3 protocol and thus missing from `reply::Version`.
+        #[derive(Deserialize)]
+        struct Version {
+            variant: Option<String>,
+        }
+
+        let version: MsgResponse<Version> = self.stream.send_receive(Msg::Version, "")?;
+        Ok(match version.body.variant.as_deref() {
+            Some("sway") => WindowManager::Sway,
+            _ => WindowManager::I3,
+        })
+    }
+
+    fn send_message(&mut self, message_type: u32, payload: &str) -> io::Result<Value> {
+        // The i3_ipc library only knows the message types of i3, and panics
+        // on replies of other types, so the message is framed by hand.
+        let mut message = Vec::with_capacity(14 + payload.len());
+        message.extend(b"i3-ipc");
+        message.extend((payload.len() as u32).to_ne_bytes());
+        message.extend(message_type.to_ne_bytes());
+        message.extend(payload.as_bytes());
+        self.stream.write_all(&message)?;
+        let (reply_type, reply) = self.stream.decode_msg()?;
+        if reply_type != message_type {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected a reply of type {message_type}, got {reply_type}"),
+            ));
+        }
+        Ok(serde_json::from_slice(&reply)?)
+    }
+}
+
+/// The window manager on the other end of the IPC connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowManager {
+    I3,
+    Sway,
+}
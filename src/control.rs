@@ -0,0 +1,138 @@
+//! Control socket for talking to running helpers.
+//!
+//! Helpers run with [`EventLoop::set_control_socket`](crate::EventLoop::set_control_socket) listen
+//! on a Unix socket for commands, one per connection: clients write a single
+//! line and read the reply until the helper closes the connection. Replies to
+//! commands that failed start with `error:`.
+
+use crate::config::invalid_data;
+use std::{
+    env,
+    io::{self, Read, Write},
+    os::unix::net,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader as AsyncBufReader},
+    net::{UnixListener, UnixStream},
+    sync::{mpsc, oneshot},
+};
+
+/// The default socket of the helper called `name`, under `$XDG_RUNTIME_DIR`.
+pub fn default_socket_path(name: &str) -> Option<PathBuf> {
+    let runtime_dir = env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty())?;
+    Some(PathBuf::from(runtime_dir).join(format!("{name}.sock")))
+}
+
+/// Send `command` to the helper listening on `path` and return its reply.
+pub fn send(path: &Path, command: &str) -> io::Result<String> {
+    let mut stream = net::UnixStream::connect(path)?;
+    writeln!(stream, "{command}")?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    Ok(reply)
+}
+
+/// Send `command` to the helper called `name`, listening on `socket` or else
+/// on its default socket, for the programs driving a helper from key
+/// bindings. An error in the reply is logged and makes for a failure exit
+/// code.
+pub fn request(name: &str, socket: Option<PathBuf>, command: &str) -> io::Result<ExitCode> {
+    let path = socket
+        .or_else(|| default_socket_path(name))
+        .ok_or_else(|| invalid_data("XDG_RUNTIME_DIR is not set, use --socket"))?;
+    let reply = send(&path, command)?;
+    Ok(match reply.strip_prefix("error: ") {
+        Some(error) => {
+            log::error!("{}", error.trim_end());
+            ExitCode::FAILURE
+        }
+        None => ExitCode::SUCCESS,
+    })
+}
+
+/// A command received on the control socket, waiting for its reply.
+pub(crate) struct Request {
+    pub(crate) command: String,
+    reply: oneshot::Sender<String>,
+}
+
+impl Request {
+    pub(crate) fn reply(self, reply: String) {
+        // The client may have gone away already, which is none of our business.
+        let _ = self.reply.send(reply);
+    }
+}
+
+/// Listens on the control socket, removing it once dropped.
+pub(crate) struct ControlSocket {
+    path: PathBuf,
+    requests: mpsc::Receiver<Request>,
+}
+
+impl ControlSocket {
+    /// Listen on `path`, replacing any socket left behind by a previous run.
+    ///
+    /// Must be called from within the tokio runtime.
+    pub(crate) fn bind(path: &Path) -> io::Result<Self> {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        let (sender, requests) = mpsc::channel(16);
+        tokio::spawn(accept(listener, sender));
+        log::info!("listening for commands on {}", path.display());
+        Ok(Self {
+            path: path.to_owned(),
+            requests,
+        })
+    }
+
+    pub(crate) async fn recv(&mut self) -> Option<Request> {
+        self.requests.recv().await
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+async fn accept(listener: UnixListener, sender: mpsc::Sender<Request>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(serve(stream, sender.clone()));
+            }
+            Err(err) => {
+                log::error!("control socket stopped accepting connections: {err}");
+                return;
+            }
+        }
+    }
+}
+
+/// Forward the command sent on `stream` to the event loop and write back its
+/// reply.
+async fn serve(stream: UnixStream, sender: mpsc::Sender<Request>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut command = String::new();
+    if let Err(err) = AsyncBufReader::new(reader).read_line(&mut command).await {
+        log::warn!("failed to read from control socket: {err}");
+        return;
+    }
+    let (reply, receiver) = oneshot::channel();
+    let request = Request {
+        command: command.trim().to_owned(),
+        reply,
+    };
+    if sender.send(request).await.is_err() {
+        return;
+    }
+    if let Ok(reply) = receiver.await {
+        let _ = writer.write_all(reply.as_bytes()).await;
+    }
+}
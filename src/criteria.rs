@@ -0,0 +1,394 @@
+//! Matching of windows against user-provided criteria.
+
+use crate::{config, expression::Expression};
+use i3_ipc::reply::Node;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::{fmt, io, iter::Peekable, str::Chars};
+
+/// Window attributes that identify a set of windows.
+///
+/// Every attribute that is set must match for a window to be considered a
+/// match. Exact values are expressed as anchored regular expressions (see
+/// [`exact`]).
+#[derive(Clone, Default)]
+pub struct Criteria {
+    pub class: Option<Regex>,
+    pub instance: Option<Regex>,
+    /// The X11 `WM_WINDOW_ROLE` property.
+    pub role: Option<Regex>,
+    pub title: Option<Regex>,
+    pub app_id: Option<Regex>,
+    /// Matches windows with at least one matching mark.
+    pub mark: Option<Regex>,
+    /// A match expression combining further criteria with `&&`, `||` and
+    /// `!`.
+    pub expression: Option<Expression>,
+}
+
+impl Criteria {
+    pub fn matches(&self, container: &Node) -> bool {
+        let props = container.window_properties.as_ref();
+        // Native Wayland windows under sway have no X11 properties, so their
+        // title is only available as the container name.
+        let title = props
+            .and_then(|props| props.title.as_ref())
+            .or(container.name.as_ref());
+        attribute_matches(&self.class, props.and_then(|props| props.class.as_ref()))
+            && attribute_matches(
+                &self.instance,
+                props.and_then(|props| props.instance.as_ref()),
+            )
+            && attribute_matches(
+                &self.role,
+                props.and_then(|props| props.window_role.as_ref()),
+            )
+            && attribute_matches(&self.title, title)
+            && attribute_matches(&self.app_id, container.app_id.as_ref())
+            && self.mark.as_ref().is_none_or(|mark| {
+                let marks = container.marks.iter().flat_map(|marks| &marks.0);
+                marks.into_iter().any(|actual| mark.is_match(actual))
+            })
+            && self
+                .expression
+                .as_ref()
+                .is_none_or(|expression| expression.matches(container))
+    }
+
+    /// The same criteria, but matching regardless of case, e.g. so that a
+    /// single rule covers both `Firefox` and `firefox`.
+    pub fn ignoring_case(self) -> Self {
+        Self {
+            class: self.class.map(case_insensitive),
+            instance: self.instance.map(case_insensitive),
+            role: self.role.map(case_insensitive),
+            title: self.title.map(case_insensitive),
+            app_id: self.app_id.map(case_insensitive),
+            mark: self.mark.map(case_insensitive),
+            expression: self.expression.map(Expression::ignoring_case),
+        }
+    }
+
+    /// Whether no attribute is set, in which case any window would match.
+    pub fn is_empty(&self) -> bool {
+        self.class.is_none()
+            && self.instance.is_none()
+            && self.role.is_none()
+            && self.title.is_none()
+            && self.app_id.is_none()
+            && self.mark.is_none()
+            && self.expression.is_none()
+    }
+
+    /// Whether these criteria rely on X11 window properties.
+    pub fn uses_x11_properties(&self) -> bool {
+        self.class.is_some()
+            || self.instance.is_some()
+            || self.role.is_some()
+            || self
+                .expression
+                .as_ref()
+                .is_some_and(Expression::uses_x11_properties)
+    }
+}
+
+/// Command line options selecting windows, shared by the helpers acting on
+/// the windows matching them, to be included with `#[command(flatten)]`.
+///
+/// Every option belongs to the `criteria` argument group, which helpers
+/// needing at least one criterion declare as required.
+#[derive(clap::Args, Clone, Default)]
+pub struct CriteriaArgs {
+    /// The X11 class of the windows.
+    #[arg(short, long, group = "criteria", conflicts_with = "class_regex")]
+    pub class: Option<String>,
+
+    /// A regular expression matching the X11 class of the windows.
+    #[arg(long, group = "criteria", value_parser = Regex::new)]
+    pub class_regex: Option<Regex>,
+
+    /// The X11 instance of the windows.
+    #[arg(short, long, group = "criteria")]
+    pub instance: Option<String>,
+
+    /// A regular expression matching the title of the windows.
+    #[arg(long, group = "criteria", value_parser = Regex::new)]
+    pub title_regex: Option<Regex>,
+
+    /// The app_id of the windows, for native Wayland windows under sway.
+    #[arg(long, group = "criteria")]
+    pub app_id: Option<String>,
+
+    /// A match expression, e.g. `class=Alacritty && title~build`.
+    #[arg(long = "match", value_name = "EXPRESSION", group = "criteria")]
+    pub expression: Option<Expression>,
+
+    /// Match the attributes of windows regardless of case.
+    #[arg(long)]
+    pub ignore_case: bool,
+}
+
+impl CriteriaArgs {
+    pub fn into_criteria(self) -> Criteria {
+        let criteria = Criteria {
+            class: self.class.as_deref().map(exact).or(self.class_regex),
+            instance: self.instance.as_deref().map(exact),
+            title: self.title_regex,
+            app_id: self.app_id.as_deref().map(exact),
+            expression: self.expression,
+            ..Criteria::default()
+        };
+        if self.ignore_case {
+            criteria.ignoring_case()
+        } else {
+            criteria
+        }
+    }
+}
+
+fn attribute_matches(expected: &Option<Regex>, actual: Option<&String>) -> bool {
+    match (expected, actual) {
+        (None, _) => true,
+        (Some(expected), Some(actual)) => expected.is_match(actual),
+        (Some(_), None) => false,
+    }
+}
+
+/// Build `regex` again, matching regardless of case.
+fn case_insensitive(regex: Regex) -> Regex {
+    RegexBuilder::new(regex.as_str())
+        .case_insensitive(true)
+        .build()
+        .expect("valid regex stays valid")
+}
+
+/// Build a regular expression that only matches `value` verbatim.
+pub fn exact(value: &str) -> Regex {
+    Regex::new(&format!("^{}$", regex::escape(value))).expect("escaped regex is always valid")
+}
+
+/// Build a regular expression matching the same strings as the shell-style
+/// `pattern`, where `*` matches any sequence of characters, `?` any single
+/// character and `[...]` any character in the brackets (or not in them, with
+/// `[!...]`). Like [`exact`], it must match whole values.
+pub fn glob(pattern: &str, ignore_case: bool) -> Result<Regex, regex::Error> {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex += ".*",
+            '?' => regex.push('.'),
+            '[' => match bracket_expression(chars.clone()) {
+                Some((class, rest)) => {
+                    regex += &class;
+                    chars = rest;
+                }
+                // Without a closing `]`, the `[` is just a character.
+                None => regex += r"\[",
+            },
+            c => regex += &regex::escape(&c.to_string()),
+        }
+    }
+    regex.push('$');
+    RegexBuilder::new(&regex)
+        .case_insensitive(ignore_case)
+        .build()
+}
+
+/// The regex class for the bracket expression at the start of `chars`, right
+/// after its `[`, and the characters after its closing `]`, if it has one.
+fn bracket_expression(mut chars: Peekable<Chars>) -> Option<(String, Peekable<Chars>)> {
+    let mut class = String::from("[");
+    if chars.next_if_eq(&'!').is_some() {
+        class.push('^');
+    }
+    // A `]` right after the `[` (or `[!`) is part of the set.
+    let mut members: Vec<char> = chars.next_if_eq(&']').into_iter().collect();
+    loop {
+        match chars.next()? {
+            ']' => break,
+            c => members.push(c),
+        }
+    }
+    let mut members = members.into_iter().peekable();
+    while let Some(c) = members.next() {
+        push_class_member(&mut class, c);
+        // A `-` between two characters makes a range, anywhere else it's
+        // just a character.
+        if members.peek() == Some(&'-') && members.len() > 1 {
+            members.next();
+            class.push('-');
+            push_class_member(&mut class, members.next()?);
+        }
+    }
+    class.push(']');
+    Some((class, chars))
+}
+
+/// Push `c` to a regex class, escaped so that it is taken literally, and not
+/// e.g. as part of a class set operation like `&&` or `--`.
+fn push_class_member(class: &mut String, c: char) {
+    if matches!(c, '\\' | '[' | ']' | '^' | '-' | '&' | '~') {
+        class.push('\\');
+    }
+    class.push(c);
+}
+
+impl fmt::Display for Criteria {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let attributes = [
+            ("class", &self.class),
+            ("instance", &self.instance),
+            ("window_role", &self.role),
+            ("title", &self.title),
+            ("app_id", &self.app_id),
+            ("mark", &self.mark),
+        ];
+        let mut separator = "";
+        for (name, regex) in attributes {
+            if let Some(regex) = regex {
+                write!(f, "{separator}{name}={:?}", regex.as_str())?;
+                separator = " ";
+            }
+        }
+        if let Some(expression) = &self.expression {
+            write!(f, "{separator}match={:?}", expression.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+/// Criteria as written in configuration files, e.g.
+/// `{ class = "dropdown", title_regex = "^scratch" }`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CriteriaConfig {
+    pub class: Option<String>,
+    pub class_regex: Option<String>,
+    pub class_glob: Option<String>,
+    pub instance: Option<String>,
+    pub role: Option<String>,
+    pub title_regex: Option<String>,
+    pub app_id: Option<String>,
+    pub mark: Option<String>,
+    /// Match all the attributes regardless of case.
+    #[serde(default)]
+    pub ignore_case: bool,
+    /// A match expression, e.g. `class=Alacritty && title~scratch`.
+    pub expression: Option<String>,
+}
+
+impl CriteriaConfig {
+    /// Compile the criteria, failing if they are invalid or empty.
+    pub fn into_criteria(self) -> io::Result<Criteria> {
+        let class = match (self.class, self.class_regex, self.class_glob) {
+            (Some(class), None, None) => Some(exact(&class)),
+            (None, Some(class_regex), None) => Some(regex(&class_regex)?),
+            (None, None, Some(class_glob)) => {
+                Some(glob(&class_glob, self.ignore_case).map_err(config::invalid_data)?)
+            }
+            (None, None, None) => None,
+            _ => {
+                return Err(config::invalid_data(
+                    "only one of class, class_regex and class_glob can be used",
+                ))
+            }
+        };
+        let criteria = Criteria {
+            class,
+            instance: self.instance.as_deref().map(exact),
+            role: self.role.as_deref().map(exact),
+            title: self.title_regex.as_deref().map(regex).transpose()?,
+            app_id: self.app_id.as_deref().map(exact),
+            mark: self.mark.as_deref().map(exact),
+            expression: self
+                .expression
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(config::invalid_data)?,
+        };
+        if criteria.is_empty() {
+            return Err(config::invalid_data("match criteria cannot be empty"));
+        }
+        Ok(match self.ignore_case {
+            true => criteria.ignoring_case(),
+            false => criteria,
+        })
+    }
+}
+
+fn regex(pattern: &str) -> io::Result<Regex> {
+    Regex::new(pattern).map_err(config::invalid_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_whole_values() {
+        let pattern = glob("scratch-*", false).unwrap();
+        assert!(pattern.is_match("scratch-term"));
+        assert!(pattern.is_match("scratch-"));
+        assert!(!pattern.is_match("my-scratch-term"));
+        assert!(!pattern.is_match("scratch"));
+        assert!(glob("term?", false).unwrap().is_match("term1"));
+        assert!(!glob("term?", false).unwrap().is_match("term12"));
+    }
+
+    #[test]
+    fn glob_escapes_regex_syntax() {
+        let pattern = glob("a.b+(c)", false).unwrap();
+        assert!(pattern.is_match("a.b+(c)"));
+        assert!(!pattern.is_match("axbb(c)"));
+    }
+
+    #[test]
+    fn glob_supports_bracket_expressions() {
+        let pattern = glob("term[0-9]", false).unwrap();
+        assert!(pattern.is_match("term7"));
+        assert!(!pattern.is_match("termx"));
+        let negated = glob("term[!0-9]", false).unwrap();
+        assert!(negated.is_match("termx"));
+        assert!(!negated.is_match("term7"));
+        let bracket = glob("[]abc]", false).unwrap();
+        assert!(bracket.is_match("]"));
+        assert!(bracket.is_match("b"));
+        assert!(!bracket.is_match("d"));
+        let not_bracket = glob("[!]]", false).unwrap();
+        assert!(not_bracket.is_match("a"));
+        assert!(!not_bracket.is_match("]"));
+        let operators = glob("[a&&b~~]", false).unwrap();
+        for c in ["a", "&", "b", "~"] {
+            assert!(operators.is_match(c), "{c}");
+        }
+        assert!(!operators.is_match("x"));
+        assert!(glob("[--]", false).unwrap().is_match("-"));
+        let dashes = glob("[-a-c-]", false).unwrap();
+        assert!(dashes.is_match("-"));
+        assert!(dashes.is_match("b"));
+        assert!(!dashes.is_match("d"));
+        let unterminated = glob("term[abc", false).unwrap();
+        assert!(unterminated.is_match("term[abc"));
+        assert!(!unterminated.is_match("terma"));
+    }
+
+    #[test]
+    fn ignoring_case_covers_every_attribute() {
+        let criteria = Criteria {
+            class: Some(exact("firefox")),
+            title: Some(Regex::new("^mozilla").unwrap()),
+            ..Criteria::default()
+        }
+        .ignoring_case();
+        let window = crate::mock::window(1, "Firefox", "Mozilla Firefox");
+        assert!(criteria.matches(&window));
+    }
+
+    #[test]
+    fn glob_is_case_sensitive_unless_told_otherwise() {
+        assert!(!glob("firefox*", false).unwrap().is_match("Firefox-esr"));
+        assert!(glob("firefox*", true).unwrap().is_match("Firefox-esr"));
+    }
+}
@@ -0,0 +1,443 @@
+//! Event loop dispatching i3 events to helpers.
+
+use crate::{
+    control::{ControlSocket, Request},
+    metrics,
+    record::{Entry, RecordedEvent, Recorder, RecordingI3},
+    Connection, I3Commands,
+};
+use i3_ipc::event::{
+    BindingData, Event, ShutdownChange, Subscribe, TickData, WindowData, WorkspaceData,
+};
+use std::{
+    future, io,
+    path::PathBuf,
+    pin::Pin,
+    time::{Duration, Instant},
+};
+use tokio::{
+    runtime,
+    signal::unix::{signal, Signal, SignalKind},
+    task, time,
+};
+use tokio_stream::{Stream, StreamExt};
+
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_millis(100);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(5);
+const RECONNECT_ATTEMPTS: u32 = 10;
+
+pub(crate) type Events = Pin<Box<dyn Stream<Item = io::Result<Event>>>>;
+
+/// A program reacting to i3 events.
+///
+/// Helpers declare the events they are interested in and get called for each
+/// one of them, along with an [`I3Commands`] to send commands to i3. Handlers
+/// for events that were not subscribed to are never called.
+pub trait Helper {
+    /// The events this helper wants to receive.
+    fn subscriptions(&self) -> Vec<Subscribe>;
+
+    /// Called once, before any event is received.
+    fn on_start(&mut self, _i3: &mut dyn I3Commands) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn on_window(&mut self, _i3: &mut dyn I3Commands, _event: &WindowData) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn on_workspace(&mut self, _i3: &mut dyn I3Commands, _event: &WorkspaceData) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn on_binding(&mut self, _i3: &mut dyn I3Commands, _event: &BindingData) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn on_tick(&mut self, _i3: &mut dyn I3Commands, _event: &TickData) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called for every event but shutdown ones, before the handler specific
+    /// to its type. Useful for events without one, like output or mode ones.
+    fn on_event(&mut self, _i3: &mut dyn I3Commands, _event: &Event) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The next time [`Helper::on_deadline`] should be called, if any.
+    fn next_deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Called once the deadline returned by [`Helper::next_deadline`] is
+    /// reached, unless an event arrives first.
+    fn on_deadline(&mut self, _i3: &mut dyn I3Commands, _now: Instant) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called when the process receives SIGHUP, if enabled with
+    /// [`EventLoop::set_reload_on_sighup`], to reload its configuration.
+    fn on_reload(&mut self, _i3: &mut dyn I3Commands) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called after reconnecting to i3 once it restarted in place, which
+    /// gives all containers new ids, so that ids remembered until then can be
+    /// looked up again.
+    fn on_reconnect(&mut self, _i3: &mut dyn I3Commands) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called when the process receives SIGTERM or SIGINT, before the event
+    /// loop returns, to undo anything that shouldn't outlive the helper.
+    fn on_exit(&mut self, _i3: &mut dyn I3Commands) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called for each command received on the control socket, if enabled
+    /// with [`EventLoop::set_control_socket`]. The returned text is sent back
+    /// to the client, starting with `error:` if the command failed.
+    fn on_control(&mut self, _i3: &mut dyn I3Commands, command: &str) -> io::Result<String> {
+        Ok(format!("error: unknown command {command:?}\n"))
+    }
+}
+
+/// Runs a [`Helper`], feeding it events until i3 exits.
+///
+/// Events, timers and signals are multiplexed on a single-threaded async
+/// runtime. Helpers are still called synchronously from it, so they can't run
+/// concurrently with each other and need no synchronization.
+///
+/// The event loop survives in-place restarts of i3 by reconnecting to it.
+pub struct EventLoop {
+    i3: Connection,
+    dry_run: bool,
+    reload_on_sighup: bool,
+    control_socket: Option<PathBuf>,
+    recorder: Option<Recorder>,
+    #[cfg(feature = "systemd")]
+    systemd: Option<crate::systemd::Notifier>,
+    #[cfg(feature = "metrics")]
+    metrics_addr: Option<std::net::SocketAddr>,
+}
+
+impl EventLoop {
+    pub fn connect() -> io::Result<Self> {
+        Ok(Self {
+            i3: Connection::connect()?,
+            dry_run: false,
+            reload_on_sighup: false,
+            control_socket: None,
+            recorder: None,
+            #[cfg(feature = "systemd")]
+            systemd: None,
+            #[cfg(feature = "metrics")]
+            metrics_addr: None,
+        })
+    }
+
+    /// Log the commands sent by the helper instead of running them. See
+    /// [`Connection::set_dry_run`].
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+        self.i3.set_dry_run(dry_run);
+    }
+
+    /// Call [`Helper::on_reload`] when the process receives SIGHUP, instead
+    /// of terminating.
+    pub fn set_reload_on_sighup(&mut self, reload_on_sighup: bool) {
+        self.reload_on_sighup = reload_on_sighup;
+    }
+
+    /// Listen for commands on a Unix socket at `path` and pass them to
+    /// [`Helper::on_control`]. See the [`control`](crate::control) module.
+    pub fn set_control_socket(&mut self, path: Option<PathBuf>) {
+        self.control_socket = path;
+    }
+
+    /// Record what the helper receives to a file at `path`, to be replayed
+    /// later. See the [`record`](crate::record) module.
+    pub fn set_record(&mut self, path: Option<&std::path::Path>) -> io::Result<()> {
+        self.recorder = path.map(Recorder::create).transpose()?;
+        Ok(())
+    }
+
+    /// Notify systemd once the helper is ready and ping its watchdog, if
+    /// enabled for the service, as long as i3 keeps answering our queries.
+    #[cfg(feature = "systemd")]
+    pub fn enable_systemd(&mut self) {
+        self.systemd = Some(crate::systemd::Notifier::new());
+    }
+
+    /// Publish the [`metrics`](crate::metrics) over HTTP on `addr`.
+    #[cfg(feature = "metrics")]
+    pub fn set_metrics_addr(&mut self, addr: Option<std::net::SocketAddr>) {
+        self.metrics_addr = addr;
+    }
+
+    pub fn run<H: Helper>(&mut self, helper: &mut H) -> io::Result<()> {
+        // Helpers talk to i3 synchronously, which blocks the thread they run
+        // on. A worker thread keeps serving the control socket and metrics
+        // meanwhile.
+        runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?
+            .block_on(self.run_async(helper))
+    }
+
+    async fn run_async<H: Helper>(&mut self, helper: &mut H) -> io::Result<()> {
+        let mut subscriptions = helper.subscriptions();
+        if !subscriptions.contains(&Subscribe::Shutdown) {
+            subscriptions.push(Subscribe::Shutdown);
+        }
+        self.record(&Entry::Start)?;
+        self.call(|i3| helper.on_start(i3))?;
+        let mut hangup = match self.reload_on_sighup {
+            true => Some(signal(SignalKind::hangup())?),
+            false => None,
+        };
+        let mut terminate = signal(SignalKind::terminate())?;
+        let mut interrupt = signal(SignalKind::interrupt())?;
+        let mut control = self
+            .control_socket
+            .as_deref()
+            .map(ControlSocket::bind)
+            .transpose()?;
+        #[cfg(feature = "metrics")]
+        if let Some(addr) = self.metrics_addr {
+            crate::metrics::serve(addr).await?;
+        }
+        // We need separate connections for listening and sending commands.
+        // Otherwise they'll step on each other's toes causing the i3_ipc
+        // library to panic when it receives messages it didn't expect.
+        let mut events = listen(&subscriptions).await?;
+        #[cfg(feature = "systemd")]
+        if let Some(systemd) = &self.systemd {
+            systemd.ready()?;
+        }
+        loop {
+            let handled = self
+                .handle_events(
+                    helper,
+                    &mut events,
+                    &mut hangup,
+                    &mut terminate,
+                    &mut interrupt,
+                    &mut control,
+                )
+                .await?;
+            match handled {
+                Handled::Shutdown(ShutdownChange::Exit) => break,
+                Handled::Terminated => {
+                    self.record(&Entry::Exit)?;
+                    self.call(|i3| helper.on_exit(i3))?;
+                    break;
+                }
+                Handled::Shutdown(ShutdownChange::Restart) => {
+                    events = self.reconnect(&subscriptions).await?;
+                    self.record(&Entry::Reconnect)?;
+                    self.call(|i3| helper.on_reconnect(i3))?;
+                }
+            }
+        }
+        #[cfg(feature = "systemd")]
+        if let Some(systemd) = &self.systemd {
+            systemd.stopping()?;
+        }
+        Ok(())
+    }
+
+    /// Handle events until i3 shuts down, either to exit or to restart, or
+    /// until we are asked to terminate.
+    async fn handle_events<H: Helper>(
+        &mut self,
+        helper: &mut H,
+        events: &mut Events,
+        hangup: &mut Option<Signal>,
+        terminate: &mut Signal,
+        interrupt: &mut Signal,
+        control: &mut Option<ControlSocket>,
+    ) -> io::Result<Handled> {
+        loop {
+            let deadline = self
+                .watchdog_deadline()
+                .into_iter()
+                .chain(helper.next_deadline())
+                .min();
+            let event = tokio::select! {
+                event = events.next() => match event {
+                    Some(event) => event?,
+                    None => return Ok(Handled::Shutdown(ShutdownChange::Exit)),
+                },
+                _ = terminate.recv() => {
+                    log::info!("received SIGTERM, exiting");
+                    return Ok(Handled::Terminated);
+                }
+                _ = interrupt.recv() => {
+                    log::info!("received SIGINT, exiting");
+                    return Ok(Handled::Terminated);
+                }
+                () = received(hangup) => {
+                    log::info!("received SIGHUP, reloading");
+                    self.record(&Entry::Reload)?;
+                    self.call(|i3| helper.on_reload(i3))?;
+                    continue;
+                }
+                Some(request) = next_request(control) => {
+                    log::debug!("received control command {:?}", request.command);
+                    self.record(&Entry::Control(request.command.clone()))?;
+                    let reply = self.call(|i3| helper.on_control(i3, &request.command))?;
+                    request.reply(reply);
+                    continue;
+                }
+                () = sleep_until(deadline) => {
+                    let now = Instant::now();
+                    self.ping_watchdog(now)?;
+                    if helper.next_deadline().is_some_and(|deadline| deadline <= now) {
+                        self.record(&Entry::Deadline)?;
+                        self.call(|i3| helper.on_deadline(i3, now))?;
+                    }
+                    continue;
+                }
+            };
+            log::trace!("received event: {event:?}");
+            metrics::EVENTS.inc();
+            if let Event::Shutdown(ev) = event {
+                log::info!("i3 is shutting down: {:?}", ev.change);
+                return Ok(Handled::Shutdown(ev.change));
+            }
+            if self.recorder.is_some() {
+                if let Some(event) = RecordedEvent::new(&event) {
+                    self.record(&Entry::Event(event))?;
+                }
+            }
+            self.call(|i3| dispatch(helper, i3, &event))?;
+        }
+    }
+
+    /// Call a handler of the helper with the connection, recording the
+    /// replies to its queries when recording.
+    ///
+    /// The handler blocks on i3's replies, so the runtime is told to run
+    /// other tasks elsewhere meanwhile.
+    fn call<T>(&mut self, handler: impl FnOnce(&mut dyn I3Commands) -> T) -> T {
+        let mut i3 = RecordingI3 {
+            i3: &mut self.i3,
+            recorder: self.recorder.as_mut(),
+        };
+        task::block_in_place(|| handler(&mut i3))
+    }
+
+    fn record(&mut self, entry: &Entry) -> io::Result<()> {
+        match &mut self.recorder {
+            Some(recorder) => recorder.record(entry),
+            None => Ok(()),
+        }
+    }
+
+    /// When the systemd watchdog is due to be pinged, if enabled.
+    fn watchdog_deadline(&self) -> Option<Instant> {
+        #[cfg(feature = "systemd")]
+        if let Some(systemd) = &self.systemd {
+            return systemd.next_ping();
+        }
+        None
+    }
+
+    fn ping_watchdog(&mut self, _now: Instant) -> io::Result<()> {
+        #[cfg(feature = "systemd")]
+        if let Some(systemd) = &mut self.systemd {
+            systemd.ping_if_due(&mut self.i3, _now)?;
+        }
+        Ok(())
+    }
+
+    /// Reconnect to i3 after it restarts, retrying with exponential backoff
+    /// while it isn't ready to accept connections yet.
+    async fn reconnect(&mut self, subscriptions: &[Subscribe]) -> io::Result<Events> {
+        let mut delay = RECONNECT_INITIAL_DELAY;
+        let mut attempts = 1;
+        loop {
+            time::sleep(delay).await;
+            let connected = match task::block_in_place(Connection::connect) {
+                Ok(i3) => listen(subscriptions).await.map(|events| (i3, events)),
+                Err(err) => Err(err),
+            };
+            match connected {
+                Ok((mut i3, events)) => {
+                    log::info!("reconnected to i3");
+                    metrics::RECONNECTS.inc();
+                    i3.set_dry_run(self.dry_run);
+                    self.i3 = i3;
+                    return Ok(events);
+                }
+                Err(err) if attempts == RECONNECT_ATTEMPTS => return Err(err),
+                Err(err) => {
+                    log::debug!("reconnection attempt {attempts} failed: {err}");
+                    delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+                    attempts += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Why [`EventLoop::handle_events`] stopped handling events.
+enum Handled {
+    Shutdown(ShutdownChange),
+    Terminated,
+}
+
+/// Call the handler of `helper` for `event`, if it has one.
+///
+/// This is what the event loop does for every event it receives, exposed so
+/// that helpers can be fed scripted events in tests.
+pub fn dispatch<H: Helper + ?Sized>(
+    helper: &mut H,
+    i3: &mut dyn I3Commands,
+    event: &Event,
+) -> io::Result<()> {
+    helper.on_event(i3, event)?;
+    match event {
+        Event::Window(ev) => helper.on_window(i3, ev),
+        Event::Workspace(ev) => helper.on_workspace(i3, ev),
+        Event::Binding(ev) => helper.on_binding(i3, ev),
+        Event::Tick(ev) => helper.on_tick(i3, ev),
+        _ => Ok(()),
+    }
+}
+
+/// Subscribe to `events` on a new connection.
+pub(crate) async fn listen(events: &[Subscribe]) -> io::Result<Events> {
+    let mut i3 = tokio_i3ipc::I3::connect().await?;
+    if !i3.subscribe(events).await?.success {
+        return Err(io::Error::other("i3 rejected the event subscription"));
+    }
+    Ok(Box::pin(i3.listen()))
+}
+
+/// Wait for the next signal, forever if there is nothing to wait for.
+async fn received(signal: &mut Option<Signal>) {
+    if let Some(signal) = signal {
+        if signal.recv().await.is_some() {
+            return;
+        }
+    }
+    future::pending().await
+}
+
+/// Wait for the next control command, forever if there is no socket.
+async fn next_request(control: &mut Option<ControlSocket>) -> Option<Request> {
+    match control {
+        Some(control) => control.recv().await,
+        None => future::pending().await,
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => time::sleep_until(deadline.into()).await,
+        None => future::pending().await,
+    }
+}
@@ -0,0 +1,294 @@
+//! Match expressions combining window criteria with boolean operators.
+//!
+//! An expression compares window attributes with `=` (verbatim) or `~` (a
+//! regular expression), and combines the comparisons with `&&`, `||`, `!` and
+//! parentheses, `&&` binding tighter than `||`:
+//!
+//! ```text
+//! class=Alacritty && (title~scratch || mark=scratchterm)
+//! ```
+//!
+//! Values containing spaces or operators are written between double quotes,
+//! e.g. `title="my notes"`. The attributes are the ones of [`Criteria`]:
+//! `class`, `instance`, `role`, `title`, `app_id` and `mark`.
+//!
+//! [`Criteria`]: crate::Criteria
+
+use crate::criteria::{exact, Criteria};
+use i3_ipc::reply::Node;
+use regex::Regex;
+use std::{fmt, str::FromStr};
+
+/// A parsed match expression.
+#[derive(Clone)]
+pub struct Expression {
+    source: String,
+    root: Term,
+}
+
+#[derive(Clone)]
+enum Term {
+    Criteria(Box<Criteria>),
+    Not(Box<Term>),
+    And(Box<Term>, Box<Term>),
+    Or(Box<Term>, Box<Term>),
+}
+
+impl Expression {
+    pub fn matches(&self, container: &Node) -> bool {
+        self.root.matches(container)
+    }
+
+    /// Whether any comparison is on X11 window properties.
+    pub fn uses_x11_properties(&self) -> bool {
+        self.root.uses_x11_properties()
+    }
+
+    /// The same expression, but comparing values regardless of case.
+    pub fn ignoring_case(self) -> Self {
+        Self {
+            source: self.source,
+            root: self.root.ignoring_case(),
+        }
+    }
+}
+
+impl Term {
+    fn matches(&self, container: &Node) -> bool {
+        match self {
+            Term::Criteria(criteria) => criteria.matches(container),
+            Term::Not(term) => !term.matches(container),
+            Term::And(left, right) => left.matches(container) && right.matches(container),
+            Term::Or(left, right) => left.matches(container) || right.matches(container),
+        }
+    }
+
+    fn ignoring_case(self) -> Self {
+        match self {
+            Term::Criteria(criteria) => Term::Criteria(Box::new(criteria.ignoring_case())),
+            Term::Not(term) => Term::Not(Box::new(term.ignoring_case())),
+            Term::And(left, right) => Term::And(
+                Box::new(left.ignoring_case()),
+                Box::new(right.ignoring_case()),
+            ),
+            Term::Or(left, right) => Term::Or(
+                Box::new(left.ignoring_case()),
+                Box::new(right.ignoring_case()),
+            ),
+        }
+    }
+
+    fn uses_x11_properties(&self) -> bool {
+        match self {
+            Term::Criteria(criteria) => criteria.uses_x11_properties(),
+            Term::Not(term) => term.uses_x11_properties(),
+            Term::And(left, right) | Term::Or(left, right) => {
+                left.uses_x11_properties() || right.uses_x11_properties()
+            }
+        }
+    }
+}
+
+impl FromStr for Expression {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            source,
+            position: 0,
+        };
+        let root = parser.or()?;
+        parser.skip_whitespace();
+        if let Some(rest) = parser.rest().chars().next() {
+            return Err(parser.error(&format!("unexpected {rest:?}")));
+        }
+        Ok(Self {
+            source: source.to_owned(),
+            root,
+        })
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Recursive descent parser over the expression text.
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn or(&mut self) -> Result<Term, String> {
+        let mut term = self.and()?;
+        while self.eat("||") {
+            term = Term::Or(Box::new(term), Box::new(self.and()?));
+        }
+        Ok(term)
+    }
+
+    fn and(&mut self) -> Result<Term, String> {
+        let mut term = self.unary()?;
+        while self.eat("&&") {
+            term = Term::And(Box::new(term), Box::new(self.unary()?));
+        }
+        Ok(term)
+    }
+
+    fn unary(&mut self) -> Result<Term, String> {
+        if self.eat("!") {
+            return Ok(Term::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let term = self.or()?;
+            if !self.eat(")") {
+                return Err(self.error("expected `)`"));
+            }
+            return Ok(term);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Term, String> {
+        self.skip_whitespace();
+        let name_len = self
+            .rest()
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest().len());
+        if name_len == 0 {
+            return Err(self.error("expected an attribute"));
+        }
+        let name = &self.rest()[..name_len];
+        self.position += name_len;
+        let regex = if self.eat("=") {
+            exact(&self.value()?)
+        } else if self.eat("~") {
+            let pattern = self.value()?;
+            Regex::new(&pattern).map_err(|err| self.error(&err.to_string()))?
+        } else {
+            return Err(self.error("expected `=` or `~`"));
+        };
+        let mut criteria = Criteria::default();
+        let attribute = match name {
+            "class" => &mut criteria.class,
+            "instance" => &mut criteria.instance,
+            "role" | "window_role" => &mut criteria.role,
+            "title" => &mut criteria.title,
+            "app_id" => &mut criteria.app_id,
+            "mark" => &mut criteria.mark,
+            _ => return Err(format!("unknown attribute {name:?}")),
+        };
+        *attribute = Some(regex);
+        Ok(Term::Criteria(Box::new(criteria)))
+    }
+
+    /// A bare word or a double-quoted string, with `\"` and `\\` escapes.
+    fn value(&mut self) -> Result<String, String> {
+        let Some(quoted) = self.rest().strip_prefix('"') else {
+            let len = self
+                .rest()
+                .find(|c: char| c.is_whitespace() || "&|()".contains(c))
+                .unwrap_or(self.rest().len());
+            if len == 0 {
+                return Err(self.error("expected a value"));
+            }
+            let value = self.rest()[..len].to_owned();
+            self.position += len;
+            return Ok(value);
+        };
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.position += index + 2;
+                    return Ok(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    /// Consume `token` if it comes next, after whitespace.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        let found = self.rest().starts_with(token);
+        if found {
+            self.position += token.len();
+        }
+        found
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.source[self.position..]
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{message} at position {}", self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    fn matches(expression: &str, container: &Node) -> bool {
+        expression.parse::<Expression>().unwrap().matches(container)
+    }
+
+    #[test]
+    fn combines_comparisons() {
+        let terminal = mock::window(1, "Alacritty", "scratch: ~");
+        assert!(matches("class=Alacritty && title~scratch", &terminal));
+        assert!(!matches("class=Alacritty && title~^notes", &terminal));
+        assert!(matches("class=firefox || title~scratch", &terminal));
+        assert!(matches("!class=firefox", &terminal));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let terminal = mock::window(1, "Alacritty", "scratch");
+        assert!(matches(
+            "class=firefox && title=x || class=Alacritty",
+            &terminal
+        ));
+        assert!(!matches(
+            "class=firefox && (title=x || class=Alacritty)",
+            &terminal
+        ));
+    }
+
+    #[test]
+    fn accepts_quoted_values() {
+        let notes = mock::window(1, "notes", "my \"notes\" && more");
+        assert!(matches(r#"title="my \"notes\" && more""#, &notes));
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for invalid in [
+            "",
+            "class",
+            "class=",
+            "colour=red",
+            "(class=a",
+            "class=a b=c",
+        ] {
+            assert!(invalid.parse::<Expression>().is_err(), "{invalid:?}");
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Following focus as it moves from window to window.
+
+use i3_ipc::event::{WindowChange, WindowData};
+
+type NodeId = usize;
+
+/// A move of the focus, as seen by [`FocusTracker`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct FocusChange {
+    /// The window that had focus before, unless it was closed meanwhile or
+    /// focus was elsewhere, e.g. on an empty workspace.
+    pub previous: Option<NodeId>,
+    pub current: NodeId,
+}
+
+/// Keeps track of the focused window from window events.
+///
+/// i3 only tells which window gets focus, so helpers acting on the window
+/// losing it have to remember which one had it.
+#[derive(Default)]
+pub struct FocusTracker {
+    focused: Option<NodeId>,
+}
+
+impl FocusTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The focused window, as far as we know.
+    pub fn focused(&self) -> Option<NodeId> {
+        self.focused
+    }
+
+    /// Forget the focused window, when focus may have left it without any
+    /// window event telling, e.g. for an empty workspace, so that it getting
+    /// focus again counts as a change.
+    pub fn clear(&mut self) {
+        self.focused = None;
+    }
+
+    /// Update the focused window from `event`, returning the change of focus
+    /// it describes, if any.
+    pub fn update(&mut self, event: &WindowData) -> Option<FocusChange> {
+        let id = event.container.id;
+        match event.change {
+            WindowChange::Focus if self.focused != Some(id) => Some(FocusChange {
+                previous: self.focused.replace(id),
+                current: id,
+            }),
+            WindowChange::Close if self.focused == Some(id) => {
+                self.focused = None;
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use i3_ipc::event::Event;
+
+    fn update(tracker: &mut FocusTracker, change: WindowChange, id: NodeId) -> Option<FocusChange> {
+        let Event::Window(event) = mock::window_event(change, mock::window(id, "app", "App"))
+        else {
+            unreachable!("mock::window_event makes window events");
+        };
+        tracker.update(&event)
+    }
+
+    #[test]
+    fn reports_moves_of_the_focus() {
+        let mut tracker = FocusTracker::new();
+        let first = update(&mut tracker, WindowChange::Focus, 1);
+        assert_eq!(
+            first,
+            Some(FocusChange {
+                previous: None,
+                current: 1
+            })
+        );
+        let second = update(&mut tracker, WindowChange::Focus, 2);
+        assert_eq!(
+            second,
+            Some(FocusChange {
+                previous: Some(1),
+                current: 2
+            })
+        );
+        assert_eq!(tracker.focused(), Some(2));
+    }
+
+    #[test]
+    fn ignores_focus_on_the_focused_window() {
+        let mut tracker = FocusTracker::new();
+        update(&mut tracker, WindowChange::Focus, 1);
+        assert_eq!(update(&mut tracker, WindowChange::Focus, 1), None);
+        assert_eq!(update(&mut tracker, WindowChange::Title, 1), None);
+        tracker.clear();
+        let again = update(&mut tracker, WindowChange::Focus, 1);
+        assert_eq!(
+            again,
+            Some(FocusChange {
+                previous: None,
+                current: 1
+            })
+        );
+    }
+
+    #[test]
+    fn forgets_closed_windows() {
+        let mut tracker = FocusTracker::new();
+        update(&mut tracker, WindowChange::Focus, 1);
+        assert_eq!(update(&mut tracker, WindowChange::Close, 2), None);
+        assert_eq!(tracker.focused(), Some(1));
+        assert_eq!(update(&mut tracker, WindowChange::Close, 1), None);
+        assert_eq!(tracker.focused(), None);
+        let next = update(&mut tracker, WindowChange::Focus, 2);
+        assert_eq!(
+            next,
+            Some(FocusChange {
+                previous: None,
+                current: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Window sizes and positions relative to the output they are shown on.
+
+use crate::command::{Action, CommandBuilder};
+use i3_ipc::reply::Rect;
+use std::{fmt, str::FromStr};
+
+/// The size of a window, parsed from `WIDTHxHEIGHT` where each dimension is
+/// either a number of pixels or a percentage of the output size, e.g.
+/// `80%x60%` or `1200x800`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Geometry {
+    pub width: Length,
+    pub height: Length,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Length {
+    Pixels(u32),
+    Percent(u32),
+}
+
+impl Length {
+    /// The length in pixels, for an output `total` pixels long.
+    pub fn to_pixels(self, total: isize) -> isize {
+        match self {
+            Length::Pixels(pixels) => pixels as isize,
+            Length::Percent(percent) => total * percent as isize / 100,
+        }
+    }
+}
+
+impl Geometry {
+    /// The command resizing container `id` to this size on an output with
+    /// the given `rect`, and centering it there.
+    pub fn command(&self, id: usize, rect: &Rect) -> String {
+        let width = self.width.to_pixels(rect.width);
+        let height = self.height.to_pixels(rect.height);
+        CommandBuilder::con_id(id)
+            .action(Action::Resize(width, height))
+            .action(Action::MovePositionCenter)
+            .build()
+    }
+}
+
+/// Where to place a floating window on its output, parsed from `center` or
+/// `X,Y` where each coordinate is either a number of pixels or a percentage of
+/// the output size, from its top left corner, e.g. `10%,5%` or `0,40`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Position {
+    Center,
+    At(Length, Length),
+}
+
+impl Position {
+    /// The command moving container `id` to this position on an output with
+    /// the given `rect`.
+    pub fn command(&self, id: usize, rect: &Rect) -> String {
+        let action = match self {
+            Position::Center => Action::MovePositionCenter,
+            Position::At(x, y) => Action::MovePosition(
+                rect.x + x.to_pixels(rect.width),
+                rect.y + y.to_pixels(rect.height),
+            ),
+        };
+        CommandBuilder::con_id(id).action(action).build()
+    }
+}
+
+impl FromStr for Geometry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s
+            .split_once('x')
+            .ok_or_else(|| format!("invalid geometry {s:?}, expected WIDTHxHEIGHT"))?;
+        Ok(Geometry {
+            width: width.parse()?,
+            height: height.parse()?,
+        })
+    }
+}
+
+impl FromStr for Length {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid length {s:?}, expected pixels or a percentage");
+        match s.strip_suffix('%') {
+            Some(percent) => match percent.parse() {
+                Ok(percent @ 1..=100) => Ok(Length::Percent(percent)),
+                _ => Err(invalid()),
+            },
+            None => match s.parse() {
+                Ok(pixels @ 1..) => Ok(Length::Pixels(pixels)),
+                _ => Err(invalid()),
+            },
+        }
+    }
+}
+
+impl FromStr for Position {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "center" {
+            return Ok(Position::Center);
+        }
+        let (x, y) = s
+            .split_once(',')
+            .ok_or_else(|| format!("invalid position {s:?}, expected center or X,Y"))?;
+        // Unlike sizes, coordinates can be zero.
+        let coordinate = |s: &str| match s.trim() {
+            "0" => Ok(Length::Pixels(0)),
+            s => s.parse(),
+        };
+        Ok(Position::At(coordinate(x)?, coordinate(y)?))
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Length::Pixels(pixels) => write!(f, "{pixels}"),
+            Length::Percent(percent) => write!(f, "{percent}%"),
+        }
+    }
+}
+
+impl fmt::Display for Geometry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
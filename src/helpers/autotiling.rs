@@ -0,0 +1,73 @@
+//! Alternating between horizontal and vertical splits, as done by
+//! `i3-autotiling`.
+
+use crate::{
+    command::{Action, CommandBuilder, Orientation},
+    tree, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData},
+    reply::{FullscreenMode, Node, NodeLayout},
+};
+use std::io;
+
+pub struct Autotiling {
+    workspaces: Vec<String>,
+}
+
+impl Helper for Autotiling {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window]
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        match event.change {
+            WindowChange::Focus | WindowChange::New | WindowChange::Move => {
+                self.autotile(i3, &event.container)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Autotiling {
+    /// Only act on the given workspaces, or on all of them if empty.
+    pub fn new(workspaces: Vec<String>) -> Self {
+        Self { workspaces }
+    }
+
+    fn autotile(&self, i3: &mut dyn I3Commands, container: &Node) -> io::Result<()> {
+        if tree::is_floating(container) || container.fullscreen_mode != FullscreenMode::None {
+            return Ok(());
+        }
+        let tree = i3.get_tree()?;
+        if !self.workspaces.is_empty() {
+            let workspace_name = tree::workspace_of(&tree, container.id)
+                .and_then(|workspace| workspace.name.as_ref());
+            if !workspace_name.is_some_and(|name| self.workspaces.contains(name)) {
+                return Ok(());
+            }
+        }
+        // Containers found in the tree are more up to date than the one in the
+        // event, which may have been resized since.
+        let Some(container) = tree::find(&tree, container.id) else {
+            return Ok(());
+        };
+        let parent_layout = tree::parent_of(&tree, container.id).map(|parent| parent.layout);
+        let (layout, split) = if container.rect.height > container.rect.width {
+            (NodeLayout::SplitV, Orientation::Vertical)
+        } else {
+            (NodeLayout::SplitH, Orientation::Horizontal)
+        };
+        match parent_layout {
+            // Splitting inside tabbed or stacked containers defeats their
+            // purpose, and there is nothing to do if the orientation is right.
+            Some(NodeLayout::Stacked | NodeLayout::Tabbed) => Ok(()),
+            Some(parent_layout) if parent_layout == layout => Ok(()),
+            _ => {
+                let command = CommandBuilder::con_id(container.id).action(Action::Split(split));
+                i3.run_command(&command.build())
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Rules loaded from a TOML configuration file.
+//!
+//! The file contains a list of rules, each one describing a set of scratchpad
+//! windows and how to handle them:
+//!
+//! ```toml
+//! [[rule]]
+//! match = { class = "dropdown" }
+//! restore_fullscreen = true
+//! recenter = true
+//! geometry = "80%x60%"
+//! same_workspace_only = false
+//! only_on_fullscreen = false
+//! keep_while_urgent = true
+//! # Which window to manage when several match: "last-focused", "newest",
+//! # "oldest", "all" or "per-workspace".
+//! multi = "newest"
+//! delay_ms = 200
+//! # Leave the window visible when focus goes to one of these windows.
+//! except = [{ class = "Zenity" }, { title_regex = "^Open File" }]
+//! ```
+
+use super::{Multi, Rule};
+use crate::{
+    config::{self, invalid_data},
+    criteria::CriteriaConfig,
+};
+use serde::Deserialize;
+use std::{io, path::Path, time::Duration};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(rename = "rule")]
+    rules: Vec<RuleConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    #[serde(rename = "match")]
+    criteria: CriteriaConfig,
+    #[serde(default)]
+    restore_fullscreen: bool,
+    #[serde(default)]
+    return_to_origin: bool,
+    #[serde(default)]
+    recenter: bool,
+    geometry: Option<String>,
+    #[serde(default)]
+    same_workspace_only: bool,
+    #[serde(default)]
+    hide_on_workspace_switch: bool,
+    #[serde(default)]
+    only_on_fullscreen: bool,
+    #[serde(default)]
+    keep_while_urgent: bool,
+    #[serde(default)]
+    delay_ms: u64,
+    #[serde(default)]
+    except: Vec<CriteriaConfig>,
+    #[serde(default)]
+    multi: Multi,
+}
+
+/// Load the rules defined in the configuration file at `path`.
+pub fn load(path: &Path) -> io::Result<Vec<Rule>> {
+    let config: Config = config::load(path)?;
+    if config.rules.is_empty() {
+        return Err(invalid_data("no rules defined"));
+    }
+    config
+        .rules
+        .into_iter()
+        .map(RuleConfig::into_rule)
+        .collect()
+}
+
+impl RuleConfig {
+    fn into_rule(self) -> io::Result<Rule> {
+        // The same combinations as the command line options conflicting
+        // with each other.
+        for (set, name) in [
+            (self.same_workspace_only, "same_workspace_only"),
+            (self.only_on_fullscreen, "only_on_fullscreen"),
+        ] {
+            if set && self.hide_on_workspace_switch {
+                return Err(invalid_data(format!(
+                    "{name} and hide_on_workspace_switch cannot be combined"
+                )));
+            }
+        }
+        Ok(Rule {
+            criteria: self.criteria.into_criteria()?,
+            restore_fullscreen: self.restore_fullscreen,
+            return_to_origin: self.return_to_origin,
+            recenter: self.recenter,
+            geometry: self
+                .geometry
+                .map(|geometry| geometry.parse())
+                .transpose()
+                .map_err(invalid_data)?,
+            same_workspace_only: self.same_workspace_only,
+            hide_on_workspace_switch: self.hide_on_workspace_switch,
+            only_on_fullscreen: self.only_on_fullscreen,
+            keep_while_urgent: self.keep_while_urgent,
+            delay: Duration::from_millis(self.delay_ms),
+            except: self
+                .except
+                .into_iter()
+                .map(CriteriaConfig::into_criteria)
+                .collect::<io::Result<_>>()?,
+            multi: self.multi,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A rule for dropdown windows, with the given `options`.
+    fn rule(options: &str) -> io::Result<Rule> {
+        let toml = format!("[[rule]]\nmatch = {{ class = \"dropdown\" }}\n{options}");
+        let config: Config = toml::from_str(&toml).unwrap();
+        config.rules.into_iter().next().unwrap().into_rule()
+    }
+
+    #[test]
+    fn rejects_conflicting_options() {
+        let err = rule("same_workspace_only = true\nhide_on_workspace_switch = true")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "same_workspace_only and hide_on_workspace_switch cannot be combined"
+        );
+        assert!(rule("only_on_fullscreen = true\nhide_on_workspace_switch = true").is_err());
+        assert!(rule("hide_on_workspace_switch = true").is_ok());
+    }
+}
@@ -0,0 +1,1236 @@
+//! Sending windows back to the scratchpad when they lose focus, as done by
+//! `i3-back-to-scratch`.
+//!
+//! A [`FocusMonitor`] tracks the visible windows matching its [`Rule`]s and
+//! hides them once focus moves elsewhere.
+
+pub mod config;
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    backend,
+    command::{Action, CommandBuilder},
+    criteria::Criteria,
+    focus::FocusTracker,
+    geometry::Geometry,
+    idle::{IdleChange, IdleMonitor},
+    metrics::Counter,
+    status::{ReportStatus, Status},
+    tree, Helper, I3Commands,
+};
+use clap::ValueEnum;
+use i3_ipc::{
+    event::{
+        BindingData, Subscribe, TickData, WindowChange, WindowData, WorkspaceChange, WorkspaceData,
+    },
+    reply::{FullscreenMode, Node},
+};
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// A set of scratchpad windows and how to handle them.
+pub struct Rule {
+    pub criteria: Criteria,
+    pub restore_fullscreen: bool,
+    /// Whether to send the window back to the workspace it was first seen on
+    /// instead of the scratchpad.
+    pub return_to_origin: bool,
+    /// Whether to center the window when shown on another output.
+    pub recenter: bool,
+    /// The size to give the window, relative to its output, when shown.
+    pub geometry: Option<Geometry>,
+    /// Whether to only hide the window when focus moves to another window on
+    /// its workspace.
+    pub same_workspace_only: bool,
+    /// Whether to hide the window when another workspace is focused.
+    pub hide_on_workspace_switch: bool,
+    /// Whether to only hide the window when focus moves to a fullscreen
+    /// window, which it would otherwise float on top of.
+    pub only_on_fullscreen: bool,
+    /// Whether to keep the window visible while it is urgent, e.g. after a
+    /// long command finished in a terminal, and only hide it once it no
+    /// longer is.
+    pub keep_while_urgent: bool,
+    /// How long to wait after the window loses focus before sending it back to
+    /// the scratchpad. The window stays visible if it regains focus meanwhile.
+    pub delay: Duration,
+    /// Windows that can take focus without the scratchpad window being hidden.
+    pub except: Vec<Criteria>,
+    /// Which windows to manage when several match the rule.
+    pub multi: Multi,
+}
+
+impl Rule {
+    fn is_excepted(&self, container: &Node) -> bool {
+        self.except.iter().any(|except| except.matches(container))
+    }
+}
+
+/// Which of the windows matching a rule are managed when several exist.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Multi {
+    /// The last focused window, hiding the previous one right away.
+    #[default]
+    LastFocused,
+    /// Only the most recently created window, leaving the others alone.
+    Newest,
+    /// Only the first created window, leaving the others alone.
+    Oldest,
+    /// All of them, which stay visible while focus moves between them and
+    /// are all hidden once it moves elsewhere.
+    All,
+    /// The last focused window on each workspace.
+    PerWorkspace,
+}
+
+impl Multi {
+    /// Whether telling the managed windows apart requires the layout tree.
+    fn needs_tree(self) -> bool {
+        matches!(self, Multi::Newest | Multi::Oldest | Multi::PerWorkspace)
+    }
+}
+
+pub static WINDOWS_HIDDEN: Counter = Counter::new(
+    "i3_back_to_scratch_windows_hidden_total",
+    "Number of windows sent back to the scratchpad or their origin workspace.",
+);
+
+type NodeId = usize;
+
+/// Index of a rule in [`FocusMonitor::rules`].
+type RuleId = usize;
+
+/// What a tracked window stands for: a rule, and for rules managing several
+/// windows at once, the window or workspace it is tracked for.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Slot {
+    rule: RuleId,
+    instance: NodeId,
+}
+
+/// A scratchpad window that is currently visible.
+struct Tracked {
+    id: NodeId,
+    fullscreen: bool,
+    output: Option<String>,
+    /// The workspace hosting the window, for rules hiding it when another
+    /// workspace is focused.
+    workspace: Option<NodeId>,
+    description: String,
+    /// The X11 class of the window, or its app_id, for status bars.
+    class: String,
+    urgent: bool,
+    /// Set once the window has lost focus and is due to be sent back to the
+    /// scratchpad.
+    pending: Option<PendingHide>,
+}
+
+impl Tracked {
+    fn new(container: &Node, fullscreen: bool, workspace: Option<NodeId>) -> Self {
+        Self {
+            id: container.id,
+            fullscreen,
+            output: container.output.clone(),
+            workspace,
+            description: tree::describe(container),
+            class: container
+                .window_properties
+                .as_ref()
+                .and_then(|props| props.class.clone())
+                .or(container.app_id.clone())
+                .unwrap_or_default(),
+            urgent: container.urgent,
+            pending: None,
+        }
+    }
+
+    /// Whether the window must stay visible for now, even if due to be hidden.
+    fn is_held(&self, rule: &Rule) -> bool {
+        rule.keep_while_urgent && self.urgent
+    }
+}
+
+struct PendingHide {
+    at: Instant,
+    reason: String,
+}
+
+/// The state of a window when we sent it back to the scratchpad, to be
+/// restored once it is shown again.
+struct Hidden {
+    fullscreen: bool,
+    output: Option<String>,
+}
+
+pub struct FocusMonitor {
+    rules: Vec<Rule>,
+    /// The configuration file the rules were loaded from, if any.
+    config: Option<PathBuf>,
+    /// Last focused scratchpad window of each slot, as long as it hasn't been
+    /// sent back to the scratchpad yet.
+    last_focused: HashMap<Slot, Tracked>,
+    /// The focused window, to ignore focus events that don't move the focus.
+    focus: FocusTracker,
+    /// Windows we sent back to the scratchpad that need their state restored
+    /// when shown again.
+    hidden: HashMap<NodeId, Hidden>,
+    /// Windows we sent to the scratchpad and that weren't shown since, with
+    /// their description.
+    sent_to_scratchpad: HashMap<NodeId, String>,
+    /// Whether to bring the windows of `sent_to_scratchpad` back on exit.
+    unhide_on_exit: bool,
+    /// Whether to hide the unfocused windows found visible on startup right
+    /// away.
+    hide_on_start: bool,
+    /// Workspace each window of a `return_to_origin` rule was first seen on.
+    origins: HashMap<NodeId, String>,
+    /// Number of times a window matching each rule got focus.
+    match_counts: Vec<usize>,
+    /// Number of windows of each rule we sent back.
+    hide_counts: Vec<usize>,
+    /// Whether hiding windows was suspended through the control socket or
+    /// the toggle binding.
+    paused: bool,
+    /// The command of the key binding toggling `paused`.
+    toggle_binding: String,
+    /// Tells when to pause while the session is idle, if asked to.
+    idle: Option<IdleMonitor>,
+    /// Whether `paused` was set because the session went idle, to be cleared
+    /// once active again.
+    paused_while_idle: bool,
+}
+
+impl Helper for FocusMonitor {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        let mut subscriptions = vec![Subscribe::Window, Subscribe::Workspace, Subscribe::Binding];
+        if self.idle.is_some() {
+            subscriptions.push(Subscribe::Tick);
+        }
+        subscriptions
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        self.warn_about_unmatchable_rules(i3)?;
+        if let Some(idle) = &mut self.idle {
+            idle.start(i3)?;
+        }
+        let tree = i3.get_tree()?;
+        if self.rules.iter().any(|rule| rule.return_to_origin) {
+            for window in tree::windows(&tree) {
+                self.record_origin(&tree, window);
+            }
+        }
+        self.adopt(i3, &tree)
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        let focus_change = self.focus.update(event);
+        match event.change {
+            // i3 also reports focus given to the focused window, e.g. when
+            // clicking it, which changes nothing.
+            WindowChange::Focus if focus_change.is_some() => {
+                debug!("focus changed to {}", tree::describe(&event.container));
+                let reason = format!("focus moved to {}", tree::describe(&event.container));
+                self.handle_last_focused(i3, Some(&event.container), reason)?;
+                self.update_last_focused(i3, &event.container)?;
+            }
+            WindowChange::FullscreenMode => {
+                self.update_fullscreen(&event.container);
+                // The focused window may only become fullscreen after taking
+                // focus, which matters to `only_on_fullscreen` rules.
+                let container = &event.container;
+                if container.focused && is_fullscreen(container) && !self.is_tracked(container.id) {
+                    let reason = format!("{} went fullscreen", tree::describe(container));
+                    self.handle_last_focused(i3, Some(container), reason)?;
+                }
+            }
+            WindowChange::Title | WindowChange::Mark => self.rematch(i3, &event.container)?,
+            WindowChange::Urgent => self.update_urgency(i3, &event.container)?,
+            WindowChange::New if self.needs_origin(&event.container) => {
+                let tree = i3.get_tree()?;
+                self.record_origin(&tree, &event.container);
+            }
+            // Focus leaves windows moved to the scratchpad or another
+            // workspace without any event if no window is left to take it.
+            WindowChange::Move if self.focus.focused() == Some(event.container.id) => {
+                self.focus.clear();
+            }
+            WindowChange::Close => self.forget(&event.container),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn on_workspace(&mut self, i3: &mut dyn I3Commands, event: &WorkspaceData) -> io::Result<()> {
+        // This branch covers the case when:
+        //
+        // 1. the scratchpad window is open in a workspace,
+        // 2. we switch to another workspace that happens to be empty
+        // 3. and then we summon the scratchpad window again.
+        //
+        // What happens in that, during 2., there are no windows to receive the
+        // Window `Focus` event and, therefore, we never send the window to the
+        // scratchpad area.
+        //
+        // This forces us to consider the Workspace `Focus` events as well, and
+        // send the window to the scratchpad area when switching to an empty
+        // workspace from having the scratchpad window focused.
+        if let WorkspaceChange::Focus = event.change {
+            let focused_workspace_is_empty = event
+                .current
+                .as_ref()
+                .map(is_empty_workspace)
+                .unwrap_or(false);
+            if focused_workspace_is_empty {
+                let name = event.current.as_ref().and_then(|ws| ws.name.as_deref());
+                let reason = format!("switched to empty workspace {:?}", name.unwrap_or_default());
+                debug!("{reason}");
+                self.focus.clear();
+                self.handle_last_focused(i3, None, reason)?;
+            } else if let Some(current) = &event.current {
+                self.handle_workspace_switch(i3, current)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.last_focused
+            .iter()
+            .filter(|(slot, tracked)| !tracked.is_held(&self.rules[slot.rule]))
+            .filter_map(|(_, tracked)| tracked.pending.as_ref())
+            .map(|pending| pending.at)
+            .chain(self.idle.as_ref().and_then(IdleMonitor::next_deadline))
+            .min()
+    }
+
+    fn on_deadline(&mut self, i3: &mut dyn I3Commands, now: Instant) -> io::Result<()> {
+        if let Some(idle) = &mut self.idle {
+            if let Some(change) = idle.on_deadline(now)? {
+                self.on_idle_change(change);
+            }
+        }
+        self.hide_due(i3, now)
+    }
+
+    fn on_tick(&mut self, _i3: &mut dyn I3Commands, event: &TickData) -> io::Result<()> {
+        if let Some(change) = self.idle.as_mut().and_then(|idle| idle.on_tick(event)) {
+            self.on_idle_change(change);
+        }
+        Ok(())
+    }
+
+    fn on_control(&mut self, i3: &mut dyn I3Commands, command: &str) -> io::Result<String> {
+        let reply = match command {
+            "status" => self.report(),
+            "pause" => {
+                self.set_paused(true);
+                "paused\n".to_owned()
+            }
+            "resume" => {
+                self.set_paused(false);
+                "resumed\n".to_owned()
+            }
+            "hide-now" => {
+                for (slot, tracked) in std::mem::take(&mut self.last_focused) {
+                    let reason = "requested through the control socket";
+                    self.hide(i3, slot.rule, tracked, reason)?;
+                }
+                "hidden\n".to_owned()
+            }
+            _ => format!("error: unknown command {command:?}\n"),
+        };
+        Ok(reply)
+    }
+
+    fn on_binding(&mut self, _i3: &mut dyn I3Commands, event: &BindingData) -> io::Result<()> {
+        if event.binding.command.trim() == self.toggle_binding {
+            self.set_paused(!self.paused);
+        }
+        Ok(())
+    }
+
+    fn on_exit(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        if !self.unhide_on_exit || self.sent_to_scratchpad.is_empty() {
+            return Ok(());
+        }
+        let tree = i3.get_tree()?;
+        for &id in self.sent_to_scratchpad.keys() {
+            // Windows sent back to the scratchpad by hand since are left there.
+            let Some(window) = tree::find(&tree, id) else {
+                continue;
+            };
+            if tree::is_hidden_in_scratchpad(&tree, id) {
+                info!("bringing back {} before exiting", tree::describe(window));
+                let command = CommandBuilder::con_id(id)
+                    .action(Action::ScratchpadShow)
+                    .action(Action::FloatingDisable);
+                i3.run_command(&command.build())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn on_reconnect(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let tree = i3.get_tree()?;
+        self.focus.clear();
+        self.resolve_stale_ids(&tree);
+        Ok(())
+    }
+
+    fn on_reload(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let Some(path) = self.config.clone() else {
+            return Ok(());
+        };
+        self.reload(i3, &path)
+    }
+}
+
+/// The windows currently tracked, and whether hiding them is paused.
+impl ReportStatus for FocusMonitor {
+    fn status(&self) -> Status {
+        let mut tracked: Vec<_> = self.last_focused.iter().collect();
+        tracked.sort_by_key(|(&slot, _)| slot);
+        let class = match (self.paused, tracked.is_empty()) {
+            (true, _) => "paused",
+            (false, true) => "idle",
+            (false, false) => "tracking",
+        };
+        Status {
+            text: tracked
+                .iter()
+                .map(|(_, tracked)| tracked.class.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            tooltip: tracked
+                .iter()
+                .map(|(_, tracked)| tracked.description.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            class: class.to_owned(),
+        }
+    }
+}
+
+impl FocusMonitor {
+    pub fn new(rules: Vec<Rule>, config: Option<PathBuf>) -> Self {
+        Self {
+            match_counts: vec![0; rules.len()],
+            hide_counts: vec![0; rules.len()],
+            rules,
+            config,
+            last_focused: HashMap::new(),
+            focus: FocusTracker::new(),
+            hidden: HashMap::new(),
+            sent_to_scratchpad: HashMap::new(),
+            unhide_on_exit: false,
+            hide_on_start: false,
+            origins: HashMap::new(),
+            paused: false,
+            toggle_binding: "nop back-to-scratch-toggle".to_owned(),
+            idle: None,
+            paused_while_idle: false,
+        }
+    }
+
+    /// Print a warning for every rule that cannot match any window under the
+    /// window manager we are connected to.
+    fn warn_about_unmatchable_rules(&self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let criteria = || self.rules.iter().map(|rule| &rule.criteria);
+        let backend = backend::detect(i3)?;
+        if !backend.has_app_ids() && criteria().any(|criteria| criteria.app_id.is_some()) {
+            warn!("i3 windows have no app_id, --app-id will never match");
+        } else if backend.has_app_ids() && criteria().any(Criteria::uses_x11_properties) {
+            warn!(
+                "running under sway, --class and --instance only match \
+                 XWayland windows, consider using --app-id"
+            );
+        }
+        Ok(())
+    }
+
+    pub fn set_toggle_binding(&mut self, command: String) {
+        self.toggle_binding = command;
+    }
+
+    /// Bring the windows we sent to the scratchpad back to the focused
+    /// workspace, tiled, when the event loop exits on SIGTERM or SIGINT.
+    pub fn set_unhide_on_exit(&mut self, unhide_on_exit: bool) {
+        self.unhide_on_exit = unhide_on_exit;
+    }
+
+    /// Send the visible windows matching a rule on the focused workspace back
+    /// to the scratchpad on startup, unless focused, instead of waiting for
+    /// focus to move.
+    pub fn set_hide_on_start(&mut self, hide_on_start: bool) {
+        self.hide_on_start = hide_on_start;
+    }
+
+    /// Pause while the session is idle, as told by `idle`, e.g. to leave a
+    /// window showing the progress of a build visible while away.
+    pub fn set_pause_when_idle(&mut self, idle: IdleMonitor) {
+        self.idle = Some(idle);
+    }
+
+    /// Start tracking the visible windows matching a rule, found on the
+    /// focused workspace of `tree` on startup, as if they had been focused
+    /// before, so that restarting the helper doesn't leave them behind. The
+    /// focused window takes precedence over other windows of its slot.
+    fn adopt(&mut self, i3: &mut dyn I3Commands, tree: &Node) -> io::Result<()> {
+        let Some(focused_workspace) = tree::focused_workspace(tree) else {
+            return Ok(());
+        };
+        let mut windows: Vec<&Node> = tree::windows(focused_workspace)
+            .filter(|window| !tree::is_hidden_in_scratchpad(tree, window.id))
+            .collect();
+        windows.sort_by_key(|window| !window.focused);
+        for window in windows {
+            let Some(rule) = self.matching_rule(window) else {
+                continue;
+            };
+            if !self.is_managed(rule, window, tree) {
+                continue;
+            }
+            let workspace = match self.rules[rule].hide_on_workspace_switch {
+                true => tree::workspace_of(tree, window.id).map(|ws| ws.id),
+                false => None,
+            };
+            let tracked = Tracked::new(window, is_fullscreen(window), workspace);
+            if self.hide_on_start && !window.focused {
+                self.hide(i3, rule, tracked, "visible but unfocused on startup")?;
+            } else if let Entry::Vacant(entry) =
+                self.last_focused.entry(self.slot(rule, window, Some(tree)))
+            {
+                info!("adopting {}, matched by rule {rule}", tracked.description);
+                entry.insert(tracked);
+            }
+        }
+        Ok(())
+    }
+
+    /// Look the tracked windows up again in `tree`, by the criteria of their
+    /// rule, after i3 restarted in place and gave all containers new ids.
+    ///
+    /// A window keeps being tracked if it is the only visible window of its
+    /// rule, or the only one with the same description. The state of the
+    /// windows that are hidden is dropped, as there is no telling them apart.
+    fn resolve_stale_ids(&mut self, tree: &Node) {
+        let mut claimed = HashSet::new();
+        for (slot, mut tracked) in std::mem::take(&mut self.last_focused) {
+            let Some(window) = self.find_again(tree, slot.rule, &tracked, &claimed) else {
+                warn!(
+                    "lost track of {} after i3 restarted, cannot tell which window it is now",
+                    tracked.description
+                );
+                continue;
+            };
+            if window.id != tracked.id {
+                warn!(
+                    "{} has a new id after i3 restarted, {} instead of {}",
+                    tracked.description, window.id, tracked.id
+                );
+            }
+            claimed.insert(window.id);
+            tracked.id = window.id;
+            tracked.description = tree::describe(window);
+            tracked.workspace = tracked
+                .workspace
+                .and_then(|_| tree::workspace_of(tree, window.id))
+                .map(|ws| ws.id);
+            let slot = self.slot(slot.rule, window, Some(tree));
+            self.last_focused.insert(slot, tracked);
+        }
+        let is_stale = |id: &NodeId| tree::find(tree, *id).is_none();
+        if self.hidden.keys().any(is_stale) || self.sent_to_scratchpad.keys().any(is_stale) {
+            warn!("forgetting the state of the hidden windows, whose ids changed");
+        }
+        self.hidden.retain(|id, _| !is_stale(id));
+        self.sent_to_scratchpad.retain(|id, _| !is_stale(id));
+        self.origins.retain(|id, _| !is_stale(id));
+        for window in tree::windows(tree) {
+            self.record_origin(tree, window);
+        }
+    }
+
+    /// The window of `tree` that `tracked` most likely is, among the visible
+    /// windows matching `rule` and not `claimed` by other tracked windows:
+    /// the only one, or else the only one with the same description.
+    fn find_again<'a>(
+        &self,
+        tree: &'a Node,
+        rule: RuleId,
+        tracked: &Tracked,
+        claimed: &HashSet<NodeId>,
+    ) -> Option<&'a Node> {
+        let candidates: Vec<&Node> = tree::windows(tree)
+            .filter(|window| !tree::is_hidden_in_scratchpad(tree, window.id))
+            .filter(|window| !claimed.contains(&window.id))
+            .filter(|window| self.matching_rule(window) == Some(rule))
+            .collect();
+        let same_description: Vec<&Node> = candidates
+            .iter()
+            .copied()
+            .filter(|window| tree::describe(window) == tracked.description)
+            .collect();
+        match (candidates.as_slice(), same_description.as_slice()) {
+            (_, [window]) | ([window], _) => Some(window),
+            _ => None,
+        }
+    }
+
+    /// Suspend or resume hiding windows. Pending hides are dropped when
+    /// pausing.
+    fn set_paused(&mut self, paused: bool) {
+        info!("{}", if paused { "pausing" } else { "resuming" });
+        self.paused = paused;
+        self.paused_while_idle = false;
+        if paused {
+            for tracked in self.last_focused.values_mut() {
+                tracked.pending = None;
+            }
+        }
+    }
+
+    /// Pause when the session goes idle, unless already paused, and resume
+    /// once active again if paused for that reason only.
+    fn on_idle_change(&mut self, change: IdleChange) {
+        match change {
+            IdleChange::Idle(_) if !self.paused => {
+                self.set_paused(true);
+                self.paused_while_idle = true;
+            }
+            IdleChange::Active if self.paused_while_idle => self.set_paused(false),
+            _ => (),
+        }
+    }
+
+    /// A report of the rules, how often they matched and hid windows, and of
+    /// the windows tracked or sent to the scratchpad, for the control socket.
+    fn report(&self) -> String {
+        let mut status = format!("paused: {}", self.paused);
+        if self.paused_while_idle {
+            status += " (idle)";
+        }
+        status += "\n";
+        for (id, rule) in self.rules.iter().enumerate() {
+            status += &format!(
+                "rule {id}: {} ({} matches, {} hidden)\n",
+                rule.criteria, self.match_counts[id], self.hide_counts[id]
+            );
+        }
+        let mut tracked: Vec<_> = self.last_focused.iter().collect();
+        tracked.sort_by_key(|(&slot, _)| slot);
+        for (slot, tracked) in tracked {
+            status += &format!("visible: {} for rule {}", tracked.description, slot.rule);
+            if tracked.is_held(&self.rules[slot.rule]) {
+                status += ", kept while urgent";
+            }
+            if let Some(pending) = &tracked.pending {
+                let remaining = pending.at.saturating_duration_since(Instant::now());
+                status += &format!(
+                    ", hiding in {}ms ({})",
+                    remaining.as_millis(),
+                    pending.reason
+                );
+            }
+            status += "\n";
+        }
+        let mut scratched: Vec<_> = self.sent_to_scratchpad.iter().collect();
+        scratched.sort();
+        for (_, description) in scratched {
+            status += &format!("scratched: {description}\n");
+        }
+        status
+    }
+
+    /// Replace the rules with the ones in the configuration file at `path`.
+    ///
+    /// Visible scratchpad windows keep being tracked as long as they match one
+    /// of the new rules. The current rules are kept if the file is invalid.
+    fn reload(&mut self, i3: &mut dyn I3Commands, path: &Path) -> io::Result<()> {
+        let rules = match config::load(path) {
+            Ok(rules) => rules,
+            Err(err) => {
+                error!(
+                    "failed to reload {}, keeping the current rules: {err}",
+                    path.display()
+                );
+                return Ok(());
+            }
+        };
+        let tree = i3.get_tree()?;
+        let tracked = std::mem::take(&mut self.last_focused);
+        self.match_counts = vec![0; rules.len()];
+        self.hide_counts = vec![0; rules.len()];
+        self.rules = rules;
+        for tracked in tracked.into_values() {
+            let slot = tree::find(&tree, tracked.id).and_then(|node| {
+                let rule = self.matching_rule(node)?;
+                let managed = self.is_managed(rule, node, &tree);
+                managed.then(|| self.slot(rule, node, Some(&tree)))
+            });
+            match slot {
+                Some(slot) => {
+                    self.last_focused.insert(slot, tracked);
+                }
+                None => info!("no longer tracking {}", tracked.description),
+            }
+        }
+        info!(
+            "reloaded {} rules from {}",
+            self.rules.len(),
+            path.display()
+        );
+        self.warn_about_unmatchable_rules(i3)
+    }
+
+    /// Schedule the tracked windows that lost focus to be sent back to the
+    /// scratchpad, unless focus went to one of their rule's exceptions.
+    ///
+    /// `focused` is the newly focused window, if any, and `reason` describes
+    /// the focus change for logging purposes.
+    fn handle_last_focused(
+        &mut self,
+        i3: &mut dyn I3Commands,
+        focused: Option<&Node>,
+        reason: String,
+    ) -> io::Result<()> {
+        if self.paused {
+            debug!("paused, not hiding anything: {reason}");
+            return Ok(());
+        }
+        let same_workspace_only = self
+            .last_focused
+            .keys()
+            .any(|slot| self.rules[slot.rule].same_workspace_only);
+        let tree = match same_workspace_only && focused.is_some() {
+            true => Some(i3.get_tree()?),
+            false => None,
+        };
+        let workspace = |id| {
+            let tree = tree.as_ref()?;
+            tree::workspace_of(tree, id).map(|workspace| workspace.id)
+        };
+        let now = Instant::now();
+        for (slot, tracked) in &mut self.last_focused {
+            let rule = &self.rules[slot.rule];
+            if focused.is_some_and(|focused| focused.id == tracked.id) {
+                continue;
+            }
+            if rule.multi == Multi::All && focused.is_some_and(|f| rule.criteria.matches(f)) {
+                debug!(
+                    "keeping {} visible: {reason}, of the same rule",
+                    tracked.description
+                );
+                continue;
+            }
+            if rule.same_workspace_only
+                && focused.is_none_or(|focused| workspace(focused.id) != workspace(tracked.id))
+            {
+                debug!(
+                    "keeping {} visible: {reason}, on another workspace",
+                    tracked.description
+                );
+                continue;
+            }
+            if rule.only_on_fullscreen && focused.is_none_or(|focused| !is_fullscreen(focused)) {
+                debug!(
+                    "keeping {} visible: {reason}, which is not fullscreen",
+                    tracked.description
+                );
+                continue;
+            }
+            if tracked.pending.is_some() {
+                debug!("{} is already due to be hidden", tracked.description);
+                continue;
+            }
+            if focused.is_some_and(|focused| rule.is_excepted(focused)) {
+                info!(
+                    "keeping {} visible: {reason}, which is excepted",
+                    tracked.description
+                );
+                continue;
+            }
+            tracked.pending = Some(PendingHide {
+                at: now + rule.delay,
+                reason: reason.clone(),
+            });
+        }
+        self.hide_due(i3, now)
+    }
+
+    /// Schedule the tracked windows of `hide_on_workspace_switch` rules to be
+    /// sent back to the scratchpad, unless they are on the newly focused
+    /// `workspace`.
+    fn handle_workspace_switch(
+        &mut self,
+        i3: &mut dyn I3Commands,
+        workspace: &Node,
+    ) -> io::Result<()> {
+        if self.paused {
+            return Ok(());
+        }
+        let name = workspace.name.as_deref().unwrap_or_default();
+        let now = Instant::now();
+        for (slot, tracked) in &mut self.last_focused {
+            let rule = &self.rules[slot.rule];
+            if !rule.hide_on_workspace_switch
+                || tracked.pending.is_some()
+                || tracked.workspace.is_none_or(|id| id == workspace.id)
+            {
+                continue;
+            }
+            tracked.pending = Some(PendingHide {
+                at: now + rule.delay,
+                reason: format!("switched to workspace {name:?}"),
+            });
+        }
+        self.hide_due(i3, now)
+    }
+
+    /// Send back to the scratchpad all the windows whose delay expired.
+    fn hide_due(&mut self, i3: &mut dyn I3Commands, now: Instant) -> io::Result<()> {
+        let due: Vec<Slot> = self
+            .last_focused
+            .iter()
+            .filter(|(_, tracked)| tracked.pending.as_ref().is_some_and(|p| p.at <= now))
+            .filter(|(slot, tracked)| {
+                let held = tracked.is_held(&self.rules[slot.rule]);
+                if held {
+                    debug!("keeping {} visible while urgent", tracked.description);
+                }
+                !held
+            })
+            .map(|(&slot, _)| slot)
+            .collect();
+        for slot in due {
+            if let Some(mut tracked) = self.last_focused.remove(&slot) {
+                let reason = tracked.pending.take().map(|p| p.reason).unwrap_or_default();
+                self.hide(i3, slot.rule, tracked, &reason)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `container` belongs to a `return_to_origin` rule but we don't
+    /// know its origin yet.
+    fn needs_origin(&self, container: &Node) -> bool {
+        !self.origins.contains_key(&container.id)
+            && self
+                .matching_rule(container)
+                .is_some_and(|rule| self.rules[rule].return_to_origin)
+    }
+
+    /// Remember the workspace `container` is on as its origin, if it belongs
+    /// to a `return_to_origin` rule and wasn't seen before.
+    fn record_origin(&mut self, tree: &Node, container: &Node) {
+        if !self.needs_origin(container) {
+            return;
+        }
+        let workspace = tree::workspace_of(tree, container.id).and_then(|ws| ws.name.clone());
+        if let Some(workspace) = workspace {
+            info!(
+                "{} originates from workspace {workspace:?}",
+                tree::describe(container)
+            );
+            self.origins.insert(container.id, workspace);
+        }
+    }
+
+    fn update_last_focused(&mut self, i3: &mut dyn I3Commands, container: &Node) -> io::Result<()> {
+        if self.needs_origin(container) {
+            let tree = i3.get_tree()?;
+            self.record_origin(&tree, container);
+        }
+        if let Some(rule) = self.matching_rule(container) {
+            self.match_counts[rule] += 1;
+            let tree = match self.rules[rule].multi.needs_tree()
+                || self.rules[rule].hide_on_workspace_switch
+            {
+                true => Some(i3.get_tree()?),
+                false => None,
+            };
+            if tree
+                .as_ref()
+                .is_some_and(|tree| !self.is_managed(rule, container, tree))
+            {
+                info!(
+                    "leaving {} alone, rule {rule} manages another window",
+                    tree::describe(container)
+                );
+                return Ok(());
+            }
+            let slot = self.slot(rule, container, tree.as_ref());
+            let shown = self
+                .last_focused
+                .get(&slot)
+                .is_none_or(|tracked| tracked.id != container.id);
+            self.sent_to_scratchpad.remove(&container.id);
+            let fullscreen = match shown {
+                true => self.restore(i3, rule, container)?,
+                false => is_fullscreen(container),
+            };
+            let workspace = match self.rules[rule].hide_on_workspace_switch {
+                true => tree
+                    .as_ref()
+                    .and_then(|tree| tree::workspace_of(tree, container.id))
+                    .map(|ws| ws.id),
+                false => None,
+            };
+            let tracked = Tracked::new(container, fullscreen, workspace);
+            let previous = self.last_focused.insert(slot, tracked);
+            match previous {
+                Some(previous) if previous.id == container.id => {
+                    if previous.pending.is_some() {
+                        info!(
+                            "{} regained focus, keeping it visible",
+                            previous.description
+                        );
+                    }
+                }
+                // A window of the same slot that is still waiting for its delay
+                // to expire would be forgotten otherwise, so hide it right away.
+                Some(previous) => {
+                    let reason = "another window of the same rule got focus";
+                    self.hide(i3, rule, previous, reason)?;
+                }
+                None => info!(
+                    "tracking {}, matched by rule {rule}",
+                    tree::describe(container)
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `container`, a window matching `rule`, is the one the rule
+    /// manages among the windows of `tree` matching it, if it only manages
+    /// the newest or oldest.
+    fn is_managed(&self, rule: RuleId, container: &Node, tree: &Node) -> bool {
+        let first_match = |window: &Node| {
+            self.rules
+                .iter()
+                .position(|rule| rule.criteria.matches(window))
+        };
+        // Container ids are allocated in increasing order.
+        let ids = tree::windows(tree)
+            .filter(|window| first_match(window) == Some(rule))
+            .map(|window| window.id)
+            .chain([container.id]);
+        match self.rules[rule].multi {
+            Multi::Newest => ids.max() == Some(container.id),
+            Multi::Oldest => ids.min() == Some(container.id),
+            Multi::LastFocused | Multi::All | Multi::PerWorkspace => true,
+        }
+    }
+
+    /// The slot tracking `container`, a window matching `rule`. The `tree` is
+    /// needed to find the workspace of `per-workspace` rules.
+    fn slot(&self, rule: RuleId, container: &Node, tree: Option<&Node>) -> Slot {
+        let instance = match self.rules[rule].multi {
+            Multi::All => container.id,
+            Multi::PerWorkspace => tree
+                .and_then(|tree| tree::workspace_of(tree, container.id))
+                .map_or(0, |workspace| workspace.id),
+            Multi::LastFocused | Multi::Newest | Multi::Oldest => 0,
+        };
+        Slot { rule, instance }
+    }
+
+    /// Restore the state of a window that just became visible, as requested
+    /// by its rule. Returns whether the window ends up fullscreen.
+    fn restore(
+        &mut self,
+        i3: &mut dyn I3Commands,
+        rule: RuleId,
+        container: &Node,
+    ) -> io::Result<bool> {
+        let hidden = self.hidden.remove(&container.id);
+        if is_fullscreen(container) {
+            return Ok(true);
+        }
+        // i3 drops the fullscreen mode of windows moved to the scratchpad, so
+        // we enable it again when the window is shown and focused.
+        if hidden.as_ref().is_some_and(|hidden| hidden.fullscreen) {
+            enable_fullscreen(i3, container.id)?;
+            return Ok(true);
+        }
+        if let Some(geometry) = self.rules[rule].geometry {
+            resize(i3, geometry, container)?;
+        } else if let Some(hidden) = hidden {
+            if hidden.output.is_some() && hidden.output != container.output {
+                info!(
+                    "{} shown on another output, centering it",
+                    tree::describe(container)
+                );
+                center(i3, container.id)?;
+            }
+        }
+        Ok(false)
+    }
+
+    /// Start or stop tracking a window whose title or marks changed, as some
+    /// apps only become identifiable once they set their final title.
+    fn rematch(&mut self, i3: &mut dyn I3Commands, container: &Node) -> io::Result<()> {
+        let tracked_by = self
+            .last_focused
+            .iter()
+            .find(|(_, tracked)| tracked.id == container.id)
+            .map(|(&slot, _)| slot);
+        let rule = self.matching_rule(container);
+        if let Some(tracked_by) = tracked_by {
+            if rule == Some(tracked_by.rule) {
+                if let Some(tracked) = self.last_focused.get_mut(&tracked_by) {
+                    tracked.description = tree::describe(container);
+                }
+                return Ok(());
+            }
+            if let Some(tracked) = self.last_focused.remove(&tracked_by) {
+                info!(
+                    "no longer tracking {}, now {}",
+                    tracked.description,
+                    tree::describe(container)
+                );
+            }
+        }
+        if rule.is_some() && container.focused {
+            self.update_last_focused(i3, container)?;
+        }
+        Ok(())
+    }
+
+    /// Record whether a tracked window is urgent, hiding it if it was only
+    /// kept visible because it was.
+    fn update_urgency(&mut self, i3: &mut dyn I3Commands, container: &Node) -> io::Result<()> {
+        let tracked = self
+            .last_focused
+            .values_mut()
+            .find(|tracked| tracked.id == container.id);
+        let Some(tracked) = tracked else {
+            return Ok(());
+        };
+        tracked.urgent = container.urgent;
+        if container.urgent {
+            return Ok(());
+        }
+        self.hide_due(i3, Instant::now())
+    }
+
+    /// Drop all state about a window that was closed, so that it isn't hidden
+    /// or restored later on.
+    fn forget(&mut self, container: &Node) {
+        let id = container.id;
+        self.last_focused.retain(|_, tracked| {
+            if tracked.id != id {
+                return true;
+            }
+            info!("{} was closed, no longer tracking it", tracked.description);
+            false
+        });
+        self.hidden.remove(&id);
+        self.origins.remove(&id);
+        self.sent_to_scratchpad.remove(&id);
+    }
+
+    fn is_tracked(&self, id: NodeId) -> bool {
+        self.last_focused.values().any(|tracked| tracked.id == id)
+    }
+
+    fn update_fullscreen(&mut self, container: &Node) {
+        let tracked = self
+            .last_focused
+            .values_mut()
+            .find(|tracked| tracked.id == container.id);
+        if let Some(tracked) = tracked {
+            tracked.fullscreen = is_fullscreen(container);
+        }
+    }
+
+    fn hide(
+        &mut self,
+        i3: &mut dyn I3Commands,
+        rule_id: RuleId,
+        mut tracked: Tracked,
+        reason: &str,
+    ) -> io::Result<()> {
+        let rule = &self.rules[rule_id];
+        if rule.return_to_origin {
+            return self.return_to_origin(i3, rule_id, tracked, reason);
+        }
+        info!(
+            "sending {} back to the scratchpad: {reason}",
+            tracked.description
+        );
+        if !self.send_to_scratchpad(i3, rule_id, &mut tracked)? {
+            return Ok(());
+        }
+        let rule = &self.rules[rule_id];
+        let hidden = Hidden {
+            fullscreen: rule.restore_fullscreen && tracked.fullscreen,
+            output: tracked.output.filter(|_| rule.recenter),
+        };
+        if hidden.fullscreen || hidden.output.is_some() {
+            self.hidden.insert(tracked.id, hidden);
+        }
+        WINDOWS_HIDDEN.inc();
+        self.hide_counts[rule_id] += 1;
+        self.sent_to_scratchpad
+            .insert(tracked.id, tracked.description);
+        Ok(())
+    }
+
+    /// Move the window of `tracked` to the scratchpad, returning whether it
+    /// worked.
+    ///
+    /// If i3 can't find the window, e.g. because it restarted and gave it a
+    /// new id, the tracked windows are looked up again by their criteria, and
+    /// the window is moved again under its new id, if it can be told apart.
+    fn send_to_scratchpad(
+        &mut self,
+        i3: &mut dyn I3Commands,
+        rule: RuleId,
+        tracked: &mut Tracked,
+    ) -> io::Result<bool> {
+        if move_to_scratchpad(i3, tracked.id)? {
+            return Ok(true);
+        }
+        let tree = i3.get_tree()?;
+        // The command failed for another reason than a stale id.
+        if tree::find(&tree, tracked.id).is_some() {
+            return Ok(false);
+        }
+        self.resolve_stale_ids(&tree);
+        let claimed = self
+            .last_focused
+            .values()
+            .map(|tracked| tracked.id)
+            .collect();
+        // Hiding the window that has focus would be worse than leaving one.
+        let window = self
+            .find_again(&tree, rule, tracked, &claimed)
+            .filter(|window| !window.focused);
+        let Some(window) = window else {
+            warn!(
+                "failed to send {} to the scratchpad, cannot tell which window it is now",
+                tracked.description
+            );
+            return Ok(false);
+        };
+        warn!(
+            "{} has a new id, {} instead of {}",
+            tracked.description, window.id, tracked.id
+        );
+        tracked.id = window.id;
+        tracked.description = tree::describe(window);
+        move_to_scratchpad(i3, tracked.id)
+    }
+
+    /// Move the window back to the workspace it was first seen on, unless it
+    /// is already there.
+    fn return_to_origin(
+        &mut self,
+        i3: &mut dyn I3Commands,
+        rule: RuleId,
+        mut tracked: Tracked,
+        reason: &str,
+    ) -> io::Result<()> {
+        let Some(origin) = self.origins.get(&tracked.id) else {
+            warn!(
+                "don't know where {} comes from, leaving it",
+                tracked.description
+            );
+            return Ok(());
+        };
+        let tree = i3.get_tree()?;
+        let current = tree::workspace_of(&tree, tracked.id).and_then(|ws| ws.name.as_deref());
+        if current == Some(origin) {
+            return Ok(());
+        }
+        info!(
+            "returning {} to workspace {origin:?}: {reason}",
+            tracked.description
+        );
+        let returned = if origin == tree::SCRATCHPAD_WORKSPACE {
+            self.send_to_scratchpad(i3, rule, &mut tracked)?
+        } else {
+            let command = CommandBuilder::con_id(tracked.id)
+                .action(Action::MoveToWorkspace(origin.to_owned()));
+            i3.run_command_checked(&command.build())?
+        };
+        if returned {
+            WINDOWS_HIDDEN.inc();
+            self.hide_counts[rule] += 1;
+        }
+        Ok(())
+    }
+
+    fn matching_rule(&self, container: &Node) -> Option<RuleId> {
+        let rule = self
+            .rules
+            .iter()
+            .position(|rule| rule.criteria.matches(container));
+        match rule {
+            Some(rule) => debug!("{} matches rule {rule}", tree::describe(container)),
+            None => debug!("{} matches no rule", tree::describe(container)),
+        }
+        rule
+    }
+}
+
+/// Move the window to the scratchpad, returning whether it worked.
+fn move_to_scratchpad(i3: &mut dyn I3Commands, container_id: usize) -> io::Result<bool> {
+    let command = CommandBuilder::con_id(container_id).action(Action::MoveScratchpad);
+    i3.run_command_checked(&command.build())
+}
+
+/// Resize the window to `geometry` relative to the output it is on, and
+/// center it there.
+fn resize(i3: &mut dyn I3Commands, geometry: Geometry, container: &Node) -> io::Result<()> {
+    let output = i3
+        .get_outputs()?
+        .into_iter()
+        .find(|output| container.output.as_ref() == Some(&output.name));
+    match output {
+        Some(output) => {
+            info!(
+                "resizing {} to {geometry} of output {:?}",
+                tree::describe(container),
+                output.name
+            );
+            i3.run_command(&geometry.command(container.id, &output.rect))
+        }
+        None => {
+            warn!(
+                "cannot find the output of {}, not resizing it",
+                tree::describe(container)
+            );
+            Ok(())
+        }
+    }
+}
+
+fn center(i3: &mut dyn I3Commands, container_id: usize) -> io::Result<()> {
+    let command = CommandBuilder::con_id(container_id).action(Action::MovePositionCenter);
+    i3.run_command(&command.build())
+}
+
+fn enable_fullscreen(i3: &mut dyn I3Commands, container_id: usize) -> io::Result<()> {
+    let command = CommandBuilder::con_id(container_id).action(Action::FullscreenEnable);
+    i3.run_command(&command.build())
+}
+
+fn is_fullscreen(node: &Node) -> bool {
+    node.fullscreen_mode != FullscreenMode::None
+}
+
+fn is_empty_workspace(node: &Node) -> bool {
+    node.floating_nodes.is_empty() && node.nodes.is_empty()
+}
@@ -0,0 +1,747 @@
+use super::*;
+use crate::{
+    criteria::exact,
+    event_loop::dispatch,
+    idle::{ACTIVE_TICK, IDLE_TICK},
+    mock::{self, MockI3},
+    WindowManager,
+};
+use i3_ipc::{
+    event::Event,
+    reply::{Marks, Output, Rect},
+};
+
+const DROPDOWN: usize = 10;
+const NOTES: usize = 11;
+const EDITOR: usize = 20;
+
+fn rule(class: &str) -> Rule {
+    Rule {
+        criteria: Criteria {
+            class: Some(exact(class)),
+            ..Criteria::default()
+        },
+        restore_fullscreen: false,
+        return_to_origin: false,
+        recenter: false,
+        geometry: None,
+        same_workspace_only: false,
+        hide_on_workspace_switch: false,
+        only_on_fullscreen: false,
+        keep_while_urgent: false,
+        delay: Duration::ZERO,
+        except: Vec::new(),
+        multi: Multi::LastFocused,
+    }
+}
+
+fn dropdown() -> Node {
+    mock::window(DROPDOWN, "dropdown", "Terminal")
+}
+
+fn notes() -> Node {
+    mock::window(NOTES, "notes", "Notes")
+}
+
+fn editor() -> Node {
+    mock::window(EDITOR, "editor", "notes.txt")
+}
+
+fn focus(window: Node) -> Event {
+    mock::window_event(WindowChange::Focus, window)
+}
+
+fn focus_workspace(name: &str, windows: Vec<Node>) -> Event {
+    mock::workspace_event(WorkspaceChange::Focus, mock::workspace(100, name, windows))
+}
+
+/// Feed `events` to `monitor` and return the commands it sent to i3.
+fn run(monitor: &mut FocusMonitor, events: Vec<Event>) -> Vec<String> {
+    let mut i3 = MockI3::default();
+    for event in events {
+        dispatch(monitor, &mut i3, &event).unwrap();
+    }
+    i3.take_commands()
+}
+
+#[test]
+fn hides_window_when_focus_moves_elsewhere() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let commands = run(&mut monitor, vec![focus(dropdown()), focus(editor())]);
+    assert_eq!(commands, ["[con_id=10] move scratchpad"]);
+}
+
+#[test]
+fn keeps_window_while_it_has_focus() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let commands = run(&mut monitor, vec![focus(dropdown()), focus(dropdown())]);
+    assert!(commands.is_empty());
+}
+
+#[test]
+fn ignores_windows_not_matching_any_rule() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let commands = run(&mut monitor, vec![focus(editor()), focus(notes())]);
+    assert!(commands.is_empty());
+}
+
+#[test]
+fn hides_window_when_switching_to_empty_workspace() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let events = vec![focus(dropdown()), focus_workspace("2", Vec::new())];
+    assert_eq!(run(&mut monitor, events), ["[con_id=10] move scratchpad"]);
+}
+
+#[test]
+fn waits_for_window_focus_when_switching_to_non_empty_workspace() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let events = vec![focus(dropdown()), focus_workspace("2", vec![editor()])];
+    assert!(run(&mut monitor, events).is_empty());
+}
+
+#[test]
+fn hides_window_only_once_when_then_switching_to_empty_workspace() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let events = vec![
+        focus(dropdown()),
+        focus(editor()),
+        focus_workspace("2", Vec::new()),
+    ];
+    assert_eq!(run(&mut monitor, events), ["[con_id=10] move scratchpad"]);
+}
+
+#[test]
+fn keeps_window_when_excepted_window_gets_focus() {
+    let mut rule = rule("dropdown");
+    rule.except.push(Criteria {
+        class: Some(exact("editor")),
+        ..Criteria::default()
+    });
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let commands = run(&mut monitor, vec![focus(dropdown()), focus(editor())]);
+    assert!(commands.is_empty());
+}
+
+#[test]
+fn hides_window_once_delay_expires() {
+    let mut rule = rule("dropdown");
+    rule.delay = Duration::from_secs(60);
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    assert!(run(&mut monitor, vec![focus(dropdown()), focus(editor())]).is_empty());
+    let deadline = monitor.next_deadline().expect("hiding should be scheduled");
+
+    let mut i3 = MockI3::default();
+    monitor.on_deadline(&mut i3, deadline).unwrap();
+    assert_eq!(i3.take_commands(), ["[con_id=10] move scratchpad"]);
+    assert_eq!(monitor.next_deadline(), None);
+}
+
+#[test]
+fn keeps_window_that_regains_focus_before_delay_expires() {
+    let mut rule = rule("dropdown");
+    rule.delay = Duration::from_secs(60);
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let events = vec![focus(dropdown()), focus(editor()), focus(dropdown())];
+    assert!(run(&mut monitor, events).is_empty());
+    assert_eq!(monitor.next_deadline(), None);
+}
+
+#[test]
+fn restores_fullscreen_when_shown_again() {
+    let mut rule = rule("dropdown");
+    rule.restore_fullscreen = true;
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let fullscreen = Node {
+        fullscreen_mode: FullscreenMode::Output,
+        ..dropdown()
+    };
+    let events = vec![focus(fullscreen), focus(editor()), focus(dropdown())];
+    assert_eq!(
+        run(&mut monitor, events),
+        [
+            "[con_id=10] move scratchpad",
+            "[con_id=10] fullscreen enable"
+        ]
+    );
+}
+
+#[test]
+fn tracks_each_rule_separately() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown"), rule("notes")], None);
+    let events = vec![focus(dropdown()), focus(notes()), focus(editor())];
+    assert_eq!(
+        run(&mut monitor, events),
+        ["[con_id=10] move scratchpad", "[con_id=11] move scratchpad"]
+    );
+}
+
+#[test]
+fn centers_window_shown_on_another_output() {
+    let mut rule = rule("dropdown");
+    rule.recenter = true;
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let on_output = |output: &str| Node {
+        output: Some(output.to_owned()),
+        ..dropdown()
+    };
+    let events = vec![
+        focus(on_output("DP-1")),
+        focus(editor()),
+        focus(on_output("DP-1")),
+        focus(editor()),
+        focus(on_output("HDMI-1")),
+    ];
+    assert_eq!(
+        run(&mut monitor, events),
+        [
+            "[con_id=10] move scratchpad",
+            "[con_id=10] move scratchpad",
+            "[con_id=10] move position center"
+        ]
+    );
+}
+
+#[test]
+fn resizes_window_relative_to_its_output_when_shown() {
+    let mut rule = rule("dropdown");
+    rule.geometry = Some("80%x50%".parse().unwrap());
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let mut i3 = MockI3::default();
+    i3.outputs = vec![Output {
+        name: "DP-1".to_owned(),
+        active: true,
+        primary: true,
+        current_workspace: Some("1".to_owned()),
+        rect: Rect {
+            x: 0,
+            y: 0,
+            width: 2560,
+            height: 1440,
+        },
+    }];
+    let shown = Node {
+        output: Some("DP-1".to_owned()),
+        ..dropdown()
+    };
+    for event in [focus(shown.clone()), focus(shown), focus(editor())] {
+        dispatch(&mut monitor, &mut i3, &event).unwrap();
+    }
+    assert_eq!(
+        i3.take_commands(),
+        [
+            "[con_id=10] resize set 2048 px 720 px, move position center",
+            "[con_id=10] move scratchpad"
+        ]
+    );
+}
+
+#[test]
+fn returns_window_to_the_workspace_it_was_first_seen_on() {
+    let mut rule = rule("dropdown");
+    rule.return_to_origin = true;
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let mut i3 = MockI3::new(mock::root(vec![
+        mock::workspace(100, "1", vec![editor()]),
+        mock::workspace(101, "music", vec![dropdown()]),
+    ]));
+    monitor.on_start(&mut i3).unwrap();
+    // The window is summoned to workspace 1, then loses focus there.
+    i3.tree = mock::root(vec![mock::workspace(100, "1", vec![editor(), dropdown()])]);
+    for event in [focus(dropdown()), focus(editor())] {
+        dispatch(&mut monitor, &mut i3, &event).unwrap();
+    }
+    assert_eq!(
+        i3.take_commands(),
+        ["[con_id=10] move container to workspace \"music\""]
+    );
+}
+
+#[test]
+fn only_counts_windows_actually_returned() {
+    let mut rule = rule("dropdown");
+    rule.return_to_origin = true;
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let mut i3 = MockI3::new(mock::root(vec![
+        mock::workspace(100, "1", vec![editor()]),
+        mock::workspace(101, "music", vec![dropdown()]),
+    ]));
+    monitor.on_start(&mut i3).unwrap();
+    i3.tree = mock::root(vec![mock::workspace(100, "1", vec![editor(), dropdown()])]);
+    i3.failing
+        .push("[con_id=10] move container to workspace \"music\"".to_owned());
+    for event in [focus(dropdown()), focus(editor())] {
+        dispatch(&mut monitor, &mut i3, &event).unwrap();
+    }
+    let status = monitor.on_control(&mut i3, "status").unwrap();
+    assert!(status.contains("(1 matches, 0 hidden)"), "{status}");
+}
+
+#[test]
+fn pauses_and_hides_on_request() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let mut i3 = MockI3::default();
+    monitor.on_control(&mut i3, "pause").unwrap();
+    for event in [focus(dropdown()), focus(editor())] {
+        dispatch(&mut monitor, &mut i3, &event).unwrap();
+    }
+    assert!(i3.take_commands().is_empty());
+
+    monitor.on_control(&mut i3, "resume").unwrap();
+    dispatch(&mut monitor, &mut i3, &focus(dropdown())).unwrap();
+    let status = monitor.on_control(&mut i3, "status").unwrap();
+    assert!(status.contains("rule 0: class=\"^dropdown$\" (2 matches, 0 hidden)"));
+    assert!(status.contains("visible: window 10 (\"dropdown\""));
+    monitor.on_control(&mut i3, "hide-now").unwrap();
+    assert_eq!(i3.take_commands(), ["[con_id=10] move scratchpad"]);
+    let status = monitor.on_control(&mut i3, "status").unwrap();
+    assert!(status.contains("(2 matches, 1 hidden)"));
+    assert!(status.contains("scratched: window 10 (\"dropdown\""));
+}
+
+#[test]
+fn tracks_window_focused_again_after_hiding_it_while_focused() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let mut i3 = MockI3::default();
+    dispatch(&mut monitor, &mut i3, &focus(dropdown())).unwrap();
+    monitor.on_control(&mut i3, "hide-now").unwrap();
+    // No other window is left to take focus, and showing the window again
+    // focuses it once more.
+    let events = vec![
+        mock::window_event(WindowChange::Move, dropdown()),
+        focus(dropdown()),
+        focus(editor()),
+    ];
+    run(&mut monitor, events);
+    let status = monitor.on_control(&mut i3, "status").unwrap();
+    assert!(status.contains("(2 matches, 2 hidden)"), "{status}");
+}
+
+#[test]
+fn toggle_binding_pauses_and_resumes() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let toggle = || mock::binding_event("nop back-to-scratch-toggle");
+    let paused = run(
+        &mut monitor,
+        vec![toggle(), focus(dropdown()), focus(editor())],
+    );
+    assert!(paused.is_empty());
+    let resumed = run(
+        &mut monitor,
+        vec![toggle(), focus(dropdown()), focus(editor())],
+    );
+    assert_eq!(resumed, ["[con_id=10] move scratchpad"]);
+}
+
+#[test]
+fn pauses_while_idle() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    monitor.set_pause_when_idle(IdleMonitor::new(Duration::from_secs(300)));
+    let idle = run(
+        &mut monitor,
+        vec![
+            mock::tick_event(IDLE_TICK),
+            focus(dropdown()),
+            focus(editor()),
+        ],
+    );
+    assert!(idle.is_empty());
+    let active = run(
+        &mut monitor,
+        vec![
+            mock::tick_event(ACTIVE_TICK),
+            focus(dropdown()),
+            focus(editor()),
+        ],
+    );
+    assert_eq!(active, ["[con_id=10] move scratchpad"]);
+}
+
+#[test]
+fn same_workspace_only_ignores_focus_on_other_workspaces() {
+    let mut rule = rule("dropdown");
+    rule.same_workspace_only = true;
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let mut i3 = MockI3::new(mock::root(vec![
+        mock::workspace(100, "1", vec![dropdown(), notes()]),
+        mock::workspace(101, "2", vec![editor()]),
+    ]));
+    for event in [focus(dropdown()), focus(editor()), focus(dropdown())] {
+        dispatch(&mut monitor, &mut i3, &event).unwrap();
+    }
+    assert!(i3.take_commands().is_empty());
+    dispatch(&mut monitor, &mut i3, &focus(notes())).unwrap();
+    assert_eq!(i3.take_commands(), ["[con_id=10] move scratchpad"]);
+}
+
+#[test]
+fn hides_window_when_switching_to_another_workspace() {
+    let mut rule = rule("dropdown");
+    rule.hide_on_workspace_switch = true;
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let mut i3 = MockI3::new(mock::root(vec![
+        mock::workspace(100, "1", vec![dropdown()]),
+        mock::workspace(101, "2", vec![editor()]),
+    ]));
+    dispatch(&mut monitor, &mut i3, &focus(dropdown())).unwrap();
+    let switch = mock::workspace_event(
+        WorkspaceChange::Focus,
+        mock::workspace(101, "2", vec![editor()]),
+    );
+    dispatch(&mut monitor, &mut i3, &switch).unwrap();
+    assert_eq!(i3.take_commands(), ["[con_id=10] move scratchpad"]);
+}
+
+#[test]
+fn tracks_window_once_its_title_matches() {
+    let mut rule = rule("spotify");
+    rule.criteria = Criteria {
+        title: Some(exact("Spotify")),
+        ..Criteria::default()
+    };
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let player = |title| Node {
+        focused: true,
+        ..mock::window(30, "spotify", title)
+    };
+    let commands = run(
+        &mut monitor,
+        vec![
+            focus(player("")),
+            mock::window_event(WindowChange::Title, player("Spotify")),
+            focus(editor()),
+        ],
+    );
+    assert_eq!(commands, ["[con_id=30] move scratchpad"]);
+}
+
+#[test]
+fn tracks_window_once_marked() {
+    let mut rule = rule("scratchterm");
+    rule.criteria = Criteria {
+        mark: Some(exact("scratchterm")),
+        ..Criteria::default()
+    };
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let marked = Node {
+        focused: true,
+        marks: Some(Marks(vec!["scratchterm".to_owned()])),
+        ..editor()
+    };
+    let commands = run(
+        &mut monitor,
+        vec![
+            focus(editor()),
+            mock::window_event(WindowChange::Mark, marked),
+            focus(notes()),
+        ],
+    );
+    assert_eq!(commands, ["[con_id=20] move scratchpad"]);
+}
+
+#[test]
+fn unhides_windows_it_hid_on_exit() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    monitor.set_unhide_on_exit(true);
+    run(&mut monitor, vec![focus(dropdown()), focus(editor())]);
+    let mut i3 = MockI3::new(mock::root(vec![
+        mock::workspace(1, tree::SCRATCHPAD_WORKSPACE, vec![dropdown()]),
+        mock::workspace(2, "1", vec![editor()]),
+    ]));
+    monitor.on_exit(&mut i3).unwrap();
+    assert_eq!(
+        i3.take_commands(),
+        ["[con_id=10] scratchpad show, floating disable"]
+    );
+}
+
+#[test]
+fn only_hides_window_when_fullscreen_window_gets_focus() {
+    let mut rule = rule("dropdown");
+    rule.only_on_fullscreen = true;
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let fullscreen_editor = Node {
+        focused: true,
+        fullscreen_mode: FullscreenMode::Output,
+        ..editor()
+    };
+    let commands = run(&mut monitor, vec![focus(dropdown()), focus(notes())]);
+    assert!(commands.is_empty());
+    let commands = run(
+        &mut monitor,
+        vec![
+            focus(editor()),
+            mock::window_event(WindowChange::FullscreenMode, fullscreen_editor),
+        ],
+    );
+    assert_eq!(commands, ["[con_id=10] move scratchpad"]);
+}
+
+#[test]
+fn keeps_urgent_window_until_urgency_clears() {
+    let mut rule = rule("dropdown");
+    rule.keep_while_urgent = true;
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let urgent = Node {
+        urgent: true,
+        ..dropdown()
+    };
+    let commands = run(
+        &mut monitor,
+        vec![
+            focus(dropdown()),
+            mock::window_event(WindowChange::Urgent, urgent),
+            focus(editor()),
+        ],
+    );
+    assert!(commands.is_empty());
+    assert_eq!(monitor.next_deadline(), None);
+    let commands = run(
+        &mut monitor,
+        vec![mock::window_event(WindowChange::Urgent, dropdown())],
+    );
+    assert_eq!(commands, ["[con_id=10] move scratchpad"]);
+}
+
+#[test]
+fn adopts_visible_windows_on_start() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown"), rule("notes")], None);
+    let focused_editor = Node {
+        focused: true,
+        ..editor()
+    };
+    let mut i3 = MockI3::new(mock::root(vec![mock::workspace(
+        100,
+        "1",
+        vec![dropdown(), notes(), focused_editor],
+    )]));
+    monitor.on_start(&mut i3).unwrap();
+    assert!(i3.take_commands().is_empty());
+    dispatch(&mut monitor, &mut i3, &focus(editor())).unwrap();
+    let mut commands = i3.take_commands();
+    commands.sort();
+    assert_eq!(
+        commands,
+        ["[con_id=10] move scratchpad", "[con_id=11] move scratchpad"]
+    );
+}
+
+#[test]
+fn hides_unfocused_windows_on_start() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown"), rule("notes")], None);
+    monitor.set_hide_on_start(true);
+    let focused_notes = Node {
+        focused: true,
+        ..notes()
+    };
+    let mut i3 = MockI3::new(mock::root(vec![
+        mock::workspace(100, "1", vec![dropdown(), focused_notes]),
+        mock::workspace(
+            101,
+            "2",
+            vec![Node {
+                id: 12,
+                ..dropdown()
+            }],
+        ),
+    ]));
+    monitor.on_start(&mut i3).unwrap();
+    // Only on the focused workspace.
+    assert_eq!(i3.take_commands(), ["[con_id=10] move scratchpad"]);
+}
+
+#[test]
+fn forgets_tracked_window_once_closed() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let events = vec![
+        focus(dropdown()),
+        mock::window_event(WindowChange::Close, dropdown()),
+        focus(editor()),
+    ];
+    assert!(run(&mut monitor, events).is_empty());
+}
+
+#[test]
+fn cancels_pending_hide_of_closed_window() {
+    let mut rule = rule("dropdown");
+    rule.delay = Duration::from_secs(60);
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let events = vec![
+        focus(dropdown()),
+        focus(editor()),
+        mock::window_event(WindowChange::Close, dropdown()),
+    ];
+    assert!(run(&mut monitor, events).is_empty());
+    assert_eq!(monitor.next_deadline(), None);
+}
+
+#[test]
+fn tracks_another_window_of_the_rule_after_close() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let other = mock::window(12, "dropdown", "Another terminal");
+    let events = vec![
+        focus(dropdown()),
+        mock::window_event(WindowChange::Close, dropdown()),
+        focus(other),
+        focus(editor()),
+    ];
+    assert_eq!(run(&mut monitor, events), ["[con_id=12] move scratchpad"]);
+}
+
+#[test]
+fn only_manages_the_newest_window() {
+    let mut rule = rule("dropdown");
+    rule.multi = Multi::Newest;
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let newer = mock::window(12, "dropdown", "Another terminal");
+    let mut i3 = MockI3::new(mock::root(vec![mock::workspace(
+        100,
+        "1",
+        vec![dropdown(), newer.clone(), editor()],
+    )]));
+    for event in [focus(dropdown()), focus(editor())] {
+        dispatch(&mut monitor, &mut i3, &event).unwrap();
+    }
+    assert!(i3.take_commands().is_empty());
+    for event in [focus(newer), focus(editor())] {
+        dispatch(&mut monitor, &mut i3, &event).unwrap();
+    }
+    assert_eq!(i3.take_commands(), ["[con_id=12] move scratchpad"]);
+}
+
+#[test]
+fn hides_all_windows_of_the_rule_together() {
+    let mut rule = rule("dropdown");
+    rule.multi = Multi::All;
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let other = mock::window(12, "dropdown", "Another terminal");
+    let commands = run(&mut monitor, vec![focus(dropdown()), focus(other)]);
+    assert!(commands.is_empty());
+    let mut commands = run(&mut monitor, vec![focus(editor())]);
+    commands.sort();
+    assert_eq!(
+        commands,
+        ["[con_id=10] move scratchpad", "[con_id=12] move scratchpad"]
+    );
+}
+
+#[test]
+fn tracks_a_window_per_workspace() {
+    let mut rule = rule("dropdown");
+    rule.multi = Multi::PerWorkspace;
+    rule.same_workspace_only = true;
+    let mut monitor = FocusMonitor::new(vec![rule], None);
+    let other = mock::window(12, "dropdown", "Another terminal");
+    let mut i3 = MockI3::new(mock::root(vec![
+        mock::workspace(100, "1", vec![dropdown(), editor()]),
+        mock::workspace(101, "2", vec![other.clone(), notes()]),
+    ]));
+    for event in [focus(dropdown()), focus(other), focus(notes())] {
+        dispatch(&mut monitor, &mut i3, &event).unwrap();
+    }
+    assert_eq!(i3.take_commands(), ["[con_id=12] move scratchpad"]);
+    dispatch(&mut monitor, &mut i3, &focus(editor())).unwrap();
+    assert_eq!(i3.take_commands(), ["[con_id=10] move scratchpad"]);
+}
+
+#[test]
+fn replays_a_recording() {
+    use crate::record::{replay_commands, Entry, RecordedEvent};
+    let event = |event: Event| Entry::Event(RecordedEvent::new(&event).unwrap());
+    let tree = mock::root(vec![mock::workspace(100, "1", vec![editor()])]);
+    let recording: Vec<String> = [
+        Entry::Start,
+        Entry::WindowManager(WindowManager::I3),
+        Entry::Tree(Box::new(tree)),
+        event(focus(dropdown())),
+        event(focus(editor())),
+    ]
+    .iter()
+    .map(|entry| serde_json::to_string(entry).unwrap())
+    .collect();
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let commands = replay_commands(recording.join("\n").as_bytes(), &mut monitor).unwrap();
+    assert_eq!(commands, [(5, "[con_id=10] move scratchpad".to_owned())]);
+}
+
+#[test]
+fn replays_a_failed_command() {
+    use crate::record::{replay_commands, Entry, RecordedEvent};
+    let event = |event: Event| Entry::Event(RecordedEvent::new(&event).unwrap());
+    let tree = mock::root(vec![mock::workspace(100, "1", vec![editor()])]);
+    // i3 restarted without the event reaching us, giving the window a new id.
+    let restarted = mock::window(30, "dropdown", "Terminal");
+    let restarted = mock::root(vec![mock::workspace(100, "1", vec![restarted, editor()])]);
+    let recording: Vec<String> = [
+        Entry::Start,
+        Entry::WindowManager(WindowManager::I3),
+        Entry::Tree(Box::new(tree)),
+        event(focus(dropdown())),
+        event(focus(editor())),
+        Entry::CommandSucceeded(false),
+        Entry::Tree(Box::new(restarted)),
+        Entry::CommandSucceeded(true),
+    ]
+    .iter()
+    .map(|entry| serde_json::to_string(entry).unwrap())
+    .collect();
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    let commands = replay_commands(recording.join("\n").as_bytes(), &mut monitor).unwrap();
+    assert_eq!(
+        commands,
+        [
+            (5, "[con_id=10] move scratchpad".to_owned()),
+            (5, "[con_id=30] move scratchpad".to_owned())
+        ]
+    );
+}
+
+#[test]
+fn follows_windows_whose_id_changed_when_i3_restarted() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    run(&mut monitor, vec![focus(dropdown())]);
+    let restarted = mock::window(30, "dropdown", "Terminal");
+    let mut i3 = MockI3::new(mock::root(vec![mock::workspace(
+        100,
+        "1",
+        vec![restarted, editor()],
+    )]));
+    monitor.on_reconnect(&mut i3).unwrap();
+    let commands = run(&mut monitor, vec![focus(editor())]);
+    assert_eq!(commands, ["[con_id=30] move scratchpad"]);
+}
+
+#[test]
+fn forgets_windows_it_cannot_tell_apart_when_i3_restarted() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    run(&mut monitor, vec![focus(dropdown())]);
+    let restarted = |id, title| mock::window(id, "dropdown", title);
+    let mut i3 = MockI3::new(mock::root(vec![mock::workspace(
+        100,
+        "1",
+        vec![restarted(30, "Shell"), restarted(31, "Logs"), editor()],
+    )]));
+    monitor.on_reconnect(&mut i3).unwrap();
+    // Neither the old id nor a guess is used.
+    assert!(run(&mut monitor, vec![focus(editor())]).is_empty());
+}
+
+#[test]
+fn looks_windows_up_again_when_hiding_them_fails() {
+    let mut monitor = FocusMonitor::new(vec![rule("dropdown")], None);
+    run(&mut monitor, vec![focus(dropdown())]);
+    // i3 restarted without the event reaching us, giving the window a new id.
+    let restarted = mock::window(30, "dropdown", "Terminal");
+    let mut i3 = MockI3::new(mock::root(vec![mock::workspace(
+        100,
+        "1",
+        vec![restarted, editor()],
+    )]));
+    i3.failing.push("[con_id=10] move scratchpad".to_owned());
+    dispatch(&mut monitor, &mut i3, &focus(editor())).unwrap();
+    assert_eq!(
+        i3.take_commands(),
+        ["[con_id=10] move scratchpad", "[con_id=30] move scratchpad"]
+    );
+    let status = monitor.on_control(&mut i3, "status").unwrap();
+    assert!(status.contains("scratched: window 30"), "{status}");
+}
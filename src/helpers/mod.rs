@@ -0,0 +1,9 @@
+//! The helpers behind the daemons of this crate, so that they can be run on
+//! their own or together by `i3-helpers daemon`.
+
+pub mod autotiling;
+pub mod back_to_scratch;
+pub mod mode_timeout;
+pub mod scratchpad_manager;
+pub mod swallow;
+pub mod workspace_names;
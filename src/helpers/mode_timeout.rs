@@ -0,0 +1,133 @@
+//! Leaving binding modes left unused, as done by `i3-mode-timeout` and by
+//! `i3-chords daemon` for the modes of unfinished chords.
+
+use crate::{command::quote, Helper, I3Commands};
+use i3_ipc::event::{BindingData, Event, Subscribe};
+use log::info;
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+const DEFAULT_MODE: &str = "default";
+
+/// Switches back to the default mode once a mode has been active for a while
+/// without any of its bindings being pressed.
+pub struct ModeTimeout {
+    timeout: Duration,
+    /// The modes to leave, all of them if empty.
+    modes: Vec<String>,
+    /// The mode to leave, and when.
+    active: Option<(String, Instant)>,
+}
+
+impl ModeTimeout {
+    /// Leave `modes`, or all modes if empty, once unused for `timeout`.
+    pub fn new(timeout: Duration, modes: Vec<String>) -> Self {
+        Self {
+            timeout,
+            modes,
+            active: None,
+        }
+    }
+}
+
+impl Helper for ModeTimeout {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Mode, Subscribe::Binding]
+    }
+
+    fn on_event(&mut self, _i3: &mut dyn I3Commands, event: &Event) -> io::Result<()> {
+        let Event::Mode(event) = event else {
+            return Ok(());
+        };
+        let mode = &event.change;
+        let watched = self.modes.is_empty() || self.modes.contains(mode);
+        self.active = match mode.as_str() {
+            DEFAULT_MODE => None,
+            _ if watched => Some((mode.clone(), Instant::now() + self.timeout)),
+            _ => None,
+        };
+        Ok(())
+    }
+
+    fn on_binding(&mut self, _i3: &mut dyn I3Commands, _event: &BindingData) -> io::Result<()> {
+        // Bindings switching modes are followed by a mode event, which takes
+        // precedence.
+        if let Some((_, at)) = &mut self.active {
+            *at = Instant::now() + self.timeout;
+        }
+        Ok(())
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.active.as_ref().map(|(_, at)| *at)
+    }
+
+    fn on_deadline(&mut self, i3: &mut dyn I3Commands, _now: Instant) -> io::Result<()> {
+        let Some((mode, _)) = self.active.take() else {
+            return Ok(());
+        };
+        info!("leaving mode {mode:?}, unused for {:?}", self.timeout);
+        i3.run_command(&format!("mode {}", quote(DEFAULT_MODE)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event_loop::dispatch,
+        mock::{self, MockI3},
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn resize_only() -> ModeTimeout {
+        ModeTimeout::new(TIMEOUT, vec!["resize".to_owned()])
+    }
+
+    #[test]
+    fn leaves_watched_modes_at_the_deadline() {
+        let mut mode_timeout = resize_only();
+        let mut i3 = MockI3::default();
+        let before = Instant::now();
+        dispatch(&mut mode_timeout, &mut i3, &mock::mode_event("resize")).unwrap();
+        let deadline = mode_timeout.next_deadline().unwrap();
+        assert!(deadline >= before + TIMEOUT);
+        mode_timeout.on_deadline(&mut i3, deadline).unwrap();
+        assert_eq!(i3.take_commands(), [r#"mode "default""#]);
+        assert_eq!(mode_timeout.next_deadline(), None);
+    }
+
+    #[test]
+    fn bindings_restart_the_timer() {
+        let mut mode_timeout = resize_only();
+        let mut i3 = MockI3::default();
+        // The mode has been active for a while, about to time out.
+        let before = Instant::now();
+        mode_timeout.active = Some(("resize".to_owned(), before));
+        let binding = mock::binding_event("resize grow width 10 px");
+        dispatch(&mut mode_timeout, &mut i3, &binding).unwrap();
+        assert!(mode_timeout.next_deadline().unwrap() >= before + TIMEOUT);
+        assert!(i3.take_commands().is_empty());
+    }
+
+    #[test]
+    fn ignores_the_default_and_unwatched_modes() {
+        let mut mode_timeout = resize_only();
+        let mut i3 = MockI3::default();
+        dispatch(&mut mode_timeout, &mut i3, &mock::mode_event("resize")).unwrap();
+        dispatch(&mut mode_timeout, &mut i3, &mock::mode_event("default")).unwrap();
+        assert_eq!(mode_timeout.next_deadline(), None);
+        dispatch(&mut mode_timeout, &mut i3, &mock::mode_event("system")).unwrap();
+        assert_eq!(mode_timeout.next_deadline(), None);
+        let binding = mock::binding_event("exec i3lock");
+        dispatch(&mut mode_timeout, &mut i3, &binding).unwrap();
+        assert_eq!(mode_timeout.next_deadline(), None);
+
+        let mut all_modes = ModeTimeout::new(TIMEOUT, Vec::new());
+        dispatch(&mut all_modes, &mut i3, &mock::mode_event("system")).unwrap();
+        assert!(all_modes.next_deadline().is_some());
+    }
+}
@@ -0,0 +1,138 @@
+//! Moving the windows of named scratchpads to the scratchpad as soon as they
+//! are created, as done by `i3-scratchpad-manager daemon`.
+//!
+//! `i3-scratchpad-manager show` and `toggle` wait for the windows they launch
+//! and show them themselves. They announce each launch with a tick event, see
+//! [`launch_tick`], for the daemon to leave the window to them: showing a
+//! window twice hides it again.
+
+use crate::{
+    command::{Action, CommandBuilder},
+    criteria::Criteria,
+    Helper, I3Commands,
+};
+use i3_ipc::event::{Subscribe, TickData, WindowChange, WindowData};
+use std::{
+    collections::HashMap,
+    io,
+    time::{Duration, Instant},
+};
+
+const LAUNCH_TICK_PREFIX: &str = "scratchpad-manager:launch:";
+
+/// The payload of the tick event announcing the launch of the window of the
+/// scratchpad called `name`.
+pub fn launch_tick(name: &str) -> String {
+    format!("{LAUNCH_TICK_PREFIX}{name}")
+}
+
+/// Moves new scratchpad windows to the scratchpad, and shows them right away
+/// since they were most likely launched to be used.
+pub struct ScratchpadDaemon {
+    /// The criteria of the window of each scratchpad, by name.
+    scratchpads: Vec<(String, Criteria)>,
+    /// The scratchpads whose window is being launched by another process,
+    /// which shows it, and until when to leave it alone.
+    launching: HashMap<String, Instant>,
+    /// How long launched windows take to appear at most.
+    timeout: Duration,
+}
+
+impl ScratchpadDaemon {
+    pub fn new(scratchpads: Vec<(String, Criteria)>, timeout: Duration) -> Self {
+        Self {
+            scratchpads,
+            launching: HashMap::new(),
+            timeout,
+        }
+    }
+}
+
+impl Helper for ScratchpadDaemon {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window, Subscribe::Tick]
+    }
+
+    fn on_tick(&mut self, _i3: &mut dyn I3Commands, event: &TickData) -> io::Result<()> {
+        if let Some(name) = event.payload.strip_prefix(LAUNCH_TICK_PREFIX) {
+            let until = Instant::now() + self.timeout;
+            self.launching.insert(name.to_owned(), until);
+        }
+        Ok(())
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        let window = &event.container;
+        if event.change != WindowChange::New {
+            return Ok(());
+        }
+        let Some((name, _)) = self
+            .scratchpads
+            .iter()
+            .find(|(_, criteria)| criteria.matches(window))
+        else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        self.launching.retain(|_, until| *until > now);
+        if self.launching.remove(name).is_some() {
+            log::debug!("leaving window {} to the process launching it", window.id);
+            return Ok(());
+        }
+        let command = CommandBuilder::con_id(window.id)
+            .action(Action::MoveScratchpad)
+            .action(Action::ScratchpadShow);
+        i3.run_command(&command.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        criteria::exact,
+        event_loop::dispatch,
+        mock::{self, MockI3},
+    };
+
+    fn daemon() -> ScratchpadDaemon {
+        let criteria = Criteria {
+            class: Some(exact("dropdown")),
+            ..Criteria::default()
+        };
+        ScratchpadDaemon::new(
+            vec![("terminal".to_owned(), criteria)],
+            Duration::from_secs(10),
+        )
+    }
+
+    fn new_window(class: &str) -> i3_ipc::event::Event {
+        mock::window_event(WindowChange::New, mock::window(10, class, "Terminal"))
+    }
+
+    #[test]
+    fn shows_new_scratchpad_windows() {
+        let mut daemon = daemon();
+        let mut i3 = MockI3::default();
+        dispatch(&mut daemon, &mut i3, &new_window("editor")).unwrap();
+        assert!(i3.take_commands().is_empty());
+        dispatch(&mut daemon, &mut i3, &new_window("dropdown")).unwrap();
+        assert_eq!(
+            i3.take_commands(),
+            ["[con_id=10] move scratchpad, scratchpad show"]
+        );
+    }
+
+    #[test]
+    fn leaves_launched_windows_to_the_launcher() {
+        let mut daemon = daemon();
+        let mut i3 = MockI3::default();
+        let tick = mock::tick_event(&launch_tick("terminal"));
+        dispatch(&mut daemon, &mut i3, &tick).unwrap();
+        dispatch(&mut daemon, &mut i3, &new_window("dropdown")).unwrap();
+        assert!(i3.take_commands().is_empty());
+        // Only the launched window, not the next one.
+        dispatch(&mut daemon, &mut i3, &new_window("dropdown")).unwrap();
+        assert_eq!(i3.take_commands().len(), 1);
+    }
+}
@@ -0,0 +1,297 @@
+//! Terminals swallowing the graphical programs they launch, as done by
+//! `i3-swallow`.
+//!
+//! Windows are related to processes through their `_NET_WM_PID` property,
+//! and processes to their parents through `/proc`, see [`Processes`].
+
+use crate::{
+    command::{Action, CommandBuilder},
+    criteria::Criteria,
+    tree, Helper, I3Commands,
+};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData},
+    reply::Node,
+};
+use log::{debug, warn};
+use std::{collections::HashMap, fs, io};
+
+type NodeId = usize;
+pub type Pid = u32;
+
+/// Tells which process owns a window, and which process launched another.
+pub trait Processes {
+    /// The PID of the process owning X11 window `window`, unless its client
+    /// doesn't tell.
+    fn window_pid(&self, window: usize) -> io::Result<Option<Pid>>;
+
+    /// The parent of the process with the given PID, read from `/proc`.
+    fn parent_pid(&self, pid: Pid) -> Option<Pid> {
+        let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+        parse_parent_pid(&stat)
+    }
+}
+
+#[cfg(feature = "x11")]
+impl Processes for crate::x11::X11 {
+    fn window_pid(&self, window: usize) -> io::Result<Option<Pid>> {
+        crate::x11::X11::window_pid(self, window as u32)
+    }
+}
+
+/// The parent PID in `stat`, the contents of `/proc/PID/stat`.
+fn parse_parent_pid(stat: &str) -> Option<Pid> {
+    // The format is `pid (comm) state ppid ...`, where `comm` may contain
+    // spaces and parentheses, so we start parsing after the last `)`.
+    let (_, fields) = stat.rsplit_once(')')?;
+    let ppid = fields.split_whitespace().nth(1)?.parse().ok()?;
+    // PID 0 is the parent of init, not an actual process.
+    (ppid != 0).then_some(ppid)
+}
+
+/// Hides a terminal in the scratchpad when a window of a process it launched
+/// appears, putting the new window in its place, and brings the terminal back
+/// once the window is closed.
+pub struct Swallow<P> {
+    processes: P,
+    terminals: Vec<Criteria>,
+    except: Vec<Criteria>,
+    /// The windows that may swallow a terminal, any window if empty.
+    only: Criteria,
+    /// The PID of each terminal window seen so far, read once.
+    terminal_pids: HashMap<NodeId, Option<Pid>>,
+    /// Terminals hidden in the scratchpad, by the window that swallowed them.
+    swallowed: HashMap<NodeId, NodeId>,
+}
+
+impl<P: Processes> Swallow<P> {
+    /// Let the windows matching `only` but none of `except` swallow the
+    /// windows matching any of `terminals`.
+    pub fn new(
+        processes: P,
+        terminals: Vec<Criteria>,
+        except: Vec<Criteria>,
+        only: Criteria,
+    ) -> Self {
+        Self {
+            processes,
+            terminals,
+            except,
+            only,
+            terminal_pids: HashMap::new(),
+            swallowed: HashMap::new(),
+        }
+    }
+
+    fn swallow(&mut self, i3: &mut dyn I3Commands, window: &Node) -> io::Result<()> {
+        if self.is_terminal(window)
+            || !self.only.matches(window)
+            || self.except.iter().any(|except| except.matches(window))
+        {
+            return Ok(());
+        }
+        let Some(pid) = self.window_pid(window) else {
+            return Ok(());
+        };
+        let tree = i3.get_tree()?;
+        let mut terminals: HashMap<Pid, NodeId> = HashMap::new();
+        for node in tree::windows(&tree) {
+            if !self.is_terminal(node) || tree::is_hidden_in_scratchpad(&tree, node.id) {
+                continue;
+            }
+            let terminal_pid = match self.terminal_pids.get(&node.id) {
+                Some(&terminal_pid) => terminal_pid,
+                None => {
+                    let terminal_pid = self.window_pid(node);
+                    self.terminal_pids.insert(node.id, terminal_pid);
+                    terminal_pid
+                }
+            };
+            if let Some(terminal_pid) = terminal_pid {
+                terminals.insert(terminal_pid, node.id);
+            }
+        }
+        let mut ancestors = std::iter::successors(self.processes.parent_pid(pid), |&pid| {
+            self.processes.parent_pid(pid)
+        });
+        let Some(terminal) = ancestors.find_map(|pid| terminals.get(&pid)) else {
+            return Ok(());
+        };
+        let terminal = *terminal;
+        debug!("{} swallows terminal {terminal}", tree::describe(window));
+        // Take the place of the terminal in the layout before hiding it.
+        let swap = CommandBuilder::con_id(window.id).action(Action::Swap(terminal));
+        if !i3.run_command_checked(&swap.build())? {
+            warn!(
+                "cannot swap {} with terminal {terminal}",
+                tree::describe(window)
+            );
+            return Ok(());
+        }
+        let hide = CommandBuilder::con_id(terminal).action(Action::MoveScratchpad);
+        if i3.run_command_checked(&hide.build())? {
+            self.swallowed.insert(window.id, terminal);
+        }
+        Ok(())
+    }
+
+    fn restore(&mut self, i3: &mut dyn I3Commands, window: NodeId) -> io::Result<()> {
+        self.terminal_pids.remove(&window);
+        match self.swallowed.remove(&window) {
+            Some(terminal) => {
+                let command = CommandBuilder::con_id(terminal)
+                    .action(Action::ScratchpadShow)
+                    .action(Action::FloatingDisable)
+                    .action(Action::Focus);
+                i3.run_command(&command.build())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// The PID of the process owning `window`, warning when it can't be told.
+    fn window_pid(&self, window: &Node) -> Option<Pid> {
+        let pid = match window.window {
+            Some(xid) => self.processes.window_pid(xid),
+            None => Ok(None),
+        };
+        match pid {
+            Ok(Some(pid)) => Some(pid),
+            Ok(None) => {
+                warn!("cannot tell which process owns {}", tree::describe(window));
+                None
+            }
+            Err(err) => {
+                warn!("cannot read the PID of {}: {err}", tree::describe(window));
+                None
+            }
+        }
+    }
+
+    fn is_terminal(&self, window: &Node) -> bool {
+        self.terminals
+            .iter()
+            .any(|terminal| terminal.matches(window))
+    }
+}
+
+impl<P: Processes> Helper for Swallow<P> {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window]
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        match event.change {
+            WindowChange::New => self.swallow(i3, &event.container),
+            WindowChange::Close => self.restore(i3, event.container.id),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        criteria::exact,
+        event_loop::dispatch,
+        mock::{self, MockI3},
+    };
+
+    const TERMINAL: usize = 1;
+    const OTHER_TERMINAL: usize = 2;
+    const VIEWER: usize = 10;
+
+    /// Windows owned by processes with the same PID as their id, with the
+    /// viewer launched by a shell run in the first terminal.
+    struct FakeProcesses;
+
+    impl Processes for FakeProcesses {
+        fn window_pid(&self, window: usize) -> io::Result<Option<Pid>> {
+            Ok(Some(window as Pid))
+        }
+
+        fn parent_pid(&self, pid: Pid) -> Option<Pid> {
+            match pid as usize {
+                VIEWER => Some(100),
+                100 => Some(TERMINAL as Pid),
+                _ => None,
+            }
+        }
+    }
+
+    fn swallow() -> Swallow<FakeProcesses> {
+        let terminal = Criteria {
+            class: Some(exact("Alacritty")),
+            ..Criteria::default()
+        };
+        Swallow::new(
+            FakeProcesses,
+            vec![terminal],
+            Vec::new(),
+            Criteria::default(),
+        )
+    }
+
+    fn terminal(id: usize) -> Node {
+        mock::window(id, "Alacritty", "zsh")
+    }
+
+    fn viewer() -> Node {
+        mock::window(VIEWER, "Zathura", "paper.pdf")
+    }
+
+    fn i3() -> MockI3 {
+        MockI3::new(mock::root(vec![mock::workspace(
+            100,
+            "1",
+            vec![terminal(OTHER_TERMINAL), terminal(TERMINAL), viewer()],
+        )]))
+    }
+
+    #[test]
+    fn swallows_and_restores_the_launching_terminal() {
+        let mut swallow = swallow();
+        let mut i3 = i3();
+        let new = mock::window_event(WindowChange::New, viewer());
+        dispatch(&mut swallow, &mut i3, &new).unwrap();
+        assert_eq!(
+            i3.take_commands(),
+            [
+                "[con_id=10] swap container with con_id 1",
+                "[con_id=1] move scratchpad"
+            ]
+        );
+        let close = mock::window_event(WindowChange::Close, viewer());
+        dispatch(&mut swallow, &mut i3, &close).unwrap();
+        assert_eq!(
+            i3.take_commands(),
+            ["[con_id=1] scratchpad show, floating disable, focus"]
+        );
+    }
+
+    #[test]
+    fn keeps_the_terminal_when_the_swap_fails() {
+        let mut swallow = swallow();
+        let mut i3 = i3();
+        i3.failing = vec!["[con_id=10] swap container with con_id 1".to_owned()];
+        let new = mock::window_event(WindowChange::New, viewer());
+        dispatch(&mut swallow, &mut i3, &new).unwrap();
+        assert_eq!(
+            i3.take_commands(),
+            ["[con_id=10] swap container with con_id 1"]
+        );
+        let close = mock::window_event(WindowChange::Close, viewer());
+        dispatch(&mut swallow, &mut i3, &close).unwrap();
+        assert!(i3.take_commands().is_empty());
+    }
+
+    #[test]
+    fn parses_the_parent_pid() {
+        assert_eq!(parse_parent_pid("42 (zsh) S 7 42 42 0"), Some(7));
+        // The command name may contain spaces and parentheses.
+        assert_eq!(parse_parent_pid("42 (a) b (c)) S 7 42 42 0"), Some(7));
+        assert_eq!(parse_parent_pid("1 (init) S 0 1 1 0"), None);
+        assert_eq!(parse_parent_pid("42 (zsh"), None);
+    }
+}
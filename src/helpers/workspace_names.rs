@@ -0,0 +1,115 @@
+//! Naming workspaces after the windows they contain, as done by
+//! `i3-workspace-names`.
+
+use crate::{command::quote, tree, Helper, I3Commands};
+use i3_ipc::{
+    event::{Subscribe, WindowChange, WindowData, WorkspaceChange, WorkspaceData},
+    reply::Node,
+};
+use serde::Deserialize;
+use std::{collections::HashMap, io};
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    icons: HashMap<String, String>,
+    /// Icon for windows whose class is not in `icons`.
+    default_icon: Option<String>,
+}
+
+pub struct WorkspaceNames {
+    /// Icons by lowercase window class.
+    icons: HashMap<String, String>,
+    default_icon: Option<String>,
+}
+
+impl Helper for WorkspaceNames {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        vec![Subscribe::Window, Subscribe::Workspace]
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        self.rename_workspaces(i3)
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        match event.change {
+            WindowChange::New | WindowChange::Close | WindowChange::Move => {
+                self.rename_workspaces(i3)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn on_workspace(&mut self, i3: &mut dyn I3Commands, event: &WorkspaceData) -> io::Result<()> {
+        match event.change {
+            WorkspaceChange::Init | WorkspaceChange::Reload => self.rename_workspaces(i3),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl WorkspaceNames {
+    pub fn new(config: Config) -> Self {
+        let icons = config
+            .icons
+            .into_iter()
+            .map(|(class, icon)| (class.to_lowercase(), icon))
+            .collect();
+        Self {
+            icons,
+            default_icon: config.default_icon,
+        }
+    }
+
+    /// Rename the workspaces whose name doesn't reflect their contents anymore.
+    fn rename_workspaces(&self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let tree = i3.get_tree()?;
+        for workspace in tree::workspaces(&tree) {
+            let (Some(name), Some(num)) = (&workspace.name, workspace.num) else {
+                continue;
+            };
+            // Workspaces without a number get -1, and we have nothing to keep
+            // in them.
+            if num < 0 {
+                continue;
+            }
+            let new_name = self.workspace_name(num, workspace);
+            if *name != new_name {
+                let command = format!("rename workspace {} to {}", quote(name), quote(&new_name));
+                i3.run_command(&command)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn workspace_name(&self, num: i32, workspace: &Node) -> String {
+        let mut icons: Vec<&str> = Vec::new();
+        for window in tree::windows(workspace) {
+            if let Some(icon) = self.icon(window) {
+                if !icons.contains(&icon) {
+                    icons.push(icon);
+                }
+            }
+        }
+        if icons.is_empty() {
+            num.to_string()
+        } else {
+            format!("{num}: {}", icons.join(" "))
+        }
+    }
+
+    fn icon<'a>(&'a self, window: &'a Node) -> Option<&'a str> {
+        let class = window
+            .window_properties
+            .as_ref()
+            .and_then(|props| props.class.as_ref())
+            .or(window.app_id.as_ref())?;
+        self.icons
+            .get(&class.to_lowercase())
+            .or(self.default_icon.as_ref())
+            .map(String::as_str)
+            .or(Some(class))
+    }
+}
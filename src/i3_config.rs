@@ -0,0 +1,546 @@
+//! Parsing of the i3 configuration, as returned by
+//! [`I3Commands::get_config`], into the bindings of each mode, the `assign`
+//! directives, the `for_window` rules and the few settings helpers depend on.
+//!
+//! Only what helpers need is parsed: anything else is skipped, and so are
+//! lines that don't make sense, which i3 would have reported already.
+
+use crate::{criteria::Criteria, I3Commands};
+use regex::Regex;
+use std::io;
+
+/// What helpers care about in an i3 configuration.
+#[derive(Debug, Default)]
+pub struct I3Config {
+    /// The modes, starting with `default`, which holds the bindings outside
+    /// of any `mode` block.
+    pub modes: Vec<Mode>,
+    pub assigns: Vec<Assign>,
+    pub for_windows: Vec<ForWindow>,
+    /// The `focus_follows_mouse` setting, if set, i3 defaulting to `yes`.
+    pub focus_follows_mouse: Option<bool>,
+}
+
+#[derive(Debug)]
+pub struct Mode {
+    pub name: String,
+    /// Whether the name of the mode uses Pango markup.
+    pub pango_markup: bool,
+    pub bindings: Vec<Binding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub kind: BindingKind,
+    /// Options like `--release` or `--whole-window`.
+    pub options: Vec<String>,
+    /// The key combination, e.g. `Mod4+Return`, with variables replaced.
+    pub key: String,
+    /// The command run by the binding, with variables replaced.
+    pub command: String,
+    /// The line of the configuration the binding is defined on, from 1.
+    pub line: usize,
+}
+
+/// An `assign` directive, moving the windows matching `criteria` to a
+/// workspace or output when they open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assign {
+    pub criteria: WindowCriteria,
+    pub target: AssignTarget,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssignTarget {
+    /// A workspace, by name.
+    Workspace(String),
+    /// A workspace, by number, with `assign [...] number 2`.
+    WorkspaceNumber(String),
+    Output(String),
+}
+
+/// A `for_window` rule, running `command` on the windows matching `criteria`
+/// when they open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForWindow {
+    pub criteria: WindowCriteria,
+    pub command: String,
+    pub line: usize,
+}
+
+/// The criteria of an `assign` or `for_window` directive, e.g.
+/// `[class="^Firefox$" window_role="pop-up"]`, as attributes and the
+/// regular expressions or values they are compared with, in order. Criteria
+/// without a value, like `floating`, have an empty one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowCriteria(pub Vec<(String, String)>);
+
+impl WindowCriteria {
+    /// The value of the criterion on `attribute`, if any.
+    pub fn get(&self, attribute: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(name, _)| name == attribute)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The equivalent [`Criteria`], if the attributes are all supported by
+    /// it, and the regular expressions valid.
+    pub fn to_criteria(&self) -> Option<Criteria> {
+        let mut criteria = Criteria::default();
+        for (attribute, value) in &self.0 {
+            // Values like `__focused__` refer to the state of i3 when the
+            // window opens, which is lost on us.
+            if value.starts_with("__") && value.ends_with("__") {
+                return None;
+            }
+            let field = match attribute.as_str() {
+                "class" => &mut criteria.class,
+                "instance" => &mut criteria.instance,
+                "window_role" => &mut criteria.role,
+                "title" => &mut criteria.title,
+                "app_id" => &mut criteria.app_id,
+                "con_mark" => &mut criteria.mark,
+                _ => return None,
+            };
+            *field = Some(Regex::new(value).ok()?);
+        }
+        (!criteria.is_empty()).then_some(criteria)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindingKind {
+    /// A `bindsym` binding, on a key symbol or mouse button.
+    Sym,
+    /// A `bindcode` binding, on a key code.
+    Code,
+}
+
+impl I3Config {
+    /// The mode called `name`, if any.
+    pub fn mode(&self, name: &str) -> Option<&Mode> {
+        self.modes.iter().find(|mode| mode.name == name)
+    }
+}
+
+/// Retrieve the configuration i3 was last loaded with and parse it.
+pub fn load(i3: &mut dyn I3Commands) -> io::Result<I3Config> {
+    Ok(parse(&i3.get_config()?))
+}
+
+/// Parse the i3 configuration `text`.
+pub fn parse(text: &str) -> I3Config {
+    let mut config = I3Config {
+        modes: vec![Mode {
+            name: "default".to_owned(),
+            pango_markup: false,
+            bindings: Vec::new(),
+        }],
+        ..I3Config::default()
+    };
+    let mut variables: Vec<(String, String)> = Vec::new();
+    // The blocks the current line is in, with the index of the mode for
+    // `mode` blocks.
+    let mut blocks: Vec<Option<usize>> = Vec::new();
+    for (number, line) in logical_lines(text) {
+        let line = replace_variables(line.trim(), &variables);
+        let tokens = tokenize(&line);
+        let Some((first, _)) = tokens.first() else {
+            continue;
+        };
+        match first.as_str() {
+            "}" => {
+                blocks.pop();
+            }
+            "set" if blocks.is_empty() => {
+                if let Some((name, _)) = tokens.get(1) {
+                    let value = rest(&line, &tokens, 2);
+                    variables.retain(|(existing, _)| existing != name);
+                    variables.push((name.clone(), value.to_owned()));
+                    // Longer names first, so that `$mod` doesn't replace the
+                    // start of `$mod_alt`.
+                    variables.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+                }
+            }
+            "mode" if tokens.last().is_some_and(|(token, _)| token == "{") => {
+                let pango_markup = tokens.iter().any(|(token, _)| token == "--pango_markup");
+                let name = tokens[1..tokens.len() - 1]
+                    .iter()
+                    .map(|(token, _)| token)
+                    .find(|token| !token.starts_with("--"));
+                let index = name.map(|name| mode_index(&mut config, name, pango_markup));
+                blocks.push(index);
+            }
+            "bindsym" | "bindcode" => {
+                let mode = match blocks.as_slice() {
+                    [] => Some(0),
+                    [mode] => *mode,
+                    _ => None,
+                };
+                let binding = parse_binding(&line, &tokens, number);
+                if let (Some(mode), Some(binding)) = (mode, binding) {
+                    config.modes[mode].bindings.push(binding);
+                }
+            }
+            "assign" if blocks.is_empty() => {
+                let assign =
+                    parse_criteria(rest(&line, &tokens, 1)).and_then(|(criteria, target)| {
+                        parse_assign_target(target).map(|t| (criteria, t))
+                    });
+                if let Some((criteria, target)) = assign {
+                    config.assigns.push(Assign {
+                        criteria,
+                        target,
+                        line: number,
+                    });
+                }
+            }
+            "for_window" if blocks.is_empty() => {
+                let rule = parse_criteria(rest(&line, &tokens, 1))
+                    .filter(|(_, command)| !command.is_empty());
+                if let Some((criteria, command)) = rule {
+                    config.for_windows.push(ForWindow {
+                        criteria,
+                        command: command.to_owned(),
+                        line: number,
+                    });
+                }
+            }
+            "focus_follows_mouse" if blocks.is_empty() => {
+                if let Some((value, _)) = tokens.get(1) {
+                    config.focus_follows_mouse = Some(parse_bool(value));
+                }
+            }
+            _ if tokens.last().is_some_and(|(token, _)| token == "{") => blocks.push(None),
+            _ => (),
+        }
+    }
+    config
+}
+
+/// A boolean setting, spelled in any of the ways i3 accepts.
+fn parse_bool(value: &str) -> bool {
+    matches!(value, "1" | "yes" | "true" | "on" | "enable" | "active")
+}
+
+/// The index of the mode called `name`, added if new.
+fn mode_index(config: &mut I3Config, name: &str, pango_markup: bool) -> usize {
+    if let Some(index) = config.modes.iter().position(|mode| mode.name == name) {
+        return index;
+    }
+    config.modes.push(Mode {
+        name: name.to_owned(),
+        pango_markup,
+        bindings: Vec::new(),
+    });
+    config.modes.len() - 1
+}
+
+fn parse_binding(line: &str, tokens: &[(String, usize)], number: usize) -> Option<Binding> {
+    let kind = match tokens[0].0.as_str() {
+        "bindcode" => BindingKind::Code,
+        _ => BindingKind::Sym,
+    };
+    let options: Vec<String> = tokens[1..]
+        .iter()
+        .map(|(token, _)| token)
+        .take_while(|token| token.starts_with("--"))
+        .cloned()
+        .collect();
+    let key = tokens.get(1 + options.len())?.0.clone();
+    let command = rest(line, tokens, 2 + options.len());
+    if command.is_empty() {
+        return None;
+    }
+    Some(Binding {
+        kind,
+        options,
+        key,
+        command: command.to_owned(),
+        line: number,
+    })
+}
+
+/// Parse the criteria `text` starts with, between brackets, and return them
+/// along with the rest of the text.
+pub(crate) fn parse_criteria(text: &str) -> Option<(WindowCriteria, &str)> {
+    let mut chars = text.strip_prefix('[')?.char_indices().peekable();
+    let mut criteria = Vec::new();
+    loop {
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let (_, c) = chars.next()?;
+        if c == ']' {
+            let end = chars.peek().map_or(text.len(), |&(index, _)| index + 1);
+            return Some((WindowCriteria(criteria), text[end..].trim()));
+        }
+        let mut attribute = c.to_string();
+        while let Some((_, c)) = chars.next_if(|&(_, c)| c != '=' && c != ']' && !c.is_whitespace())
+        {
+            attribute.push(c);
+        }
+        let mut value = String::new();
+        if chars.next_if(|&(_, c)| c == '=').is_some() {
+            if chars.next_if(|&(_, c)| c == '"').is_some() {
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                        c => value.push(c),
+                    }
+                }
+            } else {
+                while let Some((_, c)) = chars.next_if(|&(_, c)| c != ']' && !c.is_whitespace()) {
+                    value.push(c);
+                }
+            }
+        }
+        criteria.push((attribute, value));
+    }
+}
+
+/// Parse what follows the criteria of an `assign` directive.
+fn parse_assign_target(text: &str) -> Option<AssignTarget> {
+    let text = text.strip_prefix('→').unwrap_or(text).trim();
+    let unquote = |text: &str| {
+        let text = text.trim();
+        let text = text
+            .strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .unwrap_or(text);
+        (!text.is_empty()).then(|| text.to_owned())
+    };
+    let text = text.strip_prefix("workspace ").unwrap_or(text).trim();
+    if let Some(number) = text.strip_prefix("number ") {
+        return unquote(number).map(AssignTarget::WorkspaceNumber);
+    }
+    if let Some(output) = text.strip_prefix("output ") {
+        return unquote(output).map(AssignTarget::Output);
+    }
+    unquote(text).map(AssignTarget::Workspace)
+}
+
+/// The lines of `text` with their number, joining the ones ending with a
+/// backslash to the next, and skipping comments.
+fn logical_lines(text: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    for (index, line) in text.lines().enumerate() {
+        let (number, mut joined) = current.take().unwrap_or((index + 1, String::new()));
+        if joined.is_empty() && line.trim_start().starts_with('#') {
+            continue;
+        }
+        match line.strip_suffix('\\') {
+            Some(line) => {
+                joined += line;
+                current = Some((number, joined));
+            }
+            None => {
+                joined += line;
+                lines.push((number, joined));
+            }
+        }
+    }
+    lines.extend(current);
+    lines
+}
+
+fn replace_variables(line: &str, variables: &[(String, String)]) -> String {
+    let mut line = line.to_owned();
+    // Variables are only replaced in their values when defined.
+    if line.starts_with("set ") {
+        return line;
+    }
+    for (name, value) in variables {
+        line = line.replace(name.as_str(), value);
+    }
+    line
+}
+
+/// Split `line` into words, along with the offset each starts at, keeping
+/// double-quoted strings together without their quotes.
+pub(crate) fn tokenize(line: &str) -> Vec<(String, usize)> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut token = String::new();
+        if c == '"' {
+            chars.next();
+            while let Some((_, c)) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => token.extend(chars.next().map(|(_, c)| c)),
+                    c => token.push(c),
+                }
+            }
+        } else {
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+        }
+        tokens.push((token, start));
+    }
+    tokens
+}
+
+/// The rest of `line` from its token `index` on, verbatim.
+fn rest<'a>(line: &'a str, tokens: &[(String, usize)], index: usize) -> &'a str {
+    tokens
+        .get(index)
+        .map_or("", |&(_, start)| line[start..].trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Excerpts of the default i3 configuration, and of a few users'.
+    const CONFIG: &str = r#"# i3 config file (v4)
+set $mod Mod4
+set $mod_alt Mod1
+set $ws1 "1: web"
+font pango:monospace 8
+
+# start a terminal
+bindsym $mod+Return exec i3-sensible-terminal
+bindsym $mod_alt+Tab exec --no-startup-id \
+    rofi -show window
+bindcode $mod+38 focus left
+bindsym --release button2 kill
+bindsym $mod+1 workspace number $ws1
+
+assign [class="^Firefox$"] → $ws1
+assign [class="^Slack$" instance="slack"] workspace number 3
+assign [title="Spotify Premium"] output HDMI-1
+for_window [class="^Pavucontrol$"] floating enable, resize set 800 600
+for_window [window_role="pop-up"] floating enable
+for_window [class=".*" con_id=__focused__] border pixel 2
+
+mode "resize" {
+        bindsym h resize shrink width 10 px or 10 ppt
+        bindsym Escape mode "default"
+}
+
+mode --pango_markup "<b>system</b> (l)ock" {
+        bindsym l exec i3lock, mode "default"
+}
+
+bar {
+        status_command i3status
+        bindsym button4 nop
+}
+"#;
+
+    #[test]
+    fn parses_bindings_by_mode() {
+        let config = parse(CONFIG);
+        let names: Vec<&str> = config.modes.iter().map(|mode| mode.name.as_str()).collect();
+        assert_eq!(names, ["default", "resize", "<b>system</b> (l)ock"]);
+        let default = &config.modes[0];
+        let keys: Vec<&str> = default.bindings.iter().map(|b| b.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            ["Mod4+Return", "Mod1+Tab", "Mod4+38", "button2", "Mod4+1"]
+        );
+        assert_eq!(default.bindings[2].kind, BindingKind::Code);
+        assert_eq!(default.bindings[3].options, ["--release"]);
+        let resize = config.mode("resize").unwrap();
+        assert_eq!(resize.bindings[1].command, r#"mode "default""#);
+        assert_eq!(resize.bindings[1].line, 24);
+        assert!(config.modes[2].pango_markup);
+    }
+
+    #[test]
+    fn parses_focus_follows_mouse() {
+        assert_eq!(parse(CONFIG).focus_follows_mouse, None);
+        let config = parse("focus_follows_mouse yes\nfocus_follows_mouse no\n");
+        assert_eq!(config.focus_follows_mouse, Some(false));
+    }
+
+    #[test]
+    fn replaces_variables_and_joins_continued_lines() {
+        let config = parse(CONFIG);
+        let bindings = &config.modes[0].bindings;
+        assert_eq!(
+            bindings[1].command,
+            "exec --no-startup-id     rofi -show window"
+        );
+        assert_eq!(bindings[1].line, 9);
+        assert_eq!(bindings[4].command, r#"workspace number "1: web""#);
+    }
+
+    #[test]
+    fn skips_bar_bindings() {
+        let config = parse(CONFIG);
+        let all = config.modes.iter().flat_map(|mode| &mode.bindings);
+        assert!(all.clone().all(|binding| binding.key != "button4"));
+    }
+
+    #[test]
+    fn parses_assigns() {
+        let config = parse(CONFIG);
+        let targets: Vec<&AssignTarget> = config.assigns.iter().map(|a| &a.target).collect();
+        assert_eq!(
+            targets,
+            [
+                &AssignTarget::Workspace("1: web".to_owned()),
+                &AssignTarget::WorkspaceNumber("3".to_owned()),
+                &AssignTarget::Output("HDMI-1".to_owned()),
+            ]
+        );
+        let criteria = &config.assigns[1].criteria;
+        assert_eq!(criteria.get("class"), Some("^Slack$"));
+        assert_eq!(criteria.get("instance"), Some("slack"));
+        assert_eq!(
+            config.assigns[2].criteria.get("title"),
+            Some("Spotify Premium")
+        );
+    }
+
+    #[test]
+    fn parses_for_window_rules() {
+        let config = parse(CONFIG);
+        assert_eq!(config.for_windows.len(), 3);
+        assert_eq!(
+            config.for_windows[0].command,
+            "floating enable, resize set 800 600"
+        );
+        assert_eq!(
+            config.for_windows[1].criteria.get("window_role"),
+            Some("pop-up")
+        );
+    }
+
+    #[test]
+    fn converts_supported_criteria() {
+        let config = parse(CONFIG);
+        let pavucontrol = config.for_windows[0].criteria.to_criteria().unwrap();
+        assert!(pavucontrol.matches(&crate::mock::window(1, "Pavucontrol", "Volume")));
+        assert!(!pavucontrol.matches(&crate::mock::window(2, "pavucontrol-qt", "Volume")));
+        // Criteria on the focused container can't be matched later on.
+        assert!(config.for_windows[2].criteria.to_criteria().is_none());
+    }
+
+    #[test]
+    fn parses_criteria_without_values() {
+        let config = parse("for_window [floating class=\"x\"] border none");
+        let criteria = &config.for_windows[0].criteria;
+        assert_eq!(
+            criteria.0,
+            [
+                ("floating".to_owned(), String::new()),
+                ("class".to_owned(), "x".to_owned())
+            ]
+        );
+        assert_eq!(config.for_windows[0].command, "border none");
+    }
+}
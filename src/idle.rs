@@ -0,0 +1,176 @@
+//! Telling whether the user is away from the keyboard.
+//!
+//! An [`IdleMonitor`] learns that the session went idle and became active
+//! again from ticks sent by an idle daemon like swayidle or xidlehook:
+//!
+//! ```text
+//! exec swayidle -w timeout 300 'swaymsg -t send_tick session:idle' \
+//!     resume 'swaymsg -t send_tick session:active'
+//! ```
+//!
+//! Under i3, with the `x11` feature, it also polls the X11 screen saver
+//! extension for the time since the last input, so that no daemon is needed.
+//! Helpers feed it their ticks and deadlines, and act on the [`IdleChange`]s
+//! it returns.
+
+use crate::{I3Commands, WindowManager};
+use i3_ipc::event::TickData;
+use log::{debug, info};
+use std::{
+    io,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// The payload of the tick telling that the session went idle.
+pub const IDLE_TICK: &str = "session:idle";
+
+/// The payload of the tick telling that the session is active again.
+pub const ACTIVE_TICK: &str = "session:active";
+
+/// How often to check for input once idle, when polling X11.
+#[cfg(feature = "x11")]
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A change of the idle state, as seen by [`IdleMonitor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleChange {
+    /// The session went idle, the last input having happened at the given
+    /// time.
+    Idle(SystemTime),
+    Active,
+}
+
+/// Keeps track of whether the session is idle.
+pub struct IdleMonitor {
+    /// How long without input before the session is idle, when polling X11.
+    /// Ticks are taken to be sent after as long.
+    timeout: Duration,
+    idle: bool,
+    #[cfg(feature = "x11")]
+    x11: Option<crate::x11::X11>,
+    next_poll: Option<Instant>,
+}
+
+impl IdleMonitor {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            idle: false,
+            #[cfg(feature = "x11")]
+            x11: None,
+            next_poll: None,
+        }
+    }
+
+    /// Start polling X11 if connected to i3 and built with the `x11`
+    /// feature, else rely on ticks only.
+    pub fn start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        if i3.window_manager()? == WindowManager::Sway {
+            debug!("waiting for {IDLE_TICK:?} ticks to tell when the session is idle");
+            return Ok(());
+        }
+        #[cfg(feature = "x11")]
+        match crate::x11::X11::connect() {
+            Ok(x11) => {
+                self.x11 = Some(x11);
+                self.next_poll = Some(Instant::now());
+            }
+            Err(err) => info!("cannot poll X11 for input, only relying on ticks: {err}"),
+        }
+        #[cfg(not(feature = "x11"))]
+        info!("built without the x11 feature, relying on {IDLE_TICK:?} ticks only");
+        Ok(())
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Update the state from a tick, returning the change it describes, if
+    /// any.
+    pub fn on_tick(&mut self, event: &TickData) -> Option<IdleChange> {
+        match event.payload.as_str() {
+            IDLE_TICK => self.set_idle(true, self.timeout),
+            ACTIVE_TICK => self.set_idle(false, Duration::ZERO),
+            _ => None,
+        }
+    }
+
+    /// When to call [`on_deadline`](Self::on_deadline) next, if polling.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.next_poll
+    }
+
+    /// Poll for input if due, returning the change of state, if any.
+    pub fn on_deadline(&mut self, now: Instant) -> io::Result<Option<IdleChange>> {
+        if self.next_poll.is_none_or(|at| at > now) {
+            return Ok(None);
+        }
+        #[cfg(feature = "x11")]
+        if let Some(x11) = &self.x11 {
+            let idle_time = x11.idle_time()?;
+            let idle = idle_time >= self.timeout;
+            // Check again right when the timeout could be reached, or soon
+            // once idle so as to notice input quickly.
+            self.next_poll = Some(if idle {
+                now + IDLE_POLL_INTERVAL
+            } else {
+                now + (self.timeout - idle_time)
+            });
+            return Ok(self.set_idle(idle, idle_time));
+        }
+        self.next_poll = None;
+        Ok(None)
+    }
+
+    /// Set the state, the last input having happened `idle_time` ago, and
+    /// return the change if any.
+    fn set_idle(&mut self, idle: bool, idle_time: Duration) -> Option<IdleChange> {
+        if idle == self.idle {
+            return None;
+        }
+        self.idle = idle;
+        if idle {
+            info!("the session is idle");
+            let now = SystemTime::now();
+            Some(IdleChange::Idle(now.checked_sub(idle_time).unwrap_or(now)))
+        } else {
+            info!("the session is active again");
+            Some(IdleChange::Active)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockI3;
+
+    fn tick(payload: &str) -> TickData {
+        TickData {
+            first: false,
+            payload: payload.to_owned(),
+        }
+    }
+
+    #[test]
+    fn follows_ticks() {
+        let mut i3 = MockI3::default();
+        i3.window_manager = WindowManager::Sway;
+        let mut monitor = IdleMonitor::new(Duration::from_secs(300));
+        monitor.start(&mut i3).unwrap();
+        assert_eq!(monitor.next_deadline(), None);
+        assert_eq!(monitor.on_tick(&tick("focus-history:previous")), None);
+        assert!(matches!(
+            monitor.on_tick(&tick(IDLE_TICK)),
+            Some(IdleChange::Idle(_))
+        ));
+        assert!(monitor.is_idle());
+        assert_eq!(monitor.on_tick(&tick(IDLE_TICK)), None);
+        assert_eq!(
+            monitor.on_tick(&tick(ACTIVE_TICK)),
+            Some(IdleChange::Active)
+        );
+        assert!(!monitor.is_idle());
+    }
+}
@@ -0,0 +1,167 @@
+//! Locks keeping a helper from running twice with the same configuration.
+//!
+//! Two instances of a daemon handling the same windows send duplicate
+//! commands and race each other, e.g. when it is started both from the i3
+//! configuration and from a systemd unit. Daemons thus lock a file under
+//! `$XDG_RUNTIME_DIR/i3-helpers/`, named after the helper and a hash of its
+//! configuration, for as long as they run. The hash is stable across builds,
+//! so that an upgraded binary finds the lock of the one it replaces. The file holds the pid of the
+//! instance holding the lock, so that another one can take over.
+
+use log::{info, warn};
+use std::{
+    env,
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, Instant},
+};
+
+/// How long to wait for a replaced instance to exit.
+const REPLACE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The lock of a running instance, released when dropped.
+pub struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    /// Lock the instance of the helper called `name` with the configuration
+    /// described by `key`. If another instance holds the lock, fail, or with
+    /// `replace`, ask it to exit with SIGTERM and wait for it to.
+    ///
+    /// Returns `None`, without locking anything, if `$XDG_RUNTIME_DIR` is not
+    /// set.
+    pub fn acquire(name: &str, key: &str, replace: bool) -> io::Result<Option<Self>> {
+        let Some(runtime_dir) = env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) else {
+            warn!("XDG_RUNTIME_DIR is not set, not checking for other instances");
+            return Ok(None);
+        };
+        let path = lock_path(Path::new(&runtime_dir), name, key);
+        Self::acquire_at(&path, name, replace).map(Some)
+    }
+
+    /// Lock the file at `path` for the helper called `name`, as
+    /// [`acquire`](Self::acquire) does.
+    fn acquire_at(path: &Path, name: &str, replace: bool) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        match file.try_lock() {
+            Ok(()) => (),
+            Err(TryLockError::WouldBlock) => {
+                let mut pid = String::new();
+                file.read_to_string(&mut pid)?;
+                let pid = pid.trim();
+                if !replace {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!(
+                            "{name} is already running with the same configuration, as pid \
+                             {pid}, use --replace to take over"
+                        ),
+                    ));
+                }
+                info!("replacing the running instance, pid {pid}");
+                terminate(pid)?;
+                wait_for_lock(&file)?;
+            }
+            Err(TryLockError::Error(err)) => return Err(err),
+        }
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", process::id())?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// The file locked by the instances of `name` with the configuration `key`,
+/// under `runtime_dir`.
+fn lock_path(runtime_dir: &Path, name: &str, key: &str) -> PathBuf {
+    let file_name = format!("{name}-{:016x}.lock", fnv1a(key.as_bytes()));
+    runtime_dir.join("i3-helpers").join(file_name)
+}
+
+/// The 64-bit FNV-1a hash of `bytes`. Unlike the hashers of the standard
+/// library, its output doesn't change between Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Ask the process `pid` to exit, which the event loop does gracefully.
+fn terminate(pid: &str) -> io::Result<()> {
+    // A pid of 0 or less would signal whole process groups.
+    let Some(pid) = pid.parse::<libc::pid_t>().ok().filter(|&pid| pid > 0) else {
+        return Err(io::Error::other(format!(
+            "the running instance has an invalid pid {pid:?}"
+        )));
+    };
+    // SAFETY: kill only sends a signal, and takes no pointers.
+    if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+        let err = io::Error::last_os_error();
+        return Err(io::Error::other(format!(
+            "failed to terminate pid {pid}: {err}"
+        )));
+    }
+    Ok(())
+}
+
+fn wait_for_lock(file: &File) -> io::Result<()> {
+    let start = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(()),
+            Err(TryLockError::WouldBlock) if start.elapsed() < REPLACE_TIMEOUT => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(io::Error::other(
+                    "the running instance did not exit in time",
+                ));
+            }
+            Err(TryLockError::Error(err)) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locks_the_path_of_a_configuration_across_builds() {
+        let dir = Path::new("/run/user/1000");
+        assert_eq!(
+            lock_path(dir, "i3-autotiling", ""),
+            dir.join("i3-helpers/i3-autotiling-cbf29ce484222325.lock")
+        );
+        assert_eq!(
+            lock_path(dir, "i3-autotiling", "a"),
+            dir.join("i3-helpers/i3-autotiling-af63dc4c8601ec8c.lock")
+        );
+    }
+
+    #[test]
+    fn refuses_a_second_instance_until_the_first_exits() {
+        let dir = env::temp_dir().join(format!("i3-helpers-test-{}", process::id()));
+        let path = lock_path(&dir, "i3-test", "--flag");
+        let first = InstanceLock::acquire_at(&path, "i3-test", false).unwrap();
+        let err = InstanceLock::acquire_at(&path, "i3-test", false)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(err.to_string().contains(&process::id().to_string()));
+        drop(first);
+        InstanceLock::acquire_at(&path, "i3-test", false).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+//! Building blocks for event-driven i3 helpers.
+//!
+//! A helper implements the [`Helper`] trait, declaring which i3 events it is
+//! interested in and how to react to them, and is run by an [`EventLoop`]. The
+//! event loop takes care of the connection and dispatching boilerplate:
+//! subscribing to events, keeping a separate [`Connection`] for commands,
+//! waking the helper up at its deadlines and surviving i3 restarts.
+//!
+//! Helpers send commands through the [`I3Commands`] trait, so they can be
+//! tested against a [`mock::MockI3`] and scripted events instead of a running
+//! i3.
+//!
+//! ```no_run
+//! use i3_helpers::{EventLoop, Helper, I3Commands};
+//! use i3_ipc::event::{Subscribe, WindowChange, WindowData};
+//! use std::io;
+//!
+//! struct FocusPrinter;
+//!
+//! impl Helper for FocusPrinter {
+//!     fn subscriptions(&self) -> Vec<Subscribe> {
+//!         vec![Subscribe::Window]
+//!     }
+//!
+//!     fn on_window(&mut self, _i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+//!         if let WindowChange::Focus = event.change {
+//!             println!("focused {}", event.container.id);
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! fn main() -> io::Result<()> {
+//!     EventLoop::connect()?.run(&mut FocusPrinter)
+//! }
+//! ```
+
+pub mod backend;
+pub mod binding_check;
+pub mod chords;
+pub mod cli;
+pub mod command;
+pub mod config;
+pub mod connection;
+pub mod control;
+pub mod criteria;
+pub mod event_loop;
+pub mod expression;
+pub mod focus;
+pub mod geometry;
+pub mod helpers;
+pub mod i3_config;
+pub mod idle;
+pub mod instance;
+pub mod logging;
+pub mod metrics;
+pub mod mock;
+pub mod multiplexer;
+pub mod notification;
+pub mod picker;
+pub mod record;
+pub mod status;
+#[cfg(feature = "systemd")]
+mod systemd;
+pub mod tree;
+pub mod wait;
+#[cfg(feature = "x11")]
+pub mod x11;
+
+pub use connection::{Connection, I3Commands, WindowManager};
+pub use criteria::Criteria;
+pub use event_loop::{EventLoop, Helper};
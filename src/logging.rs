@@ -0,0 +1,43 @@
+//! Logging setup shared by all the helpers.
+
+use clap::ArgAction;
+use log::LevelFilter;
+
+/// Command line flags controlling how much is logged.
+///
+/// Warnings and errors are logged by default. The `RUST_LOG` environment
+/// variable, when set, takes precedence over these flags and allows filtering
+/// by module, e.g. `RUST_LOG=i3_helpers=debug`.
+#[derive(clap::Args)]
+pub struct Verbosity {
+    /// Log more details. Repeat for even more (-v: info, -vv: debug, -vvv:
+    /// trace).
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Log less. Repeat to log nothing at all (-q: errors, -qq: nothing).
+    #[arg(short, long, action = ArgAction::Count, global = true, conflicts_with = "verbose")]
+    quiet: u8,
+}
+
+impl Verbosity {
+    /// The maximum level to log, as requested by the flags.
+    pub fn level(&self) -> LevelFilter {
+        match (self.verbose, self.quiet) {
+            (0, 0) => LevelFilter::Warn,
+            (1, _) => LevelFilter::Info,
+            (2, _) => LevelFilter::Debug,
+            (_, 0) => LevelFilter::Trace,
+            (_, 1) => LevelFilter::Error,
+            (_, _) => LevelFilter::Off,
+        }
+    }
+}
+
+/// Log to stderr up to the given level, unless overridden by `RUST_LOG`.
+pub fn init(level: LevelFilter) {
+    env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .init();
+}
@@ -0,0 +1,137 @@
+//! Counters describing what helpers have been up to, for monitoring.
+//!
+//! The event loop keeps the counters below up to date, and helpers can add
+//! their own with [`register`]. With the `metrics` feature, they are published
+//! in the Prometheus text format by an HTTP endpoint enabled with
+//! [`EventLoop::set_metrics_addr`](crate::EventLoop::set_metrics_addr).
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+/// A monotonically increasing count of something.
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    value: AtomicU64,
+}
+
+impl Counter {
+    pub const fn new(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            value: AtomicU64::new(0),
+        }
+    }
+
+    pub fn inc(&self) {
+        self.value.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+pub static EVENTS: Counter =
+    Counter::new("i3_helpers_events_total", "Number of i3 events processed.");
+
+pub static RECONNECTS: Counter = Counter::new(
+    "i3_helpers_reconnects_total",
+    "Number of times the connection to i3 was reestablished after a restart.",
+);
+
+pub static COMMAND_ERRORS: Counter = Counter::new(
+    "i3_helpers_command_errors_total",
+    "Number of commands that failed or were rejected by i3.",
+);
+
+/// Counters added by helpers, on top of the built-in ones.
+static REGISTERED: Mutex<Vec<&'static Counter>> = Mutex::new(Vec::new());
+
+/// Publish `counter` along with the built-in ones.
+pub fn register(counter: &'static Counter) {
+    REGISTERED
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .push(counter);
+}
+
+/// All the counters in the Prometheus text exposition format.
+pub fn render() -> String {
+    let registered = REGISTERED.lock().unwrap_or_else(|err| err.into_inner());
+    let counters = [&EVENTS, &RECONNECTS, &COMMAND_ERRORS]
+        .into_iter()
+        .chain(registered.iter().copied());
+    let mut text = String::new();
+    for counter in counters {
+        text += &format!(
+            "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}\n",
+            name = counter.name,
+            help = counter.help,
+            value = counter.get()
+        );
+    }
+    text
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) use server::serve;
+
+#[cfg(feature = "metrics")]
+mod server {
+    use std::{io, net::SocketAddr};
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::{TcpListener, TcpStream},
+    };
+
+    /// Serve the counters over HTTP on `addr`, at `/metrics`.
+    ///
+    /// Must be called from within the tokio runtime.
+    pub(crate) async fn serve(addr: SocketAddr) -> io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        log::info!("serving metrics on http://{addr}/metrics");
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(respond(stream));
+                    }
+                    Err(err) => {
+                        log::error!("metrics endpoint stopped accepting connections: {err}");
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn respond(stream: TcpStream) {
+        let (reader, mut writer) = stream.into_split();
+        let mut request_line = String::new();
+        if let Err(err) = BufReader::new(reader).read_line(&mut request_line).await {
+            log::warn!("failed to read metrics request: {err}");
+            return;
+        }
+        let path = request_line.split_whitespace().nth(1);
+        let response = match path {
+            Some("/metrics") => {
+                let body = super::render();
+                format!(
+                    "HTTP/1.1 200 OK\r\n\
+                     Content-Type: text/plain; version=0.0.4\r\n\
+                     Content-Length: {}\r\n\
+                     Connection: close\r\n\r\n{body}",
+                    body.len()
+                )
+            }
+            _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_owned(),
+        };
+        let _ = writer.write_all(response.as_bytes()).await;
+    }
+}
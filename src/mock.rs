@@ -0,0 +1,230 @@
+//! Test doubles for running helpers without i3.
+//!
+//! A [`MockI3`] answers queries with a fixed tree and records the commands it
+//! is sent, and the functions in this module build the nodes and events to
+//! feed to a helper, usually through [`dispatch`](crate::event_loop::dispatch).
+
+use crate::{I3Commands, WindowManager};
+use i3_ipc::{
+    event::{
+        BindType, BindingData, BindingObject, Event, ModeData, TickData, WindowChange, WindowData,
+        WorkspaceChange, WorkspaceData,
+    },
+    reply::{
+        FullscreenMode, Node, NodeBorder, NodeLayout, NodeOrientation, NodeType, Outputs, Rect,
+        ScratchpadState, WindowProperties, Workspaces,
+    },
+};
+use serde_json::Value;
+use std::{collections::HashMap, io};
+
+/// An [`I3Commands`] implementation recording commands instead of running
+/// them.
+pub struct MockI3 {
+    /// The tree returned by [`I3Commands::get_tree`].
+    pub tree: Node,
+    pub workspaces: Workspaces,
+    pub outputs: Outputs,
+    /// The text returned by [`I3Commands::get_config`].
+    pub config: String,
+    pub window_manager: WindowManager,
+    /// The replies returned by [`I3Commands::send_message`], by message type.
+    pub messages: HashMap<u32, Value>,
+    /// The commands [`I3Commands::run_command_checked`] reports as failed,
+    /// e.g. because they act on a window that is gone. They are recorded all
+    /// the same.
+    pub failing: Vec<String>,
+    commands: Vec<String>,
+}
+
+impl MockI3 {
+    pub fn new(tree: Node) -> Self {
+        Self {
+            tree,
+            workspaces: Vec::new(),
+            outputs: Vec::new(),
+            config: String::new(),
+            window_manager: WindowManager::I3,
+            messages: HashMap::new(),
+            failing: Vec::new(),
+            commands: Vec::new(),
+        }
+    }
+
+    /// The commands run since the last call, oldest first.
+    pub fn take_commands(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.commands)
+    }
+}
+
+impl Default for MockI3 {
+    fn default() -> Self {
+        Self::new(root(Vec::new()))
+    }
+}
+
+impl I3Commands for MockI3 {
+    fn run_command(&mut self, command: &str) -> io::Result<()> {
+        self.commands.push(command.to_owned());
+        Ok(())
+    }
+
+    fn run_command_checked(&mut self, command: &str) -> io::Result<bool> {
+        self.run_command(command)?;
+        Ok(!self.failing.iter().any(|failing| failing == command))
+    }
+
+    fn get_tree(&mut self) -> io::Result<Node> {
+        Ok(self.tree.clone())
+    }
+
+    fn get_workspaces(&mut self) -> io::Result<Workspaces> {
+        Ok(self.workspaces.clone())
+    }
+
+    fn get_outputs(&mut self) -> io::Result<Outputs> {
+        Ok(self.outputs.clone())
+    }
+
+    fn get_config(&mut self) -> io::Result<String> {
+        Ok(self.config.clone())
+    }
+
+    fn window_manager(&mut self) -> io::Result<WindowManager> {
+        Ok(self.window_manager)
+    }
+
+    fn send_message(&mut self, message_type: u32, _payload: &str) -> io::Result<Value> {
+        self.messages.get(&message_type).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("no reply to messages of type {message_type}"),
+            )
+        })
+    }
+}
+
+/// A container of the given type with no children and default properties.
+pub fn node(id: usize, node_type: NodeType) -> Node {
+    let rect = || Rect {
+        x: 0,
+        y: 0,
+        width: 0,
+        height: 0,
+    };
+    Node {
+        id,
+        name: None,
+        num: None,
+        node_type,
+        layout: NodeLayout::SplitH,
+        output: None,
+        orientation: NodeOrientation::None,
+        border: NodeBorder::Normal,
+        scratchpad_state: ScratchpadState::None,
+        percent: None,
+        rect: rect(),
+        window_rect: rect(),
+        deco_rect: rect(),
+        geometry: rect(),
+        window: None,
+        window_properties: None,
+        window_type: None,
+        current_border_width: 0,
+        urgent: false,
+        marks: None,
+        focused: false,
+        focus: Vec::new(),
+        sticky: false,
+        floating: None,
+        floating_nodes: Vec::new(),
+        fullscreen_mode: FullscreenMode::None,
+        nodes: Vec::new(),
+        app_id: None,
+    }
+}
+
+/// An X11 window with the given class, also used as its instance, and title.
+pub fn window(id: usize, class: &str, title: &str) -> Node {
+    Node {
+        name: Some(title.to_owned()),
+        window: Some(id),
+        window_properties: Some(WindowProperties {
+            title: Some(title.to_owned()),
+            instance: Some(class.to_owned()),
+            class: Some(class.to_owned()),
+            window_role: None,
+            transient_for: None,
+            machine: None,
+            window_type: None,
+        }),
+        ..node(id, NodeType::Con)
+    }
+}
+
+/// A workspace named `name` containing `windows`.
+pub fn workspace(id: usize, name: &str, windows: Vec<Node>) -> Node {
+    Node {
+        name: Some(name.to_owned()),
+        num: name.parse().ok(),
+        nodes: windows,
+        ..node(id, NodeType::Workspace)
+    }
+}
+
+/// The root of a tree with a single output holding `workspaces`.
+pub fn root(workspaces: Vec<Node>) -> Node {
+    let output = Node {
+        name: Some("mock-output".to_owned()),
+        nodes: workspaces,
+        ..node(1, NodeType::Output)
+    };
+    Node {
+        name: Some("root".to_owned()),
+        nodes: vec![output],
+        ..node(0, NodeType::Root)
+    }
+}
+
+pub fn window_event(change: WindowChange, container: Node) -> Event {
+    Event::Window(Box::new(WindowData { change, container }))
+}
+
+/// A workspace event, with `current` being the workspace it is about.
+pub fn workspace_event(change: WorkspaceChange, current: Node) -> Event {
+    Event::Workspace(Box::new(WorkspaceData {
+        change,
+        current: Some(current),
+        old: None,
+    }))
+}
+
+/// A binding event for a key binding running `command`.
+pub fn binding_event(command: &str) -> Event {
+    Event::Binding(BindingData {
+        change: "run".to_owned(),
+        binding: BindingObject {
+            command: command.to_owned(),
+            event_state_mask: Vec::new(),
+            input_code: 0,
+            symbol: None,
+            input_type: BindType::Keyboard,
+        },
+    })
+}
+
+/// A mode event for switching to the binding mode called `mode`.
+pub fn mode_event(mode: &str) -> Event {
+    Event::Mode(ModeData {
+        change: mode.to_owned(),
+        pango_markup: false,
+    })
+}
+
+/// A tick event carrying `payload`, as sent by `i3-msg -t send_tick`.
+pub fn tick_event(payload: &str) -> Event {
+    Event::Tick(TickData {
+        first: false,
+        payload: payload.to_owned(),
+    })
+}
@@ -0,0 +1,147 @@
+//! Running several helpers over a single pair of connections.
+
+use crate::{event_loop::dispatch, Helper, I3Commands};
+use i3_ipc::event::{Event, Subscribe};
+use std::{io, time::Instant};
+
+/// A [`Helper`] made of several others.
+///
+/// It subscribes to the events any of them is interested in, and passes each
+/// event on to the helpers that subscribed to it, so that they share the event
+/// subscription and command connection of a single [`EventLoop`].
+///
+/// Control commands are routed by their first word, the name the helper was
+/// added with, e.g. `back-to-scratch pause`.
+///
+/// [`EventLoop`]: crate::EventLoop
+#[derive(Default)]
+pub struct Multiplexer {
+    helpers: Vec<Entry>,
+}
+
+struct Entry {
+    name: String,
+    helper: Box<dyn Helper>,
+    subscriptions: Vec<Subscribe>,
+}
+
+impl Multiplexer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, helper: impl Helper + 'static) {
+        let subscriptions = helper.subscriptions();
+        self.helpers.push(Entry {
+            name: name.into(),
+            helper: Box::new(helper),
+            subscriptions,
+        });
+    }
+}
+
+impl Helper for Multiplexer {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        let mut subscriptions = Vec::new();
+        for subscription in self.helpers.iter().flat_map(|entry| &entry.subscriptions) {
+            if !subscriptions.contains(subscription) {
+                subscriptions.push(*subscription);
+            }
+        }
+        subscriptions
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        for entry in &mut self.helpers {
+            entry.helper.on_start(i3)?;
+        }
+        Ok(())
+    }
+
+    // The handlers specific to each type of event are left alone, `dispatch`
+    // calling them for each helper from here.
+    fn on_event(&mut self, i3: &mut dyn I3Commands, event: &Event) -> io::Result<()> {
+        let Some(subscription) = subscription_of(event) else {
+            return Ok(());
+        };
+        for entry in &mut self.helpers {
+            if entry.subscriptions.contains(&subscription) {
+                dispatch(entry.helper.as_mut(), i3, event)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.helpers
+            .iter()
+            .filter_map(|entry| entry.helper.next_deadline())
+            .min()
+    }
+
+    fn on_deadline(&mut self, i3: &mut dyn I3Commands, now: Instant) -> io::Result<()> {
+        for entry in &mut self.helpers {
+            if entry
+                .helper
+                .next_deadline()
+                .is_some_and(|deadline| deadline <= now)
+            {
+                entry.helper.on_deadline(i3, now)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn on_reload(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        for entry in &mut self.helpers {
+            entry.helper.on_reload(i3)?;
+        }
+        Ok(())
+    }
+
+    fn on_reconnect(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        for entry in &mut self.helpers {
+            entry.helper.on_reconnect(i3)?;
+        }
+        Ok(())
+    }
+
+    fn on_exit(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        for entry in &mut self.helpers {
+            entry.helper.on_exit(i3)?;
+        }
+        Ok(())
+    }
+
+    fn on_control(&mut self, i3: &mut dyn I3Commands, command: &str) -> io::Result<String> {
+        let (name, command) = command.split_once(' ').unwrap_or((command, ""));
+        match self.helpers.iter_mut().find(|entry| entry.name == name) {
+            Some(entry) => entry.helper.on_control(i3, command.trim()),
+            None => {
+                let names: Vec<&str> = self
+                    .helpers
+                    .iter()
+                    .map(|entry| entry.name.as_str())
+                    .collect();
+                Ok(format!(
+                    "error: unknown helper {name:?}, expected one of: {}\n",
+                    names.join(", ")
+                ))
+            }
+        }
+    }
+}
+
+/// The subscription `event` was received for, if helpers can subscribe to it.
+fn subscription_of(event: &Event) -> Option<Subscribe> {
+    match event {
+        Event::Window(_) => Some(Subscribe::Window),
+        Event::Workspace(_) => Some(Subscribe::Workspace),
+        Event::Output(_) => Some(Subscribe::Output),
+        Event::Mode(_) => Some(Subscribe::Mode),
+        Event::BarConfig(_) => Some(Subscribe::BarConfigUpdate),
+        Event::Binding(_) => Some(Subscribe::Binding),
+        Event::Tick(_) => Some(Subscribe::Tick),
+        Event::Shutdown(_) => None,
+    }
+}
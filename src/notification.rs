@@ -0,0 +1,178 @@
+//! Desktop notifications, sent to the notification daemon over D-Bus.
+//!
+//! Notifications go through the `org.freedesktop.Notifications` interface,
+//! which every notification daemon implements, by way of `gdbus` as found
+//! wherever GLib is installed, rather than through a D-Bus library.
+
+use serde::Deserialize;
+use std::{io, process::Command};
+
+const DESTINATION: &str = "org.freedesktop.Notifications";
+const OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+
+/// How much a notification matters, which daemons may show differently.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// A notification to show.
+#[derive(Debug, Clone, Default)]
+pub struct Notification {
+    pub summary: String,
+    /// The text below the summary, which may hold a subset of HTML-like
+    /// markup, see [`escape_markup`].
+    pub body: String,
+    /// An icon name from the icon theme, or the path of an image.
+    pub icon: String,
+    pub urgency: Urgency,
+    /// How long the notification stays up, in milliseconds, or `None` to let
+    /// the daemon decide.
+    pub timeout_ms: Option<u32>,
+}
+
+/// Sends notifications on behalf of an application.
+pub struct Notifier {
+    app_name: String,
+}
+
+impl Notifier {
+    pub fn new(app_name: &str) -> Self {
+        Self {
+            app_name: app_name.to_owned(),
+        }
+    }
+
+    /// Show `notification`, replacing the one with id `replaces` if still up,
+    /// and return the id of the new one.
+    pub fn notify(&self, notification: &Notification, replaces: Option<u32>) -> io::Result<u32> {
+        let timeout = notification.timeout_ms.map_or(-1, i64::from);
+        let urgency = notification.urgency as u8;
+        let reply = call(
+            "Notify",
+            &[
+                string(&self.app_name),
+                replaces.unwrap_or(0).to_string(),
+                string(&notification.icon),
+                string(&notification.summary),
+                string(&notification.body),
+                "@as []".to_owned(),
+                format!("{{'urgency': <byte {urgency}>}}"),
+                timeout.to_string(),
+            ],
+        )?;
+        parse_id(&reply).ok_or_else(|| {
+            io::Error::other(format!(
+                "unexpected reply from the notification daemon: {reply}"
+            ))
+        })
+    }
+
+    /// Take down the notification with id `id`, if still up.
+    pub fn close(&self, id: u32) -> io::Result<()> {
+        call("CloseNotification", &[id.to_string()]).map(drop)
+    }
+}
+
+/// Call `method` of the notification daemon with `args`, in GVariant text
+/// format, and return the reply in the same format.
+fn call(method: &str, args: &[String]) -> io::Result<String> {
+    let output = command(method, args).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!(
+            "{method} failed: {}",
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// The `gdbus` command calling `method` with `args`.
+fn command(method: &str, args: &[String]) -> Command {
+    let mut command = Command::new("gdbus");
+    command
+        .args(["call", "--session", "--dest", DESTINATION])
+        .args(["--object-path", OBJECT_PATH])
+        .arg("--method")
+        .arg(format!("{DESTINATION}.{method}"))
+        // Arguments like the default timeout, -1, would pass for options.
+        .arg("--")
+        .args(args);
+    command
+}
+
+/// `text` as a GVariant string literal.
+fn string(text: &str) -> String {
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push('\'');
+    for c in text.chars() {
+        match c {
+            '\\' | '\'' => {
+                literal.push('\\');
+                literal.push(c);
+            }
+            '\n' => literal += "\\n",
+            c => literal.push(c),
+        }
+    }
+    literal.push('\'');
+    literal
+}
+
+/// The notification id in a reply to `Notify`, like `(uint32 42,)`.
+fn parse_id(reply: &str) -> Option<u32> {
+    let id = reply.strip_prefix("(uint32 ")?.strip_suffix(",)")?;
+    id.parse().ok()
+}
+
+/// Escape `text` so that notification daemons show it verbatim in bodies,
+/// rather than take it for markup.
+pub fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_strings_for_gdbus() {
+        assert_eq!(string("it's a\\b\nc"), r"'it\'s a\\b\nc'");
+    }
+
+    #[test]
+    fn passes_negative_arguments_after_options() {
+        let args = ["'app'".to_owned(), "-1".to_owned()];
+        let command = command("Notify", &args);
+        let argv: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            argv,
+            [
+                "call",
+                "--session",
+                "--dest",
+                DESTINATION,
+                "--object-path",
+                OBJECT_PATH,
+                "--method",
+                "org.freedesktop.Notifications.Notify",
+                "--",
+                "'app'",
+                "-1",
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_notification_ids() {
+        assert_eq!(parse_id("(uint32 42,)"), Some(42));
+        assert_eq!(parse_id("()"), None);
+    }
+}
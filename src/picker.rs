@@ -0,0 +1,86 @@
+//! Prompting the user through an external menu program like rofi or dmenu.
+
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// Run `menu` through `sh -c`, feeding it `items` one per line, and return the
+/// line it prints, e.g. the chosen item or some text typed by the user.
+///
+/// Returns `None` if the menu was dismissed or printed nothing.
+pub fn pick<S: AsRef<str>>(menu: &str, items: &[S]) -> io::Result<Option<String>> {
+    let mut child = Command::new("sh")
+        .args(["-c", menu])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let written = items
+            .iter()
+            .try_for_each(|item| writeln!(stdin, "{}", item.as_ref()));
+        // The menu may exit without reading all the items, e.g. once
+        // dismissed.
+        match written {
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => return Err(err),
+            _ => {}
+        }
+    }
+    let output = child.wait_with_output()?;
+    // Menus exit with an error when dismissed.
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let choice = String::from_utf8(output.stdout).map_err(io::Error::other)?;
+    let choice = choice.trim_end_matches('\n');
+    Ok((!choice.is_empty()).then(|| choice.to_owned()))
+}
+
+/// Like [`pick`], but return the index of the chosen item, or `None` if the
+/// menu was dismissed or printed something that isn't one of the `items`.
+///
+/// Items are numbered, like `3: Firefox`, and the choice is told by its
+/// number, so that items with the same text, like windows with the same
+/// title, can be told apart.
+pub fn pick_index<S: AsRef<str>>(menu: &str, items: &[S]) -> io::Result<Option<usize>> {
+    let numbered: Vec<String> = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| format!("{}: {}", index + 1, item.as_ref()))
+        .collect();
+    let Some(choice) = pick(menu, &numbered)? else {
+        return Ok(None);
+    };
+    let number = choice
+        .split_once(": ")
+        .and_then(|(number, _)| number.parse::<usize>().ok());
+    Ok(number
+        .and_then(|number| number.checked_sub(1))
+        .filter(|&index| index < items.len()))
+}
+
+/// `item` shown along with the icon named `icon` by rofi with `-show-icons`.
+pub fn with_icon(item: &str, icon: &str) -> String {
+    format!("{item}\0icon\x1f{icon}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_index_of_items_with_icons() {
+        let items = [with_icon("one", "firefox"), with_icon("two", "alacritty")];
+        assert_eq!(pick_index("sed -n 2p", &items).unwrap(), Some(1));
+        assert_eq!(pick_index("echo 3: three", &items).unwrap(), None);
+        assert_eq!(pick_index("echo two", &items).unwrap(), None);
+        assert_eq!(pick_index("exit 1", &items).unwrap(), None);
+    }
+
+    #[test]
+    fn tells_items_with_the_same_text_apart() {
+        let items = ["Terminal", "Terminal", "Terminal"];
+        assert_eq!(pick_index("sed -n 3p", &items).unwrap(), Some(2));
+        assert_eq!(pick_index("grep -m1 Terminal", &items).unwrap(), Some(0));
+    }
+}
@@ -0,0 +1,382 @@
+//! Recording of what helpers receive, and its replay, for debugging.
+//!
+//! With [`EventLoop::set_record`](crate::EventLoop::set_record), the event
+//! loop writes one [`Entry`] per line to a JSON Lines file: everything that
+//! makes the helper act, like events, deadlines or control commands, each
+//! followed by the replies to the queries the helper sent while handling it.
+//! Feeding such a file to [`replay`] puts a fresh helper through the same
+//! decisions without i3, printing the commands it sends instead of running
+//! them, so that bug reports can come with a reproduction, and reproductions
+//! can become regression tests through [`replay_commands`].
+
+use crate::{Helper, I3Commands, WindowManager};
+use i3_ipc::{
+    event::{BindingData, Event, ModeData, OutputData, TickData, WindowData, WorkspaceData},
+    reply::{Node, Outputs, Workspaces},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    time::Instant,
+};
+
+/// A line of a recording.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Entry {
+    /// The helper started.
+    Start,
+    Event(RecordedEvent),
+    /// The deadline of the helper was reached.
+    Deadline,
+    /// The configuration was reloaded on SIGHUP.
+    Reload,
+    /// i3 restarted in place and we reconnected to it.
+    Reconnect,
+    /// A command was received on the control socket.
+    Control(String),
+    /// The process was asked to terminate.
+    Exit,
+    /// The reply to a query of the helper.
+    Tree(Box<Node>),
+    Workspaces(Workspaces),
+    Outputs(Outputs),
+    Config(String),
+    WindowManager(WindowManager),
+    /// The reply to a message sent with [`I3Commands::send_message`].
+    Message {
+        message_type: u32,
+        reply: Value,
+    },
+    /// Whether all the actions of a command sent with
+    /// [`I3Commands::run_command_checked`] succeeded.
+    CommandSucceeded(bool),
+}
+
+/// The events helpers can subscribe to, in a form that can be recorded.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedEvent {
+    Window(Box<WindowData>),
+    Workspace(Box<WorkspaceData>),
+    Output(OutputData),
+    Mode(ModeData),
+    Binding(BindingData),
+    Tick(TickData),
+}
+
+impl RecordedEvent {
+    /// The recordable form of `event`, unless it is not meant for helpers.
+    pub fn new(event: &Event) -> Option<Self> {
+        Some(match event {
+            Event::Window(data) => RecordedEvent::Window(data.clone()),
+            Event::Workspace(data) => RecordedEvent::Workspace(data.clone()),
+            Event::Output(data) => RecordedEvent::Output(data.clone()),
+            Event::Mode(data) => RecordedEvent::Mode(data.clone()),
+            Event::Binding(data) => RecordedEvent::Binding(data.clone()),
+            Event::Tick(data) => RecordedEvent::Tick(data.clone()),
+            Event::BarConfig(_) | Event::Shutdown(_) => return None,
+        })
+    }
+
+    fn into_event(self) -> Event {
+        match self {
+            RecordedEvent::Window(data) => Event::Window(data),
+            RecordedEvent::Workspace(data) => Event::Workspace(data),
+            RecordedEvent::Output(data) => Event::Output(data),
+            RecordedEvent::Mode(data) => Event::Mode(data),
+            RecordedEvent::Binding(data) => Event::Binding(data),
+            RecordedEvent::Tick(data) => Event::Tick(data),
+        }
+    }
+}
+
+/// Writes the entries of a recording to a file.
+pub(crate) struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        log::info!("recording events to {}", path.display());
+        Ok(Self {
+            out: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    /// Write `entry`, flushed right away so that the recording survives a
+    /// crash, which is when it is most useful.
+    pub(crate) fn record(&mut self, entry: &Entry) -> io::Result<()> {
+        serde_json::to_writer(&mut self.out, entry)?;
+        writeln!(self.out)?;
+        self.out.flush()
+    }
+}
+
+/// An [`I3Commands`] recording the replies to the queries sent through it, if
+/// there is a recorder.
+pub(crate) struct RecordingI3<'a> {
+    pub(crate) i3: &'a mut dyn I3Commands,
+    pub(crate) recorder: Option<&'a mut Recorder>,
+}
+
+impl RecordingI3<'_> {
+    fn record<T>(&mut self, reply: T, entry: impl FnOnce(T) -> Entry) -> io::Result<T>
+    where
+        T: Clone,
+    {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(&entry(reply.clone()))?;
+        }
+        Ok(reply)
+    }
+}
+
+impl I3Commands for RecordingI3<'_> {
+    fn run_command(&mut self, command: &str) -> io::Result<()> {
+        self.i3.run_command(command)
+    }
+
+    fn run_commands(&mut self, commands: &[String]) -> io::Result<()> {
+        self.i3.run_commands(commands)
+    }
+
+    fn run_command_checked(&mut self, command: &str) -> io::Result<bool> {
+        let succeeded = self.i3.run_command_checked(command)?;
+        self.record(succeeded, Entry::CommandSucceeded)
+    }
+
+    fn get_tree(&mut self) -> io::Result<Node> {
+        let tree = self.i3.get_tree()?;
+        self.record(tree, |tree| Entry::Tree(Box::new(tree)))
+    }
+
+    fn get_workspaces(&mut self) -> io::Result<Workspaces> {
+        let workspaces = self.i3.get_workspaces()?;
+        self.record(workspaces, Entry::Workspaces)
+    }
+
+    fn get_outputs(&mut self) -> io::Result<Outputs> {
+        let outputs = self.i3.get_outputs()?;
+        self.record(outputs, Entry::Outputs)
+    }
+
+    fn get_config(&mut self) -> io::Result<String> {
+        let config = self.i3.get_config()?;
+        self.record(config, Entry::Config)
+    }
+
+    fn window_manager(&mut self) -> io::Result<WindowManager> {
+        let window_manager = self.i3.window_manager()?;
+        self.record(window_manager, Entry::WindowManager)
+    }
+
+    fn send_message(&mut self, message_type: u32, payload: &str) -> io::Result<Value> {
+        let reply = self.i3.send_message(message_type, payload)?;
+        self.record(reply, |reply| Entry::Message {
+            message_type,
+            reply,
+        })
+    }
+}
+
+/// Answers queries with recorded replies, in order, and collects commands.
+#[derive(Default)]
+struct ReplayI3 {
+    replies: Replies,
+    commands: Vec<String>,
+}
+
+/// The replies recorded for an entry, by kind.
+///
+/// Once those of a kind are used up, the last one is reused, including for the
+/// next entries until new ones are recorded, as the helper may not send the
+/// same queries when replayed, e.g. after a bug fix. Checked commands are the
+/// exception: past the recorded replies of the entry, they succeed.
+#[derive(Default)]
+struct Replies {
+    trees: Vec<Node>,
+    workspaces: Vec<Workspaces>,
+    outputs: Vec<Outputs>,
+    configs: Vec<String>,
+    window_managers: Vec<WindowManager>,
+    messages: BTreeMap<u32, Vec<Value>>,
+    commands_succeeded: Vec<bool>,
+}
+
+impl Replies {
+    fn add(&mut self, reply: Entry) {
+        match reply {
+            Entry::Tree(tree) => self.trees.push(*tree),
+            Entry::Workspaces(workspaces) => self.workspaces.push(workspaces),
+            Entry::Outputs(outputs) => self.outputs.push(outputs),
+            Entry::Config(config) => self.configs.push(config),
+            Entry::WindowManager(window_manager) => self.window_managers.push(window_manager),
+            Entry::Message {
+                message_type,
+                reply,
+            } => self.messages.entry(message_type).or_default().push(reply),
+            Entry::CommandSucceeded(succeeded) => self.commands_succeeded.push(succeeded),
+            _ => (),
+        }
+    }
+
+    /// Move on to the replies of the next entry, `next`.
+    fn replace(&mut self, next: Replies) {
+        refill(&mut self.trees, next.trees);
+        refill(&mut self.workspaces, next.workspaces);
+        refill(&mut self.outputs, next.outputs);
+        refill(&mut self.configs, next.configs);
+        refill(&mut self.window_managers, next.window_managers);
+        for replies in self.messages.values_mut() {
+            refill(replies, Vec::new());
+        }
+        for (message_type, replies) in next.messages {
+            self.messages.insert(message_type, replies);
+        }
+        self.commands_succeeded = next.commands_succeeded;
+    }
+}
+
+fn refill<T>(replies: &mut Vec<T>, next: Vec<T>) {
+    if next.is_empty() {
+        let unused = replies.len().saturating_sub(1);
+        replies.drain(..unused);
+    } else {
+        *replies = next;
+    }
+}
+
+/// The next recorded reply of a kind, or the last one once used up.
+fn next_reply<T: Clone>(replies: &mut Vec<T>, kind: &str) -> io::Result<T> {
+    match replies.len() {
+        0 => Err(io::Error::other(format!(
+            "the recording has no {kind} to answer the helper with"
+        ))),
+        1 => Ok(replies[0].clone()),
+        _ => Ok(replies.remove(0)),
+    }
+}
+
+impl I3Commands for ReplayI3 {
+    fn run_command(&mut self, command: &str) -> io::Result<()> {
+        self.commands.push(command.to_owned());
+        Ok(())
+    }
+
+    fn run_command_checked(&mut self, command: &str) -> io::Result<bool> {
+        self.run_command(command)?;
+        let succeeded = &mut self.replies.commands_succeeded;
+        Ok(succeeded.is_empty() || succeeded.remove(0))
+    }
+
+    fn get_tree(&mut self) -> io::Result<Node> {
+        next_reply(&mut self.replies.trees, "tree")
+    }
+
+    fn get_workspaces(&mut self) -> io::Result<Workspaces> {
+        next_reply(&mut self.replies.workspaces, "workspaces")
+    }
+
+    fn get_outputs(&mut self) -> io::Result<Outputs> {
+        next_reply(&mut self.replies.outputs, "outputs")
+    }
+
+    fn get_config(&mut self) -> io::Result<String> {
+        next_reply(&mut self.replies.configs, "configuration")
+    }
+
+    fn window_manager(&mut self) -> io::Result<WindowManager> {
+        next_reply(&mut self.replies.window_managers, "window manager")
+    }
+
+    fn send_message(&mut self, message_type: u32, _payload: &str) -> io::Result<Value> {
+        let replies = self.replies.messages.entry(message_type).or_default();
+        next_reply(
+            replies,
+            &format!("reply to messages of type {message_type}"),
+        )
+    }
+}
+
+/// Feed the recording at `path` to `helper` and print the commands it sends,
+/// each after the line of the entry it was sent for.
+pub fn replay<H: Helper + ?Sized>(path: &Path, helper: &mut H) -> io::Result<()> {
+    let recording = BufReader::new(File::open(path)?);
+    for (line, command) in replay_commands(recording, helper)? {
+        println!("{line}: {command}");
+    }
+    Ok(())
+}
+
+/// Feed a recording to `helper` and return the commands it sends, along with
+/// the line number of the entry each was sent for.
+pub fn replay_commands<H: Helper + ?Sized>(
+    recording: impl BufRead,
+    helper: &mut H,
+) -> io::Result<Vec<(usize, String)>> {
+    let mut entries = Vec::new();
+    for (index, line) in recording.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry = serde_json::from_str(&line).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {err}", index + 1),
+            )
+        })?;
+        entries.push((index + 1, entry));
+    }
+    let mut i3 = ReplayI3::default();
+    let mut commands = Vec::new();
+    let mut entries = entries.into_iter().peekable();
+    while let Some((line, entry)) = entries.next() {
+        // The replies following an entry are the ones to answer it with.
+        let mut replies = Replies::default();
+        while let Some((_, reply)) = entries.next_if(|(_, entry)| is_reply(entry)) {
+            replies.add(reply);
+        }
+        i3.replies.replace(replies);
+        match entry {
+            Entry::Start => helper.on_start(&mut i3)?,
+            Entry::Event(event) => {
+                crate::event_loop::dispatch(helper, &mut i3, &event.into_event())?
+            }
+            Entry::Deadline => {
+                // Deadlines were set relative to the time of the replay, so
+                // the helper's own one is what was reached in the recording.
+                let now = helper.next_deadline().unwrap_or_else(Instant::now);
+                helper.on_deadline(&mut i3, now)?;
+            }
+            Entry::Reload => helper.on_reload(&mut i3)?,
+            Entry::Reconnect => helper.on_reconnect(&mut i3)?,
+            Entry::Control(command) => {
+                helper.on_control(&mut i3, &command)?;
+            }
+            Entry::Exit => helper.on_exit(&mut i3)?,
+            // Replies recorded before any entry, ignored.
+            _ => (),
+        }
+        commands.extend(i3.commands.drain(..).map(|command| (line, command)));
+    }
+    Ok(commands)
+}
+
+fn is_reply(entry: &Entry) -> bool {
+    matches!(
+        entry,
+        Entry::Tree(_)
+            | Entry::Workspaces(_)
+            | Entry::Outputs(_)
+            | Entry::Config(_)
+            | Entry::WindowManager(_)
+            | Entry::Message { .. }
+            | Entry::CommandSucceeded(_)
+    )
+}
@@ -0,0 +1,164 @@
+//! Showing the state of a helper in a status bar.
+//!
+//! A helper implementing [`ReportStatus`] and run wrapped in a
+//! [`StatusReporter`] prints its status to stdout as a line of JSON whenever
+//! it changes, for a persistent i3blocks block with `format=json` or a waybar
+//! custom module with `"return-type": "json"` to show without polling:
+//!
+//! ```text
+//! [scratchpad]
+//! command=i3-back-to-scratch --class Alacritty --status
+//! interval=persist
+//! format=json
+//! ```
+
+use crate::{Helper, I3Commands};
+use i3_ipc::event::{BindingData, Event, Subscribe, TickData, WindowData, WorkspaceData};
+use serde::Serialize;
+use std::{
+    io::{self, Write},
+    time::Instant,
+};
+
+/// What a status bar shows about a helper.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Status {
+    pub text: String,
+    /// More details, shown on hover by waybar.
+    pub tooltip: String,
+    /// A word describing the state, for waybar to style the module by.
+    pub class: String,
+}
+
+impl Status {
+    /// The status as a line of JSON understood by both i3blocks, which reads
+    /// `full_text`, and waybar, which reads `text`, `tooltip` and `class`.
+    pub fn to_json(&self) -> String {
+        #[derive(Serialize)]
+        struct Line<'a> {
+            full_text: &'a str,
+            text: &'a str,
+            tooltip: &'a str,
+            class: &'a str,
+        }
+        let line = Line {
+            full_text: &self.text,
+            text: &self.text,
+            tooltip: &self.tooltip,
+            class: &self.class,
+        };
+        serde_json::to_string(&line).expect("a status can always be serialized")
+    }
+}
+
+/// A helper with a state worth showing in a status bar.
+pub trait ReportStatus {
+    fn status(&self) -> Status;
+}
+
+/// A [`Helper`] printing the status of the one it wraps after handling
+/// anything, if changed since last printed.
+pub struct StatusReporter<H> {
+    helper: H,
+    last: Option<Status>,
+}
+
+impl<H: Helper + ReportStatus> StatusReporter<H> {
+    pub fn new(helper: H) -> Self {
+        Self { helper, last: None }
+    }
+
+    /// Pass `result` on, after printing the status if it changed.
+    fn report<T>(&mut self, result: io::Result<T>) -> io::Result<T> {
+        let status = self.helper.status();
+        if self.last.as_ref() != Some(&status) {
+            let mut stdout = io::stdout().lock();
+            writeln!(stdout, "{}", status.to_json())?;
+            stdout.flush()?;
+            self.last = Some(status);
+        }
+        result
+    }
+}
+
+impl<H: Helper + ReportStatus> Helper for StatusReporter<H> {
+    fn subscriptions(&self) -> Vec<Subscribe> {
+        self.helper.subscriptions()
+    }
+
+    fn on_start(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let result = self.helper.on_start(i3);
+        self.report(result)
+    }
+
+    fn on_window(&mut self, i3: &mut dyn I3Commands, event: &WindowData) -> io::Result<()> {
+        let result = self.helper.on_window(i3, event);
+        self.report(result)
+    }
+
+    fn on_workspace(&mut self, i3: &mut dyn I3Commands, event: &WorkspaceData) -> io::Result<()> {
+        let result = self.helper.on_workspace(i3, event);
+        self.report(result)
+    }
+
+    fn on_binding(&mut self, i3: &mut dyn I3Commands, event: &BindingData) -> io::Result<()> {
+        let result = self.helper.on_binding(i3, event);
+        self.report(result)
+    }
+
+    fn on_tick(&mut self, i3: &mut dyn I3Commands, event: &TickData) -> io::Result<()> {
+        let result = self.helper.on_tick(i3, event);
+        self.report(result)
+    }
+
+    fn on_event(&mut self, i3: &mut dyn I3Commands, event: &Event) -> io::Result<()> {
+        let result = self.helper.on_event(i3, event);
+        self.report(result)
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.helper.next_deadline()
+    }
+
+    fn on_deadline(&mut self, i3: &mut dyn I3Commands, now: Instant) -> io::Result<()> {
+        let result = self.helper.on_deadline(i3, now);
+        self.report(result)
+    }
+
+    fn on_reload(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let result = self.helper.on_reload(i3);
+        self.report(result)
+    }
+
+    fn on_reconnect(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        let result = self.helper.on_reconnect(i3);
+        self.report(result)
+    }
+
+    fn on_exit(&mut self, i3: &mut dyn I3Commands) -> io::Result<()> {
+        self.helper.on_exit(i3)
+    }
+
+    fn on_control(&mut self, i3: &mut dyn I3Commands, command: &str) -> io::Result<String> {
+        let result = self.helper.on_control(i3, command);
+        self.report(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prints_fields_for_both_i3blocks_and_waybar() {
+        let status = Status {
+            text: "work".to_owned(),
+            tooltip: "group \"work\"".to_owned(),
+            class: "active".to_owned(),
+        };
+        assert_eq!(
+            status.to_json(),
+            r#"{"full_text":"work","text":"work","tooltip":"group \"work\"","class":"active"}"#
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Integration with systemd for helpers run as `Type=notify` services.
+
+use crate::I3Commands;
+use sd_notify::NotifyState;
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+/// Notifies systemd about the state of the event loop.
+pub(crate) struct Notifier {
+    watchdog: Option<Watchdog>,
+}
+
+struct Watchdog {
+    interval: Duration,
+    next_ping: Instant,
+}
+
+impl Notifier {
+    pub(crate) fn new() -> Self {
+        // Ping twice per watchdog period, as recommended by sd_watchdog_enabled(3).
+        let watchdog = sd_notify::watchdog_enabled().map(|timeout| Watchdog {
+            interval: timeout / 2,
+            next_ping: Instant::now(),
+        });
+        Self { watchdog }
+    }
+
+    /// Tell systemd that the helper is subscribed to events and running.
+    pub(crate) fn ready(&self) -> io::Result<()> {
+        sd_notify::notify(&[NotifyState::Ready])
+    }
+
+    pub(crate) fn stopping(&self) -> io::Result<()> {
+        sd_notify::notify(&[NotifyState::Stopping])
+    }
+
+    /// When the watchdog should be pinged next, if it is enabled.
+    pub(crate) fn next_ping(&self) -> Option<Instant> {
+        self.watchdog.as_ref().map(|watchdog| watchdog.next_ping)
+    }
+
+    /// Ping the watchdog if it is due, after checking that i3 still answers
+    /// our queries. A wedged connection thus results in systemd restarting
+    /// the helper.
+    pub(crate) fn ping_if_due(&mut self, i3: &mut dyn I3Commands, now: Instant) -> io::Result<()> {
+        let Some(watchdog) = &mut self.watchdog else {
+            return Ok(());
+        };
+        if watchdog.next_ping > now {
+            return Ok(());
+        }
+        i3.window_manager()?;
+        log::trace!("pinging the systemd watchdog");
+        sd_notify::notify(&[NotifyState::Watchdog])?;
+        watchdog.next_ping = now + watchdog.interval;
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Queries over the layout tree returned by `get_tree`.
+
+use i3_ipc::reply::{Floating, Node, NodeType, ScratchpadState};
+
+type NodeId = usize;
+
+/// The name of the hidden workspace holding the scratchpad windows.
+pub const SCRATCHPAD_WORKSPACE: &str = "__i3_scratch";
+
+/// Iterate over `root` and all its descendants, tiling and floating, in
+/// depth-first order.
+pub fn descendants(root: &Node) -> impl Iterator<Item = &Node> {
+    let mut stack = vec![root];
+    std::iter::from_fn(move || {
+        let node = stack.pop()?;
+        stack.extend(node.floating_nodes.iter().rev());
+        stack.extend(node.nodes.iter().rev());
+        Some(node)
+    })
+}
+
+/// Iterate over the nodes under `root` that hold an actual window.
+pub fn windows(root: &Node) -> impl Iterator<Item = &Node> {
+    descendants(root).filter(|node| is_window(node))
+}
+
+/// Iterate over the workspaces under `root`, including the scratchpad one.
+pub fn workspaces(root: &Node) -> impl Iterator<Item = &Node> {
+    descendants(root).filter(|node| node.node_type == NodeType::Workspace)
+}
+
+/// Find the node with the given id under `root`.
+pub fn find(root: &Node, id: NodeId) -> Option<&Node> {
+    descendants(root).find(|node| node.id == id)
+}
+
+/// Find the parent of the node with the given id under `root`.
+pub fn parent_of(root: &Node, id: NodeId) -> Option<&Node> {
+    descendants(root).find(|node| children(node).any(|child| child.id == id))
+}
+
+/// Find the workspace containing the node with the given id under `root`.
+pub fn workspace_of(root: &Node, id: NodeId) -> Option<&Node> {
+    workspaces(root).find(|workspace| find(workspace, id).is_some())
+}
+
+/// Find the workspace holding the focus under `root`.
+pub fn focused_workspace(root: &Node) -> Option<&Node> {
+    workspace_of(root, focused(root)?.id)
+}
+
+/// Find the output containing the node with the given id under `root`.
+pub fn output_of(root: &Node, id: NodeId) -> Option<&Node> {
+    descendants(root)
+        .filter(|node| node.node_type == NodeType::Output)
+        .find(|output| find(output, id).is_some())
+}
+
+/// Find the focused node under `root`.
+pub fn focused(root: &Node) -> Option<&Node> {
+    descendants(root).find(|node| node.focused)
+}
+
+/// Iterate over the direct children of `node`, tiling and floating.
+pub fn children(node: &Node) -> impl Iterator<Item = &Node> {
+    node.nodes.iter().chain(node.floating_nodes.iter())
+}
+
+/// A window along with the workspace it is on, as listed by [`list_windows`].
+pub struct WindowEntry<'a> {
+    pub window: &'a Node,
+    pub workspace: &'a Node,
+}
+
+impl WindowEntry<'_> {
+    /// The name of the workspace, `scratchpad` for hidden scratchpad windows.
+    pub fn workspace_name(&self) -> &str {
+        match self.workspace.name.as_deref() {
+            Some(SCRATCHPAD_WORKSPACE) => "scratchpad",
+            name => name.unwrap_or_default(),
+        }
+    }
+
+    /// The X11 class of the window, or its app_id for native Wayland windows.
+    pub fn class(&self) -> &str {
+        let props = self.window.window_properties.as_ref();
+        props
+            .and_then(|props| props.class.as_deref())
+            .or(self.window.app_id.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// The name of the icon of the window in icon themes, which is its class
+    /// in lowercase for most applications.
+    pub fn icon_name(&self) -> String {
+        self.class().to_lowercase()
+    }
+
+    pub fn title(&self) -> &str {
+        self.window.name.as_deref().unwrap_or_default()
+    }
+
+    pub fn marks(&self) -> impl Iterator<Item = &str> {
+        self.window
+            .marks
+            .iter()
+            .flat_map(|marks| &marks.0)
+            .map(String::as_str)
+    }
+}
+
+/// List the windows under `root` along with the workspace they are on, in
+/// depth-first order.
+pub fn list_windows(root: &Node) -> Vec<WindowEntry<'_>> {
+    workspaces(root)
+        .flat_map(|workspace| {
+            windows(workspace).map(move |window| WindowEntry { window, workspace })
+        })
+        .collect()
+}
+
+/// A short human readable description of a window, for logging purposes.
+pub fn describe(node: &Node) -> String {
+    let props = node.window_properties.as_ref();
+    let class = props
+        .and_then(|props| props.class.as_deref())
+        .or(node.app_id.as_deref())
+        .unwrap_or_default();
+    let title = node.name.as_deref().unwrap_or_default();
+    format!("window {} ({class:?}, {title:?})", node.id)
+}
+
+/// Whether the node holds an actual window, as opposed to being a split,
+/// workspace or output container.
+pub fn is_window(node: &Node) -> bool {
+    node.window.is_some() || node.app_id.is_some()
+}
+
+/// Whether the node is floating, either by itself or as decided by i3.
+pub fn is_floating(node: &Node) -> bool {
+    matches!(node.floating, Some(Floating::AutoOn | Floating::UserOn))
+}
+
+/// Whether the node with the given id is hidden in the scratchpad workspace.
+pub fn is_hidden_in_scratchpad(root: &Node, id: NodeId) -> bool {
+    workspace_of(root, id)
+        .and_then(|workspace| workspace.name.as_deref())
+        .is_some_and(|name| name == SCRATCHPAD_WORKSPACE)
+}
+
+/// Whether `window` was moved to the scratchpad, hidden or not. i3 flags its
+/// floating container rather than the window itself, unlike sway.
+pub fn is_scratchpad_window(root: &Node, window: &Node) -> bool {
+    let flagged = |node: &Node| node.scratchpad_state != ScratchpadState::None;
+    flagged(window) || parent_of(root, window.id).is_some_and(flagged)
+}
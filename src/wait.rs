@@ -0,0 +1,147 @@
+//! Waiting for an event from short-lived commands.
+//!
+//! Commands acting on something they trigger, like a window they launch,
+//! don't run an [`EventLoop`](crate::EventLoop). They subscribe with an
+//! [`EventWaiter`] first, so as not to miss the event, then trigger it and
+//! wait, or wait for a window with [`wait_for_window`]:
+//!
+//! ```no_run
+//! use i3_helpers::{wait::EventWaiter, Connection, I3Commands};
+//! use i3_ipc::event::{Event, Subscribe, WindowChange};
+//! use std::time::Duration;
+//!
+//! let mut waiter = EventWaiter::subscribe(&[Subscribe::Window])?;
+//! Connection::connect()?.run_command("exec --no-startup-id xterm")?;
+//! let new_window = waiter.wait(Some(Duration::from_secs(5)), |event| match event {
+//!     Event::Window(data) if data.change == WindowChange::New => Some(data.container.id),
+//!     _ => None,
+//! })?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::{
+    event_loop::{listen, Events},
+    tree, Criteria, I3Commands,
+};
+use i3_ipc::{
+    event::{Event, Subscribe, WindowChange},
+    reply::Node,
+};
+use std::{io, time::Duration};
+use tokio::{runtime::Runtime, time};
+use tokio_stream::StreamExt;
+
+/// A subscription to i3 events, waited on synchronously.
+pub struct EventWaiter {
+    runtime: Runtime,
+    events: Events,
+}
+
+impl EventWaiter {
+    /// Subscribe to `subscriptions` on a new connection.
+    pub fn subscribe(subscriptions: &[Subscribe]) -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let events = runtime.block_on(listen(subscriptions))?;
+        Ok(Self { runtime, events })
+    }
+
+    /// Wait for the first event `select` returns something for, and return
+    /// that. Returns `None` if `timeout` expires first, and fails if i3 goes
+    /// away, since the event can't come anymore.
+    pub fn wait<T>(
+        &mut self,
+        timeout: Option<Duration>,
+        mut select: impl FnMut(&Event) -> Option<T>,
+    ) -> io::Result<Option<T>> {
+        let events = &mut self.events;
+        let wait = async {
+            while let Some(event) = events.next().await {
+                if let Some(selected) = select(&event?) {
+                    return Ok(Some(selected));
+                }
+            }
+            Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "i3 closed the event connection",
+            ))
+        };
+        self.runtime.block_on(async {
+            match timeout {
+                Some(timeout) => time::timeout(timeout, wait).await.unwrap_or(Ok(None)),
+                None => wait.await,
+            }
+        })
+    }
+
+    /// Wait for a new window matching `criteria`, up to `timeout`, and
+    /// return it. Needs a subscription to window events.
+    pub fn wait_for_new_window(
+        &mut self,
+        criteria: &Criteria,
+        timeout: Option<Duration>,
+    ) -> io::Result<Option<Node>> {
+        self.wait(timeout, |event| match event {
+            Event::Window(data)
+                if data.change == WindowChange::New && criteria.matches(&data.container) =>
+            {
+                Some(data.container.clone())
+            }
+            _ => None,
+        })
+    }
+}
+
+/// Wait for a window matching `criteria` to exist, up to `timeout`, and
+/// return it. Returns right away if there is one already.
+pub fn wait_for_window(
+    i3: &mut dyn I3Commands,
+    criteria: &Criteria,
+    timeout: Option<Duration>,
+) -> io::Result<Option<Node>> {
+    // Subscribe before looking, so as not to miss a window appearing between
+    // the two.
+    let mut waiter = EventWaiter::subscribe(&[Subscribe::Window])?;
+    let tree = i3.get_tree()?;
+    if let Some(window) = tree::windows(&tree).find(|window| criteria.matches(window)) {
+        return Ok(Some(window.clone()));
+    }
+    waiter.wait(timeout, |event| matching_window(criteria, event))
+}
+
+/// The window of `event` if it just appeared or changed so as to match
+/// `criteria`, e.g. by getting the title it is waited for.
+fn matching_window(criteria: &Criteria, event: &Event) -> Option<Node> {
+    match event {
+        Event::Window(data)
+            if matches!(
+                data.change,
+                WindowChange::New | WindowChange::Title | WindowChange::Mark
+            ) && criteria.matches(&data.container) =>
+        {
+            Some(data.container.clone())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{criteria::exact, mock};
+
+    #[test]
+    fn matches_new_and_retitled_windows() {
+        let criteria = Criteria {
+            title: Some(exact("build")),
+            ..Criteria::default()
+        };
+        let event = |change, title| mock::window_event(change, mock::window(7, "Alacritty", title));
+        let matched = |event| matching_window(&criteria, &event).map(|window| window.id);
+        assert_eq!(matched(event(WindowChange::New, "build")), Some(7));
+        assert_eq!(matched(event(WindowChange::Title, "build")), Some(7));
+        assert_eq!(matched(event(WindowChange::New, "shell")), None);
+        assert_eq!(matched(event(WindowChange::Focus, "build")), None);
+    }
+}
@@ -0,0 +1,105 @@
+//! Direct access to X11, for window properties i3 has no command for.
+
+use std::{io, time::Duration};
+use x11rb::{
+    connection::Connection as _,
+    protocol::{
+        screensaver::ConnectionExt as _,
+        xproto::{Atom, AtomEnum, ConnectionExt as _, PropMode, Window},
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+};
+
+/// A connection to the X server i3 runs on.
+pub struct X11 {
+    connection: RustConnection,
+    /// The root window of the screen i3 manages.
+    root: Window,
+    /// The `_NET_WM_WINDOW_OPACITY` atom, read by compositors like picom.
+    opacity: Atom,
+    /// The `_NET_WM_PID` atom, set by clients to the PID of their process.
+    pid: Atom,
+}
+
+impl X11 {
+    /// Connect to the X server named by `$DISPLAY`.
+    pub fn connect() -> io::Result<Self> {
+        let (connection, screen) = x11rb::connect(None).map_err(io::Error::other)?;
+        let root = connection.setup().roots[screen].root;
+        let intern = |name: &[u8]| -> io::Result<Atom> {
+            Ok(connection
+                .intern_atom(false, name)
+                .map_err(io::Error::other)?
+                .reply()
+                .map_err(io::Error::other)?
+                .atom)
+        };
+        let opacity = intern(b"_NET_WM_WINDOW_OPACITY")?;
+        let pid = intern(b"_NET_WM_PID")?;
+        Ok(Self {
+            connection,
+            root,
+            opacity,
+            pid,
+        })
+    }
+
+    /// The PID of the process owning X11 window `window`, unless its client
+    /// doesn't tell.
+    pub fn window_pid(&self, window: u32) -> io::Result<Option<u32>> {
+        let reply = self
+            .connection
+            .get_property(false, window, self.pid, AtomEnum::CARDINAL, 0, 1)
+            .map_err(io::Error::other)?
+            .reply()
+            .map_err(io::Error::other)?;
+        Ok(reply.value32().and_then(|mut values| values.next()))
+    }
+
+    /// Set the opacity of X11 window `window`, from 0 to 1. Fully opaque
+    /// windows get the property removed, as compositors expect.
+    pub fn set_opacity(&self, window: u32, opacity: f64) -> io::Result<()> {
+        if opacity >= 1.0 {
+            self.connection
+                .delete_property(window, self.opacity)
+                .map_err(io::Error::other)?;
+        } else {
+            let value = (opacity.max(0.0) * f64::from(u32::MAX)) as u32;
+            self.connection
+                .change_property32(
+                    PropMode::REPLACE,
+                    window,
+                    self.opacity,
+                    AtomEnum::CARDINAL,
+                    &[value],
+                )
+                .map_err(io::Error::other)?;
+        }
+        self.connection.flush().map_err(io::Error::other)
+    }
+
+    /// The position of the pointer, relative to the top left corner of the
+    /// screen.
+    pub fn pointer_position(&self) -> io::Result<(i32, i32)> {
+        let reply = self
+            .connection
+            .query_pointer(self.root)
+            .map_err(io::Error::other)?
+            .reply()
+            .map_err(io::Error::other)?;
+        Ok((i32::from(reply.root_x), i32::from(reply.root_y)))
+    }
+
+    /// How long since the last keyboard or mouse input, as told by the
+    /// screen saver extension.
+    pub fn idle_time(&self) -> io::Result<Duration> {
+        let reply = self
+            .connection
+            .screensaver_query_info(self.root)
+            .map_err(io::Error::other)?
+            .reply()
+            .map_err(io::Error::other)?;
+        Ok(Duration::from_millis(reply.ms_since_user_input.into()))
+    }
+}